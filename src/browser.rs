@@ -0,0 +1,53 @@
+//! This module provides functionality for opening the generated site in a web browser.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+
+/// Abstraction over opening a file in a web browser, allowing the behavior to be swapped out
+/// (i.e. in tests).
+pub trait BrowserOpener {
+    /// Opens the file at the given path in a web browser.
+    fn open(&self, path: &Path) -> Result<(), io::Error>;
+}
+
+/// Opens files using the system's default web browser.
+pub struct DefaultBrowser;
+
+impl BrowserOpener for DefaultBrowser {
+    fn open(&self, path: &Path) -> Result<(), io::Error> {
+        webbrowser::open(&path.to_string_lossy())
+    }
+}
+
+/// Opens the generated `index.html` in the output directory using the provided opener.
+///
+/// # Arguments
+/// * `opener` - The `BrowserOpener` used to open the file.
+/// * `output_dir` - The directory the site was generated in.
+///
+/// # Returns
+/// Returns a `Result` containing the path that was opened, or `None` if `index.html` was not
+/// generated (i.e. the input directory was empty).
+pub fn open_index<B: BrowserOpener>(
+    opener: &B,
+    output_dir: &str,
+) -> Result<Option<PathBuf>, io::Error> {
+    let index_path = Path::new(output_dir).join("index.html");
+    if !index_path.exists() {
+        warn!(
+            "index.html does not exist at path: {}, nothing to open",
+            index_path.display()
+        );
+        return Ok(None);
+    }
+
+    opener.open(&index_path)?;
+    info!("Opened {} in browser.", index_path.display());
+
+    Ok(Some(index_path))
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,47 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use crate::browser::{BrowserOpener, open_index};
+
+struct MockBrowser {
+    opened: RefCell<Vec<PathBuf>>,
+}
+
+impl BrowserOpener for MockBrowser {
+    fn open(&self, path: &Path) -> Result<(), io::Error> {
+        self.opened.borrow_mut().push(path.to_path_buf());
+        Ok(())
+    }
+}
+
+#[test]
+fn opens_index_in_output_dir() {
+    let output_dir = TempDir::new().unwrap();
+    fs::write(output_dir.path().join("index.html"), "<html></html>").unwrap();
+
+    let browser = MockBrowser {
+        opened: RefCell::new(Vec::new()),
+    };
+    let opened = open_index(&browser, &output_dir.path().to_string_lossy()).unwrap();
+
+    let expected = output_dir.path().join("index.html");
+    assert_eq!(opened, Some(expected.clone()));
+    assert_eq!(*browser.opened.borrow(), vec![expected]);
+}
+
+#[test]
+fn missing_index_is_not_opened() {
+    let output_dir = TempDir::new().unwrap();
+
+    let browser = MockBrowser {
+        opened: RefCell::new(Vec::new()),
+    };
+    let opened = open_index(&browser, &output_dir.path().to_string_lossy()).unwrap();
+
+    assert_eq!(opened, None);
+    assert!(browser.opened.borrow().is_empty());
+}
@@ -0,0 +1,181 @@
+//! This module provides the build cache used for incremental builds, which records the
+//! modification time and size of every source file in a `.markrs-cache` file in the output
+//! directory so that pages whose source hasn't changed can be skipped on the next build. The
+//! summary of each page is recorded with it, so that skipped pages can still be listed in the
+//! index.
+//!
+//! A page's HTML also depends on the config and, through the sidebar, on the list of pages, so the
+//! cache starts with a fingerprint of both. If either changes, every page is regenerated.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use mark_rs::config::Config;
+use mark_rs::html_generator::{PageOutput, inline_css};
+use mark_rs::io::is_dry_run;
+use mark_rs::minify::is_minify_enabled;
+use mark_rs::utils::Fingerprint;
+
+/// The name of the cache file in the output directory.
+const CACHE_FILE: &str = ".markrs-cache";
+
+/// The state of the sources a site was built from, mapping each page's file name to a stamp of
+/// its source file and the summary of the page generated from it.
+#[derive(Debug, Default, PartialEq)]
+pub struct BuildCache {
+    fingerprint: String,
+    pages: BTreeMap<String, (String, PageOutput)>,
+}
+
+impl BuildCache {
+    /// Creates an empty cache for a build with the given fingerprint.
+    pub fn new(fingerprint: String) -> Self {
+        BuildCache {
+            fingerprint,
+            pages: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the cache left in the output directory by the previous build.
+    ///
+    /// # Returns
+    /// The previous cache, or an empty one if it doesn't exist or can't be read.
+    pub fn load(output_dir: &str) -> Self {
+        let Ok(contents) = fs::read_to_string(Path::new(output_dir).join(CACHE_FILE)) else {
+            return BuildCache::default();
+        };
+
+        let mut lines = contents.lines();
+        let fingerprint = lines.next().unwrap_or_default().to_string();
+        // Entries whose summary can't be read are left out, so that their pages are regenerated
+        let pages = lines
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let (stamp, file_name) = (fields.next()?, fields.next()?);
+                let page = serde_json::from_str(fields.next()?).ok()?;
+                Some((file_name.to_string(), (stamp.to_string(), page)))
+            })
+            .collect();
+
+        BuildCache { fingerprint, pages }
+    }
+
+    /// Checks whether a page was generated from the same source by a build with the same
+    /// fingerprint.
+    ///
+    /// # Arguments
+    /// * `fingerprint` - The fingerprint of the current build, from `build_fingerprint`.
+    /// * `file_name` - The page's file name, relative to the input directory.
+    /// * `stamp` - The current stamp of the page's source, from `source_stamp`.
+    ///
+    /// # Returns
+    /// The summary of the page if it's fresh, otherwise `None`.
+    pub fn fresh_page(
+        &self,
+        fingerprint: &str,
+        file_name: &str,
+        stamp: &str,
+    ) -> Option<&PageOutput> {
+        if self.fingerprint != fingerprint {
+            return None;
+        }
+
+        self.pages
+            .get(file_name)
+            .filter(|(previous_stamp, _)| previous_stamp == stamp)
+            .map(|(_, page)| page)
+    }
+
+    /// Records the stamp of a page's source and the summary of the page after it has been
+    /// generated.
+    pub fn insert(&mut self, file_name: String, stamp: String, page: PageOutput) {
+        self.pages.insert(file_name, (stamp, page));
+    }
+
+    /// Writes the cache to the output directory, to be read by the next build.
+    ///
+    /// # Returns
+    /// Returns a `Result` indicating success or failure.
+    pub fn write(&self, output_dir: &str) -> Result<(), io::Error> {
+        // The cache is bookkeeping rather than output, so it isn't reported by a dry run
+        if is_dry_run() {
+            return Ok(());
+        }
+
+        let mut contents = format!("{}\n", self.fingerprint);
+        for (file_name, (stamp, page)) in &self.pages {
+            let page = serde_json::to_string(page).map_err(io::Error::other)?;
+            contents.push_str(&format!("{stamp}\t{file_name}\t{page}\n"));
+        }
+
+        fs::create_dir_all(output_dir)?;
+        fs::write(Path::new(output_dir).join(CACHE_FILE), contents)
+    }
+}
+
+/// Builds the fingerprint of everything other than its own source that a page's HTML depends on:
+/// the version, the config, whether minification is enabled, the inlined stylesheet, the contents
+/// of the head, nav and footer partials, the sorted list of pages, and the order of the
+/// previous/next page links.
+///
+/// # Arguments
+/// * `file_names` - The names of every page in the site.
+/// * `nav_order` - The pages in the order they're linked by previous/next links, which is empty
+///   when those links are disabled.
+/// * `config` - The configuration the pages are generated with.
+///
+/// # Returns
+/// Returns the fingerprint as a hex string.
+pub fn build_fingerprint(file_names: &[String], nav_order: &[String], config: &Config) -> String {
+    let mut fingerprint = Fingerprint::new();
+    fingerprint
+        .add(env!("CARGO_PKG_VERSION"))
+        .add(toml_edit::ser::to_string(config).unwrap_or_default())
+        .add([u8::from(is_minify_enabled())]);
+    // An inlined stylesheet is part of every page, so editing it has to regenerate them
+    if config.html.inline_css {
+        fingerprint.add(inline_css());
+    }
+    // Partials are read when each page is generated, so editing one has to regenerate them too
+    for partial in [
+        &config.html.head_partial,
+        &config.html.nav_partial,
+        &config.html.footer_partial,
+    ] {
+        if !partial.is_empty() {
+            fingerprint.add(fs::read_to_string(partial).unwrap_or_default());
+        }
+    }
+
+    let mut sorted_names = file_names.to_vec();
+    sorted_names.sort();
+    fingerprint.add((sorted_names.len() as u64).to_le_bytes());
+    for name in &sorted_names {
+        fingerprint.add(name);
+    }
+    // Each page links to its neighbours, so reordering pages has to regenerate them
+    fingerprint.add((nav_order.len() as u64).to_le_bytes());
+    for name in nav_order {
+        fingerprint.add(name);
+    }
+
+    fingerprint.to_hex()
+}
+
+/// Returns a stamp of a source file made up of its modification time and size, which changes
+/// whenever the file is edited.
+///
+/// # Returns
+/// Returns the stamp, or `None` if the file's metadata can't be read.
+pub fn source_stamp(file_path: &Path) -> Option<String> {
+    let metadata = fs::metadata(file_path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+
+    Some(format!("{}-{}", modified.as_nanos(), metadata.len()))
+}
+
+#[cfg(test)]
+mod test;
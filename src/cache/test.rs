@@ -0,0 +1,128 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use crate::cache::{BuildCache, build_fingerprint, source_stamp};
+use mark_rs::config::Config;
+use mark_rs::html_generator::PageOutput;
+
+fn page(path: &str) -> PageOutput {
+    PageOutput {
+        path: path.to_string(),
+        title: String::from("Title"),
+        word_count: 3,
+        headings: vec![String::from("Heading")],
+        date: Some(String::from("2025-01-31")),
+    }
+}
+
+#[test]
+fn cache_round_trips_through_output_dir() {
+    let output_dir = TempDir::new().unwrap();
+    let output_dir_str = output_dir.path().to_string_lossy();
+
+    let mut cache = BuildCache::new(String::from("abc123"));
+    cache.insert(
+        String::from("guides/setup.md"),
+        String::from("100-42"),
+        page("guides/setup.md"),
+    );
+    cache.insert(
+        String::from("index.md"),
+        String::from("200-7"),
+        page("index.md"),
+    );
+    cache.write(&output_dir_str).unwrap();
+
+    let loaded = BuildCache::load(&output_dir_str);
+    assert_eq!(loaded, cache);
+    assert_eq!(
+        loaded.fresh_page("abc123", "guides/setup.md", "100-42"),
+        Some(&page("guides/setup.md"))
+    );
+}
+
+#[test]
+fn changed_source_or_fingerprint_is_not_fresh() {
+    let mut cache = BuildCache::new(String::from("abc123"));
+    cache.insert(
+        String::from("index.md"),
+        String::from("100-42"),
+        page("index.md"),
+    );
+
+    assert!(cache.fresh_page("abc123", "index.md", "101-42").is_none());
+    assert!(cache.fresh_page("def456", "index.md", "100-42").is_none());
+    assert!(cache.fresh_page("abc123", "new.md", "100-42").is_none());
+}
+
+#[test]
+fn missing_cache_is_empty() {
+    let output_dir = TempDir::new().unwrap();
+
+    let cache = BuildCache::load(&output_dir.path().to_string_lossy());
+    assert!(cache.fresh_page("", "index.md", "100-42").is_none());
+}
+
+#[test]
+fn fingerprint_changes_with_page_set() {
+    let config = Config::default();
+    let pages = [String::from("a.md"), String::from("b.md")];
+    let reordered = [String::from("b.md"), String::from("a.md")];
+    let added = [
+        String::from("a.md"),
+        String::from("b.md"),
+        String::from("c.md"),
+    ];
+
+    assert_eq!(
+        build_fingerprint(&pages, &[], &config),
+        build_fingerprint(&reordered, &[], &config)
+    );
+    assert_ne!(
+        build_fingerprint(&pages, &[], &config),
+        build_fingerprint(&added, &[], &config)
+    );
+}
+
+#[test]
+fn fingerprint_changes_with_navigation_order() {
+    let config = Config::default();
+    let pages = [String::from("a.md"), String::from("b.md")];
+    let reordered = [String::from("b.md"), String::from("a.md")];
+
+    assert_ne!(
+        build_fingerprint(&pages, &pages, &config),
+        build_fingerprint(&pages, &reordered, &config)
+    );
+}
+
+#[test]
+fn fingerprint_changes_when_partial_is_edited() {
+    let dir = TempDir::new().unwrap();
+    let footer_path = dir.path().join("footer.html");
+    let mut config = Config::default();
+    config.html.footer_partial = footer_path.to_string_lossy().to_string();
+    let pages = [String::from("a.md")];
+
+    fs::write(&footer_path, "<footer>Before</footer>").unwrap();
+    let before = build_fingerprint(&pages, &[], &config);
+    fs::write(&footer_path, "<footer>After</footer>").unwrap();
+    let after = build_fingerprint(&pages, &[], &config);
+
+    assert_ne!(before, after);
+}
+
+#[test]
+fn source_stamp_changes_when_file_is_edited() {
+    let dir = TempDir::new().unwrap();
+    let file_path = dir.path().join("page.md");
+
+    fs::write(&file_path, "# Page").unwrap();
+    let before = source_stamp(&file_path).unwrap();
+    fs::write(&file_path, "# Edited page").unwrap();
+    let after = source_stamp(&file_path).unwrap();
+
+    assert_ne!(before, after);
+    assert!(source_stamp(&dir.path().join("missing.md")).is_none());
+}
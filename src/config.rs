@@ -1,6 +1,10 @@
 //! This module handles the configuration I/O for the application.
 
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::fmt;
+use std::ops::Deref;
+use std::rc::Rc;
 use std::str::FromStr;
 
 use log::{error, info, warn};
@@ -10,7 +14,7 @@ use crate::CONFIG;
 use crate::io::{does_config_exist, get_config_path, write_default_config};
 
 /// Represents the global configuration for the application.
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct Config {
     #[serde(default)]
     pub lexer: LexerConfig,
@@ -19,15 +23,23 @@ pub struct Config {
 }
 
 /// Manages all configuration for tokenization
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct LexerConfig {
     #[serde(default = "default_tab_size")]
     pub tab_size: usize,
+    #[serde(default)]
+    pub extended_list_markers: bool,
+    #[serde(default = "setext_headings_by_default")]
+    pub setext_headings: bool,
 }
 
 impl Default for LexerConfig {
     fn default() -> Self {
-        LexerConfig { tab_size: 4 }
+        LexerConfig {
+            tab_size: 4,
+            extended_list_markers: false,
+            setext_headings: setext_headings_by_default(),
+        }
     }
 }
 
@@ -35,8 +47,12 @@ fn default_tab_size() -> usize {
     4
 }
 
+fn setext_headings_by_default() -> bool {
+    true
+}
+
 /// Manages all configuration for HTML generation
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct HtmlConfig {
     #[serde(default = "default_css")]
     pub css_file: String,
@@ -48,6 +64,126 @@ pub struct HtmlConfig {
     pub prism_theme: String,
     #[serde(default = "sanitize_by_default")]
     pub sanitize_html: bool,
+    #[serde(default)]
+    pub blockquote_cite: bool,
+    #[serde(default)]
+    pub escaped_space_nbsp: bool,
+    #[serde(default)]
+    pub autolink_emails: bool,
+    #[serde(default)]
+    pub mention_url: String,
+    #[serde(default)]
+    pub hashtag_url: String,
+    #[serde(default)]
+    pub smart_punctuation: bool,
+    #[serde(default)]
+    pub math: bool,
+    #[serde(default)]
+    pub preserve_code_tabs: bool,
+    #[serde(default)]
+    pub code_tab_size: usize,
+    #[serde(default)]
+    pub code_collapse_lines: usize,
+    #[serde(default = "default_content_id")]
+    pub content_id: String,
+    #[serde(default)]
+    pub content_class: String,
+    #[serde(default = "index_page_by_default")]
+    pub index_page: bool,
+    #[serde(default)]
+    pub home_page: String,
+    #[serde(default)]
+    pub not_found_page: bool,
+    #[serde(default)]
+    pub title_from_heading: bool,
+    #[serde(default)]
+    pub strip_title_heading: bool,
+    #[serde(default)]
+    pub index_exclude: Vec<String>,
+    #[serde(default)]
+    pub sidebar: bool,
+    #[serde(default)]
+    pub prev_next_links: bool,
+    #[serde(default)]
+    pub reading_time: bool,
+    #[serde(default = "default_words_per_minute")]
+    pub words_per_minute: usize,
+    #[serde(default)]
+    pub reading_time_include_code: bool,
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default)]
+    pub head_partial: String,
+    #[serde(default)]
+    pub nav_partial: String,
+    #[serde(default)]
+    pub nav: Vec<NavLink>,
+    #[serde(default)]
+    pub footer_partial: String,
+    #[serde(default = "default_toc_min_level")]
+    pub toc_min_level: u8,
+    #[serde(default)]
+    pub pretty_urls: bool,
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub feed: bool,
+    #[serde(default = "default_feed_entries")]
+    pub feed_entries: usize,
+    #[serde(default)]
+    pub minify: bool,
+    #[serde(default)]
+    pub indent: usize,
+    #[serde(default)]
+    pub highlighter: Highlighter,
+    #[serde(default = "default_syntect_theme")]
+    pub syntect_theme: String,
+    #[serde(default = "external_links_new_tab_by_default")]
+    pub external_links_new_tab: bool,
+    #[serde(default)]
+    pub inline_css: bool,
+    #[serde(default = "default_lang")]
+    pub lang: String,
+    #[serde(default)]
+    pub dir: String,
+    #[serde(default)]
+    pub sanitize: SanitizeConfig,
+}
+
+/// Extra tags and attributes for the sanitizer to allow, configured under `[html.sanitize]`. These
+/// are added to the ones the generator itself emits.
+///
+/// # Fields
+/// * `allowed_tags` - Tags to allow, i.e. `["figure", "figcaption"]`.
+/// * `allowed_attributes` - Attributes to allow on each tag, where the tag `"*"` allows them on
+///   every tag.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SanitizeConfig {
+    #[serde(default)]
+    pub allowed_tags: Vec<String>,
+    #[serde(default)]
+    pub allowed_attributes: BTreeMap<String, Vec<String>>,
+}
+
+/// A link in the navbar, configured with a `[[html.nav]]` entry.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct NavLink {
+    pub label: String,
+    /// Either an absolute URL, or a path relative to the root of the output directory.
+    pub url: String,
+}
+
+/// The highlighter used for code blocks.
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Highlighter {
+    /// PrismJS, loaded from a CDN when `use_prism` is enabled.
+    #[default]
+    Prism,
+    /// Build-time highlighting with syntect, which requires the `syntect` feature.
+    Syntect,
+    /// No highlighting at all.
+    None,
 }
 
 impl Default for HtmlConfig {
@@ -58,8 +194,84 @@ impl Default for HtmlConfig {
             use_prism: false,
             prism_theme: default_prism_theme(),
             sanitize_html: sanitize_by_default(),
+            blockquote_cite: false,
+            escaped_space_nbsp: false,
+            autolink_emails: false,
+            mention_url: String::new(),
+            hashtag_url: String::new(),
+            smart_punctuation: false,
+            math: false,
+            preserve_code_tabs: false,
+            code_tab_size: 0,
+            code_collapse_lines: 0,
+            content_id: default_content_id(),
+            content_class: String::new(),
+            index_page: index_page_by_default(),
+            home_page: String::new(),
+            not_found_page: false,
+            title_from_heading: false,
+            strip_title_heading: false,
+            index_exclude: Vec::new(),
+            sidebar: false,
+            prev_next_links: false,
+            reading_time: false,
+            words_per_minute: default_words_per_minute(),
+            reading_time_include_code: false,
+            date_format: default_date_format(),
+            head_partial: String::new(),
+            nav_partial: String::new(),
+            nav: Vec::new(),
+            footer_partial: String::new(),
+            toc_min_level: default_toc_min_level(),
+            pretty_urls: false,
+            base_url: String::new(),
+            feed: false,
+            feed_entries: default_feed_entries(),
+            minify: false,
+            indent: 0,
+            highlighter: Highlighter::default(),
+            syntect_theme: default_syntect_theme(),
+            external_links_new_tab: external_links_new_tab_by_default(),
+            inline_css: false,
+            lang: default_lang(),
+            dir: String::new(),
+            sanitize: SanitizeConfig::default(),
+        }
+    }
+}
+
+impl HtmlConfig {
+    /// Returns whether PrismJS should be used, which requires both `use_prism` and the `prism`
+    /// feature.
+    pub fn prism_enabled(&self) -> bool {
+        cfg!(feature = "prism") && self.use_prism && self.highlighter == Highlighter::Prism
+    }
+
+    /// Returns whether code blocks should be highlighted with syntect, which requires both
+    /// `highlighter = "syntect"` and the `syntect` feature.
+    pub fn syntect_enabled(&self) -> bool {
+        cfg!(feature = "syntect") && self.highlighter == Highlighter::Syntect
+    }
+
+    /// Returns the string each level of the generated HTML is indented with, which is a tab when
+    /// `indent` is 0 and that many spaces otherwise.
+    pub fn indent_unit(&self) -> String {
+        match self.indent {
+            0 => String::from("\t"),
+            spaces => " ".repeat(spaces),
         }
     }
+
+    /// Returns whether the generated HTML should be sanitized, which requires both
+    /// `sanitize_html` and the `sanitize` feature.
+    pub fn sanitize_enabled(&self) -> bool {
+        cfg!(feature = "sanitize") && self.sanitize_html
+    }
+}
+
+/// Sets the default syntect theme to "base16-ocean.dark" in `config.toml`
+fn default_syntect_theme() -> String {
+    "base16-ocean.dark".to_string()
 }
 
 /// Sets the default PrismJS theme to "vsc-dark-plus" in `config.toml`
@@ -72,12 +284,74 @@ fn sanitize_by_default() -> bool {
     true
 }
 
+/// Sets `index_page` to true by default in `config.toml`
+fn index_page_by_default() -> bool {
+    true
+}
+
+/// Sets `external_links_new_tab` to true by default in `config.toml`
+fn external_links_new_tab_by_default() -> bool {
+    true
+}
+
+/// Sets the language of each page's `<html>` element to "en" by default
+fn default_lang() -> String {
+    "en".to_string()
+}
+
+/// Sets the id of the element wrapping each page's content to "content" by default
+fn default_content_id() -> String {
+    "content".to_string()
+}
+
+/// Lists the 20 most recent pages in the feed by default
+fn default_feed_entries() -> usize {
+    20
+}
+
+/// Estimates reading time at 200 words per minute by default
+fn default_words_per_minute() -> usize {
+    200
+}
+
+/// Shows dates as ISO 8601 dates (i.e. "2025-01-31") by default
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+/// Includes every heading level in the table of contents by default
+fn default_toc_min_level() -> u8 {
+    1
+}
+
 /// Sets the default CSS file to "default" in the case that the `css_file` field is omitted
 fn default_css() -> String {
     "default".to_string()
 }
 
 impl Config {
+    /// Creates a new `Config` instance from a TOML string, filling any missing fields with their
+    /// defaults.
+    ///
+    /// # Arguments
+    /// * `contents` - The TOML configuration, in the same format as `config.toml`.
+    ///
+    /// # Returns
+    /// Returns a `Result` containing the `Config` instance if the TOML is valid
+    pub fn from_toml_str(contents: &str) -> Result<Self, Error> {
+        let config: Config = toml_edit::de::from_str(contents)?;
+
+        Ok(config)
+    }
+
+    /// Serializes the config to TOML, in the same format as `config.toml` but without comments.
+    ///
+    /// # Returns
+    /// Returns a `Result` containing the TOML string, with every field filled in.
+    pub fn to_toml_string(&self) -> Result<String, Error> {
+        Ok(toml_edit::ser::to_string_pretty(self)?)
+    }
+
     /// Creates a new `Config` instance from the specified file path
     ///
     /// # Arguments
@@ -92,7 +366,7 @@ impl Config {
             info!("Loading config from file: {}", file_path);
             let contents = std::fs::read_to_string(file_path)?;
 
-            let config: Config = toml_edit::de::from_str(&contents)?;
+            let config = Config::from_toml_str(&contents)?;
 
             validate_config(file_path, &contents, &config)?;
 
@@ -106,8 +380,7 @@ impl Config {
         if does_config_exist()? {
             let contents = std::fs::read_to_string(&config_path)?;
 
-            let config: Config =
-                toml_edit::de::from_str(&contents).map_err(Error::TomlDeserialization)?;
+            let config = Config::from_toml_str(&contents)?;
 
             validate_config(&config_path.to_string_lossy(), &contents, &config)?;
 
@@ -200,24 +473,91 @@ fn validate_config(file_path: &str, contents: &str, config: &Config) -> Result<(
     Ok(())
 }
 
-/// Initializes the global configuration from the specified file path
+/// Initializes the global configuration from an inline TOML string if one is given, otherwise
+/// from the specified file path
 ///
 /// # Arguments
 /// * `config_path` - The path to the configuration file.
+/// * `inline_config` - A TOML string to use as the config, bypassing the config file entirely.
+/// * `overrides` - Applied to the loaded config before it's stored, i.e. for CLI flags that take
+///   precedence over the config file.
 ///
 /// # Returns
 /// Returns a `Result` indicating success or failure. If successful, a global `CONFIG` has been
 /// initialized.
-pub fn init_config(config_path: &str) -> Result<(), Error> {
+pub fn init_config<F>(
+    config_path: &str,
+    inline_config: Option<&str>,
+    overrides: F,
+) -> Result<(), Error>
+where
+    F: FnOnce(&mut Config),
+{
     CONFIG.get_or_init(|| {
-        Config::from_file(config_path).unwrap_or_else(|err| {
-            error!("Failed to load config: {err}");
-            std::process::exit(1);
-        })
+        let mut config = match inline_config {
+            Some(contents) => Config::from_toml_str(contents).unwrap_or_else(|err| {
+                error!("Failed to parse inline config from --config-inline: {err}");
+                std::process::exit(1);
+            }),
+            None => Config::from_file(config_path).unwrap_or_else(|err| {
+                error!("Failed to load config: {err}");
+                std::process::exit(1);
+            }),
+        };
+        overrides(&mut config);
+        config
     });
     Ok(())
 }
 
+thread_local! {
+    /// The configuration given to `markdown_to_html` while it renders a document on this thread,
+    /// which takes precedence over the global `CONFIG`.
+    static SCOPED_CONFIG: RefCell<Option<Rc<Config>>> = const { RefCell::new(None) };
+}
+
+/// The configuration in effect on the current thread, as returned by [`current_config`].
+pub enum ActiveConfig {
+    Global(&'static Config),
+    Scoped(Rc<Config>),
+}
+
+impl Deref for ActiveConfig {
+    type Target = Config;
+
+    fn deref(&self) -> &Config {
+        match self {
+            ActiveConfig::Global(config) => config,
+            ActiveConfig::Scoped(config) => config,
+        }
+    }
+}
+
+/// Returns the configuration in effect on the current thread: the one given to
+/// `markdown_to_html` while a document is being rendered through the library, otherwise the
+/// global `CONFIG`, which falls back to the default configuration if it was never initialized.
+pub fn current_config() -> ActiveConfig {
+    match SCOPED_CONFIG.with_borrow(|scoped| scoped.clone()) {
+        Some(config) => ActiveConfig::Scoped(config),
+        None => ActiveConfig::Global(CONFIG.get_or_init(Config::default)),
+    }
+}
+
+/// Runs `f` with `config` in effect on the current thread in place of the global `CONFIG`. The
+/// previous configuration is restored afterwards, even if `f` panics.
+pub(crate) fn with_config<R>(config: &Config, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Rc<Config>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED_CONFIG.set(self.0.take());
+        }
+    }
+
+    let _restore = Restore(SCOPED_CONFIG.replace(Some(Rc::new(config.clone()))));
+    f()
+}
+
 #[derive(Debug)]
 pub enum Error {
     Io(std::io::Error),
@@ -272,3 +612,6 @@ impl std::error::Error for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod test;
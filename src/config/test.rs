@@ -0,0 +1,49 @@
+use crate::config::{Config, Error};
+
+#[test]
+fn from_toml_str_fills_missing_fields() {
+    let config = Config::from_toml_str("[html]\nuse_prism = false\n").unwrap();
+
+    assert!(!config.html.use_prism);
+    assert_eq!(config.html.css_file, "default");
+    assert_eq!(config.lexer.tab_size, 4);
+}
+
+#[test]
+fn from_toml_str_rejects_malformed_toml() {
+    let result = Config::from_toml_str("[html\nuse_prism = ");
+
+    assert!(matches!(result, Err(Error::TomlDeserialization(_))));
+}
+
+#[test]
+fn external_links_open_in_new_tab_unless_disabled() {
+    let config = Config::from_toml_str("[html]\n").unwrap();
+    assert!(config.html.external_links_new_tab);
+
+    let config = Config::from_toml_str("[html]\nexternal_links_new_tab = false\n").unwrap();
+    assert!(!config.html.external_links_new_tab);
+}
+
+#[test]
+fn index_page_is_generated_unless_disabled() {
+    let config = Config::from_toml_str("[html]\n").unwrap();
+    assert!(config.html.index_page);
+    assert!(config.html.home_page.is_empty());
+
+    let config = Config::from_toml_str("[html]\nindex_page = false\n").unwrap();
+    assert!(!config.html.index_page);
+}
+
+#[test]
+fn to_toml_string_round_trips() {
+    let mut config = Config::from_toml_str("[html]\nmath = true\n").unwrap();
+    config.html.sanitize.allowed_tags = vec![String::from("figure")];
+
+    let toml = config.to_toml_string().unwrap();
+    assert!(toml.contains("tab_size = 4"), "{toml}");
+
+    let parsed = Config::from_toml_str(&toml).unwrap();
+    assert!(parsed.html.math);
+    assert_eq!(parsed.html.sanitize, config.html.sanitize);
+}
@@ -0,0 +1,299 @@
+//! This module parses the `date` from a page's front matter and formats it for display with the
+//! configured `date_format`.
+//!
+//! Formats use the strftime-style specifiers that `chrono` does, with English month and day names
+//! regardless of the system locale:
+//!
+//! | Specifier | Meaning | Example |
+//! |-----------|---------|---------|
+//! | `%Y` | The year | `2025` |
+//! | `%y` | The last two digits of the year | `25` |
+//! | `%m` | The month, zero-padded | `01` |
+//! | `%B` / `%b` | The month's full or abbreviated name | `January` / `Jan` |
+//! | `%d` | The day of the month, zero-padded | `05` |
+//! | `%e` | The day of the month, space-padded | ` 5` |
+//! | `%A` / `%a` | The weekday's full or abbreviated name | `Sunday` / `Sun` |
+//! | `%H`, `%M`, `%S` | The hour, minute and second, which are `00` for dates without a time | `09` |
+//! | `%F` | The ISO 8601 date, the same as `%Y-%m-%d` | `2025-01-05` |
+//! | `%%` | A literal `%` | `%` |
+//!
+//! Numeric specifiers can be written as `%-d` to leave out their padding. Any other specifier is
+//! written out as-is.
+
+use crate::diagnostics::report_warning;
+use crate::frontmatter::Frontmatter;
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// A calendar date from front matter, with the time of day if one was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageDate {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    /// The hour, minute and second.
+    pub time: Option<(u32, u32, u32)>,
+    /// The time's offset from UTC in minutes, if it was given one.
+    pub offset: Option<i32>,
+}
+
+impl PageDate {
+    /// Parses a `YYYY-MM-DD` date, optionally followed by a time as `THH:MM` or `THH:MM:SS` (or
+    /// with a space in place of the `T`). The time can be followed by fractional seconds, which
+    /// are ignored, and a UTC offset as `Z` or `+HH:MM`/`-HH:MM`.
+    ///
+    /// # Returns
+    /// The date, or `None` if it isn't in that format or isn't a real date (i.e. `2025-02-30`).
+    ///
+    /// # Example
+    /// ```
+    /// use mark_rs::dates::PageDate;
+    ///
+    /// let date = PageDate::parse("2024-02-29T09:30").unwrap();
+    /// assert_eq!(date.format("%B %-d, %Y at %H:%M"), "February 29, 2024 at 09:30");
+    /// assert_eq!(PageDate::parse("2025-02-29"), None);
+    /// ```
+    pub fn parse(date: &str) -> Option<Self> {
+        let date = date.trim();
+        let year = parse_digits(date.get(..4)?)?;
+        let month = parse_digits(date.get(5..7)?)?;
+        let day = parse_digits(date.get(8..10)?)?;
+        if &date[4..5] != "-" || &date[7..8] != "-" {
+            return None;
+        }
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        let (time, offset) = match &date[10..] {
+            "" => (None, None),
+            time if time.starts_with(['T', ' ']) => {
+                let (time, offset) = parse_time(&time[1..])?;
+                (Some(time), offset)
+            }
+            _ => return None,
+        };
+
+        Some(PageDate {
+            year,
+            month,
+            day,
+            time,
+            offset,
+        })
+    }
+
+    /// Formats the date with the given strftime-style pattern, as described in the module
+    /// documentation.
+    pub fn format(&self, pattern: &str) -> String {
+        let (hour, minute, second) = self.time.unwrap_or_default();
+        let month_name = MONTHS[self.month as usize - 1];
+        let weekday_name = WEEKDAYS[self.weekday()];
+
+        let mut formatted = String::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if ch != '%' {
+                formatted.push(ch);
+                continue;
+            }
+
+            let unpadded = chars.next_if_eq(&'-').is_some();
+            let number = |value: u32, padding: &str| match unpadded {
+                true => value.to_string(),
+                false if padding == " " => format!("{value:>2}"),
+                false => format!("{value:0>2}"),
+            };
+            match chars.next() {
+                Some('Y') => formatted.push_str(&self.year.to_string()),
+                Some('y') => formatted.push_str(&number(self.year % 100, "0")),
+                Some('m') => formatted.push_str(&number(self.month, "0")),
+                Some('B') => formatted.push_str(month_name),
+                Some('b') => formatted.push_str(&month_name[..3]),
+                Some('d') => formatted.push_str(&number(self.day, "0")),
+                Some('e') => formatted.push_str(&number(self.day, " ")),
+                Some('A') => formatted.push_str(weekday_name),
+                Some('a') => formatted.push_str(&weekday_name[..3]),
+                Some('H') => formatted.push_str(&number(hour, "0")),
+                Some('M') => formatted.push_str(&number(minute, "0")),
+                Some('S') => formatted.push_str(&number(second, "0")),
+                Some('F') => formatted.push_str(&self.iso_date()),
+                Some('%') => formatted.push('%'),
+                Some(other) => {
+                    formatted.push('%');
+                    if unpadded {
+                        formatted.push('-');
+                    }
+                    formatted.push(other);
+                }
+                None => formatted.push('%'),
+            }
+        }
+
+        formatted
+    }
+
+    /// Returns the date as a `YYYY-MM-DD` string, i.e. for the `datetime` attribute of a `<time>`
+    /// element.
+    pub fn iso_date(&self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Returns the date as an RFC 3339 timestamp, i.e. for an Atom feed. A date without a time is
+    /// taken as midnight, and a time without an offset as UTC.
+    ///
+    /// # Example
+    /// ```
+    /// use mark_rs::dates::PageDate;
+    ///
+    /// let date = PageDate::parse("2024-01-02 09:30").unwrap();
+    /// assert_eq!(date.rfc3339(), "2024-01-02T09:30:00Z");
+    /// ```
+    pub fn rfc3339(&self) -> String {
+        let (hour, minute, second) = self.time.unwrap_or_default();
+        let offset = match self.offset {
+            None | Some(0) => String::from("Z"),
+            Some(offset) => format!(
+                "{}{:02}:{:02}",
+                if offset < 0 { '-' } else { '+' },
+                offset.abs() / 60,
+                offset.abs() % 60
+            ),
+        };
+
+        format!(
+            "{}T{hour:02}:{minute:02}:{second:02}{offset}",
+            self.iso_date()
+        )
+    }
+
+    /// Returns the day of the week, counting from 0 for Monday.
+    fn weekday(&self) -> usize {
+        // Sakamoto's method, which counts from 0 for Sunday
+        const MONTH_OFFSETS: [u32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+        let year = if self.month < 3 {
+            self.year.saturating_sub(1)
+        } else {
+            self.year
+        };
+        let sunday_based = (year + year / 4 - year / 100
+            + year / 400
+            + MONTH_OFFSETS[self.month as usize - 1]
+            + self.day)
+            % 7;
+
+        (sunday_based as usize + 6) % 7
+    }
+}
+
+/// Reads the `date` from a page's front matter, warning if it's set but isn't a valid date.
+///
+/// # Returns
+/// The page's date, or `None` if it has no valid one.
+pub fn frontmatter_date(frontmatter: &Frontmatter) -> Option<PageDate> {
+    let date = frontmatter.get("date").filter(|date| !date.is_empty())?;
+    let parsed = PageDate::parse(date);
+    if parsed.is_none() {
+        report_warning(&format!(
+            "Invalid date in front matter, it will be left out: {date}"
+        ));
+    }
+
+    parsed
+}
+
+/// Parses a time of day as `HH:MM` or `HH:MM:SS`, followed by optional fractional seconds and an
+/// optional UTC offset.
+///
+/// # Returns
+/// The hour, minute and second, and the offset in minutes if there is one.
+fn parse_time(time: &str) -> Option<((u32, u32, u32), Option<i32>)> {
+    let hour = parse_digits(time.get(..2)?)?;
+    let minute = parse_digits(time.get(3..5)?)?;
+    if &time[2..3] != ":" {
+        return None;
+    }
+    let (second, rest) = match time.get(5..6) {
+        Some(":") => (parse_digits(time.get(6..8)?)?, &time[8..]),
+        _ => (0, &time[5..]),
+    };
+    let rest = match rest.strip_prefix('.') {
+        Some(fraction) => fraction.trim_start_matches(|ch: char| ch.is_ascii_digit()),
+        None => rest,
+    };
+
+    if hour >= 24 || minute >= 60 || second >= 60 {
+        return None;
+    }
+
+    Some(((hour, minute, second), parse_offset(rest)?))
+}
+
+/// Parses a UTC offset as `Z` or `+HH:MM`/`-HH:MM`.
+///
+/// # Returns
+/// `Some(None)` if there is no offset, the offset in minutes if there is a valid one, or `None` if
+/// it's invalid.
+fn parse_offset(offset: &str) -> Option<Option<i32>> {
+    let sign = match offset.get(..1) {
+        None => return Some(None),
+        Some("Z" | "z") if offset.len() == 1 => return Some(Some(0)),
+        Some("+") => 1,
+        Some("-") => -1,
+        _ => return None,
+    };
+    let hours = parse_digits(offset.get(1..3)?)?;
+    let minutes = parse_digits(offset.get(4..)?)?;
+    if &offset[3..4] != ":" || offset.len() != 6 || hours >= 24 || minutes >= 60 {
+        return None;
+    }
+
+    Some(Some(sign * (hours * 60 + minutes) as i32))
+}
+
+/// Parses a string made up only of ASCII digits.
+fn parse_digits(digits: &str) -> Option<u32> {
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    digits.parse().ok()
+}
+
+/// Returns the number of days in the given month of the given year.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,94 @@
+use crate::dates::{PageDate, frontmatter_date};
+use crate::diagnostics::Diagnostics;
+use crate::frontmatter::extract_frontmatter;
+
+fn date(date: &str) -> PageDate {
+    PageDate::parse(date).unwrap()
+}
+
+#[test]
+fn dates_and_times_are_parsed() {
+    assert_eq!(
+        date("2025-01-31"),
+        PageDate {
+            year: 2025,
+            month: 1,
+            day: 31,
+            time: None,
+            offset: None,
+        }
+    );
+    assert_eq!(date("2025-01-31T08:05").time, Some((8, 5, 0)));
+    assert_eq!(date("2025-01-31 08:05:09+02:00").time, Some((8, 5, 9)));
+    assert_eq!(date("2025-01-31 08:05:09+02:00").offset, Some(120));
+    assert_eq!(date("2025-01-31T08:05:09.250-05:30").offset, Some(-330));
+    assert_eq!(date("2025-01-31T08:05Z").offset, Some(0));
+}
+
+#[test]
+fn dates_are_formatted_as_rfc3339() {
+    assert_eq!(date("2025-01-31").rfc3339(), "2025-01-31T00:00:00Z");
+    assert_eq!(date("2024-01-02 09:30").rfc3339(), "2024-01-02T09:30:00Z");
+    assert_eq!(
+        date("2025-01-31T08:00:00.5+02:00").rfc3339(),
+        "2025-01-31T08:00:00+02:00"
+    );
+    assert_eq!(
+        date("2025-01-31T08:00:00-00:30").rfc3339(),
+        "2025-01-31T08:00:00-00:30"
+    );
+}
+
+#[test]
+fn invalid_dates_are_rejected() {
+    for invalid in [
+        "",
+        "2025",
+        "2025/01/31",
+        "2025-13-01",
+        "2025-00-10",
+        "2025-04-31",
+        "2023-02-29",
+        "1900-02-29",
+        "2025-01-31T25:00",
+        "2025-01-31T12",
+        "2025-01-31 and more",
+        "2025-01-31T08:00 and more",
+        "2025-01-31T08:00+2",
+        "2025-01-31T08:00+24:00",
+        "２０２５-01-31",
+    ] {
+        assert_eq!(PageDate::parse(invalid), None, "{invalid}");
+    }
+    assert!(PageDate::parse("2000-02-29").is_some());
+}
+
+#[test]
+fn dates_are_formatted_with_patterns() {
+    let date = date("2025-01-05T09:03:07");
+    assert_eq!(date.format("%Y-%m-%d"), "2025-01-05");
+    assert_eq!(date.format("%F"), "2025-01-05");
+    assert_eq!(date.format("%B %-d, %Y"), "January 5, 2025");
+    assert_eq!(date.format("%a %e %b %y"), "Sun  5 Jan 25");
+    assert_eq!(date.format("%A %H:%M:%S"), "Sunday 09:03:07");
+    assert_eq!(date.format("100%% %q %-q %"), "100% %q %-q %");
+}
+
+#[test]
+fn weekdays_are_correct_across_months_and_leap_years() {
+    assert_eq!(date("2024-02-29").format("%A"), "Thursday");
+    assert_eq!(date("2000-01-01").format("%A"), "Saturday");
+    assert_eq!(date("2025-12-31").format("%A"), "Wednesday");
+    assert_eq!(date("2025-03-03").format("%A"), "Monday");
+}
+
+#[test]
+fn invalid_frontmatter_dates_warn() {
+    let mut diagnostics = Diagnostics::new("page.md");
+    let (frontmatter, _) = extract_frontmatter("---\ndate: 2025-02-30\n---\n");
+    assert_eq!(diagnostics.collect(|| frontmatter_date(&frontmatter)), None);
+    assert_eq!(diagnostics.warnings().len(), 1);
+
+    let (frontmatter, _) = extract_frontmatter("---\ntitle: Undated\n---\n");
+    assert_eq!(frontmatter_date(&frontmatter), None);
+}
@@ -0,0 +1,134 @@
+//! This module provides a collector for the warnings reported while parsing a document, so that
+//! they can be reported with their location or treated as errors.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::mem::take;
+
+use log::warn;
+
+thread_local! {
+    /// The collector of the document being parsed on this thread, if warnings are being
+    /// collected.
+    static ACTIVE_DIAGNOSTICS: RefCell<Option<Diagnostics>> = const { RefCell::new(None) };
+
+    /// The line of the document that the block being parsed on this thread starts on, counting
+    /// from 0, if known.
+    static CURRENT_LINE: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// A warning reported while parsing a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The file the warning was reported in.
+    pub file: String,
+    /// The line the block the warning was reported in starts on, if known. Warnings from inside
+    /// a list or blockquote point at its first line.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// Collects the warnings reported while parsing a single document.
+///
+/// Warnings are always logged, whether or not a collector is active, so collecting them doesn't
+/// change how a document is parsed.
+///
+/// # Example
+/// ```
+/// use mark_rs::diagnostics::Diagnostics;
+/// use mark_rs::parse_markdown;
+///
+/// let mut diagnostics = Diagnostics::new("table.md");
+/// diagnostics.collect(|| parse_markdown("| a |\n| left |\n| b |"));
+/// assert_eq!(diagnostics.warnings().len(), 1);
+/// assert_eq!(diagnostics.warnings()[0].line, Some(1));
+/// ```
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    file: String,
+    first_line: usize,
+    warnings: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Creates an empty collector for the given file, whose first line is line 1.
+    pub fn new(file: &str) -> Self {
+        Diagnostics {
+            file: file.to_string(),
+            first_line: 1,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Sets the line of the file that the parsed markdown starts on, i.e. the line after any
+    /// front matter.
+    pub fn starting_at_line(mut self, first_line: usize) -> Self {
+        self.first_line = first_line;
+        self
+    }
+
+    /// Runs `f`, collecting any warnings reported on this thread while it runs.
+    pub fn collect<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let previous = ACTIVE_DIAGNOSTICS.with(|active| active.replace(Some(take(self))));
+        let previous_line = CURRENT_LINE.replace(None);
+
+        let result = f();
+
+        CURRENT_LINE.set(previous_line);
+        if let Some(collected) = ACTIVE_DIAGNOSTICS.with(|active| active.replace(previous)) {
+            *self = collected;
+        }
+        result
+    }
+
+    /// Returns the warnings collected so far.
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    /// Consumes the collector, returning the warnings it collected.
+    pub fn into_warnings(self) -> Vec<Diagnostic> {
+        self.warnings
+    }
+
+    /// Returns whether no warnings have been collected.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Sets the line of the document that the block being parsed starts on, counting from 0.
+pub(crate) fn set_current_line(line: usize) {
+    CURRENT_LINE.set(Some(line));
+}
+
+/// Logs a warning, and records it in the active collector if there is one.
+///
+/// # Arguments
+/// * `message` - The warning to report.
+pub(crate) fn report_warning(message: &str) {
+    warn!("{message}");
+
+    ACTIVE_DIAGNOSTICS.with(|active| {
+        if let Some(diagnostics) = active.borrow_mut().as_mut() {
+            let line = CURRENT_LINE.get().map(|line| diagnostics.first_line + line);
+            diagnostics.warnings.push(Diagnostic {
+                file: diagnostics.file.clone(),
+                line,
+                message: message.to_string(),
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod test;
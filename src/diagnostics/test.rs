@@ -0,0 +1,84 @@
+use crate::config::{Config, with_config};
+use crate::diagnostics::{Diagnostic, Diagnostics, report_warning};
+use crate::frontmatter::extract_frontmatter;
+use crate::parse_markdown;
+
+#[test]
+fn warnings_are_located_by_block() {
+    let mut diagnostics = Diagnostics::new("guide.md");
+    with_config(&Config::default(), || {
+        diagnostics.collect(|| {
+            parse_markdown("# Title\n\nSome text\nacross lines\n\n| a |\n| left |\n| b |")
+        })
+    });
+
+    assert_eq!(
+        diagnostics.warnings(),
+        [Diagnostic {
+            file: String::from("guide.md"),
+            line: Some(6),
+            message: String::from(
+                "Table alignment should not contain text as it could result in unexpected behavior: left"
+            ),
+        }]
+    );
+    assert_eq!(
+        diagnostics.warnings()[0].to_string(),
+        "guide.md:6: Table alignment should not contain text as it could result in unexpected behavior: left"
+    );
+}
+
+#[test]
+fn lines_are_offset_by_the_first_line() {
+    let mut diagnostics = Diagnostics::new("guide.md").starting_at_line(4);
+    with_config(&Config::default(), || {
+        diagnostics.collect(|| parse_markdown("```rust {bad!}\nfn main() {}\n```"))
+    });
+
+    assert_eq!(diagnostics.warnings().len(), 1);
+    assert_eq!(diagnostics.warnings()[0].line, Some(4));
+}
+
+#[test]
+fn frontmatter_warnings_have_no_line() {
+    let mut diagnostics = Diagnostics::new("guide.md");
+    diagnostics.collect(|| extract_frontmatter("+++\ntitle = Guide\n+++\n# Guide"));
+
+    assert_eq!(diagnostics.warnings().len(), 1);
+    assert_eq!(diagnostics.warnings()[0].line, None);
+    assert!(
+        diagnostics.warnings()[0]
+            .to_string()
+            .starts_with("guide.md: ")
+    );
+}
+
+#[test]
+fn warnings_outside_of_collect_are_not_recorded() {
+    let mut diagnostics = Diagnostics::new("guide.md");
+    report_warning("Before collecting");
+    diagnostics.collect(|| report_warning("While collecting"));
+    report_warning("After collecting");
+
+    let messages: Vec<&str> = diagnostics
+        .warnings()
+        .iter()
+        .map(|warning| warning.message.as_str())
+        .collect();
+    assert_eq!(messages, ["While collecting"]);
+}
+
+#[test]
+fn nested_collectors_keep_their_own_warnings() {
+    let mut outer = Diagnostics::new("outer.md");
+    let mut inner = Diagnostics::new("inner.md");
+    outer.collect(|| {
+        report_warning("Outer");
+        inner.collect(|| report_warning("Inner"));
+    });
+
+    assert_eq!(outer.warnings().len(), 1);
+    assert_eq!(outer.warnings()[0].file, "outer.md");
+    assert_eq!(inner.warnings().len(), 1);
+    assert_eq!(inner.warnings()[0].file, "inner.md");
+}
@@ -9,6 +9,17 @@ pub enum Error {
     Io(io::Error),
     Config(config::Error),
     ThreadPool(thread_pool::Error),
+    /// Some of the pages in a build couldn't be generated. The cause of each failure has already
+    /// been logged.
+    PageGeneration {
+        failed: usize,
+        total: usize,
+    },
+    /// Warnings were reported while parsing the pages of a `--strict` build. Each warning has
+    /// already been logged.
+    Warnings {
+        count: usize,
+    },
 }
 
 impl fmt::Display for Error {
@@ -17,6 +28,12 @@ impl fmt::Display for Error {
             Error::Io(e) => write!(f, "I/O Error: {e}"),
             Error::Config(e) => write!(f, "Configuration error: {e}"),
             Error::ThreadPool(e) => write!(f, "Thread pool error: {e}"),
+            Error::PageGeneration { failed, total } => {
+                write!(f, "{failed} of {total} pages failed to generate")
+            }
+            Error::Warnings { count } => {
+                write!(f, "{count} warnings were reported in strict mode")
+            }
         }
     }
 }
@@ -45,6 +62,7 @@ impl std::error::Error for Error {
             Error::Io(e) => Some(e),
             Error::Config(e) => Some(e),
             Error::ThreadPool(e) => Some(e),
+            Error::PageGeneration { .. } | Error::Warnings { .. } => None,
         }
     }
 }
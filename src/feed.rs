@@ -0,0 +1,147 @@
+//! This module generates an Atom feed of the most recently dated pages, enabled by the `feed`
+//! config option.
+
+use log::warn;
+
+use crate::config::current_config;
+use crate::dates::PageDate;
+use crate::frontmatter::extract_frontmatter;
+use crate::html_generator::{escape_title, format_title};
+use crate::parse_markdown;
+use crate::types::{MdBlockElement, MdInlineElement};
+use crate::utils::page_link_path;
+
+/// The metadata of a page that's needed to list it in the feed.
+#[derive(Debug, Default, PartialEq)]
+pub struct PageMeta {
+    pub title: String,
+    /// The path to link to, relative to the site root.
+    pub link: String,
+    /// The `date` from the page's front matter, if it has one.
+    pub date: Option<String>,
+    /// The plain text of the page's first paragraph.
+    pub summary: Option<String>,
+}
+
+/// Collects the feed metadata of a page from its markdown.
+///
+/// # Arguments
+/// * `file_name` - The path of the markdown file, relative to the input directory.
+/// * `contents` - The contents of the markdown file, including any front matter.
+///
+/// # Returns
+/// The page's title, link, date and summary.
+pub fn page_meta(file_name: &str, contents: &str) -> PageMeta {
+    let (frontmatter, markdown) = extract_frontmatter(contents);
+
+    let parsed_elements = parse_markdown(markdown);
+    let summary = parsed_elements.iter().find_map(|element| match element {
+        MdBlockElement::Paragraph { content } => Some(
+            content
+                .iter()
+                .map(MdInlineElement::to_plain_text)
+                .collect::<String>(),
+        ),
+        _ => None,
+    });
+
+    PageMeta {
+        title: match frontmatter.title() {
+            Some(title) => title.to_string(),
+            None => format_title(file_name),
+        },
+        link: page_link_path(file_name, current_config().html.pretty_urls),
+        date: frontmatter.get("date").map(str::to_string),
+        summary,
+    }
+}
+
+/// Generates an Atom feed listing the most recent pages by date, up to the configured
+/// `feed_entries`.
+///
+/// Pages without a date are left out, as are pages whose date isn't valid (see
+/// `PageDate::parse`).
+///
+/// # Arguments
+/// * `pages` - The metadata of every page in the site.
+/// * `base_url` - The absolute URL the site is served from, i.e. `https://example.com/docs`.
+///
+/// # Returns
+/// Returns a `String` containing the generated feed.
+pub fn generate_feed(pages: &[PageMeta], base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+
+    let mut entries: Vec<(String, &PageMeta)> = pages
+        .iter()
+        .filter_map(|page| {
+            let date = page.date.as_deref()?;
+            match feed_timestamp(date) {
+                Some(timestamp) => Some((timestamp, page)),
+                None => {
+                    warn!(
+                        "Leaving {} out of the feed, invalid date: {date}",
+                        page.link
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+    // Newest first, with pages from the same date kept in a stable order
+    entries.sort_by(|(a_time, a_page), (b_time, b_page)| {
+        b_time
+            .cmp(a_time)
+            .then_with(|| a_page.link.cmp(&b_page.link))
+    });
+    entries.truncate(current_config().html.feed_entries);
+
+    let site_url = escape_title(&format!("{base_url}/"));
+    let updated = entries
+        .first()
+        .map_or("1970-01-01T00:00:00Z", |(timestamp, _)| timestamp);
+
+    let mut feed = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+         \t<title>{site_url}</title>\n\
+         \t<link href=\"{site_url}\"/>\n\
+         \t<link rel=\"self\" href=\"{site_url}atom.xml\"/>\n\
+         \t<id>{site_url}</id>\n\
+         \t<updated>{updated}</updated>\n\
+         \t<author>\n\t\t<name>{site_url}</name>\n\t</author>\n"
+    );
+
+    for (timestamp, page) in entries {
+        let page_url = escape_title(&format!("{base_url}/{}", page.link));
+        feed.push_str(&format!(
+            "\t<entry>\n\
+             \t\t<title>{}</title>\n\
+             \t\t<link href=\"{page_url}\"/>\n\
+             \t\t<id>{page_url}</id>\n\
+             \t\t<updated>{timestamp}</updated>\n",
+            escape_title(&page.title)
+        ));
+        if let Some(summary) = page.summary.as_deref().filter(|s| !s.is_empty()) {
+            feed.push_str(&format!(
+                "\t\t<summary>{}</summary>\n",
+                escape_title(summary)
+            ));
+        }
+        feed.push_str("\t</entry>\n");
+    }
+
+    feed.push_str("</feed>\n");
+    feed
+}
+
+/// Converts a front matter date to the RFC 3339 timestamp Atom expects, as parsed by
+/// `PageDate::parse`.
+///
+/// # Returns
+/// The timestamp, or `None` if the date isn't valid.
+fn feed_timestamp(date: &str) -> Option<String> {
+    PageDate::parse(date).map(|date| date.rfc3339())
+}
+
+#[cfg(test)]
+mod test;
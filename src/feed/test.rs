@@ -0,0 +1,116 @@
+use crate::CONFIG;
+use crate::config::Config;
+use crate::feed::{PageMeta, feed_timestamp, generate_feed, page_meta};
+
+use std::sync::Once;
+static INIT: Once = Once::new();
+
+fn init_test_config() {
+    INIT.call_once(|| {
+        CONFIG.get_or_init(Config::default);
+    });
+}
+
+fn page(link: &str, date: Option<&str>) -> PageMeta {
+    PageMeta {
+        title: link.to_string(),
+        link: link.to_string(),
+        date: date.map(str::to_string),
+        summary: None,
+    }
+}
+
+#[test]
+fn page_meta_reads_front_matter_and_first_paragraph() {
+    init_test_config();
+    let contents = "---\ntitle: Release Notes\ndate: 2025-03-01\n---\n\n# Heading\n\nFirst *paragraph*.\n\nSecond paragraph.";
+
+    assert_eq!(
+        page_meta("news/release.md", contents),
+        PageMeta {
+            title: "Release Notes".to_string(),
+            link: "news/release.html".to_string(),
+            date: Some("2025-03-01".to_string()),
+            summary: Some("First paragraph.".to_string()),
+        }
+    );
+}
+
+#[test]
+fn page_meta_without_front_matter_has_no_date() {
+    init_test_config();
+    let meta = page_meta("getting_started.md", "Some text");
+
+    assert_eq!(meta.title, "Getting Started");
+    assert_eq!(meta.date, None);
+}
+
+#[test]
+fn feed_lists_dated_pages_newest_first() {
+    init_test_config();
+    let pages = [
+        page("old.html", Some("2024-01-01")),
+        page("undated.html", None),
+        page("new.html", Some("2025-06-01T12:30:00Z")),
+        page("bad.html", Some("June 1st")),
+    ];
+
+    let feed = generate_feed(&pages, "https://example.com/");
+
+    let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                    <feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+                    \t<title>https://example.com/</title>\n\
+                    \t<link href=\"https://example.com/\"/>\n\
+                    \t<link rel=\"self\" href=\"https://example.com/atom.xml\"/>\n\
+                    \t<id>https://example.com/</id>\n\
+                    \t<updated>2025-06-01T12:30:00Z</updated>\n\
+                    \t<author>\n\t\t<name>https://example.com/</name>\n\t</author>\n\
+                    \t<entry>\n\
+                    \t\t<title>new.html</title>\n\
+                    \t\t<link href=\"https://example.com/new.html\"/>\n\
+                    \t\t<id>https://example.com/new.html</id>\n\
+                    \t\t<updated>2025-06-01T12:30:00Z</updated>\n\
+                    \t</entry>\n\
+                    \t<entry>\n\
+                    \t\t<title>old.html</title>\n\
+                    \t\t<link href=\"https://example.com/old.html\"/>\n\
+                    \t\t<id>https://example.com/old.html</id>\n\
+                    \t\t<updated>2024-01-01T00:00:00Z</updated>\n\
+                    \t</entry>\n\
+                    </feed>\n";
+    assert_eq!(feed, expected);
+}
+
+#[test]
+fn feed_escapes_titles_and_summaries() {
+    init_test_config();
+    let mut entry = page("a.html", Some("2025-01-01"));
+    entry.title = "Tom & Jerry".to_string();
+    entry.summary = Some("1 < 2".to_string());
+
+    let feed = generate_feed(&[entry], "https://example.com");
+
+    assert!(feed.contains("<title>Tom &amp; Jerry</title>"));
+    assert!(feed.contains("<summary>1 &lt; 2</summary>"));
+}
+
+#[test]
+fn feed_timestamps_accept_dates_and_rfc3339() {
+    assert_eq!(
+        feed_timestamp("2025-01-31"),
+        Some("2025-01-31T00:00:00Z".to_string())
+    );
+    assert_eq!(
+        feed_timestamp("2025-01-31T08:00:00+02:00"),
+        Some("2025-01-31T08:00:00+02:00".to_string())
+    );
+    assert_eq!(
+        feed_timestamp("2024-01-02 09:30"),
+        Some("2024-01-02T09:30:00Z".to_string())
+    );
+    assert_eq!(feed_timestamp("2025-1-31"), None);
+    assert_eq!(feed_timestamp("31/01/2025"), None);
+    assert_eq!(feed_timestamp("2025-01-31 extra"), None);
+    assert_eq!(feed_timestamp("2024-13-45"), None);
+    assert_eq!(feed_timestamp("2025-01-31Tjunk"), None);
+}
@@ -0,0 +1,201 @@
+//! This module handles the front matter block at the top of a markdown file, which holds metadata
+//! like the page's title and date.
+//!
+//! Front matter is either a small subset of YAML between `---` fences, or TOML between `+++`
+//! fences:
+//!
+//! ```markdown
+//! ---
+//! title: Getting Started
+//! date: 2025-01-31
+//! ---
+//! ```
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::diagnostics::report_warning;
+
+/// The metadata parsed from a file's front matter.
+///
+/// Values are kept as strings regardless of the format they were written in, so `draft = true`
+/// in TOML and `draft: true` in YAML both give `"true"`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Frontmatter {
+    pub fields: BTreeMap<String, String>,
+}
+
+impl Frontmatter {
+    /// Returns the value of the given front matter field, if it was set.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+
+    /// Returns the page title set by the `title` field, if any.
+    pub fn title(&self) -> Option<&str> {
+        self.get("title").filter(|title| !title.is_empty())
+    }
+
+    /// Returns the page language set by the `lang` field, if any.
+    pub fn lang(&self) -> Option<&str> {
+        self.get("lang").filter(|lang| !lang.is_empty())
+    }
+
+    /// Returns the text direction set by the `dir` field, if any.
+    pub fn dir(&self) -> Option<&str> {
+        self.get("dir").filter(|dir| !dir.is_empty())
+    }
+
+    /// Returns the page's position in the previous/next page links set by the `order` field, if
+    /// it's a valid integer.
+    pub fn order(&self) -> Option<i64> {
+        self.get("order").and_then(|order| order.parse().ok())
+    }
+
+    /// Returns the extra stylesheets set by the `css` field. Several can be given as a
+    /// comma-separated list (`css: a.css, b.css`) or, in TOML, an array of strings.
+    pub fn css_files(&self) -> Vec<&str> {
+        let Some(css) = self.get("css") else {
+            return Vec::new();
+        };
+
+        let list = css
+            .strip_prefix('[')
+            .and_then(|css| css.strip_suffix(']'))
+            .unwrap_or(css);
+        list.split(',')
+            .map(|file| unquote(file.trim()))
+            .filter(|file| !file.is_empty())
+            .collect()
+    }
+
+    /// Returns whether the page is marked as a draft with `draft: true`.
+    pub fn is_draft(&self) -> bool {
+        self.get("draft") == Some("true")
+    }
+}
+
+/// Splits a file's front matter from the rest of its markdown.
+///
+/// The front matter must be the first thing in the file, although a UTF-8 byte order mark and
+/// blank lines before the opening fence are skipped. If there is no front matter, the opening
+/// fence is never closed, or a `---` block holds anything other than `key: value` lines (i.e. a
+/// paragraph between two thematic breaks), the contents are returned untouched so that the file is
+/// parsed exactly as if front matter wasn't supported.
+///
+/// # Arguments
+/// * `contents` - The contents of the markdown file.
+///
+/// # Returns
+/// The parsed front matter, which is empty if there was none, and the markdown following it.
+pub fn extract_frontmatter(contents: &str) -> (Frontmatter, &str) {
+    // Indentation isn't skipped along with the blank lines, since an indented `---` is code
+    let mut trimmed = contents.trim_start_matches('\u{feff}');
+    while let Some((line, rest)) = trimmed.split_once('\n')
+        && line.trim().is_empty()
+    {
+        trimmed = rest;
+    }
+
+    let Some((first_line, rest)) = trimmed.split_once('\n') else {
+        return (Frontmatter::default(), contents);
+    };
+
+    let fence = first_line.trim_end();
+    if fence != "---" && fence != "+++" {
+        return (Frontmatter::default(), contents);
+    }
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let block = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+
+            let frontmatter = if fence == "+++" {
+                parse_toml_frontmatter(block)
+            } else {
+                match parse_yaml_frontmatter(block) {
+                    Some(frontmatter) => frontmatter,
+                    None => return (Frontmatter::default(), contents),
+                }
+            };
+            return (frontmatter, body);
+        }
+        offset += line.len();
+    }
+
+    (Frontmatter::default(), contents)
+}
+
+/// Parses the subset of YAML used for front matter: one `key: value` pair per line, with
+/// optionally quoted values. Comments and blank lines are ignored.
+///
+/// # Returns
+/// The front matter, or `None` if any other line isn't a `key: value` pair, in which case the
+/// block is markdown rather than front matter.
+fn parse_yaml_frontmatter(block: &str) -> Option<Frontmatter> {
+    let mut fields = BTreeMap::new();
+
+    for line in block.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = line.split_once(':')?;
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return None;
+        }
+        fields.insert(key.to_string(), unquote(value.trim()).to_string());
+    }
+
+    Some(Frontmatter { fields })
+}
+
+/// Parses TOML front matter, keeping each top-level value as a string.
+fn parse_toml_frontmatter(block: &str) -> Frontmatter {
+    let mut fields = BTreeMap::new();
+
+    let doc = match toml_edit::DocumentMut::from_str(block) {
+        Ok(doc) => doc,
+        Err(e) => {
+            report_warning(&format!(
+                "Failed to parse TOML front matter, ignoring it: {e}"
+            ));
+            return Frontmatter { fields };
+        }
+    };
+
+    for (key, item) in doc.iter() {
+        let Some(value) = item.as_value() else {
+            continue;
+        };
+
+        let value = match value.as_str() {
+            Some(string) => string.to_string(),
+            None => value.to_string().trim().to_string(),
+        };
+        fields.insert(key.to_string(), value);
+    }
+
+    Frontmatter { fields }
+}
+
+/// Removes a matching pair of single or double quotes from around a YAML value.
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod test;
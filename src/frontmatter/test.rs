@@ -0,0 +1,87 @@
+use crate::frontmatter::extract_frontmatter;
+
+#[test]
+fn yaml_frontmatter_is_parsed() {
+    let (frontmatter, body) =
+        extract_frontmatter("---\ntitle: \"Getting Started\"\ndate: 2025-01-31\n---\n# Hello\n");
+
+    assert_eq!(frontmatter.title(), Some("Getting Started"));
+    assert_eq!(frontmatter.get("date"), Some("2025-01-31"));
+    assert_eq!(body, "# Hello\n");
+}
+
+#[test]
+fn toml_frontmatter_is_parsed() {
+    let (frontmatter, body) =
+        extract_frontmatter("+++\ntitle = \"Getting Started\"\ndraft = true\n+++\n# Hello\n");
+
+    assert_eq!(frontmatter.title(), Some("Getting Started"));
+    assert_eq!(frontmatter.get("draft"), Some("true"));
+    assert_eq!(body, "# Hello\n");
+}
+
+#[test]
+fn file_without_frontmatter_is_untouched() {
+    let contents = "# Hello\n\n---\n\nA thematic break, not front matter.\n";
+    let (frontmatter, body) = extract_frontmatter(contents);
+
+    assert!(frontmatter.fields.is_empty());
+    assert_eq!(body, contents);
+}
+
+#[test]
+fn unclosed_frontmatter_is_untouched() {
+    let contents = "---\ntitle: Oops\n\nNo closing fence.\n";
+    let (frontmatter, body) = extract_frontmatter(contents);
+
+    assert!(frontmatter.fields.is_empty());
+    assert_eq!(body, contents);
+}
+
+#[test]
+fn paragraph_between_thematic_breaks_is_untouched() {
+    let contents = "---\nIntro paragraph here.\n---\n# Heading\n";
+    let (frontmatter, body) = extract_frontmatter(contents);
+
+    assert!(frontmatter.fields.is_empty());
+    assert_eq!(body, contents);
+
+    let contents = "---\ntitle: Page\nAnd then some prose.\n---\n";
+    assert_eq!(extract_frontmatter(contents).1, contents);
+}
+
+#[test]
+fn indented_fence_is_untouched() {
+    let contents = "    ---\n    title: Code\n    ---\n";
+    let (frontmatter, body) = extract_frontmatter(contents);
+
+    assert!(frontmatter.fields.is_empty());
+    assert_eq!(body, contents);
+}
+
+#[test]
+fn frontmatter_after_bom_is_recognized() {
+    let (frontmatter, body) = extract_frontmatter("\u{feff}---\ntitle: About\n---\nBody\n");
+
+    assert_eq!(frontmatter.title(), Some("About"));
+    assert_eq!(body, "Body\n");
+}
+
+#[test]
+fn frontmatter_after_blank_lines_is_recognized() {
+    let (frontmatter, body) = extract_frontmatter("\n\r\n---\r\ntitle: About\r\n---\r\nBody\r\n");
+
+    assert_eq!(frontmatter.title(), Some("About"));
+    assert_eq!(body, "Body\r\n");
+}
+
+#[test]
+fn css_files_are_listed_in_either_format() {
+    let (yaml, _) = extract_frontmatter("---\ncss: extra.css, \"theme/dark.css\"\n---\n");
+    let (toml, _) = extract_frontmatter("+++\ncss = [\"extra.css\", \"theme/dark.css\"]\n+++\n");
+    let (none, _) = extract_frontmatter("---\ntitle: Page\n---\n");
+
+    assert_eq!(yaml.css_files(), vec!["extra.css", "theme/dark.css"]);
+    assert_eq!(toml.css_files(), vec!["extra.css", "theme/dark.css"]);
+    assert!(none.css_files().is_empty());
+}
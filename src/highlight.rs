@@ -0,0 +1,166 @@
+//! This module provides build-time syntax highlighting for code blocks using syntect, as an
+//! alternative to loading PrismJS from a CDN. It requires the `syntect` feature.
+
+#[cfg(feature = "syntect")]
+use std::sync::LazyLock;
+
+#[cfg(feature = "syntect")]
+use log::warn;
+#[cfg(feature = "syntect")]
+use syntect::{
+    highlighting::ThemeSet,
+    html::{ClassStyle, css_for_theme_with_class_style, line_tokens_to_classed_spans},
+    parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet},
+};
+
+use crate::types::CodeBlockAttributes;
+
+/// The syntax definitions bundled with syntect.
+#[cfg(feature = "syntect")]
+static SYNTAX_SET: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+
+/// The themes bundled with syntect.
+#[cfg(feature = "syntect")]
+static THEME_SET: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+/// The prefix added to every highlighting class, so they can't clash with the page's own styles.
+const CLASS_PREFIX: &str = "hl-";
+
+#[cfg(feature = "syntect")]
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed {
+    prefix: CLASS_PREFIX,
+};
+
+/// Highlights a code block, producing a `<pre>` with each line in its own `<code>` element.
+///
+/// Every line is self-contained, with the spans for any scopes left open by the previous line
+/// reopened at its start, so lines can be indented and sanitized independently.
+///
+/// # Arguments
+/// * `lines` - The lines of code in the block.
+/// * `language` - The language from the code fence, matched against syntect's syntax
+///   definitions by name or file extension.
+/// * `attributes` - The attributes from the code fence's info string, added to the `<pre>`.
+///
+/// # Returns
+/// The highlighted HTML. Unknown languages are rendered as plain escaped text.
+pub fn highlight_code_block(
+    lines: &[String],
+    language: Option<&str>,
+    attributes: &CodeBlockAttributes,
+) -> String {
+    // Raw HTML tags in code blocks are already escaped by the parser
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| line.replace("&lt;", "<").replace("&gt;", ">"))
+        .collect();
+
+    let highlighted_lines = language
+        .and_then(|language| highlight_lines(&lines, language))
+        .unwrap_or_else(|| lines.iter().map(|line| escape_code(line)).collect());
+
+    let language_class = match language {
+        Some(language) => format!("language-{language}"),
+        None => "language-none".to_string(),
+    };
+
+    let code = highlighted_lines
+        .iter()
+        .map(|line| format!("<code class=\"{CLASS_PREFIX}line\">{line}</code>"))
+        .collect::<String>();
+
+    let extra_classes = attributes.class_suffix();
+    let extra_attrs = attributes.html_attributes();
+
+    format!(
+        "<pre class=\"highlight {CLASS_PREFIX}code {language_class}{extra_classes}\"{extra_attrs}>{code}</pre>"
+    )
+}
+
+/// Highlights each line of code, returning `None` if the language isn't recognized.
+#[cfg(feature = "syntect")]
+fn highlight_lines(lines: &[String], language: &str) -> Option<Vec<String>> {
+    let syntax = SYNTAX_SET.find_syntax_by_token(language)?;
+
+    highlight_with_syntax(lines, syntax)
+        .map_err(|e| warn!("Failed to highlight {language} code block: {e}"))
+        .ok()
+}
+
+#[cfg(not(feature = "syntect"))]
+fn highlight_lines(_lines: &[String], _language: &str) -> Option<Vec<String>> {
+    None
+}
+
+#[cfg(feature = "syntect")]
+fn highlight_with_syntax(
+    lines: &[String],
+    syntax: &SyntaxReference,
+) -> Result<Vec<String>, syntect::Error> {
+    let mut parse_state = ParseState::new(syntax);
+    let mut scope_stack = ScopeStack::new();
+    let mut highlighted_lines = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        // Reopen the scopes that are still open from the previous line
+        let mut highlighted = scope_stack
+            .as_slice()
+            .iter()
+            .map(|scope| {
+                let classes = scope
+                    .build_string()
+                    .split('.')
+                    .map(|atom| format!("{CLASS_PREFIX}{atom}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("<span class=\"{classes}\">")
+            })
+            .collect::<String>();
+
+        // The default syntaxes expect each line to end with a newline
+        let line = format!("{line}\n");
+        let ops = parse_state.parse_line(&line, &SYNTAX_SET)?;
+        let (spans, _) = line_tokens_to_classed_spans(&line, &ops, CLASS_STYLE, &mut scope_stack)?;
+
+        highlighted.push_str(&spans.replace('\n', ""));
+        highlighted.push_str(&"</span>".repeat(scope_stack.len()));
+        highlighted_lines.push(highlighted);
+    }
+
+    Ok(highlighted_lines)
+}
+
+/// Generates the CSS for the given syntect theme, i.e. `"base16-ocean.dark"`, to be added to the
+/// default stylesheet.
+///
+/// # Returns
+/// The theme's CSS, or an empty string if the theme doesn't exist.
+#[cfg(feature = "syntect")]
+pub fn theme_css(theme_name: &str) -> String {
+    let Some(theme) = THEME_SET.themes.get(theme_name) else {
+        let mut theme_names: Vec<&String> = THEME_SET.themes.keys().collect();
+        theme_names.sort();
+        warn!("Unknown syntect theme \"{theme_name}\", expected one of: {theme_names:?}");
+        return String::new();
+    };
+
+    css_for_theme_with_class_style(theme, CLASS_STYLE).unwrap_or_else(|e| {
+        warn!("Failed to generate CSS for syntect theme \"{theme_name}\": {e}");
+        String::new()
+    })
+}
+
+#[cfg(not(feature = "syntect"))]
+pub fn theme_css(_theme_name: &str) -> String {
+    String::new()
+}
+
+/// Escapes the characters in a line of code that would otherwise be read as HTML.
+fn escape_code(line: &str) -> String {
+    line.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod test;
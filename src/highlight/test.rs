@@ -0,0 +1,50 @@
+use crate::highlight::highlight_code_block;
+use crate::types::CodeBlockAttributes;
+
+#[cfg(feature = "syntect")]
+#[test]
+fn known_language_is_highlighted() {
+    let html = highlight_code_block(
+        &[String::from("fn main() {}")],
+        Some("rust"),
+        &CodeBlockAttributes::default(),
+    );
+
+    assert!(
+        html.starts_with("<pre class=\"highlight hl-code language-rust\"><code class=\"hl-line\">")
+    );
+    assert!(html.contains("<span class=\"hl-storage hl-type hl-function hl-rust\">fn</span>"));
+    assert!(html.ends_with("</code></pre>"));
+}
+
+#[test]
+fn unknown_language_is_escaped_text() {
+    let html = highlight_code_block(
+        &[String::from("a &lt;b&gt; && c"), String::from("  d")],
+        Some("not-a-language"),
+        &CodeBlockAttributes::default(),
+    );
+
+    assert_eq!(
+        html,
+        "<pre class=\"highlight hl-code language-not-a-language\"><code class=\"hl-line\">a &lt;b&gt; &amp;&amp; c</code><code class=\"hl-line\">  d</code></pre>"
+    );
+}
+
+#[cfg(feature = "syntect")]
+#[test]
+fn scopes_spanning_lines_are_closed_on_each_line() {
+    let lines = [
+        String::from("/* A comment"),
+        String::from("over two lines */"),
+    ];
+    let html = highlight_code_block(&lines, Some("rs"), &CodeBlockAttributes::default());
+
+    for line in html.split("<code class=\"hl-line\">").skip(1) {
+        assert_eq!(
+            line.matches("<span").count(),
+            line.matches("</span>").count()
+        );
+        assert!(line.contains("hl-comment"));
+    }
+}
@@ -1,11 +1,119 @@
 //! This module provides functionality to generate HTML from markdown block elements.
 
-use ammonia::clean;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::RwLock;
 
-use crate::CONFIG;
-use crate::config::Config;
-use crate::types::{MdBlockElement, ToHtml};
-use crate::utils::build_rel_prefix;
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, current_config};
+use crate::dates::{PageDate, frontmatter_date};
+use crate::frontmatter::Frontmatter;
+use crate::highlight::theme_css;
+use crate::io::{copy_page_css_to_output_dir, read_file};
+use crate::minify::{is_minify_enabled, minify_css, minify_html};
+use crate::parser::flatten_inline;
+use crate::post_process::{PageContext, run_post_processors};
+use crate::types::{MdBlockElement, MdInlineElement, ToHtml};
+use crate::utils::{
+    build_rel_prefix, is_relative_url, markdown_link_href, output_relative_path, page_link_path,
+    slugify,
+};
+
+thread_local! {
+    /// The heading slugs used so far in the document being rendered on this thread, along with
+    /// how many times each has been used. Pages are rendered on a single thread each, so this is
+    /// effectively per-document as long as it is reset before each page.
+    static HEADING_SLUGS: RefCell<HashMap<String, usize>> = RefCell::new(HashMap::new());
+
+    /// The labels of the footnotes referenced so far in the document being rendered on this
+    /// thread, in the order they were first referenced.
+    static FOOTNOTE_ORDER: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The stylesheet embedded in each page's `<head>` when `inline_css` is enabled.
+static INLINE_CSS: RwLock<String> = RwLock::new(String::new());
+
+/// Sets the stylesheet to embed in each page when `inline_css` is enabled. Any `</style>` in the
+/// stylesheet is escaped so that it can't end the `<style>` element early.
+pub fn set_inline_css(css: &str) {
+    let escaped_css = Regex::new(r"(?i)</(style)")
+        .unwrap()
+        .replace_all(css, r"<\/${1}")
+        .into_owned();
+
+    *INLINE_CSS.write().expect("Failed to lock inline CSS") = escaped_css;
+}
+
+/// Returns the stylesheet embedded in each page when `inline_css` is enabled.
+pub fn inline_css() -> String {
+    INLINE_CSS
+        .read()
+        .expect("Failed to lock inline CSS")
+        .clone()
+}
+
+/// Forgets the heading slugs seen so far, so the next document starts with a clean slate.
+pub fn reset_heading_slugs() {
+    HEADING_SLUGS.with(|slugs| slugs.borrow_mut().clear());
+}
+
+/// Returns a slug for the given heading text that is unique within the current document.
+///
+/// The first heading with a given slug keeps it as-is, while duplicates get `-1`, `-2`, etc.
+/// appended, matching GitHub's behaviour.
+pub fn unique_heading_slug(heading_text: &str) -> String {
+    let base_slug = slugify(heading_text);
+
+    HEADING_SLUGS.with(|slugs| {
+        let mut slugs = slugs.borrow_mut();
+        let mut slug = base_slug.clone();
+        let mut count = slugs.get(&base_slug).copied().unwrap_or(0);
+        // A suffixed slug can collide with a heading that is literally named e.g. "Intro 1"
+        while slugs.contains_key(&slug) {
+            count += 1;
+            slug = format!("{base_slug}-{count}");
+        }
+        slugs.insert(base_slug, count);
+        slugs.entry(slug.clone()).or_insert(0);
+
+        slug
+    })
+}
+
+/// Forgets the footnotes referenced so far, so the next document's footnotes are numbered from 1.
+pub fn reset_footnote_numbers() {
+    FOOTNOTE_ORDER.with(|order| order.borrow_mut().clear());
+}
+
+/// Returns the number of the given footnote, which follows the order footnotes are first
+/// referenced in, along with whether this is the footnote's first reference.
+pub fn footnote_number(label: &str) -> (usize, bool) {
+    FOOTNOTE_ORDER.with(|order| {
+        let mut order = order.borrow_mut();
+        match order.iter().position(|seen| seen == label) {
+            Some(index) => (index + 1, false),
+            None => {
+                order.push(label.to_string());
+                (order.len(), true)
+            }
+        }
+    })
+}
+
+/// The scripts that load Prism and its plugins, appended to the end of the body when Prism is
+/// enabled.
+const PRISM_SCRIPTS: [&str; 6] = [
+    "<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/components/prism-core.min.js\" integrity=\"sha512-Uw06iFFf9hwoN77+kPl/1DZL66tKsvZg6EWm7n6QxInyptVuycfrO52hATXDRozk7KWeXnrSueiglILct8IkkA==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>",
+    "<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/line-numbers/prism-line-numbers.min.js\" integrity=\"sha512-BttltKXFyWnGZQcRWj6osIg7lbizJchuAMotOkdLxHxwt/Hyo+cl47bZU0QADg+Qt5DJwni3SbYGXeGMB5cBcw==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>",
+    "<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/autoloader/prism-autoloader.min.js\" integrity=\"sha512-SkmBfuA2hqjzEVpmnMt/LINrjop3GKWqsuLSSB3e7iBmYK7JuWw4ldmmxwD9mdm2IRTTi0OxSAfEGvgEi0i2Kw==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>",
+    "<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/toolbar/prism-toolbar.min.js\" integrity=\"sha512-st608h+ZqzliahyzEpETxzU0f7z7a9acN6AFvYmHvpFhmcFuKT8a22TT5TpKpjDa3pt3Wv7Z3SdQBCBdDPhyWA==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>",
+    "<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/copy-to-clipboard/prism-copy-to-clipboard.min.js\" integrity=\"sha512-/kVH1uXuObC0iYgxxCKY41JdWOkKOxorFVmip+YVifKsJ4Au/87EisD1wty7vxN2kAhnWh6Yc8o/dSAXj6Oz7A==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>",
+    "<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/show-language/prism-show-language.min.js\" integrity=\"sha512-d1t+YumgzdIHUL78me4B9NzNTu9Lcj6RdGVbdiFDlxRV9JTN9s+iBQRhUqLRq5xtWUp1AD+cW2sN2OlST716fw==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>",
+];
 
 /// Generates an HTML string from a vector of MdBlockElements
 ///
@@ -16,137 +124,901 @@ use crate::utils::build_rel_prefix;
 /// * `input_dir` - The directory where the markdown files are located, used for relative paths.
 /// * `html_rel_path` - The relative path to the HTML file from the output directory, used for
 ///   linking resources.
+/// * `page_list` - The names of all pages in the site in navigation order, used to build the
+///   sidebar and the previous/next page links when they're enabled.
+/// * `frontmatter` - The page's front matter. Its `title`, if set, is used instead of the file
+///   name.
 ///
 /// # Returns
-/// Returns a `String` containing the generated HTML.
+/// Returns a `String` containing the generated HTML, after it has been passed through any
+/// registered `HtmlPostProcessor`s.
 pub fn generate_html(
     file_name: &str,
     md_elements: &[MdBlockElement],
     output_dir: &str,
     input_dir: &str,
     html_rel_path: &str,
+    page_list: &[String],
+    frontmatter: &Frontmatter,
 ) -> String {
     let mut html_output = String::new();
-    let config = CONFIG.get().unwrap();
+    let config = &current_config();
 
-    let head = generate_head(file_name, html_rel_path, config);
+    let title = page_title(file_name, md_elements, frontmatter, config);
+    // The heading that's used as the title can be left out of the body, but not when the front
+    // matter sets the title instead
+    let md_elements = if config.html.title_from_heading
+        && config.html.strip_title_heading
+        && frontmatter.title().is_none()
+        && title_heading(md_elements).is_some()
+    {
+        &md_elements[1..]
+    } else {
+        md_elements
+    };
+    let page_css = page_stylesheets(frontmatter, output_dir, input_dir, html_rel_path);
+    let head = generate_head(&title, html_rel_path, config, frontmatter, &page_css);
+    let indent = |level: usize| config.html.indent_unit().repeat(level);
 
-    let mut body = String::from("\t<body>\n");
-    body.push_str(&indent_html(&generate_navbar(html_rel_path), 2));
-    body.push_str("\n\t\t<div id=\"content\">");
+    let mut body = format!("{}<body>\n", indent(1));
+    body.push_str(&indent_html(
+        &generate_navbar(&title, html_rel_path, config),
+        2,
+    ));
+    if config.html.sidebar {
+        body.push_str(&format!("\n{}<div class=\"sidebar-layout\">\n", indent(2)));
+        body.push_str(&indent_html(&generate_sidebar(page_list, html_rel_path), 3));
+    }
+    body.push_str(&format!(
+        "\n{}{}",
+        indent(2),
+        content_wrapper_open_tag(config)
+    ));
+    if let Some(date) = frontmatter_date(frontmatter)
+        && !config.html.date_format.is_empty()
+    {
+        let date = format!("<p class=\"page-date\">{}</p>", format_date(&date, config));
+        body.push_str(&format!("\n{}", indent_html(&date, 3)));
+    }
+    if config.html.reading_time {
+        let reading_time = generate_reading_time(md_elements, config);
+        body.push_str(&format!("\n{}", indent_html(&reading_time, 3)));
+    }
 
-    let inner_html: String = md_elements
+    let inner_html = render_content(md_elements, output_dir, input_dir, html_rel_path, config);
+    body.push_str(&indent_html(&inner_html, 3));
+    if config.html.prev_next_links {
+        let page_nav = generate_page_nav(file_name, page_list, html_rel_path, config);
+        if !page_nav.is_empty() {
+            body.push_str(&format!("\n{}", indent_html(&page_nav, 3)));
+        }
+    }
+    body.push_str(&format!("\n{}</div>", indent(2)));
+    if config.html.sidebar {
+        body.push_str(&format!("\n{}</div>", indent(2)));
+    }
+
+    let footer = generate_footer(&title, html_rel_path, config);
+    if !footer.is_empty() {
+        body.push_str(&format!("\n{}", indent_html(&footer, 2)));
+    }
+
+    if config.html.prism_enabled() {
+        body.push('\n');
+        for script in PRISM_SCRIPTS {
+            body.push_str(&format!("\n{}{script}", indent(2)));
+        }
+    }
+
+    body.push_str(&format!("\n{}</body>\n", indent(1)));
+
+    html_output.push_str(&head);
+    html_output.push_str(&body);
+    html_output.push_str("</html>\n");
+
+    let page = PageContext {
+        path: html_rel_path,
+        title: &title,
+        output_dir,
+    };
+    let html_output = run_post_processors(html_output, &page);
+
+    if is_minify_enabled() {
+        minify_html(&html_output)
+    } else {
+        html_output
+    }
+}
+
+/// Renders the content of a page: its elements, with the table of contents in place of a `[TOC]`
+/// marker and any footnotes collected at the end. The result is sanitized when sanitization is
+/// enabled.
+///
+/// # Arguments
+/// * `md_elements` - The parsed elements of the page.
+/// * `output_dir` - The directory where the generated HTML file will be saved. Relative images are
+///   only copied to it when it isn't empty.
+/// * `input_dir` - The directory where the markdown files are located, used for relative paths.
+/// * `html_rel_path` - The relative path to the HTML file from the output directory.
+/// * `config` - The configuration to render with.
+///
+/// # Returns
+/// Returns a `String` containing the page's content HTML, without the surrounding document.
+pub fn render_content(
+    md_elements: &[MdBlockElement],
+    output_dir: &str,
+    input_dir: &str,
+    html_rel_path: &str,
+    config: &Config,
+) -> String {
+    let toc_html = if md_elements.iter().any(is_toc_marker) {
+        generate_toc(md_elements, config.html.toc_min_level)
+    } else {
+        String::new()
+    };
+
+    reset_heading_slugs();
+    reset_footnote_numbers();
+    let mut inner_html: String = md_elements
         .iter()
-        .map(|element| element.to_html(output_dir, input_dir, html_rel_path))
+        .filter(|element| !matches!(element, MdBlockElement::FootnoteDefinition { .. }))
+        .map(|element| {
+            if is_toc_marker(element) {
+                toc_html.clone()
+            } else {
+                element.to_html(output_dir, input_dir, html_rel_path)
+            }
+        })
         .collect::<Vec<String>>()
         .join("\n");
 
-    let inner_html = if config.html.sanitize_html {
-        let mut builder = ammonia::Builder::default();
-        builder
-            .add_tag_attributes("a", &["href", "title", "target"])
-            .add_tag_attribute_values("a", "target", &["_blank", "_self"])
-            .add_tag_attributes("pre", &["class"])
-            .add_tag_attributes("code", &["class"])
-            .add_tags(&["iframe"])
-            .add_tag_attributes(
-                "iframe",
-                &[
-                    "src",
-                    "width",
-                    "height",
-                    "title",
-                    "frameborder",
-                    "allowfullscreen",
-                ],
-            );
-        for tag in &["h1", "h2", "h3", "h4", "h5", "h6"] {
-            builder.add_tag_attributes(tag, &["id"]);
-        }
+    let footnotes_html = generate_footnotes(md_elements, output_dir, input_dir, html_rel_path);
+    if !footnotes_html.is_empty() {
+        inner_html.push_str(&format!("\n{footnotes_html}"));
+    }
 
-        builder.clean(&inner_html).to_string()
+    if config.html.sanitize_enabled() {
+        sanitize_html(&inner_html, config)
     } else {
         inner_html
-    };
-
-    body.push_str(&indent_html(&inner_html, 3));
-    body.push_str("\n\t\t</div>");
+    }
+}
 
-    if config.html.use_prism {
-        body.push_str(
-            "\n\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/components/prism-core.min.js\" integrity=\"sha512-Uw06iFFf9hwoN77+kPl/1DZL66tKsvZg6EWm7n6QxInyptVuycfrO52hATXDRozk7KWeXnrSueiglILct8IkkA==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>",
+/// Sanitizes the generated HTML to prevent XSS attacks, while allowing the tags and attributes
+/// that the generator itself emits along with any configured under `[html.sanitize]`.
+#[cfg(feature = "sanitize")]
+fn sanitize_html(html: &str, config: &Config) -> String {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tag_attributes("a", &["href", "title", "target", "id", "name"])
+        .add_tag_attribute_values("a", "target", &["_blank", "_self"])
+        .add_tag_attributes("pre", &["class", "id"])
+        .add_tag_attributes("code", &["class"])
+        .add_tag_attributes("span", &["class"])
+        .add_tag_attributes("ol", &["type"])
+        .add_tag_attributes("img", &["width", "height"])
+        .add_allowed_classes("a", &["heading-anchor", "footnote-backref"])
+        .add_allowed_classes("sup", &["footnote-ref"])
+        .add_allowed_classes(
+            "div",
+            &[
+                "math",
+                "math-display",
+                "callout",
+                "callout-note",
+                "callout-tip",
+                "callout-important",
+                "callout-warning",
+                "callout-caution",
+                "code-collapse",
+            ],
+        )
+        .add_allowed_classes("p", &["callout-title"])
+        .add_tags(&["section", "details", "summary"])
+        .add_allowed_classes("section", &["footnotes"])
+        .add_tag_attributes("li", &["id"])
+        .add_allowed_classes("nav", &["toc"])
+        .add_allowed_classes("li", &["task-list-item"])
+        .add_tags(&["input"])
+        .add_tag_attributes("input", &["disabled", "checked"])
+        .add_tag_attribute_values("input", "type", &["checkbox"])
+        .add_tags(&["label"])
+        .add_allowed_classes("label", &["code-collapse-toggle"])
+        .add_tags(&["iframe"])
+        .add_tag_attributes(
+            "iframe",
+            &[
+                "src",
+                "width",
+                "height",
+                "title",
+                "frameborder",
+                "allowfullscreen",
+            ],
         );
-        body.push_str("\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/line-numbers/prism-line-numbers.min.js\" integrity=\"sha512-BttltKXFyWnGZQcRWj6osIg7lbizJchuAMotOkdLxHxwt/Hyo+cl47bZU0QADg+Qt5DJwni3SbYGXeGMB5cBcw==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>");
-        body.push_str(
-            "\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/autoloader/prism-autoloader.min.js\" integrity=\"sha512-SkmBfuA2hqjzEVpmnMt/LINrjop3GKWqsuLSSB3e7iBmYK7JuWw4ldmmxwD9mdm2IRTTi0OxSAfEGvgEi0i2Kw==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>"
-        );
-        body.push_str("\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/toolbar/prism-toolbar.min.js\" integrity=\"sha512-st608h+ZqzliahyzEpETxzU0f7z7a9acN6AFvYmHvpFhmcFuKT8a22TT5TpKpjDa3pt3Wv7Z3SdQBCBdDPhyWA==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>");
-        body.push_str("\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/copy-to-clipboard/prism-copy-to-clipboard.min.js\" integrity=\"sha512-/kVH1uXuObC0iYgxxCKY41JdWOkKOxorFVmip+YVifKsJ4Au/87EisD1wty7vxN2kAhnWh6Yc8o/dSAXj6Oz7A==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>");
-        body.push_str("\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/show-language/prism-show-language.min.js\" integrity=\"sha512-d1t+YumgzdIHUL78me4B9NzNTu9Lcj6RdGVbdiFDlxRV9JTN9s+iBQRhUqLRq5xtWUp1AD+cW2sN2OlST716fw==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>");
+    // Code block attributes that aren't otherwise known are passed through as data attributes
+    builder.add_generic_attribute_prefixes(&["data-"]);
+    for tag in &["h1", "h2", "h3", "h4", "h5", "h6"] {
+        builder.add_tag_attributes(tag, &["id"]);
     }
 
-    body.push_str("\n\t</body>\n");
+    let allowlist = &config.html.sanitize;
+    builder.add_tags(
+        allowlist
+            .allowed_tags
+            .iter()
+            .map(String::as_str)
+            .filter(|tag| is_allowed_tag(tag)),
+    );
+    let mut allowed_classes = builder.clone_allowed_classes();
+    for (tag, attributes) in &allowlist.allowed_attributes {
+        if tag != "*" && !is_allowed_tag(tag) {
+            continue;
+        }
 
-    html_output.push_str(&head);
-    html_output.push_str(&body);
-    html_output.push_str("</html>\n");
+        let attributes = attributes
+            .iter()
+            .map(String::as_str)
+            .filter(|attribute| is_allowed_attribute(attribute))
+            .collect::<Vec<_>>();
+
+        // Allowing any class on a tag replaces the specific classes it was limited to
+        if attributes.contains(&"class") {
+            match tag.as_str() {
+                "*" => allowed_classes.clear(),
+                tag => {
+                    allowed_classes.remove(tag);
+                }
+            }
+        }
+
+        match tag.as_str() {
+            "*" => builder.add_generic_attributes(attributes),
+            tag => builder.add_tag_attributes(tag, attributes),
+        };
+    }
+    builder.allowed_classes(allowed_classes);
+
+    builder.clean(html).to_string()
+}
+
+/// Checks whether a tag from `[html.sanitize]` can be allowed, warning if it can't. Tags whose
+/// content the sanitizer always removes, like `<script>`, can never be allowed.
+#[cfg(feature = "sanitize")]
+fn is_allowed_tag(tag: &str) -> bool {
+    let is_valid = is_valid_html_name(tag) && !matches!(tag, "script" | "style");
+    if !is_valid {
+        warn!("Ignoring invalid tag \"{tag}\" in [html.sanitize]");
+    }
+    is_valid
+}
+
+/// Checks whether an attribute from `[html.sanitize]` can be allowed, warning if it can't. Event
+/// handlers are never allowed, and neither is `rel`, which the sanitizer sets on links itself.
+#[cfg(feature = "sanitize")]
+fn is_allowed_attribute(attribute: &str) -> bool {
+    let is_valid = is_valid_html_name(attribute)
+        && !attribute.to_ascii_lowercase().starts_with("on")
+        && attribute != "rel";
+    if !is_valid {
+        warn!("Ignoring invalid attribute \"{attribute}\" in [html.sanitize]");
+    }
+    is_valid
+}
+
+/// Checks whether a name is a valid lowercase HTML tag or attribute name, i.e. `figcaption` or
+/// `aria-label`.
+#[cfg(feature = "sanitize")]
+fn is_valid_html_name(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-')
+}
+
+/// Without the `sanitize` feature, HTML is passed through unchanged.
+#[cfg(not(feature = "sanitize"))]
+fn sanitize_html(html: &str, _config: &Config) -> String {
+    html.to_string()
+}
+
+/// A summary of a generated page, collected while it's generated so that the index can list it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageOutput {
+    /// The page's file name, relative to the input directory.
+    pub path: String,
+    /// The page's title, from its front matter or its file name, escaped for HTML.
+    pub title: String,
+    pub word_count: usize,
+    /// The text of the page's headings in document order, escaped for HTML.
+    pub headings: Vec<String>,
+    /// The `date` from the page's front matter, if it's a valid date.
+    #[serde(default)]
+    pub date: Option<String>,
+}
+
+/// Summarizes a page from its parsed elements.
+///
+/// # Arguments
+/// * `file_name` - The page's file name, relative to the input directory.
+/// * `md_elements` - The parsed markdown elements of the page.
+/// * `frontmatter` - The page's front matter, whose `title` is used if it has one.
+///
+/// # Returns
+/// Returns the page's `PageOutput`, whose word count includes code when the reading time does.
+pub fn page_output(
+    file_name: &str,
+    md_elements: &[MdBlockElement],
+    frontmatter: &Frontmatter,
+) -> PageOutput {
+    let config = &current_config();
+
+    // Collecting headings assigns their slugs, which must be left unused for the page itself
+    reset_heading_slugs();
+    let mut entries = Vec::new();
+    collect_toc_entries(md_elements, &mut entries);
+    reset_heading_slugs();
 
-    html_output
+    PageOutput {
+        path: file_name.to_string(),
+        title: page_title(file_name, md_elements, frontmatter, config),
+        word_count: count_words(md_elements, config.html.reading_time_include_code),
+        headings: entries.into_iter().map(|entry| entry.text).collect(),
+        // Invalid dates are warned about when the page itself is generated
+        date: frontmatter
+            .get("date")
+            .filter(|date| PageDate::parse(date).is_some())
+            .map(str::to_string),
+    }
+}
+
+/// Returns a page's title, escaped for HTML. This is its front matter `title`, then the level 1
+/// heading it starts with if `title_from_heading` is enabled, and otherwise its formatted file
+/// name.
+fn page_title(
+    file_name: &str,
+    md_elements: &[MdBlockElement],
+    frontmatter: &Frontmatter,
+    config: &Config,
+) -> String {
+    if let Some(title) = frontmatter.title() {
+        return escape_title(title);
+    }
+
+    match title_heading(md_elements) {
+        Some(heading) if config.html.title_from_heading => heading,
+        _ => format_title(file_name),
+    }
+}
+
+/// Finds the level 1 heading that a page starts with, which can be used as its title. A heading
+/// further down the page is a section heading rather than the page's title, so it isn't used.
+///
+/// # Returns
+/// The heading's text escaped for HTML, or `None` if the page doesn't start with a level 1 heading
+/// that has text.
+fn title_heading(md_elements: &[MdBlockElement]) -> Option<String> {
+    match md_elements.first()? {
+        MdBlockElement::Header { level: 1, content } => {
+            let text = content
+                .iter()
+                .map(MdInlineElement::to_plain_text)
+                .collect::<String>();
+            let text = text.trim();
+            (!text.is_empty()).then(|| escape_title(text))
+        }
+        _ => None,
+    }
 }
 
 /// Generates the index HTML file that lists all pages
 ///
 /// # Arguments
-/// * `file_names` - A slice of `String` containing the names of the markdown files.
+/// * `pages` - The pages to list, by their titles.
 ///
 /// # Returns
 /// Returns a `String` containing the generated HTML for the index page.
-pub fn generate_index(file_names: &[String]) -> String {
+pub fn generate_index(pages: &[PageOutput]) -> String {
     let mut html_output = String::new();
 
-    let head = generate_head("index", "index.html", CONFIG.get().unwrap());
+    let config = &current_config();
+    let title = format_title("index");
+    let head = generate_head(&title, "index.html", config, &Frontmatter::default(), &[]);
+    let indent = config.html.indent_unit();
 
-    let mut body = String::from("\t<body>\n");
-    body.push_str(&generate_navbar("index.html"));
-    body.push_str("\n\t<div id=\"content\">\n");
+    let mut body = format!("{indent}<body>\n");
+    body.push_str(&generate_navbar(&title, "index.html", config));
+    body.push_str(&format!("\n{indent}{}\n", content_wrapper_open_tag(config)));
     body.push_str("<h1>All Pages</h1>\n");
+    if pages.is_empty() {
+        body.push_str("<p>No pages found.</p>\n");
+    }
 
-    file_names.iter().for_each(|file_name| {
+    pages.iter().for_each(|page| {
+        let date = match page.date.as_deref().and_then(PageDate::parse) {
+            Some(date) if !config.html.date_format.is_empty() => {
+                format!(" {}", format_date(&date, config))
+            }
+            _ => String::new(),
+        };
         body.push_str(&format!(
-            "<a href=\"./{}.html\">{}</a><br>\n",
-            file_name.trim_end_matches(".md"),
-            format_title(file_name)
+            "<a href=\"./{}\">{}</a>{date}<br>\n",
+            page_link_path(&page.path, config.html.pretty_urls),
+            page.title
         ));
     });
 
-    body.push_str("\n</div>\n\t</body>\n");
+    body.push_str("\n</div>\n");
+
+    let footer = generate_footer(&title, "index.html", config);
+    if !footer.is_empty() {
+        body.push_str(&format!("{}\n", indent_html(&footer, 1)));
+    }
+
+    body.push_str(&format!("{indent}</body>\n"));
+
+    html_output.push_str(&head);
+    html_output.push_str(&body);
+    html_output.push_str("</html>\n");
+
+    if is_minify_enabled() {
+        minify_html(&html_output)
+    } else {
+        html_output
+    }
+}
+
+/// Generates a `sitemap.xml` listing the index and every page in the site.
+///
+/// # Arguments
+/// * `file_names` - A slice of `String` containing the names of the markdown files.
+/// * `base_url` - The absolute URL the site is served from, i.e. `https://example.com/docs`.
+///
+/// # Returns
+/// Returns a `String` containing the generated sitemap.
+pub fn generate_sitemap(file_names: &[String], base_url: &str) -> String {
+    let config = &current_config();
+    let base_url = base_url.trim_end_matches('/');
+
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+
+    let index_path = if config.html.pretty_urls {
+        ""
+    } else {
+        "index.html"
+    };
+    let file_paths = file_names
+        .iter()
+        .map(|file_name| page_link_path(file_name, config.html.pretty_urls))
+        .collect::<Vec<_>>();
+    // The generated index is only listed if it exists, and a page at the same path replaces it
+    let index_path = (config.html.index_page && !file_paths.iter().any(|path| path == index_path))
+        .then(|| index_path.to_string());
+    let page_paths = index_path.into_iter().chain(file_paths);
+
+    for page_path in page_paths {
+        sitemap.push_str(&format!(
+            "\t<url>\n\t\t<loc>{}</loc>\n\t</url>\n",
+            escape_title(&format!("{base_url}/{page_path}"))
+        ));
+    }
+
+    sitemap.push_str("</urlset>\n");
+    sitemap
+}
+
+/// The markdown the 404 page is generated from when the input directory has no `404.md`.
+pub const DEFAULT_NOT_FOUND_PAGE: &str = "---\ntitle: Page Not Found\n---\n# Page Not Found\n\nSorry, the page you were looking for doesn't exist.\n";
+
+/// Generates the `404.html` page, with the same head, navbar and footer as every other page.
+///
+/// Servers return the 404 page for URLs at any depth, so a `<base>` tag is added to make the
+/// page's relative links resolve from the site root, taken from the path of `base_url` if it's
+/// set.
+///
+/// # Arguments
+/// * `md_elements` - The parsed contents of `404.md`, or of [`DEFAULT_NOT_FOUND_PAGE`].
+/// * `output_dir` - The output directory, used to copy any images the page references.
+/// * `input_dir` - The input directory the page's images are relative to.
+/// * `page_list` - The names of all pages, used for the sidebar.
+/// * `frontmatter` - The front matter of `404.md`, if it has any.
+///
+/// # Returns
+/// Returns a `String` containing the generated HTML for the 404 page.
+pub fn generate_not_found_page(
+    md_elements: &[MdBlockElement],
+    output_dir: &str,
+    input_dir: &str,
+    page_list: &[String],
+    frontmatter: &Frontmatter,
+) -> String {
+    let config = &current_config();
+    let html_output = generate_html(
+        "404.md",
+        md_elements,
+        output_dir,
+        input_dir,
+        "404.html",
+        page_list,
+        frontmatter,
+    );
+
+    let base_tag = format!(
+        "<base href=\"{}\">",
+        escape_title(&site_root(&config.html.base_url))
+    );
+    match html_output.find("<head>") {
+        Some(index) => {
+            let (before, after) = html_output.split_at(index + "<head>".len());
+            if is_minify_enabled() {
+                format!("{before}{base_tag}{after}")
+            } else {
+                format!(
+                    "{before}\n{}{base_tag}{after}",
+                    config.html.indent_unit().repeat(2)
+                )
+            }
+        }
+        None => html_output,
+    }
+}
+
+/// Returns the path the site is served from, i.e. `/docs/` for `https://example.com/docs`, or `/`
+/// if `base_url` isn't set.
+fn site_root(base_url: &str) -> String {
+    let path = match base_url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |index| &rest[index..]),
+        None => base_url,
+    };
+    let path = path.trim_matches('/');
+
+    if path.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/{path}/")
+    }
+}
+
+/// A directory in the sidebar's page tree.
+#[derive(Default)]
+struct SidebarDir {
+    pages: Vec<String>,
+    subdirs: BTreeMap<String, SidebarDir>,
+}
+
+/// Generates a sidebar listing every page in the site, grouped by directory, with the current
+/// page marked as active.
+///
+/// # Arguments
+/// * `page_list` - The names of all pages, relative to the input directory.
+/// * `html_rel_path` - The relative path of the current page, used both for linking and to mark
+///   the active page.
+///
+/// # Returns
+/// Returns a `String` containing the sidebar's HTML.
+fn generate_sidebar(page_list: &[String], html_rel_path: &str) -> String {
+    let mut root = SidebarDir::default();
+    for page in page_list {
+        let mut dir = &mut root;
+        let mut components: Vec<&str> = page.split('/').collect();
+        components.pop();
+        for component in components {
+            dir = dir.subdirs.entry(component.to_string()).or_default();
+        }
+        dir.pages.push(page.clone());
+    }
+
+    let rel_prefix = build_rel_prefix(html_rel_path);
+    let tree_html = generate_sidebar_dir(&root, &rel_prefix, html_rel_path);
+
+    format!(
+        "<aside id=\"sidebar\">\n{}\n</aside>",
+        indent_html(&tree_html, 1)
+    )
+}
+
+/// Recursively generates the list for a directory in the sidebar, listing its pages before its
+/// subdirectories.
+fn generate_sidebar_dir(dir: &SidebarDir, rel_prefix: &Path, html_rel_path: &str) -> String {
+    let pretty_urls = current_config().html.pretty_urls;
+    let mut pages = dir.pages.clone();
+    pages.sort();
+
+    let mut items: Vec<String> = pages
+        .iter()
+        .map(|page| {
+            let page_path = output_relative_path(page, pretty_urls);
+            let mut href = rel_prefix
+                .join(page_link_path(page, pretty_urls))
+                .to_string_lossy()
+                .into_owned();
+            if href.is_empty() {
+                href = String::from("./");
+            }
+            let title = format_title(page.rsplit('/').next().unwrap_or(page));
+
+            if page == html_rel_path || page_path == html_rel_path {
+                format!(
+                    "<li><a href=\"{href}\" class=\"active\" aria-current=\"page\">{title}</a></li>"
+                )
+            } else {
+                format!("<li><a href=\"{href}\">{title}</a></li>")
+            }
+        })
+        .collect();
+
+    for (dir_name, subdir) in &dir.subdirs {
+        let subdir_html = generate_sidebar_dir(subdir, rel_prefix, html_rel_path);
+        items.push(format!(
+            "<li>\n{}\n{}\n</li>",
+            indent_html(&format!("<span>{}</span>", format_title(dir_name)), 1),
+            indent_html(&subdir_html, 1)
+        ));
+    }
+
+    format!("<ul>\n{}\n</ul>", indent_html(&items.join("\n"), 1))
+}
+
+/// Generates the list of footnotes that is appended to the end of a page, numbered in the order
+/// they were referenced. This must be called after the rest of the page has been rendered, since
+/// rendering the references is what numbers the footnotes.
+///
+/// # Arguments
+/// * `md_elements` - The blocks of the document, including its footnote definitions.
+/// * `output_dir` - The directory where the generated HTML file will be saved.
+/// * `input_dir` - The directory where the markdown files are located.
+/// * `html_rel_path` - The relative path to the HTML file from the output directory.
+///
+/// # Returns
+/// Returns the footnotes in a `<section class="footnotes">`, or an empty string if no footnotes
+/// were referenced. Footnotes that are defined but never referenced are left out.
+fn generate_footnotes(
+    md_elements: &[MdBlockElement],
+    output_dir: &str,
+    input_dir: &str,
+    html_rel_path: &str,
+) -> String {
+    let mut definitions: HashMap<&str, &[MdInlineElement]> = HashMap::new();
+    for element in md_elements {
+        if let MdBlockElement::FootnoteDefinition { label, content } = element {
+            // Like link references, the first definition of a label wins
+            definitions.entry(label.as_str()).or_insert(content);
+        }
+    }
+
+    let mut items = Vec::new();
+    // Footnotes can reference other footnotes, which are numbered as their notes are rendered
+    let mut index = 0;
+    while let Some(label) = FOOTNOTE_ORDER.with(|order| order.borrow().get(index).cloned()) {
+        index += 1;
+        let Some(content) = definitions.get(label.as_str()) else {
+            continue;
+        };
+
+        let note_html = content
+            .iter()
+            .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+            .collect::<String>();
+        let slug = slugify(&label);
+        items.push(format!(
+            "<li id=\"fn-{slug}\">{note_html} <a href=\"#fnref-{slug}\" class=\"footnote-backref\">↩</a></li>"
+        ));
+    }
+
+    if items.is_empty() {
+        return String::new();
+    }
+
+    let list = format!("<hr>\n<ol>\n{}\n</ol>", indent_html(&items.join("\n"), 1));
+    format!(
+        "<section class=\"footnotes\">\n{}\n</section>",
+        indent_html(&list, 1)
+    )
+}
+
+/// A heading collected for the table of contents.
+struct TocEntry {
+    level: u8,
+    text: String,
+    slug: String,
+}
+
+/// Returns whether the given block is a table of contents marker, i.e. a paragraph containing
+/// only `[[toc]]` or `{:toc}`.
+fn is_toc_marker(element: &MdBlockElement) -> bool {
+    match element {
+        MdBlockElement::Paragraph { content } => {
+            let text = content
+                .iter()
+                .map(MdInlineElement::to_plain_text)
+                .collect::<String>();
+            matches!(text.trim(), "[[toc]]" | "{:toc}")
+        }
+        _ => false,
+    }
+}
+
+/// Generates a nested list linking to every heading in the document, to replace any table of
+/// contents markers.
+///
+/// # Arguments
+/// * `md_elements` - The blocks of the document.
+/// * `min_level` - Headings with a lower level than this (i.e. `<h1>` when `min_level` is 2) are
+///   left out of the table of contents.
+///
+/// # Returns
+/// Returns the table of contents wrapped in a `<nav class="toc">`, or an empty string if there are
+/// no headings to list.
+fn generate_toc(md_elements: &[MdBlockElement], min_level: u8) -> String {
+    // Slugs are assigned in document order, so collecting them the same way the headings are
+    // rendered gives the same ids as the heading anchors
+    reset_heading_slugs();
+    let mut entries = Vec::new();
+    collect_toc_entries(md_elements, &mut entries);
+    reset_heading_slugs();
+
+    entries.retain(|entry| entry.level >= min_level);
+    if entries.is_empty() {
+        return String::new();
+    }
+
+    format!(
+        "<nav class=\"toc\">\n{}\n</nav>",
+        indent_html(&generate_toc_list(&entries), 1)
+    )
+}
+
+/// Recursively collects the headings in the given blocks, including those nested in blockquotes,
+/// details blocks, callouts and lists.
+fn collect_toc_entries(md_elements: &[MdBlockElement], entries: &mut Vec<TocEntry>) {
+    for element in md_elements {
+        match element {
+            MdBlockElement::Header { level, content } => {
+                let text = content
+                    .iter()
+                    .map(MdInlineElement::to_plain_text)
+                    .collect::<String>();
+                let slug = unique_heading_slug(&text);
+                entries.push(TocEntry {
+                    level: *level,
+                    text: text.replace('<', "&lt;").replace('>', "&gt;"),
+                    slug,
+                });
+            }
+            MdBlockElement::BlockQuote { content, .. }
+            | MdBlockElement::Details { content, .. }
+            | MdBlockElement::Callout { content, .. } => collect_toc_entries(content, entries),
+            MdBlockElement::UnorderedList { items, .. }
+            | MdBlockElement::OrderedList { items, .. } => {
+                for item in items {
+                    collect_toc_entries(std::slice::from_ref(&item.content), entries);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Builds a `<ul>` from the given headings, nesting each heading under the closest preceding
+/// heading with a lower level.
+fn generate_toc_list(entries: &[TocEntry]) -> String {
+    let mut items = Vec::new();
+
+    let mut i = 0;
+    while i < entries.len() {
+        let entry = &entries[i];
+        let mut end = i + 1;
+        while end < entries.len() && entries[end].level > entry.level {
+            end += 1;
+        }
+
+        let link = format!("<a href=\"#{}\">{}</a>", entry.slug, entry.text);
+        if end > i + 1 {
+            let sublist = generate_toc_list(&entries[i + 1..end]);
+            items.push(format!(
+                "<li>\n{}\n{}\n</li>",
+                indent_html(&link, 1),
+                indent_html(&sublist, 1)
+            ));
+        } else {
+            items.push(format!("<li>{link}</li>"));
+        }
+
+        i = end;
+    }
+
+    format!("<ul>\n{}\n</ul>", indent_html(&items.join("\n"), 1))
+}
+
+/// Generates the opening tag of the element that wraps each page's content, using the configured
+/// `content_id` and `content_class`.
+///
+/// # Arguments
+/// * `config` - The configuration to read the id and class from.
+///
+/// # Returns
+/// Returns the opening `<div>` tag, i.e. `<div id="content">`.
+fn content_wrapper_open_tag(config: &Config) -> String {
+    let content_id = &config.html.content_id;
+    let content_class = &config.html.content_class;
+
+    if content_class.is_empty() {
+        format!("<div id=\"{content_id}\">")
+    } else {
+        format!("<div id=\"{content_id}\" class=\"{content_class}\">")
+    }
+}
+
+/// Copies the extra stylesheets listed in a page's `css` front matter field to the output
+/// directory, skipping any that can't be copied.
+///
+/// # Arguments
+/// * `frontmatter` - The page's front matter.
+/// * `output_dir` - The directory where the generated HTML file will be saved. Stylesheets are
+///   only copied to it when it isn't empty.
+/// * `input_dir` - The directory where the markdown files are located, used for relative paths.
+/// * `html_rel_path` - The relative path to the HTML file from the output directory.
+///
+/// # Returns
+/// The URLs to link the page's stylesheets from, in the order they were listed. Absolute URLs are
+/// kept as written.
+fn page_stylesheets(
+    frontmatter: &Frontmatter,
+    output_dir: &str,
+    input_dir: &str,
+    html_rel_path: &str,
+) -> Vec<String> {
+    frontmatter
+        .css_files()
+        .into_iter()
+        .filter_map(|css_file| {
+            if !is_relative_url(css_file) || output_dir.is_empty() {
+                return Some(css_file.to_string());
+            }
 
-    html_output.push_str(&head);
-    html_output.push_str(&body);
-    html_output.push_str("</html>\n");
+            if let Err(e) = copy_page_css_to_output_dir(css_file, output_dir, input_dir) {
+                warn!(
+                    "Unable to copy stylesheet {css_file}, leaving it out of {html_rel_path}: {e}"
+                );
+                return None;
+            }
 
-    html_output
+            let file_name = css_file.rsplit('/').next().unwrap_or(css_file);
+            let css_path = build_rel_prefix(html_rel_path).join("css").join(file_name);
+            Some(css_path.to_string_lossy().replace('\\', "/"))
+        })
+        .collect()
 }
 
 /// Generates the HTML head section
 ///
 /// # Arguments
-/// * `file_name` - The name of the markdown file, used to set the title of the HTML document.
+/// * `title` - The title of the HTML document.
 /// * `html_rel_path` - The relative path to the HTML file from the output directory, used for
 ///   linking
-fn generate_head(file_name: &str, html_rel_path: &str, config: &Config) -> String {
-    let mut head = String::from(
-        r#"<!DOCTYPE html>
-    <html lang="en">
-    <head>
-        <meta charset="UTF-8">
-        <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    "#,
+/// * `frontmatter` - The page's front matter, whose `lang` and `dir` override the configured ones.
+/// * `page_css` - The URLs of the page's own stylesheets, from `page_stylesheets`.
+fn generate_head(
+    title: &str,
+    html_rel_path: &str,
+    config: &Config,
+    frontmatter: &Frontmatter,
+    page_css: &[String],
+) -> String {
+    let indent = config.html.indent_unit();
+    let mut head = format!(
+        "<!DOCTYPE html>\n{}\n{indent}<head>\n",
+        html_open_tag(config, frontmatter)
     );
-
-    // Remove the file extension from the file name and make it title case
-    let title = format_title(file_name);
-    head.push_str(&format!("\t<title>{}</title>\n", title));
+    // Everything inside the head is indented under it, so the tags below are written unindented
+    let mut tags = vec![
+        String::from("<meta charset=\"UTF-8\">"),
+        String::from("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">"),
+        format!("<title>{}</title>", title),
+    ];
 
     let favicon_file = &config.html.favicon_file;
     if !favicon_file.is_empty() {
@@ -155,10 +1027,7 @@ fn generate_head(file_name: &str, html_rel_path: &str, config: &Config) -> Strin
         favicon_path.push(favicon_file.rsplit("/").next().unwrap());
         let favicon_href = favicon_path.to_string_lossy();
 
-        head.push_str(&format!(
-            "\t<link rel=\"icon\" href=\"{}\">\n",
-            favicon_href
-        ));
+        tags.push(format!("<link rel=\"icon\" href=\"{}\">", favicon_href));
     }
 
     let css_file = &config.html.css_file;
@@ -166,69 +1035,455 @@ fn generate_head(file_name: &str, html_rel_path: &str, config: &Config) -> Strin
     css_path.push("styles.css");
     let css_href = css_path.to_string_lossy();
 
-    if css_file == "default" {
-        head.push_str(&format!(
-            "\t\t<link rel=\"stylesheet\" href=\"{}\">\n",
-            css_href
-        ));
+    if config.html.inline_css {
+        tags.push(format!("<style>\n{}\n</style>", inline_css().trim()));
+    } else if css_file == "default" {
+        tags.push(format!("<link rel=\"stylesheet\" href=\"{}\">", css_href));
     } else {
-        head.push_str(&format!(
-            "\t\t<link rel=\"stylesheet\" href=\"{}\">\n",
-            css_file
+        tags.push(format!("<link rel=\"stylesheet\" href=\"{}\">", css_file));
+    }
+
+    // Linked after the site stylesheet so that a page's own rules take precedence
+    for css_href in page_css {
+        tags.push(format!(
+            "<link rel=\"stylesheet\" href=\"{}\">",
+            escape_title(css_href)
         ));
     }
 
-    if config.html.use_prism {
+    // Only the delimiters written by the renderer are used, so stray dollar signs stay as text
+    if config.html.math {
+        tags.push(String::from("<link rel=\"stylesheet\" href=\"https://cdn.jsdelivr.net/npm/katex@0.16.22/dist/katex.min.css\" crossorigin=\"anonymous\">"));
+        tags.push(String::from("<script defer src=\"https://cdn.jsdelivr.net/npm/katex@0.16.22/dist/katex.min.js\" crossorigin=\"anonymous\"></script>"));
+        tags.push(String::from("<script defer src=\"https://cdn.jsdelivr.net/npm/katex@0.16.22/dist/contrib/auto-render.min.js\" crossorigin=\"anonymous\" onload=\"renderMathInElement(document.body, {delimiters: [{left: '\\\\(', right: '\\\\)', display: false}, {left: '\\\\[', right: '\\\\]', display: true}]});\"></script>"));
+    }
+
+    if config.html.prism_enabled() {
         if !config.html.prism_theme.is_empty() {
-            let theme = if config.html.sanitize_html {
-                &clean(&config.html.prism_theme)
+            let theme = if config.html.sanitize_enabled() {
+                &sanitize_html(&config.html.prism_theme, config)
             } else {
                 &config.html.prism_theme
             };
 
-            head.push_str(&format!("\t\t<link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/prism-themes/1.9.0/prism-{}.min.css\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\" />", theme));
+            tags.push(format!("<link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/prism-themes/1.9.0/prism-{}.min.css\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\" />", theme));
         } else {
-            head.push_str("\t\t<link rel=\"stylesheet\" href=\"https://cdn.jsdelivr.net/npm/prismjs@1.30.0/themes/prism-okaidia.min.css\">");
+            tags.push(String::from("<link rel=\"stylesheet\" href=\"https://cdn.jsdelivr.net/npm/prismjs@1.30.0/themes/prism-okaidia.min.css\">"));
         }
-        head.push_str("\t\t<link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/toolbar/prism-toolbar.min.css\" integrity=\"sha512-Dqf5696xtofgH089BgZJo2lSWTvev4GFo+gA2o4GullFY65rzQVQLQVlzLvYwTo0Bb2Gpb6IqwxYWtoMonfdhQ==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\" />");
-        head.push_str("\t\t<link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/line-numbers/prism-line-numbers.min.css\" integrity=\"sha512-cbQXwDFK7lj2Fqfkuxbo5iD1dSbLlJGXGpfTDqbggqjHJeyzx88I3rfwjS38WJag/ihH7lzuGlGHpDBymLirZQ==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\" />");
+        tags.push(String::from("<link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/toolbar/prism-toolbar.min.css\" integrity=\"sha512-Dqf5696xtofgH089BgZJo2lSWTvev4GFo+gA2o4GullFY65rzQVQLQVlzLvYwTo0Bb2Gpb6IqwxYWtoMonfdhQ==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\" />"));
+        tags.push(String::from("<link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/line-numbers/prism-line-numbers.min.css\" integrity=\"sha512-cbQXwDFK7lj2Fqfkuxbo5iD1dSbLlJGXGpfTDqbggqjHJeyzx88I3rfwjS38WJag/ihH7lzuGlGHpDBymLirZQ==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\" />"));
+    }
+
+    if let Some(head_partial) = render_partial(&config.html.head_partial, title, html_rel_path) {
+        tags.push(head_partial);
     }
 
-    head.push_str("\t</head>\n");
+    head.push_str(&indent_html(&tags.join("\n"), 2));
+    head.push_str(&format!("\n{indent}</head>\n"));
     head
 }
 
-/// Generates the HTML for the navigation bar
-fn generate_navbar(html_rel_path: &str) -> String {
-    let mut navbar = String::from("<header>\n\t<nav>\n\t\t<ul>\n");
+/// Generates the opening `<html>` tag, with the page's `lang` and `dir` attributes.
+///
+/// # Arguments
+/// * `config` - The configuration to read the default `lang` and `dir` from.
+/// * `frontmatter` - The page's front matter, whose `lang` and `dir` take precedence.
+///
+/// # Returns
+/// Returns the opening tag, i.e. `<html lang="ar" dir="rtl">`. Empty or invalid values leave the
+/// attribute out.
+fn html_open_tag(config: &Config, frontmatter: &Frontmatter) -> String {
+    let lang = frontmatter.lang().unwrap_or(&config.html.lang);
+    let dir = frontmatter.dir().unwrap_or(&config.html.dir);
+
+    let mut tag = String::from("<html");
+    if !lang.is_empty() {
+        tag.push_str(&format!(
+            " lang=\"{}\"",
+            escape_title(lang).replace('"', "&quot;")
+        ));
+    }
+
+    match dir {
+        "" => {}
+        "ltr" | "rtl" | "auto" => tag.push_str(&format!(" dir=\"{dir}\"")),
+        _ => warn!("Ignoring invalid dir \"{dir}\", expected \"ltr\", \"rtl\" or \"auto\""),
+    }
+
+    tag.push('>');
+    tag
+}
+
+/// Generates the HTML for the navigation bar from the configured `nav` links, or with a single
+/// Home link if there are none. A page is marked as active in the navbar when a link points to it.
+fn generate_navbar(title: &str, html_rel_path: &str, config: &Config) -> String {
+    if let Some(nav_partial) = render_partial(&config.html.nav_partial, title, html_rel_path) {
+        return format!("{nav_partial}\n\n");
+    }
+
+    let indent = |level: usize| config.html.indent_unit().repeat(level);
+    let mut navbar = format!("<header>\n{}<nav>\n{}<ul>\n", indent(1), indent(2));
+
+    let rel_prefix = build_rel_prefix(html_rel_path);
+    let items: Vec<String> = if config.html.nav.is_empty() {
+        let mut home_path = rel_prefix;
+        // Without a generated index, the Home link can point at one of the site's own pages
+        let home_page = match config.html.index_page {
+            true => "",
+            false => config.html.home_page.trim_start_matches("./"),
+        };
+        if !home_page.is_empty() {
+            home_path.push(home_page);
+        } else if !config.html.pretty_urls {
+            home_path.push("index.html");
+        }
+        let home_href = match home_path.to_string_lossy() {
+            href if href.is_empty() => "./".into(),
+            href if config.html.pretty_urls && home_page.is_empty() => format!("{href}/").into(),
+            href => href,
+        };
 
-    let mut home_path = build_rel_prefix(html_rel_path);
-    home_path.push("index.html");
-    let home_href = home_path.to_string_lossy();
+        vec![format!(
+            "{}<li><a href=\"{}\">Home</a></li>",
+            indent(3),
+            home_href
+        )]
+    } else {
+        config
+            .html
+            .nav
+            .iter()
+            .map(|link| {
+                let label = escape_title(&link.label);
+                if !is_relative_url(&link.url) {
+                    return format!(
+                        "{}<li><a href=\"{}\">{label}</a></li>",
+                        indent(3),
+                        link.url
+                    );
+                }
+
+                // Relative links are written relative to the site root, so they're resolved
+                // from the current page the same way as the Home link
+                let target = link.url.trim_start_matches("./");
+                let mut href = rel_prefix.join(target).to_string_lossy().into_owned();
+                if href.is_empty() {
+                    href = String::from("./");
+                }
+
+                let is_current_page = target == html_rel_path
+                    || html_rel_path.strip_suffix("index.html") == Some(target);
+                if is_current_page {
+                    format!(
+                        "{}<li><a href=\"{href}\" class=\"active\" aria-current=\"page\">{label}</a></li>",
+                        indent(3)
+                    )
+                } else {
+                    format!("{}<li><a href=\"{href}\">{label}</a></li>", indent(3))
+                }
+            })
+            .collect()
+    };
 
+    navbar.push_str(&items.join("\n"));
     navbar.push_str(&format!(
-        "\t\t\t<li><a href=\"{}\">Home</a></li>",
-        home_href
+        "\n{}</ul>\n{}</nav>\n</header>\n\n",
+        indent(2),
+        indent(1)
     ));
-    navbar.push_str("\n\t\t</ul>\n\t</nav>\n</header>\n\n");
     navbar
 }
+
+/// Counts the words in a page's parsed elements, including its footnotes.
+///
+/// # Arguments
+/// * `md_elements` - The parsed elements of the page.
+/// * `include_code` - Whether to count the words in code blocks.
+///
+/// # Returns
+/// The number of whitespace-separated words in the page's text.
+pub fn count_words(md_elements: &[MdBlockElement], include_code: bool) -> usize {
+    let count_inline =
+        |content: &[MdInlineElement]| flatten_inline(content).split_whitespace().count();
+
+    md_elements
+        .iter()
+        .map(|element| match element {
+            MdBlockElement::Header { content, .. }
+            | MdBlockElement::Paragraph { content }
+            | MdBlockElement::FootnoteDefinition { content, .. } => count_inline(content),
+            MdBlockElement::CodeBlock { lines, .. } if include_code => lines
+                .iter()
+                .map(|line| line.split_whitespace().count())
+                .sum(),
+            MdBlockElement::UnorderedList { items, .. }
+            | MdBlockElement::OrderedList { items, .. } => items
+                .iter()
+                .map(|item| count_words(std::slice::from_ref(&item.content), include_code))
+                .sum(),
+            MdBlockElement::Table { headers, body } => headers
+                .iter()
+                .chain(body.iter().flatten())
+                .map(|cell| count_inline(&cell.content))
+                .sum(),
+            MdBlockElement::BlockQuote { content, citation } => {
+                count_words(content, include_code) + citation.as_deref().map_or(0, count_inline)
+            }
+            MdBlockElement::Details { summary, content } => {
+                count_inline(summary) + count_words(content, include_code)
+            }
+            MdBlockElement::Callout { content, .. } => count_words(content, include_code),
+            MdBlockElement::DefinitionList { items } => items
+                .iter()
+                .flat_map(|item| item.terms.iter().chain(&item.definitions))
+                .map(|content| count_inline(content))
+                .sum(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Rewrites every relative link to a markdown page (i.e. `[Setup](setup.md)`) to point at the HTML
+/// page generated from it. See [`markdown_link_href`].
+///
+/// # Arguments
+/// * `md_elements` - The parsed elements of the page, whose links are rewritten in place.
+/// * `page_path` - The path of the page, relative to the input directory.
+/// * `pretty_urls` - Whether pages are written as `name/index.html` rather than `name.html`.
+pub fn rewrite_page_links(md_elements: &mut [MdBlockElement], page_path: &str, pretty_urls: bool) {
+    let rewrite_inline =
+        |content: &mut [MdInlineElement]| rewrite_inline_links(content, page_path, pretty_urls);
+
+    for element in md_elements {
+        match element {
+            MdBlockElement::Header { content, .. }
+            | MdBlockElement::Paragraph { content }
+            | MdBlockElement::FootnoteDefinition { content, .. } => rewrite_inline(content),
+            MdBlockElement::UnorderedList { items, .. }
+            | MdBlockElement::OrderedList { items, .. } => {
+                for item in items {
+                    rewrite_page_links(
+                        std::slice::from_mut(&mut item.content),
+                        page_path,
+                        pretty_urls,
+                    );
+                }
+            }
+            MdBlockElement::Table { headers, body } => {
+                for cell in headers.iter_mut().chain(body.iter_mut().flatten()) {
+                    rewrite_inline(&mut cell.content);
+                }
+            }
+            MdBlockElement::BlockQuote { content, citation } => {
+                rewrite_page_links(content, page_path, pretty_urls);
+                if let Some(citation) = citation {
+                    rewrite_inline(citation);
+                }
+            }
+            MdBlockElement::Details { summary, content } => {
+                rewrite_inline(summary);
+                rewrite_page_links(content, page_path, pretty_urls);
+            }
+            MdBlockElement::Callout { content, .. } => {
+                rewrite_page_links(content, page_path, pretty_urls)
+            }
+            MdBlockElement::DefinitionList { items } => {
+                for content in items
+                    .iter_mut()
+                    .flat_map(|item| item.terms.iter_mut().chain(&mut item.definitions))
+                {
+                    rewrite_inline(content);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Rewrites the links to markdown pages in inline elements, including those nested in other
+/// elements.
+fn rewrite_inline_links(content: &mut [MdInlineElement], page_path: &str, pretty_urls: bool) {
+    for element in content {
+        match element {
+            MdInlineElement::Link { text, url, .. } => {
+                if let Some(href) = markdown_link_href(url, page_path, pretty_urls) {
+                    *url = href;
+                }
+                rewrite_inline_links(text, page_path, pretty_urls);
+            }
+            MdInlineElement::Bold { content }
+            | MdInlineElement::Italic { content }
+            | MdInlineElement::Strikethrough { content }
+            | MdInlineElement::Subscript { content }
+            | MdInlineElement::Superscript { content } => {
+                rewrite_inline_links(content, page_path, pretty_urls)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Generates the word count and estimated reading time shown above a page's content.
+///
+/// # Arguments
+/// * `md_elements` - The parsed elements of the page.
+/// * `config` - The configuration, used for the reading speed and whether to count code blocks.
+///
+/// # Returns
+/// Returns a `<p>` element with the word count and reading time, rounded up to whole minutes.
+fn generate_reading_time(md_elements: &[MdBlockElement], config: &Config) -> String {
+    let words = count_words(md_elements, config.html.reading_time_include_code);
+    let minutes = words.div_ceil(config.html.words_per_minute.max(1)).max(1);
+    let word_label = if words == 1 { "word" } else { "words" };
+
+    format!("<p class=\"reading-time\">{words} {word_label} &middot; {minutes} min read</p>")
+}
+
+/// Formats a page's date with the configured `date_format`, as a `<time>` element.
+fn format_date(date: &PageDate, config: &Config) -> String {
+    format!(
+        "<time datetime=\"{}\">{}</time>",
+        date.iso_date(),
+        escape_title(&date.format(&config.html.date_format))
+    )
+}
+
+/// Generates the links to the previous and next pages in navigation order. The first page has no
+/// previous link and the last page has no next link.
+///
+/// # Arguments
+/// * `file_name` - The name of the current page's markdown file.
+/// * `nav_order` - The pages in navigation order.
+/// * `html_rel_path` - The relative path to the HTML file from the output directory.
+/// * `config` - The configuration, used to determine whether pretty URLs are enabled.
+///
+/// # Returns
+/// Returns the `<nav>` element, or an empty string if the page isn't in the navigation order or
+/// has no neighbours.
+fn generate_page_nav(
+    file_name: &str,
+    nav_order: &[String],
+    html_rel_path: &str,
+    config: &Config,
+) -> String {
+    let Some(index) = nav_order.iter().position(|page| page == file_name) else {
+        return String::new();
+    };
+
+    let rel_prefix = build_rel_prefix(html_rel_path);
+    let page_link = |page: &String, rel: &str, label: String| {
+        let mut href = rel_prefix
+            .join(page_link_path(page, config.html.pretty_urls))
+            .to_string_lossy()
+            .into_owned();
+        if href.is_empty() {
+            href = String::from("./");
+        }
+
+        indent_html(
+            &format!("<a class=\"{rel}\" href=\"{href}\" rel=\"{rel}\">{label}</a>"),
+            1,
+        )
+    };
+    let page_title = |page: &String| format_title(page.rsplit('/').next().unwrap_or(page));
+
+    let mut links: Vec<String> = Vec::new();
+    if let Some(previous) = index.checked_sub(1).and_then(|i| nav_order.get(i)) {
+        links.push(page_link(
+            previous,
+            "prev",
+            format!("&larr; {}", page_title(previous)),
+        ));
+    }
+    if let Some(next) = nav_order.get(index + 1) {
+        links.push(page_link(
+            next,
+            "next",
+            format!("{} &rarr;", page_title(next)),
+        ));
+    }
+
+    if links.is_empty() {
+        return String::new();
+    }
+
+    format!("<nav class=\"page-nav\">\n{}\n</nav>", links.join("\n"))
+}
+
+/// Generates the footer for a page from the configured `footer_partial`.
+///
+/// # Arguments
+/// * `title` - The title of the page, used for the `{{title}}` placeholder.
+/// * `html_rel_path` - The relative path to the HTML file from the output directory.
+/// * `config` - The configuration to read the footer partial from.
+///
+/// # Returns
+/// Returns the footer HTML, or an empty string if there is no footer partial.
+fn generate_footer(title: &str, html_rel_path: &str, config: &Config) -> String {
+    render_partial(&config.html.footer_partial, title, html_rel_path).unwrap_or_default()
+}
+
+/// Reads a partial template file and fills in its placeholders.
+///
+/// The supported placeholders are `{{title}}`, the page title, and `{{root}}`, the relative path
+/// from the page to the root of the output directory (i.e. `..` for a page one directory deep).
+///
+/// # Arguments
+/// * `partial_path` - The path to the partial file. If empty, there is no partial.
+/// * `title` - The title of the page.
+/// * `html_rel_path` - The relative path to the HTML file from the output directory.
+///
+/// # Returns
+/// Returns the filled-in partial, or `None` if there is no partial or it can't be read.
+fn render_partial(partial_path: &str, title: &str, html_rel_path: &str) -> Option<String> {
+    if partial_path.is_empty() {
+        return None;
+    }
+
+    let partial = read_file(partial_path)
+        .map_err(|e| warn!("Unable to read partial {partial_path}: {e}"))
+        .ok()?;
+
+    let rel_prefix = build_rel_prefix(html_rel_path);
+    let root = if rel_prefix.as_os_str().is_empty() {
+        String::from(".")
+    } else {
+        rel_prefix.to_string_lossy().to_string()
+    };
+
+    Some(
+        partial
+            .trim_end()
+            .replace("{{title}}", title)
+            .replace("{{root}}", &root),
+    )
+}
+
+/// Escapes a title taken from front matter so it can be placed in the `<title>` element as-is.
+pub(crate) fn escape_title(title: &str) -> String {
+    title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 /// Formats the file name to create a title for the HTML document
 ///
 /// # Arguments
 /// * `file_name` - The name of the file, typically ending with `.md`.
 ///
 /// # Returns
-/// The formatted title (i.e. "my_test_page.md" -> "My Test Page")
-fn format_title(file_name: &str) -> String {
-    let title = file_name.trim_end_matches(".md").replace('_', " ");
+/// The formatted title (i.e. "my_test_page.md" or "my-test-page.md" -> "My Test Page"). Only the
+/// first letter of each word is changed, so the rest keeps its case.
+pub(crate) fn format_title(file_name: &str) -> String {
+    let title = file_name.trim_end_matches(".md").replace(['_', '-'], " ");
 
     title
         .split_whitespace()
         .map(|word| {
             let mut chars = word.chars();
             match chars.next() {
-                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+                Some(first) => to_titlecase(first) + chars.as_str(),
                 None => String::new(),
             }
         })
@@ -236,9 +1491,41 @@ fn format_title(file_name: &str) -> String {
         .join(" ")
 }
 
-/// Indents each line of the given HTML string by the specified number of tabs.
+/// Maps a character to its titlecase form, which is what the first letter of a word takes.
+///
+/// This differs from the uppercase form for digraphs (i.e. "ǆ" -> "ǅ", not "Ǆ"), Greek letters with
+/// an iota subscript, and characters that uppercase to more than one character, where only the
+/// first is uppercased (i.e. "ß" -> "Ss" and "ﬁ" -> "Fi").
+fn to_titlecase(ch: char) -> String {
+    let titlecase = match ch {
+        'Ǆ'..='ǆ' => Some('ǅ'),
+        'Ǉ'..='ǉ' => Some('ǈ'),
+        'Ǌ'..='ǌ' => Some('ǋ'),
+        'Ǳ'..='ǳ' => Some('ǲ'),
+        // Each of these blocks is eight lowercase letters followed by their titlecase forms
+        '\u{1F80}'..='\u{1F87}' | '\u{1F90}'..='\u{1F97}' | '\u{1FA0}'..='\u{1FA7}' => {
+            char::from_u32(ch as u32 + 8)
+        }
+        'ᾳ' => Some('ᾼ'),
+        'ῃ' => Some('ῌ'),
+        'ῳ' => Some('ῼ'),
+        _ => None,
+    };
+    if let Some(titlecase) = titlecase {
+        return titlecase.to_string();
+    }
+
+    let mut uppercase = ch.to_uppercase();
+    match uppercase.next() {
+        Some(first) => first.to_string() + &uppercase.collect::<String>().to_lowercase(),
+        None => ch.to_string(),
+    }
+}
+
+/// Indents each line of the given HTML string by the specified number of levels, using the
+/// configured `indent`.
 pub fn indent_html(html: &str, level: usize) -> String {
-    let indent = "\t".repeat(level);
+    let indent = current_config().html.indent_unit().repeat(level);
     html.lines()
         .map(|line| {
             let first_non_whitespace_token = line.chars().find(|c| !c.is_whitespace());
@@ -253,9 +1540,13 @@ pub fn indent_html(html: &str, level: usize) -> String {
         .join("\n")
 }
 
-/// Generates a default CSS stylesheet as a string.
+/// Generates a default CSS stylesheet as a string, styling the content wrapper by its configured
+/// `content_id`. The stylesheet is minified when minification is enabled.
 pub fn generate_default_css() -> String {
-    r#"
+    let config = &current_config();
+    let content_id = &config.html.content_id;
+
+    let css = r#"
     body {
     background-color: #121212;
     color: #e0e0e0;
@@ -320,6 +1611,85 @@ pub fn generate_default_css() -> String {
     background-color: #4ea1f3;
     color: #121212;
     }
+
+    /* Sidebar listing every page, shown beside the page content */
+    .sidebar-layout {
+    display: flex;
+    align-items: flex-start;
+    gap: 1.5rem;
+    max-width: 1100px;
+    margin: 0 auto;
+    padding: 0 1rem;
+    }
+
+    .sidebar-layout > :last-child {
+    flex: 1;
+    min-width: 0;
+    }
+
+    #sidebar {
+    position: sticky;
+    top: 5rem;
+    flex: 0 0 220px;
+    margin-top: 1.5rem;
+    padding: 1rem;
+    background-color: #1e1e1e;
+    border-radius: 12px;
+    box-shadow: 0 0 0 1px #2c2c2c;
+    }
+
+    #sidebar ul {
+    list-style: none;
+    margin: 0;
+    padding-left: 1rem;
+    }
+
+    #sidebar > ul {
+    padding-left: 0;
+    }
+
+    #sidebar span {
+    color: #aaa;
+    font-weight: bold;
+    }
+
+    #sidebar a.active {
+    color: #4ea1f3;
+    font-weight: bold;
+    }
+
+    /* The date from the page's front matter, and the word count and reading time, above the page
+    content */
+    .page-date,
+    .reading-time {
+    color: #aaa;
+    font-size: 0.9rem;
+    }
+
+    /* Links to the previous and next pages, below the page content */
+    .page-nav {
+    display: flex;
+    justify-content: space-between;
+    gap: 1rem;
+    margin-top: 2rem;
+    padding-top: 1rem;
+    border-top: 1px solid #2c2c2c;
+    }
+
+    .page-nav a.next {
+    margin-left: auto;
+    }
+
+    @media (max-width: 900px) {
+    .sidebar-layout {
+        flex-direction: column;
+        align-items: stretch;
+    }
+
+    #sidebar {
+        position: static;
+    }
+    }
     h1,
     h2,
     h3,
@@ -417,6 +1787,20 @@ pub fn generate_default_css() -> String {
     text-align: right;
     }
 
+    /* Styles for when "highlighter = \"syntect\"" is set in config.toml */
+    pre.highlight {
+    padding: 1rem;
+    border-radius: 8px;
+    overflow-x: auto;
+    font-size: 0.9rem;
+    }
+    pre.highlight code.hl-line {
+    display: block;
+    min-height: 1.2em;
+    white-space: pre;
+    font-family: SFMono-Regular, Consolas, "Liberation Mono", Menlo, monospace;
+    }
+
     code {
     font-style: normal;
     }
@@ -431,6 +1815,69 @@ pub fn generate_default_css() -> String {
     border-radius: 2px;
     }
 
+    /* Blockquotes ending in a "-- Author" line */
+    figure {
+    margin: 1.5rem 0;
+    }
+    figure blockquote {
+    margin-bottom: 0.5rem;
+    }
+    figcaption {
+    color: #888;
+    font-size: 0.9rem;
+    text-align: right;
+    }
+    figcaption cite {
+    font-style: normal;
+    }
+
+    /* Collapsible "[!details]" blocks */
+    details {
+    border: 1px solid #333;
+    border-radius: 4px;
+    padding: 0.5rem 1rem;
+    margin: 1.5rem 0;
+    background-color: #1a1a1a;
+    }
+    summary {
+    cursor: pointer;
+    font-weight: 600;
+    }
+    details[open] > summary {
+    margin-bottom: 0.5rem;
+    }
+
+    /* GitHub-style "[!NOTE]" callouts */
+    .callout {
+    border-left: 4px solid var(--callout-color);
+    padding: 0.1rem 1rem;
+    margin: 1.5rem 0;
+    background-color: #1a1a1a;
+    border-radius: 2px;
+    }
+    .callout-title {
+    color: var(--callout-color);
+    font-weight: 600;
+    }
+    .callout-icon {
+    margin-right: 0.4em;
+    }
+    .callout-note {
+    --callout-color: #4493f8;
+    }
+    .callout-tip {
+    --callout-color: #3fb950;
+    }
+    .callout-important {
+    --callout-color: #ab7df8;
+    }
+    .callout-warning {
+    --callout-color: #d29922;
+    }
+    .callout-caution {
+    --callout-color: #f85149;
+    }
+
     .toolbar-item {
     font-style: normal;
     margin-right: 0.2em;
@@ -445,6 +1892,39 @@ pub fn generate_default_css() -> String {
     margin-bottom: 0.5rem;
     }
 
+    .heading-anchor {
+    margin-left: 0.4rem;
+    color: #888;
+    text-decoration: none;
+    visibility: hidden;
+    }
+
+    h1:hover .heading-anchor,
+    h2:hover .heading-anchor,
+    h3:hover .heading-anchor,
+    h4:hover .heading-anchor,
+    h5:hover .heading-anchor,
+    h6:hover .heading-anchor {
+    visibility: visible;
+    }
+
+    .footnotes {
+    margin-top: 3rem;
+    font-size: 0.9rem;
+    }
+
+    .footnote-backref {
+    text-decoration: none;
+    }
+
+    li.task-list-item {
+    list-style: none;
+    }
+
+    li.task-list-item > p {
+    display: inline;
+    }
+
     table {
     width: 100%;
     border-spacing: 0;
@@ -487,5 +1967,55 @@ pub fn generate_default_css() -> String {
     margin: 2rem 0;
     }
     "#
-    .to_string()
+    .replace("#content {", &format!("#{content_id} {{"));
+
+    let css = if config.html.syntect_enabled() {
+        css + &theme_css(&config.html.syntect_theme)
+    } else {
+        css
+    };
+
+    let css = match config.html.code_collapse_lines {
+        0 => css,
+        lines => css + &code_collapse_css(lines),
+    };
+
+    if is_minify_enabled() {
+        minify_css(&css)
+    } else {
+        css
+    }
+}
+
+/// Generates the styles for collapsed code blocks, which are cut off after the given number of
+/// lines until their "Show more" toggle is checked.
+fn code_collapse_css(lines: usize) -> String {
+    format!(
+        r#"
+    /* Styles for code blocks longer than "code_collapse_lines" */
+    .code-collapse:not(:has(.code-collapse-toggle input:checked)) pre {{
+    max-height: calc({lines}lh + 2rem);
+    overflow-y: hidden;
+    }}
+    .code-collapse-toggle {{
+    display: inline-block;
+    margin-top: 0.5rem;
+    color: #8ab4f8;
+    font-size: 0.9rem;
+    cursor: pointer;
+    }}
+    .code-collapse-toggle input {{
+    display: none;
+    }}
+    .code-collapse-toggle::after {{
+    content: "Show more";
+    }}
+    .code-collapse-toggle:has(input:checked)::after {{
+    content: "Show less";
+    }}
+    "#
+    )
 }
+
+#[cfg(test)]
+mod test;
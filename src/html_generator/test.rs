@@ -0,0 +1,822 @@
+use crate::CONFIG;
+use crate::config::{Config, NavLink, with_config};
+use crate::diagnostics::Diagnostics;
+use crate::frontmatter::{Frontmatter, extract_frontmatter};
+#[cfg(feature = "sanitize")]
+use crate::html_generator::sanitize_html;
+use crate::html_generator::{
+    DEFAULT_NOT_FOUND_PAGE, PageOutput, content_wrapper_open_tag, count_words, format_title,
+    generate_default_css, generate_footer, generate_footnotes, generate_head, generate_html,
+    generate_index, generate_navbar, generate_not_found_page, generate_page_nav,
+    generate_reading_time, generate_sidebar, generate_sitemap, generate_toc, html_open_tag,
+    is_toc_marker, page_output, page_stylesheets, reset_footnote_numbers, reset_heading_slugs,
+    set_inline_css, site_root,
+};
+use crate::lexer::tokenize;
+use crate::parser::{group_lines_to_blocks, parse_blocks};
+use crate::types::ToHtml;
+
+use std::fs;
+use std::sync::Once;
+use tempfile::{NamedTempFile, TempDir};
+static INIT: Once = Once::new();
+
+fn init_test_config() {
+    INIT.call_once(|| {
+        CONFIG.get_or_init(Config::default);
+    });
+}
+
+fn render(lines: &[&str]) -> String {
+    let blocks = group_lines_to_blocks(lines.iter().map(|line| tokenize(line)).collect());
+    parse_blocks(&blocks)
+        .iter()
+        .map(|block| block.to_html("test_output", "test_input", "test_rel_path"))
+        .collect()
+}
+
+#[cfg(feature = "sanitize")]
+#[test]
+fn named_anchor_survives_sanitization() {
+    init_test_config();
+    let html = sanitize_html(
+        &render(&[
+            "<a name=\"intro\" id=\"intro\"></a>Introduction",
+            "",
+            "[Back to the introduction](#intro)",
+        ]),
+        &Config::default(),
+    );
+
+    assert!(html.contains("<a name=\"intro\" id=\"intro\""), "{html}");
+    assert!(html.contains("href=\"#intro\""), "{html}");
+}
+
+#[cfg(feature = "sanitize")]
+#[test]
+fn sanitizer_allows_configured_tags_and_attributes() {
+    let html = "<figure class=\"wide\"><img src=\"a.png\"><figcaption>A</figcaption></figure>\
+                <div class=\"callout grid\" data-x=\"1\">B</div><dialog open=\"\">C</dialog>";
+    assert_eq!(
+        sanitize_html(html, &Config::default()),
+        "<figure><img src=\"a.png\"><figcaption>A</figcaption></figure>\
+         <div class=\"callout\" data-x=\"1\">B</div>C"
+    );
+
+    let mut config = Config::default();
+    config.html.sanitize.allowed_tags = vec![String::from("dialog")];
+    config.html.sanitize.allowed_attributes = [
+        (String::from("dialog"), vec![String::from("open")]),
+        (String::from("*"), vec![String::from("class")]),
+    ]
+    .into();
+
+    assert_eq!(
+        sanitize_html(html, &config),
+        "<figure class=\"wide\"><img src=\"a.png\"><figcaption>A</figcaption></figure>\
+         <div class=\"callout grid\" data-x=\"1\">B</div><dialog open=\"\">C</dialog>"
+    );
+}
+
+#[cfg(feature = "sanitize")]
+#[test]
+fn sanitizer_skips_invalid_allowlist_entries() {
+    let mut config = Config::default();
+    config.html.sanitize.allowed_tags = vec![
+        String::from("script"),
+        String::from("not a tag"),
+        String::from("mark"),
+    ];
+    config.html.sanitize.allowed_attributes = [(
+        String::from("a"),
+        vec![String::from("onclick"), String::from("rel")],
+    )]
+    .into();
+
+    let html = sanitize_html(
+        "<script>alert(1)</script><a href=\"x.html\" onclick=\"alert(1)\"><mark>x</mark></a>",
+        &config,
+    );
+    assert_eq!(
+        html,
+        "<a href=\"x.html\" rel=\"noopener noreferrer\"><mark>x</mark></a>"
+    );
+}
+
+#[test]
+fn duplicate_headings_get_numbered_ids() {
+    init_test_config();
+    reset_heading_slugs();
+    let html = render(&["# Setup", "## Setup", "## Setup", "### Setup 1"]);
+
+    assert!(html.contains("<h1 id=\"setup\">"), "{html}");
+    assert!(html.contains("<h2 id=\"setup-1\">"), "{html}");
+    assert!(html.contains("<h2 id=\"setup-2\">"), "{html}");
+    assert!(html.contains("<h3 id=\"setup-1-1\">"), "{html}");
+    assert!(html.contains("href=\"#setup-2\""), "{html}");
+}
+
+#[test]
+fn index_without_pages_says_so() {
+    init_test_config();
+    let index_html = generate_index(&[]);
+
+    assert!(
+        index_html.contains("<p>No pages found.</p>"),
+        "{index_html}"
+    );
+    let about = page_output("about.md", &[], &Frontmatter::default());
+    assert!(!generate_index(&[about]).contains("No pages found"));
+}
+
+#[test]
+fn index_lists_page_titles() {
+    init_test_config();
+    let (frontmatter, markdown) =
+        extract_frontmatter("---\ntitle: Getting <Started>\n---\n# Setup\n\nInstall it.\n\n## Run");
+    let page = page_output(
+        "guides/setup.md",
+        &crate::parse_markdown(markdown),
+        &frontmatter,
+    );
+
+    assert_eq!(
+        page,
+        PageOutput {
+            path: String::from("guides/setup.md"),
+            title: String::from("Getting &lt;Started&gt;"),
+            word_count: 4,
+            headings: vec![String::from("Setup"), String::from("Run")],
+            date: None,
+        }
+    );
+    let index_html = generate_index(&[page]);
+    assert!(
+        index_html.contains("<a href=\"./guides/setup.html\">Getting &lt;Started&gt;</a>"),
+        "{index_html}"
+    );
+}
+
+fn parse(lines: &[&str]) -> Vec<crate::types::MdBlockElement> {
+    parse_blocks(&group_lines_to_blocks(
+        lines.iter().map(|line| tokenize(line)).collect(),
+    ))
+}
+
+#[test]
+fn footnotes_are_numbered_in_reference_order() {
+    init_test_config();
+    let blocks = parse(&[
+        "Second[^b], first[^a], second again[^b].",
+        "",
+        "[^a]: Note A",
+        "[^b]: Note B",
+        "[^unused]: Never referenced",
+    ]);
+    reset_footnote_numbers();
+    let body = blocks[0].to_html("test_output", "test_input", "test_rel_path");
+
+    assert_eq!(
+        body,
+        "<p>Second<sup class=\"footnote-ref\"><a href=\"#fn-b\" id=\"fnref-b\">1</a></sup>, first<sup class=\"footnote-ref\"><a href=\"#fn-a\" id=\"fnref-a\">2</a></sup>, second again<sup class=\"footnote-ref\"><a href=\"#fn-b\">1</a></sup>.</p>"
+    );
+    assert_eq!(
+        generate_footnotes(&blocks, "test_output", "test_input", "test_rel_path"),
+        "<section class=\"footnotes\">\n\t<hr>\n\t<ol>\n\t\t<li id=\"fn-b\">Note B <a href=\"#fnref-b\" class=\"footnote-backref\">↩</a></li>\n\t\t<li id=\"fn-a\">Note A <a href=\"#fnref-a\" class=\"footnote-backref\">↩</a></li>\n\t</ol>\n</section>"
+    );
+}
+
+#[test]
+fn toc_markers_are_recognized() {
+    init_test_config();
+    let blocks = parse(&["[[toc]]", "", "{:toc}", "", "Not [[toc]]"]);
+
+    assert!(is_toc_marker(&blocks[0]));
+    assert!(is_toc_marker(&blocks[1]));
+    assert!(!is_toc_marker(&blocks[2]));
+}
+
+#[test]
+fn toc_nests_headings_by_level() {
+    init_test_config();
+    let blocks = parse(&["# Guide", "## Install", "### Linux", "## Usage", "## Usage"]);
+
+    assert_eq!(
+        generate_toc(&blocks, 1),
+        "<nav class=\"toc\">\n\t<ul>\n\t\t<li>\n\t\t\t<a href=\"#guide\">Guide</a>\n\t\t\t<ul>\n\t\t\t\t<li>\n\t\t\t\t\t<a href=\"#install\">Install</a>\n\t\t\t\t\t<ul>\n\t\t\t\t\t\t<li><a href=\"#linux\">Linux</a></li>\n\t\t\t\t\t</ul>\n\t\t\t\t</li>\n\t\t\t\t<li><a href=\"#usage\">Usage</a></li>\n\t\t\t\t<li><a href=\"#usage-1\">Usage</a></li>\n\t\t\t</ul>\n\t\t</li>\n\t</ul>\n</nav>"
+    );
+}
+
+#[test]
+fn toc_skips_headings_below_min_level() {
+    init_test_config();
+    let blocks = parse(&["# Guide", "## Install"]);
+
+    assert_eq!(
+        generate_toc(&blocks, 2),
+        "<nav class=\"toc\">\n\t<ul>\n\t\t<li><a href=\"#install\">Install</a></li>\n\t</ul>\n</nav>"
+    );
+}
+
+#[test]
+fn toc_is_empty_without_headings() {
+    init_test_config();
+    assert_eq!(generate_toc(&parse(&["Just a paragraph."]), 1), "");
+}
+
+#[test]
+fn content_wrapper_uses_default_id() {
+    assert_eq!(
+        content_wrapper_open_tag(&Config::default()),
+        "<div id=\"content\">"
+    );
+}
+
+#[test]
+fn content_wrapper_uses_configured_id_and_class() {
+    let mut config = Config::default();
+    config.html.content_id = String::from("main");
+    config.html.content_class = String::from("container prose");
+
+    assert_eq!(
+        content_wrapper_open_tag(&config),
+        "<div id=\"main\" class=\"container prose\">"
+    );
+}
+
+#[test]
+fn sidebar_groups_pages_by_directory_and_marks_active_page() {
+    let page_list = vec![
+        String::from("index.md"),
+        String::from("guides/setup.md"),
+        String::from("guides/advanced/tips.md"),
+        String::from("about.md"),
+    ];
+
+    assert_eq!(
+        generate_sidebar(&page_list, "guides/setup.md"),
+        "<aside id=\"sidebar\">\n\t<ul>\n\t\t<li><a href=\"../about.html\">About</a></li>\n\t\t<li><a href=\"../index.html\">Index</a></li>\n\t\t<li>\n\t\t\t<span>Guides</span>\n\t\t\t<ul>\n\t\t\t\t<li><a href=\"../guides/setup.html\" class=\"active\" aria-current=\"page\">Setup</a></li>\n\t\t\t\t<li>\n\t\t\t\t\t<span>Advanced</span>\n\t\t\t\t\t<ul>\n\t\t\t\t\t\t<li><a href=\"../guides/advanced/tips.html\">Tips</a></li>\n\t\t\t\t\t</ul>\n\t\t\t\t</li>\n\t\t\t</ul>\n\t\t</li>\n\t</ul>\n</aside>"
+    );
+}
+
+#[test]
+fn page_nav_links_to_neighbouring_pages() {
+    let nav_order = vec![
+        String::from("intro.md"),
+        String::from("guides/setup.md"),
+        String::from("faq.md"),
+    ];
+    let config = Config::default();
+
+    assert_eq!(
+        generate_page_nav("guides/setup.md", &nav_order, "guides/setup.html", &config),
+        "<nav class=\"page-nav\">\n\t<a class=\"prev\" href=\"../intro.html\" rel=\"prev\">&larr; Intro</a>\n\t<a class=\"next\" href=\"../faq.html\" rel=\"next\">Faq &rarr;</a>\n</nav>"
+    );
+}
+
+#[test]
+fn page_nav_omits_missing_directions() {
+    let nav_order = vec![String::from("intro.md"), String::from("faq.md")];
+    let config = Config::default();
+
+    assert_eq!(
+        generate_page_nav("intro.md", &nav_order, "intro.html", &config),
+        "<nav class=\"page-nav\">\n\t<a class=\"next\" href=\"faq.html\" rel=\"next\">Faq &rarr;</a>\n</nav>"
+    );
+    assert_eq!(
+        generate_page_nav("faq.md", &nav_order, "faq.html", &config),
+        "<nav class=\"page-nav\">\n\t<a class=\"prev\" href=\"intro.html\" rel=\"prev\">&larr; Intro</a>\n</nav>"
+    );
+    assert_eq!(
+        generate_page_nav("other.md", &nav_order, "other.html", &config),
+        ""
+    );
+}
+
+#[test]
+fn word_count_covers_nested_blocks_and_optionally_code() {
+    init_test_config();
+    let elements = parse(&[
+        "# Two words",
+        "",
+        "Some **bold** text.",
+        "",
+        "- a list item",
+        "",
+        "> quoted here",
+        "",
+        "```",
+        "let x = 1;",
+        "```",
+    ]);
+
+    assert_eq!(count_words(&elements, false), 10);
+    assert_eq!(count_words(&elements, true), 14);
+}
+
+#[test]
+fn reading_time_rounds_up_to_whole_minutes() {
+    init_test_config();
+    let mut config = Config::default();
+    config.html.words_per_minute = 2;
+
+    assert_eq!(
+        generate_reading_time(&parse(&["one two three"]), &config),
+        "<p class=\"reading-time\">3 words &middot; 2 min read</p>"
+    );
+    assert_eq!(
+        generate_reading_time(&parse(&["one"]), &config),
+        "<p class=\"reading-time\">1 word &middot; 1 min read</p>"
+    );
+}
+
+fn write_partial(contents: &str) -> NamedTempFile {
+    let file = tempfile::Builder::new().suffix(".html").tempfile().unwrap();
+    fs::write(file.path(), contents).unwrap();
+    file
+}
+
+#[test]
+fn head_partial_is_added_to_head() {
+    let partial = write_partial("<meta name=\"description\" content=\"{{title}}\">\n");
+    let mut config = Config::default();
+    config.html.head_partial = partial.path().to_string_lossy().to_string();
+
+    let head = generate_head(
+        "Getting Started",
+        "guides/getting_started.md",
+        &config,
+        &Frontmatter::default(),
+        &[],
+    );
+
+    assert!(
+        head.ends_with("\t\t<meta name=\"description\" content=\"Getting Started\">\n\t</head>\n"),
+        "{head}"
+    );
+}
+
+#[test]
+fn nav_partial_replaces_navbar() {
+    let partial = write_partial("<nav><a href=\"{{root}}/index.html\">Docs</a></nav>");
+    let mut config = Config::default();
+    config.html.nav_partial = partial.path().to_string_lossy().to_string();
+
+    assert_eq!(
+        generate_navbar("Setup", "guides/setup.md", &config),
+        "<nav><a href=\"../index.html\">Docs</a></nav>\n\n"
+    );
+    assert_eq!(
+        generate_navbar("Index", "index.html", &config),
+        "<nav><a href=\"./index.html\">Docs</a></nav>\n\n"
+    );
+}
+
+#[test]
+fn navbar_defaults_to_home_link() {
+    let config = Config::default();
+
+    assert_eq!(
+        generate_navbar("Setup", "guides/setup.html", &config),
+        "<header>\n\t<nav>\n\t\t<ul>\n\t\t\t<li><a href=\"../index.html\">Home</a></li>\n\t\t</ul>\n\t</nav>\n</header>\n\n"
+    );
+}
+
+#[test]
+fn navbar_links_home_page_when_index_is_disabled() {
+    let mut config = Config::default();
+    config.html.home_page = String::from("home.html");
+
+    // The home page is only used in place of a generated index
+    assert!(
+        generate_navbar("Setup", "guides/setup.html", &config)
+            .contains("<a href=\"../index.html\">Home</a>")
+    );
+
+    config.html.index_page = false;
+    config.html.pretty_urls = true;
+    assert!(
+        generate_navbar("Setup", "guides/setup/index.html", &config)
+            .contains("<a href=\"../../home.html\">Home</a>")
+    );
+}
+
+#[test]
+fn navbar_uses_configured_links() {
+    let mut config = Config::default();
+    config.html.nav = vec![
+        NavLink {
+            label: String::from("Guides"),
+            url: String::from("guides/setup.html"),
+        },
+        NavLink {
+            label: String::from("About & Contact"),
+            url: String::from("./about.html"),
+        },
+        NavLink {
+            label: String::from("GitHub"),
+            url: String::from("https://github.com"),
+        },
+    ];
+
+    assert_eq!(
+        generate_navbar("Setup", "guides/setup.html", &config),
+        "<header>\n\t<nav>\n\t\t<ul>\n\
+         \t\t\t<li><a href=\"../guides/setup.html\" class=\"active\" aria-current=\"page\">Guides</a></li>\n\
+         \t\t\t<li><a href=\"../about.html\">About &amp; Contact</a></li>\n\
+         \t\t\t<li><a href=\"https://github.com\">GitHub</a></li>\n\
+         \t\t</ul>\n\t</nav>\n</header>\n\n"
+    );
+}
+
+#[test]
+fn footer_partial_is_rendered() {
+    let partial = write_partial("<footer>{{title}} &copy; 2025</footer>\n");
+    let mut config = Config::default();
+
+    assert_eq!(generate_footer("About", "about.md", &config), "");
+
+    config.html.footer_partial = partial.path().to_string_lossy().to_string();
+    assert_eq!(
+        generate_footer("About", "about.md", &config),
+        "<footer>About &copy; 2025</footer>"
+    );
+}
+
+#[test]
+fn sitemap_lists_index_and_pages() {
+    init_test_config();
+
+    let file_names = vec![String::from("about.md"), String::from("guides/a&b.md")];
+    let sitemap = generate_sitemap(&file_names, "https://example.com/docs/");
+
+    let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                    <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n\
+                    \t<url>\n\t\t<loc>https://example.com/docs/index.html</loc>\n\t</url>\n\
+                    \t<url>\n\t\t<loc>https://example.com/docs/about.html</loc>\n\t</url>\n\
+                    \t<url>\n\t\t<loc>https://example.com/docs/guides/a&amp;b.html</loc>\n\t</url>\n\
+                    </urlset>\n";
+    assert_eq!(sitemap, expected);
+}
+
+#[test]
+fn sitemap_omits_disabled_or_replaced_index() {
+    let mut config = Config::default();
+    let file_names = vec![String::from("index.md"), String::from("about.md")];
+
+    let sitemap = with_config(&config, || {
+        generate_sitemap(&file_names, "https://example.com")
+    });
+    assert_eq!(sitemap.matches("/index.html</loc>").count(), 1, "{sitemap}");
+
+    config.html.index_page = false;
+    let sitemap = with_config(&config, || {
+        generate_sitemap(&file_names[1..], "https://example.com")
+    });
+    assert!(!sitemap.contains("index.html"), "{sitemap}");
+}
+
+#[test]
+fn not_found_page_uses_default_content_and_site_root() {
+    let mut config = Config::default();
+    config.html.base_url = String::from("https://example.com/docs");
+    let (frontmatter, markdown) = extract_frontmatter(DEFAULT_NOT_FOUND_PAGE);
+    let md_elements = crate::parse_markdown(markdown);
+
+    let html = with_config(&config, || {
+        generate_not_found_page(&md_elements, "output", "input", &[], &frontmatter)
+    });
+    assert!(
+        html.contains("<head>\n\t\t<base href=\"/docs/\">"),
+        "{html}"
+    );
+    assert!(html.contains("<title>Page Not Found</title>"), "{html}");
+    assert!(html.contains("<nav>"), "{html}");
+    assert!(
+        html.contains("<p>Sorry, the page you were looking for doesn't exist.</p>"),
+        "{html}"
+    );
+}
+
+#[test]
+fn site_root_is_path_of_base_url() {
+    assert_eq!(site_root(""), "/");
+    assert_eq!(site_root("https://example.com"), "/");
+    assert_eq!(site_root("https://example.com/"), "/");
+    assert_eq!(site_root("https://example.com/docs/"), "/docs/");
+    assert_eq!(site_root("/blog"), "/blog/");
+}
+
+#[test]
+fn html_tag_uses_configured_lang_and_dir() {
+    let mut config = Config::default();
+    assert_eq!(
+        html_open_tag(&config, &Frontmatter::default()),
+        "<html lang=\"en\">"
+    );
+
+    config.html.lang = String::from("ar");
+    config.html.dir = String::from("rtl");
+    assert_eq!(
+        html_open_tag(&config, &Frontmatter::default()),
+        "<html lang=\"ar\" dir=\"rtl\">"
+    );
+
+    config.html.dir = String::from("sideways");
+    assert_eq!(
+        html_open_tag(&config, &Frontmatter::default()),
+        "<html lang=\"ar\">"
+    );
+}
+
+#[test]
+fn frontmatter_overrides_lang_and_dir() {
+    let config = Config::default();
+    let (frontmatter, _) = extract_frontmatter("---\nlang: he\ndir: rtl\n---\n# Shalom\n");
+
+    assert_eq!(
+        html_open_tag(&config, &frontmatter),
+        "<html lang=\"he\" dir=\"rtl\">"
+    );
+}
+
+#[test]
+fn inline_css_is_embedded_in_head() {
+    let mut config = Config::default();
+    config.html.inline_css = true;
+    set_inline_css("p { color: red; }\n/* </STYLE> */\n");
+
+    let head = generate_head("Page", "page.md", &config, &Frontmatter::default(), &[]);
+
+    assert!(
+        head.contains("\t\t<style>\np { color: red; }\n/* <\\/STYLE> */\n\t\t</style>\n"),
+        "{head}"
+    );
+    assert!(!head.contains("rel=\"stylesheet\""), "{head}");
+}
+
+#[test]
+fn katex_is_loaded_only_when_math_is_enabled() {
+    let mut config = Config::default();
+    let head = generate_head("Page", "page.md", &config, &Frontmatter::default(), &[]);
+    assert!(!head.contains("katex"), "{head}");
+
+    config.html.math = true;
+    let head = generate_head("Page", "page.md", &config, &Frontmatter::default(), &[]);
+    assert!(head.contains("katex.min.css"), "{head}");
+    assert!(head.contains("renderMathInElement(document.body"), "{head}");
+}
+
+#[test]
+fn page_stylesheets_are_copied_and_missing_ones_skipped() {
+    init_test_config();
+    let root = TempDir::new().unwrap();
+    let input_dir = root.path().join("input");
+    let output_dir = root.path().join("output");
+    fs::create_dir_all(input_dir.join("styles")).unwrap();
+    fs::write(input_dir.join("styles/extra.css"), "p { color: red; }").unwrap();
+
+    let (frontmatter, _) = extract_frontmatter(
+        "---\ncss: styles/extra.css, missing.css, https://example.com/theme.css\n---\n",
+    );
+    let page_css = page_stylesheets(
+        &frontmatter,
+        &output_dir.to_string_lossy(),
+        &input_dir.to_string_lossy(),
+        "guides/setup.html",
+    );
+
+    assert_eq!(
+        page_css,
+        vec!["../css/extra.css", "https://example.com/theme.css"]
+    );
+    assert!(output_dir.join("css/extra.css").is_file());
+
+    let head = generate_head(
+        "Setup",
+        "guides/setup.html",
+        &Config::default(),
+        &frontmatter,
+        &page_css,
+    );
+    assert!(
+        head.contains("<link rel=\"stylesheet\" href=\"../styles.css\">\n\t\t<link rel=\"stylesheet\" href=\"../css/extra.css\">"),
+        "{head}"
+    );
+}
+
+#[test]
+fn html_is_indented_with_configured_spaces() {
+    let mut config = Config::default();
+    config.html.indent = 2;
+    let md_elements = crate::parse_markdown("| a |\n| - |\n| b |");
+
+    let html = with_config(&config, || {
+        generate_html(
+            "page.md",
+            &md_elements,
+            "output",
+            "input",
+            "page.html",
+            &[],
+            &Frontmatter::default(),
+        )
+    });
+    assert!(html.contains("\n  <head>\n    <meta charset"), "{html}");
+    assert!(
+        html.contains("\n    <header>\n      <nav>\n        <ul>\n"),
+        "{html}"
+    );
+    assert!(
+        html.contains("\n        <thead>\n          <tr>\n"),
+        "{html}"
+    );
+    assert!(!html.contains('\t'), "{html}");
+}
+
+#[test]
+fn long_code_blocks_are_collapsible() {
+    let mut config = Config::default();
+    config.html.use_prism = false;
+    config.html.code_collapse_lines = 2;
+
+    let (short, long) = with_config(&config, || {
+        (
+            render(&["```", "a", "b", "```"]),
+            render(&["```", "a", "b", "c", "```"]),
+        )
+    });
+    assert!(short.starts_with("<pre class=\"non_prism\">"), "{short}");
+    assert!(
+        long.starts_with("<div class=\"code-collapse\">\n<pre class=\"non_prism\">"),
+        "{long}"
+    );
+    assert!(
+        long.ends_with(
+            "</pre>\n\t<label class=\"code-collapse-toggle\"><input type=\"checkbox\"></label>\n</div>"
+        ),
+        "{long}"
+    );
+
+    let css = with_config(&config, generate_default_css);
+    assert!(css.contains("max-height: calc(2lh + 2rem);"), "{css}");
+    config.html.code_collapse_lines = 0;
+    let css = with_config(&config, generate_default_css);
+    assert!(!css.contains("code-collapse"), "{css}");
+}
+
+#[cfg(feature = "sanitize")]
+#[test]
+fn collapsible_code_blocks_survive_sanitization() {
+    let html = "<div class=\"code-collapse\"><pre>a</pre>\
+                <label class=\"code-collapse-toggle\"><input type=\"checkbox\"></label></div>";
+    assert_eq!(sanitize_html(html, &Config::default()), html);
+}
+
+#[test]
+fn titles_are_formatted_from_file_names() {
+    assert_eq!(format_title("my_test_page.md"), "My Test Page");
+    assert_eq!(format_title("getting-started.md"), "Getting Started");
+    assert_eq!(format_title("already-UPPER_case.md"), "Already UPPER Case");
+}
+
+#[test]
+fn titles_use_unicode_titlecase() {
+    assert_eq!(format_title("école_d'été.md"), "École D'été");
+    assert_eq!(format_title("привет-мир.md"), "Привет Мир");
+    assert_eq!(format_title("日本語_ページ.md"), "日本語 ページ");
+    assert_eq!(format_title("ßtraße.md"), "Sstraße");
+    assert_eq!(format_title("ﬁle_names.md"), "File Names");
+    assert_eq!(format_title("ǆungla.md"), "ǅungla");
+    assert_eq!(format_title("ᾳδω.md"), "ᾼδω");
+}
+
+#[test]
+fn dates_are_shown_in_the_configured_format() {
+    let mut config = Config::default();
+    config.html.date_format = String::from("%B %-d, %Y <%a>");
+    let (frontmatter, markdown) = extract_frontmatter("---\ndate: 2025-01-05\n---\nText");
+    let md_elements = crate::parse_markdown(markdown);
+
+    let (html, index_html) = with_config(&config, || {
+        let html = generate_html(
+            "page.md",
+            &md_elements,
+            "output",
+            "input",
+            "page.html",
+            &[],
+            &frontmatter,
+        );
+        let page = page_output("page.md", &md_elements, &frontmatter);
+        (html, generate_index(&[page]))
+    });
+    let time = "<time datetime=\"2025-01-05\">January 5, 2025 &lt;Sun&gt;</time>";
+    assert!(
+        html.contains(&format!("<p class=\"page-date\">{time}</p>")),
+        "{html}"
+    );
+    assert!(
+        index_html.contains(&format!("<a href=\"./page.html\">Page</a> {time}<br>")),
+        "{index_html}"
+    );
+
+    config.html.date_format = String::new();
+    let index_html = with_config(&config, || {
+        generate_index(&[page_output("page.md", &md_elements, &frontmatter)])
+    });
+    assert!(!index_html.contains("<time"), "{index_html}");
+}
+
+#[test]
+fn invalid_dates_are_left_out() {
+    let (frontmatter, markdown) = extract_frontmatter("---\ndate: 2025-02-30\n---\nText");
+    let md_elements = crate::parse_markdown(markdown);
+
+    let mut diagnostics = Diagnostics::new("page.md");
+    let html = with_config(&Config::default(), || {
+        diagnostics.collect(|| {
+            generate_html(
+                "page.md",
+                &md_elements,
+                "output",
+                "input",
+                "page.html",
+                &[],
+                &frontmatter,
+            )
+        })
+    });
+    assert!(!html.contains("page-date"), "{html}");
+    assert_eq!(diagnostics.warnings().len(), 1);
+    assert_eq!(
+        page_output("page.md", &md_elements, &frontmatter).date,
+        None
+    );
+}
+
+fn render_page(config: &Config, contents: &str) -> (String, PageOutput) {
+    let (frontmatter, markdown) = extract_frontmatter(contents);
+    let md_elements = crate::parse_markdown(markdown);
+
+    with_config(config, || {
+        let html = generate_html(
+            "my_page.md",
+            &md_elements,
+            "output",
+            "input",
+            "my_page.html",
+            &[],
+            &frontmatter,
+        );
+        (html, page_output("my_page.md", &md_elements, &frontmatter))
+    })
+}
+
+#[test]
+fn first_heading_is_used_as_title_when_enabled() {
+    let markdown = "# Getting *Started*\n\nText\n\n# Second";
+    let (html, page) = render_page(&Config::default(), markdown);
+    assert!(html.contains("<title>My Page</title>"), "{html}");
+    assert_eq!(page.title, "My Page");
+
+    let mut config = Config::default();
+    config.html.title_from_heading = true;
+    let (html, page) = render_page(&config, markdown);
+    assert!(html.contains("<title>Getting Started</title>"), "{html}");
+    assert!(html.contains("<h1 id=\"getting-started\">"), "{html}");
+    assert_eq!(page.title, "Getting Started");
+
+    let (html, _) = render_page(&config, "## Not a title\n\nText");
+    assert!(html.contains("<title>My Page</title>"), "{html}");
+}
+
+#[test]
+fn later_heading_is_not_used_as_title() {
+    let mut config = Config::default();
+    config.html.title_from_heading = true;
+    config.html.strip_title_heading = true;
+
+    let (html, page) = render_page(&config, "No heading\n\n# Later");
+    assert!(html.contains("<title>My Page</title>"), "{html}");
+    assert!(html.contains("<h1 id=\"later\">"), "{html}");
+    assert_eq!(page.title, "My Page");
+}
+
+#[test]
+fn title_heading_is_stripped_when_enabled() {
+    let mut config = Config::default();
+    config.html.title_from_heading = true;
+    config.html.strip_title_heading = true;
+
+    let (html, _) = render_page(&config, "# Getting Started\n\nText\n\n# Second");
+    assert!(html.contains("<title>Getting Started</title>"), "{html}");
+    assert!(!html.contains("<h1 id=\"getting-started\">"), "{html}");
+    assert!(html.contains("<h1 id=\"second\">"), "{html}");
+
+    // A front matter title takes precedence, so the heading is kept
+    let (html, page) = render_page(&config, "---\ntitle: Custom\n---\n# Getting Started");
+    assert!(html.contains("<title>Custom</title>"), "{html}");
+    assert!(html.contains("<h1 id=\"getting-started\">"), "{html}");
+    assert_eq!(page.title, "Custom");
+}
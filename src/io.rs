@@ -1,11 +1,14 @@
 //! This module provides functionality related to reading/writing files.
 
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{
     fs::{File, ReadDir, create_dir_all, read_dir},
     io,
-    io::{Read, Write},
+    io::{BufRead, BufReader, Read, Write},
     path::Path,
 };
 
@@ -13,30 +16,148 @@ use dirs::config_dir;
 use log::{error, info};
 
 use crate::config::Config;
-use crate::html_generator::generate_default_css;
+use crate::frontmatter::{Frontmatter, extract_frontmatter};
+use crate::html_generator::{PageOutput, generate_default_css};
+use crate::utils::Fingerprint;
 
-/// Reads all markdown files from the specified input directory and returns their contents.
+/// Whether writes to the output directory should be skipped, set from the `--dry-run` CLI flag.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// The output files that would have been written during a dry run, along with their sizes in
+/// bytes.
+static PLANNED_OUTPUTS: Mutex<Vec<(PathBuf, u64)>> = Mutex::new(Vec::new());
+
+/// The source paths of the images copied to the output directory during the current build, so
+/// that an image referenced several times is only copied once.
+static COPIED_IMAGES: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Enables or disables dry run mode, in which the write and copy functions in this module record
+/// the output paths they would have written instead of touching the filesystem.
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::SeqCst);
+}
+
+/// Returns whether dry run mode is enabled.
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::SeqCst)
+}
+
+/// Returns the output paths and sizes recorded during a dry run, sorted by path and with
+/// duplicates removed.
+pub fn take_planned_outputs() -> Vec<(PathBuf, u64)> {
+    let mut planned_outputs = std::mem::take(
+        &mut *PLANNED_OUTPUTS
+            .lock()
+            .expect("Failed to lock planned outputs mutex"),
+    );
+    planned_outputs.sort();
+    planned_outputs.dedup_by(|(path, _), (other_path, _)| path == other_path);
+    planned_outputs
+}
+
+/// Records an output path that would have been written during a dry run.
+///
+/// # Arguments
+/// * `output_path` - The path that would have been written.
+/// * `size` - The number of bytes that would have been written to it.
+fn plan_output(output_path: &Path, size: u64) {
+    info!(
+        "Dry run, skipping write of {size} bytes to: {}",
+        output_path.display()
+    );
+    PLANNED_OUTPUTS
+        .lock()
+        .expect("Failed to lock planned outputs mutex")
+        .push((output_path.to_path_buf(), size));
+}
+
+/// Collects the paths of all markdown files in the specified input directory without reading
+/// them, so that the reads can be distributed across threads.
 ///
 /// # Arguments
 /// * `input_dir` - The directory containing markdown files.
+/// * `run_recursively` - Whether subdirectories should be visited.
+/// * `excluded_entries` - File or directory names (relative to `input_dir`) to skip.
 ///
 /// # Returns
 /// Returns a `Result` containing a vector of tuples, where each tuple contains the file name
-/// and its contents as a string.
-pub fn read_input_dir(
+/// (relative to `input_dir`) and the full path to the file. The files are sorted
+/// case-insensitively by their relative path, so that the order doesn't depend on the
+/// filesystem.
+pub fn collect_input_files(
     input_dir: &str,
     run_recursively: &bool,
     excluded_entries: &[String],
-) -> Result<Vec<(String, String)>, io::Error> {
+) -> Result<Vec<(String, PathBuf)>, io::Error> {
+    collect_files(
+        input_dir,
+        run_recursively,
+        excluded_entries,
+        is_markdown_file,
+    )
+}
+
+/// Collects the paths of the files in the input directory that aren't markdown, such as images,
+/// PDFs or pre-built HTML pages, so that they can be copied to the output directory as-is.
+///
+/// Hidden files and directories (i.e. `.git`) are skipped.
+///
+/// # Arguments
+/// * `input_dir` - The directory containing the site's files.
+/// * `run_recursively` - Whether subdirectories should be visited.
+/// * `excluded_entries` - File or directory names (relative to `input_dir`) to skip.
+///
+/// # Returns
+/// Returns a `Result` containing a vector of tuples, where each tuple contains the file name
+/// (relative to `input_dir`) and the full path to the file, sorted like `collect_input_files`.
+pub fn collect_asset_files(
+    input_dir: &str,
+    run_recursively: &bool,
+    excluded_entries: &[String],
+) -> Result<Vec<(String, PathBuf)>, io::Error> {
+    collect_files(
+        input_dir,
+        run_recursively,
+        excluded_entries,
+        |relative_path| {
+            !is_markdown_file(relative_path)
+                && !relative_path
+                    .components()
+                    .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+        },
+    )
+}
+
+/// Checks whether a file is a markdown file, going by its extension.
+fn is_markdown_file(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("md")
+}
+
+/// Collects the paths of the files in the input directory that `is_wanted` accepts, as described
+/// in `collect_input_files`.
+///
+/// # Arguments
+/// * `input_dir` - The directory to collect files from.
+/// * `run_recursively` - Whether subdirectories should be visited.
+/// * `excluded_entries` - File or directory names (relative to `input_dir`) to skip.
+/// * `is_wanted` - Checks whether a file should be collected, given its path relative to
+///   `input_dir`.
+fn collect_files(
+    input_dir: &str,
+    run_recursively: &bool,
+    excluded_entries: &[String],
+    is_wanted: fn(&Path) -> bool,
+) -> Result<Vec<(String, PathBuf)>, io::Error> {
     if *run_recursively {
         // If recursive, visit all subdirectories
-        let mut file_contents: Vec<(String, String)> = Vec::new();
+        let mut input_files: Vec<(String, PathBuf)> = Vec::new();
         let input_dir = Path::new(input_dir);
         visit_dir(
             Path::new(input_dir),
             input_dir,
-            &mut file_contents,
+            &mut input_files,
             excluded_entries,
+            is_wanted,
         )
         .map_err(|e| {
             error!(
@@ -47,15 +168,16 @@ pub fn read_input_dir(
             e
         })?;
 
-        Ok(file_contents)
+        sort_input_files(&mut input_files);
+        Ok(input_files)
     } else {
         let entries: ReadDir = read_dir(input_dir).map_err(|e| {
             error!("Failed to read input directory '{input_dir}': {e}");
             e
         })?;
 
-        // Collect the contents of all markdown files in the directory
-        let mut file_contents: Vec<(String, String)> = Vec::new();
+        // Collect the paths of all wanted files in the directory
+        let mut input_files: Vec<(String, PathBuf)> = Vec::new();
         for entry in entries {
             let entry = entry?;
 
@@ -78,32 +200,41 @@ pub fn read_input_dir(
                 continue;
             }
 
-            if file_path.extension().and_then(|s| s.to_str()) == Some("md") {
-                let contents = read_file(file_path.to_str().unwrap()).map_err(|e| {
-                    io::Error::other(format!(
-                        "Failed to read file '{}': {}",
-                        file_path.display(),
-                        e
-                    ))
-                })?;
-                file_contents.push((file_name, contents));
+            if file_path.is_file() && is_wanted(Path::new(&file_name)) {
+                input_files.push((file_name, file_path));
             }
         }
 
-        Ok(file_contents)
+        sort_input_files(&mut input_files);
+        Ok(input_files)
     }
 }
 
-/// Helper function to recursively visit subdirectories and collect markdown file contents.
+/// Sorts input files case-insensitively by their relative path. Paths that only differ in case
+/// are ordered by their exact spelling, so the order is always the same.
+fn sort_input_files(input_files: &mut [(String, PathBuf)]) {
+    input_files.sort_by(|(a, _), (b, _)| {
+        a.to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b))
+    });
+}
+
+/// Helper function to recursively visit subdirectories and collect the paths of wanted files.
 fn visit_dir(
     dir: &Path,
     base: &Path,
-    file_contents: &mut Vec<(String, String)>,
+    input_files: &mut Vec<(String, PathBuf)>,
     excluded_entries: &[String],
+    is_wanted: fn(&Path) -> bool,
 ) -> Result<(), std::io::Error> {
-    for entry in read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
+    // Entries are visited in a fixed order rather than the order the filesystem returns them in
+    let mut paths = read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<PathBuf>, io::Error>>()?;
+    paths.sort_by_key(|path| path.to_string_lossy().to_lowercase());
+
+    for path in paths {
         let relative_path = path
             .strip_prefix(base)
             .map_err(|e| io::Error::other(format!("Failed to strip base path: {e}")))?
@@ -115,24 +246,84 @@ fn visit_dir(
         }
 
         if path.is_dir() {
-            visit_dir(&path, base, file_contents, excluded_entries)?;
-        } else if path.extension().and_then(|s| s.to_str()) == Some("md") {
-            let rel_path = path
-                .strip_prefix(base)
-                .map_err(|e| io::Error::other(format!("Failed to strip base path: {e}")))?
-                .to_string_lossy()
-                .to_string();
-            let contents = read_file(path.to_str().unwrap()).map_err(|e| {
-                io::Error::other(format!("Failed to read file '{}': {}", path.display(), e))
-            })?;
+            visit_dir(&path, base, input_files, excluded_entries, is_wanted)?;
+        } else if is_wanted(Path::new(&relative_path)) {
+            input_files.push((relative_path, path));
+        }
+    }
+
+    Ok(())
+}
 
-            file_contents.push((rel_path, contents));
+/// Reads only the front matter at the top of a file, without reading the rest of it. This lets
+/// drafts be found before any pages are generated.
+///
+/// # Arguments
+/// * `file_path` - The path of the markdown file.
+///
+/// # Returns
+/// Returns the file's front matter, which is empty if it has none.
+pub fn read_frontmatter(file_path: &Path) -> Result<Frontmatter, io::Error> {
+    let reader = BufReader::new(File::open(file_path)?);
+
+    let mut header = String::new();
+    let mut fence: Option<String> = None;
+    for line in reader.lines() {
+        let line = line?;
+        header.push_str(&line);
+        header.push('\n');
+
+        let trimmed = line.trim_start_matches('\u{feff}').trim();
+        match &fence {
+            None if trimmed.is_empty() => {}
+            None if trimmed == "---" || trimmed == "+++" => fence = Some(trimmed.to_string()),
+            None => break,
+            Some(opening_fence) if trimmed == opening_fence => break,
+            Some(_) => {}
         }
     }
 
+    Ok(extract_frontmatter(&header).0)
+}
+
+/// Removes a previously generated page from the output directory, i.e. one for a page that is now
+/// a draft. Does nothing if the page was never generated, or during a dry run.
+pub fn remove_stale_output(output_dir: &str, html_rel_path: &str) -> Result<(), io::Error> {
+    let output_path = Path::new(output_dir).join(html_rel_path);
+    if is_dry_run() || !output_path.is_file() {
+        return Ok(());
+    }
+
+    fs::remove_file(&output_path)?;
+    info!("Removed stale output: {}", output_path.display());
     Ok(())
 }
 
+/// Reads a markdown input file, attaching the file path to any error that occurs.
+///
+/// A leading UTF-8 byte order mark is removed, so that it can't end up in the first block of the
+/// page or hide anything that must start the file.
+///
+/// # Arguments
+/// * `file_path` - The path of the markdown file to read.
+///
+/// # Returns
+/// Returns a `Result` containing the file contents as a string on success.
+pub fn read_input_file(file_path: &Path) -> Result<String, io::Error> {
+    let contents = read_file(&file_path.to_string_lossy()).map_err(|e| {
+        io::Error::other(format!(
+            "Failed to read file '{}': {}",
+            file_path.display(),
+            e
+        ))
+    })?;
+
+    match contents.strip_prefix('\u{feff}') {
+        Some(without_bom) => Ok(without_bom.to_string()),
+        None => Ok(contents),
+    }
+}
+
 /// Reads the contents of a file into a String.
 ///
 /// # Arguments
@@ -167,6 +358,11 @@ pub fn write_html_to_file(
     info!("Writing output to directory: {}", output_dir);
     let output_dir = Path::new(output_dir).join(input_filepath);
 
+    if is_dry_run() {
+        plan_output(&output_dir, html.len() as u64);
+        return Ok(());
+    }
+
     if let Some(parent) = output_dir.parent() {
         create_dir_all(parent)?;
     }
@@ -179,6 +375,37 @@ pub fn write_html_to_file(
     Ok(())
 }
 
+/// Writes the JSON serialization of a page's parsed elements to a file in the specified output
+/// directory.
+///
+/// # Arguments
+/// * `json` - The JSON content to write to the file.
+/// * `output_dir` - The directory where the JSON file should be saved.
+/// * `json_rel_path` - The path of the JSON file, relative to the output directory.
+///
+/// # Returns
+/// Returns a `Result` indicating success or failure.
+pub fn write_json_to_file(
+    json: &str,
+    output_dir: &str,
+    json_rel_path: &str,
+) -> Result<(), io::Error> {
+    let output_path = Path::new(output_dir).join(json_rel_path);
+
+    if is_dry_run() {
+        plan_output(&output_path, json.len() as u64);
+        return Ok(());
+    }
+
+    if let Some(parent) = output_path.parent() {
+        create_dir_all(parent)?;
+    }
+    fs::write(&output_path, json)?;
+
+    info!("JSON written to: {}", output_path.display());
+    Ok(())
+}
+
 /// Copies a file from the input path to the specified output directory, optionally creating a
 /// subdirectory.
 ///
@@ -220,6 +447,17 @@ pub fn copy_file_to_output_dir(
         )
     })?;
 
+    if is_dry_run() {
+        // Still make sure the source exists, so that a dry run reports missing files
+        let metadata = fs::metadata(&abs_input_path)?;
+
+        let mut output_file_path = PathBuf::from(output_dir);
+        output_file_path.extend(subdir);
+        output_file_path.push(file_name);
+        plan_output(&output_file_path, metadata.len());
+        return Ok(());
+    }
+
     let mut output_file_path = PathBuf::from(output_dir);
     if let Some(sub) = subdir {
         output_file_path.push(sub);
@@ -242,25 +480,100 @@ pub fn copy_favicon_to_output_dir(
     copy_file_to_output_dir(input_file_path, output_dir, Some("media"), None)
 }
 
-/// Copies an image file to the specified output directory.
+/// Copies an image file to the `media` directory in the specified output directory.
+///
+/// Each image is only copied once per build, no matter how many times it is referenced. Call
+/// `reset_copied_images` before rebuilding the site so that changed images are copied again.
 pub fn copy_image_to_output_dir(
     input_file_path: &str,
     output_dir: &str,
     md_dir: &str,
 ) -> Result<(), io::Error> {
+    // Collecting the components drops any "." segments, so "./a.png" and "a.png" match
+    let source_path: PathBuf = Path::new(md_dir)
+        .join(input_file_path)
+        .components()
+        .collect();
+    let is_first_copy = COPIED_IMAGES
+        .lock()
+        .expect("Failed to lock copied images mutex")
+        .insert(source_path);
+    if !is_first_copy {
+        return Ok(());
+    }
+
     copy_file_to_output_dir(input_file_path, output_dir, Some("media"), Some(md_dir))
 }
 
+/// Forgets which images have been copied, so that the next build copies them again.
+pub fn reset_copied_images() {
+    COPIED_IMAGES
+        .lock()
+        .expect("Failed to lock copied images mutex")
+        .clear();
+}
+
+/// Copies a file from the input directory to the same relative path in the output directory,
+/// creating any directories it's in.
+///
+/// # Arguments
+/// * `input_file_path` - The path of the file to copy.
+/// * `output_dir` - The output directory.
+/// * `relative_path` - The path of the file relative to the input directory, i.e. `docs/a.pdf`.
+///
+/// # Returns
+/// Returns a `Result` indicating success or failure.
+pub fn copy_asset_to_output_dir(
+    input_file_path: &Path,
+    output_dir: &str,
+    relative_path: &str,
+) -> Result<(), io::Error> {
+    let output_file_path = Path::new(output_dir).join(relative_path);
+
+    if is_dry_run() {
+        let metadata = fs::metadata(input_file_path)?;
+        plan_output(&output_file_path, metadata.len());
+        return Ok(());
+    }
+
+    if let Some(parent) = output_file_path.parent() {
+        create_dir_all(parent)?;
+    }
+    fs::copy(input_file_path, &output_file_path)?;
+
+    Ok(())
+}
+
 /// Copies a CSS file to the specified output directory.
 pub fn copy_css_to_output_dir(input_file_path: &str, output_dir: &str) -> Result<(), io::Error> {
     copy_file_to_output_dir(input_file_path, output_dir, None, None)
 }
 
+/// Copies a stylesheet listed in a page's front matter to the `css` directory in the specified
+/// output directory.
+///
+/// # Arguments
+/// * `input_file_path` - The path of the stylesheet, relative to `input_dir` unless it's absolute.
+/// * `output_dir` - The directory where the generated site is written.
+/// * `input_dir` - The directory where the markdown files are located.
+pub fn copy_page_css_to_output_dir(
+    input_file_path: &str,
+    output_dir: &str,
+    input_dir: &str,
+) -> Result<(), io::Error> {
+    copy_file_to_output_dir(input_file_path, output_dir, Some("css"), Some(input_dir))
+}
+
 /// Writes a default CSS file to the specified output directory.
 pub fn write_default_css_file(output_dir: &str) -> Result<(), io::Error> {
     let css_content = generate_default_css();
     let css_file_path = format!("{}/styles.css", output_dir);
 
+    if is_dry_run() {
+        plan_output(Path::new(&css_file_path), css_content.len() as u64);
+        return Ok(());
+    }
+
     let mut file = File::create(&css_file_path)?;
 
     file.write_all(css_content.as_bytes())?;
@@ -268,6 +581,103 @@ pub fn write_default_css_file(output_dir: &str) -> Result<(), io::Error> {
     Ok(())
 }
 
+/// Writes a generated `sitemap.xml` to the specified output directory.
+pub fn write_sitemap_to_file(sitemap: &str, output_dir: &str) -> Result<(), io::Error> {
+    let sitemap_path = Path::new(output_dir).join("sitemap.xml");
+
+    if is_dry_run() {
+        plan_output(&sitemap_path, sitemap.len() as u64);
+        return Ok(());
+    }
+
+    create_dir_all(output_dir)?;
+    fs::write(&sitemap_path, sitemap)?;
+
+    info!("Sitemap written to: {}", sitemap_path.display());
+    Ok(())
+}
+
+/// Writes a generated Atom feed to `atom.xml` in the specified output directory.
+pub fn write_feed_to_file(feed: &str, output_dir: &str) -> Result<(), io::Error> {
+    let feed_path = Path::new(output_dir).join("atom.xml");
+
+    if is_dry_run() {
+        plan_output(&feed_path, feed.len() as u64);
+        return Ok(());
+    }
+
+    create_dir_all(output_dir)?;
+    fs::write(&feed_path, feed)?;
+
+    info!("Feed written to: {}", feed_path.display());
+    Ok(())
+}
+
+/// The name of the file in the output directory recording what the current `index.html` was
+/// generated from.
+const INDEX_MANIFEST_FILE: &str = ".markrs-index";
+
+/// Builds the manifest describing an index page, made up of a fingerprint of the version and
+/// config followed by the sorted list of pages it links to, along with their titles and dates.
+///
+/// # Arguments
+/// * `pages` - The pages listed in the index.
+/// * `config` - The configuration the index is generated with.
+///
+/// # Returns
+/// Returns the manifest contents as a string.
+pub fn build_index_manifest(pages: &[PageOutput], config: &Config) -> String {
+    let mut fingerprint = Fingerprint::new();
+    fingerprint
+        .add(env!("CARGO_PKG_VERSION"))
+        .add(toml_edit::ser::to_string(config).unwrap_or_default());
+
+    let mut entries: Vec<String> = pages
+        .iter()
+        .map(|page| {
+            let date = page.date.as_deref().unwrap_or_default();
+            format!("{}\t{}\t{date}", page.path, page.title)
+        })
+        .collect();
+    entries.sort();
+
+    format!("{}\n{}\n", fingerprint.to_hex(), entries.join("\n"))
+}
+
+/// Checks whether the `index.html` in the output directory was generated from the same manifest,
+/// meaning that no pages have been added or removed and it doesn't need to be regenerated.
+///
+/// # Arguments
+/// * `output_dir` - The output directory containing `index.html`.
+/// * `manifest` - The manifest for the current build, from `build_index_manifest`.
+///
+/// # Returns
+/// Returns `true` if the index exists and its manifest matches, otherwise `false`.
+pub fn is_index_up_to_date(output_dir: &str, manifest: &str) -> bool {
+    let output_dir = Path::new(output_dir);
+
+    output_dir.join("index.html").is_file()
+        && fs::read_to_string(output_dir.join(INDEX_MANIFEST_FILE))
+            .is_ok_and(|previous_manifest| previous_manifest == manifest)
+}
+
+/// Writes the manifest for a newly generated `index.html` to the output directory.
+///
+/// # Arguments
+/// * `output_dir` - The output directory containing `index.html`.
+/// * `manifest` - The manifest for the current build, from `build_index_manifest`.
+///
+/// # Returns
+/// Returns a `Result` indicating success or failure.
+pub fn write_index_manifest(output_dir: &str, manifest: &str) -> Result<(), io::Error> {
+    // The manifest is bookkeeping rather than output, so it isn't reported by a dry run
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    fs::write(Path::new(output_dir).join(INDEX_MANIFEST_FILE), manifest)
+}
+
 /// Returns the OS-specific configuration path.
 ///
 /// This function creates a directory named "markrs" in the user's configuration directory.
@@ -303,8 +713,7 @@ pub fn write_default_config() -> Result<Config, crate::config::Error> {
 
     let default_config = Config::default();
 
-    let default_config_content = toml_edit::ser::to_string_pretty(&default_config)
-        .map_err(crate::config::Error::TomlSerialization)?;
+    let default_config_content = default_config.to_toml_string()?;
 
     file.write_all(default_config_content.as_bytes())?;
 
@@ -312,3 +721,6 @@ pub fn write_default_config() -> Result<Config, crate::config::Error> {
 
     Ok(default_config)
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,200 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use crate::config::Config;
+use crate::html_generator::PageOutput;
+use crate::io::{
+    build_index_manifest, collect_asset_files, collect_input_files, copy_asset_to_output_dir,
+    copy_image_to_output_dir, is_index_up_to_date, read_frontmatter, remove_stale_output,
+    write_index_manifest,
+};
+
+fn page(path: &str, title: &str) -> PageOutput {
+    PageOutput {
+        path: path.to_string(),
+        title: title.to_string(),
+        word_count: 0,
+        headings: Vec::new(),
+        date: None,
+    }
+}
+
+#[test]
+fn index_is_not_regenerated_for_unchanged_file_set() {
+    let output_dir = TempDir::new().unwrap();
+    let output_dir_str = output_dir.path().to_string_lossy();
+    let config = Config::default();
+
+    let manifest = build_index_manifest(&[page("a.md", "A"), page("b.md", "B")], &config);
+    assert!(!is_index_up_to_date(&output_dir_str, &manifest));
+
+    fs::write(output_dir.path().join("index.html"), "<html></html>").unwrap();
+    write_index_manifest(&output_dir_str, &manifest).unwrap();
+
+    // The same pages in a different order don't change the index
+    let reordered = build_index_manifest(&[page("b.md", "B"), page("a.md", "A")], &config);
+    assert!(is_index_up_to_date(&output_dir_str, &reordered));
+}
+
+#[test]
+fn index_is_regenerated_when_file_set_changes() {
+    let output_dir = TempDir::new().unwrap();
+    let output_dir_str = output_dir.path().to_string_lossy();
+    let config = Config::default();
+
+    let manifest = build_index_manifest(&[page("a.md", "A")], &config);
+    fs::write(output_dir.path().join("index.html"), "<html></html>").unwrap();
+    write_index_manifest(&output_dir_str, &manifest).unwrap();
+
+    let added = build_index_manifest(&[page("a.md", "A"), page("b.md", "B")], &config);
+    assert!(!is_index_up_to_date(&output_dir_str, &added));
+
+    let removed = build_index_manifest(&[], &config);
+    assert!(!is_index_up_to_date(&output_dir_str, &removed));
+
+    let retitled = build_index_manifest(&[page("a.md", "Renamed")], &config);
+    assert!(!is_index_up_to_date(&output_dir_str, &retitled));
+}
+
+#[test]
+fn image_referenced_twice_is_copied_once() {
+    let input_dir = TempDir::new().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    let input_dir_str = input_dir.path().to_string_lossy();
+    let output_dir_str = output_dir.path().to_string_lossy();
+    fs::create_dir_all(input_dir.path().join("img")).unwrap();
+    fs::write(input_dir.path().join("img/logo.png"), "png").unwrap();
+
+    copy_image_to_output_dir("img/logo.png", &output_dir_str, &input_dir_str).unwrap();
+    let copied_path = output_dir.path().join("media/logo.png");
+    assert_eq!(fs::read_to_string(&copied_path).unwrap(), "png");
+
+    // The second reference is skipped, so the removed copy isn't recreated
+    fs::remove_file(&copied_path).unwrap();
+    copy_image_to_output_dir("img/logo.png", &output_dir_str, &input_dir_str).unwrap();
+    assert!(!copied_path.exists());
+}
+
+#[test]
+fn frontmatter_is_read_without_the_body() {
+    let dir = TempDir::new().unwrap();
+    let draft_path = dir.path().join("draft.md");
+    let plain_path = dir.path().join("plain.md");
+    fs::write(&draft_path, "---\ntitle: WIP\ndraft: true\n---\n# Draft\n").unwrap();
+    fs::write(&plain_path, "# Not a draft\n\ndraft: true\n").unwrap();
+
+    let frontmatter = read_frontmatter(&draft_path).unwrap();
+    assert!(frontmatter.is_draft());
+    assert_eq!(frontmatter.title(), Some("WIP"));
+    assert!(!read_frontmatter(&plain_path).unwrap().is_draft());
+}
+
+#[test]
+fn stale_output_is_removed() {
+    let output_dir = TempDir::new().unwrap();
+    let output_dir_str = output_dir.path().to_string_lossy();
+    fs::create_dir_all(output_dir.path().join("notes")).unwrap();
+    fs::write(output_dir.path().join("notes/draft.html"), "<html></html>").unwrap();
+
+    remove_stale_output(&output_dir_str, "notes/draft.html").unwrap();
+    assert!(!output_dir.path().join("notes/draft.html").exists());
+
+    // Pages that were never generated are ignored
+    remove_stale_output(&output_dir_str, "notes/missing.html").unwrap();
+}
+
+#[test]
+fn input_files_are_sorted_case_insensitively() {
+    let input_dir = TempDir::new().unwrap();
+    fs::create_dir_all(input_dir.path().join("Guides")).unwrap();
+    for file in [
+        "b.md",
+        "A.md",
+        "c.md",
+        "Guides/setup.md",
+        "guides.md",
+        "notes.txt",
+    ] {
+        fs::write(input_dir.path().join(file), "# Page").unwrap();
+    }
+    let input_dir_str = input_dir.path().to_string_lossy();
+
+    let names = |recursive: bool| -> Vec<String> {
+        collect_input_files(&input_dir_str, &recursive, &[])
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    };
+
+    assert_eq!(names(false), vec!["A.md", "b.md", "c.md", "guides.md"]);
+    assert_eq!(
+        names(true),
+        vec!["A.md", "b.md", "c.md", "guides.md", "Guides/setup.md"]
+    );
+}
+
+#[test]
+fn asset_files_skip_markdown_and_hidden_files() {
+    let input_dir = TempDir::new().unwrap();
+    fs::create_dir_all(input_dir.path().join("docs")).unwrap();
+    fs::create_dir_all(input_dir.path().join(".git")).unwrap();
+    for file in [
+        "a.md",
+        "logo.png",
+        ".DS_Store",
+        ".git/HEAD",
+        "docs/guide.md",
+        "docs/guide.pdf",
+    ] {
+        fs::write(input_dir.path().join(file), "contents").unwrap();
+    }
+    let input_dir_str = input_dir.path().to_string_lossy();
+
+    let names = |recursive: bool| -> Vec<String> {
+        collect_asset_files(&input_dir_str, &recursive, &[])
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    };
+
+    assert_eq!(names(false), vec!["logo.png"]);
+    assert_eq!(names(true), vec!["docs/guide.pdf", "logo.png"]);
+}
+
+#[test]
+fn assets_are_copied_to_the_same_relative_path() {
+    let input_dir = TempDir::new().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    fs::create_dir_all(input_dir.path().join("docs/files")).unwrap();
+    let input_path = input_dir.path().join("docs/files/guide.pdf");
+    fs::write(&input_path, "%PDF").unwrap();
+
+    copy_asset_to_output_dir(
+        &input_path,
+        &output_dir.path().to_string_lossy(),
+        "docs/files/guide.pdf",
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(output_dir.path().join("docs/files/guide.pdf")).unwrap(),
+        "%PDF"
+    );
+}
+
+#[test]
+fn empty_input_dir_has_no_input_files() {
+    let input_dir = TempDir::new().unwrap();
+    fs::write(input_dir.path().join("notes.txt"), "Not markdown").unwrap();
+    let input_dir_str = input_dir.path().to_string_lossy();
+
+    for recursive in [false, true] {
+        assert!(
+            collect_input_files(&input_dir_str, &recursive, &[])
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -3,8 +3,8 @@
 
 use std::mem::take;
 
-use crate::CONFIG;
-use crate::types::Token;
+use crate::config::current_config;
+use crate::types::{ListMarkerKind, Token, roman_to_number};
 use crate::utils::push_buffer_to_collection;
 use unicode_categories::UnicodeCategories;
 use unicode_segmentation::UnicodeSegmentation;
@@ -19,10 +19,10 @@ use unicode_segmentation::UnicodeSegmentation;
 ///
 /// # Example
 /// ```
-/// use lexer::tokenize;
-/// use types::Token;
+/// use mark_rs::lexer::tokenize;
+/// use mark_rs::types::Token;
 /// let tokens = tokenize("This is *italic* and **bold** text.");
-/// assert_eq!(tokens.len(), 9);
+/// assert_eq!(tokens.len(), 16);
 /// assert_eq!(tokens[4], Token::EmphasisRun { delimiter: '*', length: 1 });
 /// ```
 pub fn tokenize(markdown_line: &str) -> Vec<Token> {
@@ -30,6 +30,11 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
         return vec![Token::Newline];
     }
 
+    // A thematic break takes up the whole line, so it's never split into other tokens
+    if is_thematic_break(markdown_line) {
+        return vec![Token::ThematicBreak(markdown_line.to_string())];
+    }
+
     let mut tokens: Vec<Token> = Vec::new();
     let mut buffer: String = String::new();
 
@@ -50,7 +55,11 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
                 // Start of unordered list
                 tokens.push(Token::Punctuation(String::from(chars[i])));
             }
-            "*" | "_" => {
+            "^" if matches!(tokens.last(), Some(Token::OpenBracket)) && buffer.is_empty() => {
+                // The "^" of a footnote label (`[^1]`) is part of the label's text
+                buffer.push_str(chars[i]);
+            }
+            "*" | "_" | "~" | "^" => {
                 // if the current buffer isn't empty, append a Text token to the Vec<Token>
                 push_buffer_to_collection(&mut tokens, &mut buffer);
 
@@ -60,23 +69,53 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
                     run_length += 1;
                 }
 
-                tokens.push(Token::EmphasisRun {
-                    delimiter: delimiter.chars().next().unwrap(),
-                    length: run_length,
-                });
+                // A run of three or more tildes at the start of a line is a code fence rather
+                // than strikethrough
+                if delimiter == "~" && run_length >= 3 && is_at_line_start(&tokens) {
+                    tokens.push(Token::CodeFence {
+                        delimiter: '~',
+                        length: run_length,
+                    });
+                } else {
+                    tokens.push(Token::EmphasisRun {
+                        delimiter: delimiter.chars().next().unwrap(),
+                        length: run_length,
+                    });
+                }
 
                 i += run_length - 1;
             }
             "`" => {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
 
-                if i + 2 < str_len && chars[i + 1] == "`" && chars[i + 2] == "`" {
-                    tokens.push(Token::CodeFence);
-                    i += 2;
+                let mut run_length = 1;
+                while i + run_length < str_len && chars[i + run_length] == "`" {
+                    run_length += 1;
+                }
+
+                if run_length >= 3 {
+                    tokens.push(Token::CodeFence {
+                        delimiter: '`',
+                        length: run_length,
+                    });
+                    i += run_length - 1;
                 } else {
-                    tokens.push(Token::CodeTick);
+                    tokens.push(Token::CodeTick(run_length));
+                    i += run_length - 1;
                 }
             }
+            "$" => {
+                push_buffer_to_collection(&mut tokens, &mut buffer);
+
+                // `$` opens or closes inline math and `$$` display math, so the run is kept whole
+                let mut run_length = 1;
+                while i + run_length < str_len && chars[i + run_length] == "$" {
+                    run_length += 1;
+                }
+
+                tokens.push(Token::Punctuation("$".repeat(run_length)));
+                i += run_length - 1;
+            }
             "\\" => {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
 
@@ -87,17 +126,6 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
                     buffer.push_str(chars[i]);
                 }
             }
-            "-" => {
-                // Check for thematic break
-                push_buffer_to_collection(&mut tokens, &mut buffer);
-
-                if i + 2 < str_len && chars[i + 1] == "-" && chars[i + 2] == "-" {
-                    tokens.push(Token::ThematicBreak);
-                    i += 2;
-                } else {
-                    tokens.push(Token::Punctuation(String::from(chars[i])));
-                }
-            }
             "[" => {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
 
@@ -126,12 +154,23 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
             ">" => {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
 
-                if i == 0 {
+                // A `>` directly after another marker starts a nested blockquote
+                let is_nested_marker = tokens.first() == Some(&Token::BlockQuoteMarker)
+                    && tokens
+                        .iter()
+                        .all(|token| matches!(token, Token::BlockQuoteMarker | Token::Whitespace));
+
+                if i == 0 || is_nested_marker {
                     tokens.push(Token::BlockQuoteMarker);
                 } else {
                     buffer.push_str(chars[i]);
                 }
             }
+            "<" if !is_tag_start(&chars[i + 1..]) => {
+                // A `<` that can't open a tag is literal, and the rest of the line is tokenized as
+                // usual to keep i.e. the closing `$` of math like `$a < b$`, or a real tag after it
+                buffer.push_str(chars[i]);
+            }
             "<" => {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
 
@@ -150,35 +189,46 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
                 }
             }
             "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => {
+                // Numeric list markers are only recognized at the start of a line, so that text
+                // like "step 2) below" stays text
+                let is_marker_position = buffer.is_empty() && is_at_marker_position(&tokens);
                 let mut marker = String::from(chars[i]);
                 while i + 1 < str_len && chars[i + 1].chars().next().unwrap().is_ascii_digit() {
                     i += 1;
                     marker.push_str(chars[i]);
                 }
 
-                if i + 1 < str_len && chars[i + 1] != "." {
-                    buffer.push_str(&marker);
-                } else if i + 2 < str_len && chars[i + 2] == " " {
-                    push_buffer_to_collection(&mut tokens, &mut buffer);
-                    tokens.push(Token::OrderedListMarker(marker));
-
-                    i += 2;
-                    continue;
-                } else {
-                    buffer.push_str(&marker);
+                let delimiter = match chars.get(i + 1) {
+                    Some(&".") => Some('.'),
+                    Some(&")") => Some(')'),
+                    _ => None,
+                };
+                match delimiter {
+                    Some(delimiter) if is_marker_position && chars.get(i + 2) == Some(&" ") => {
+                        tokens.push(Token::OrderedListMarker(
+                            marker,
+                            ListMarkerKind::Numeric,
+                            delimiter,
+                        ));
+
+                        // Skip the delimiter, leaving the following space to be tokenized
+                        i += 2;
+                        continue;
+                    }
+                    _ => buffer.push_str(&marker),
                 }
             }
             "\t" => {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
 
-                tokens.push(Token::Tab);
+                tokens.push(Token::Tab { literal: true });
             }
             " " => {
                 // Will be configurable later, but for now we'll stick to 4 spaces = 1 tab
-                let tab_size = CONFIG.get().unwrap().lexer.tab_size;
+                let tab_size = current_config().lexer.tab_size;
                 if i + tab_size <= str_len && chars[i + 1..i + tab_size].iter().all(|&c| c == " ") {
                     push_buffer_to_collection(&mut tokens, &mut buffer);
-                    tokens.push(Token::Tab);
+                    tokens.push(Token::Tab { literal: false });
                     i += tab_size; // i won't increment after continue, so we do it here
                     continue;
                 }
@@ -198,7 +248,21 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
                 tokens.push(Token::Punctuation(String::from(chars[i])));
             }
-            _ => buffer.push_str(chars[i]),
+            _ => {
+                // Lettered/roman list markers are only recognized at the start of a line
+                if current_config().lexer.extended_list_markers
+                    && buffer.is_empty()
+                    && is_at_marker_position(&tokens)
+                    && let Some((marker, kind)) = lettered_list_marker(&chars[i..])
+                {
+                    // Skip the marker and the ".", leaving the following space to be tokenized
+                    i += marker.len() + 1;
+                    tokens.push(Token::OrderedListMarker(marker, kind, '.'));
+                    continue;
+                }
+
+                buffer.push_str(chars[i])
+            }
         }
 
         i += 1;
@@ -210,6 +274,119 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
     tokens
 }
 
+/// Checks whether the given graphemes start with an alphabetic (`a.`, `B.`) or roman numeral
+/// (`iv.`, `II.`) ordered list marker followed by a space.
+///
+/// A lone `i`/`I` is treated as a roman numeral, while any other single letter is alphabetic.
+///
+/// # Arguments
+/// * `chars` - The graphemes of the line, starting at the potential marker.
+///
+/// # Returns
+/// The marker text (without the `.`) and its kind, or `None` if there is no marker.
+fn lettered_list_marker(chars: &[&str]) -> Option<(String, ListMarkerKind)> {
+    let marker_len = chars
+        .iter()
+        .take_while(|grapheme| grapheme.len() == 1 && grapheme.as_bytes()[0].is_ascii_alphabetic())
+        .count();
+
+    if marker_len == 0
+        || chars.get(marker_len) != Some(&".")
+        || chars.get(marker_len + 1) != Some(&" ")
+    {
+        return None;
+    }
+
+    let marker = chars[..marker_len].concat();
+    let is_lowercase = marker.chars().all(|ch| ch.is_ascii_lowercase());
+    let is_uppercase = marker.chars().all(|ch| ch.is_ascii_uppercase());
+
+    let kind = if marker_len == 1 && !marker.eq_ignore_ascii_case("i") {
+        if is_lowercase {
+            ListMarkerKind::LowerAlpha
+        } else {
+            ListMarkerKind::UpperAlpha
+        }
+    } else if roman_to_number(&marker).is_some() && is_lowercase {
+        ListMarkerKind::LowerRoman
+    } else if roman_to_number(&marker).is_some() && is_uppercase {
+        ListMarkerKind::UpperRoman
+    } else {
+        return None;
+    };
+
+    Some((marker, kind))
+}
+
+/// Checks whether a line is a thematic break: three or more `-`, `*` or `_` characters, all the
+/// same and optionally separated by spaces or tabs, with at most three spaces of indentation.
+///
+/// # Arguments
+/// * `line` - A single line of markdown.
+///
+/// # Returns
+/// `true` if the line is a thematic break.
+fn is_thematic_break(line: &str) -> bool {
+    let indentation = line.len() - line.trim_start_matches(' ').len();
+    let markers: Vec<char> = line
+        .chars()
+        .filter(|ch| !matches!(ch, ' ' | '\t'))
+        .collect();
+
+    indentation < 4
+        && markers.len() >= 3
+        && matches!(markers[0], '-' | '*' | '_')
+        && markers.iter().all(|&marker| marker == markers[0])
+}
+
+/// Checks whether only whitespace has been tokenized since the start of the current line.
+///
+/// # Arguments
+/// * `tokens` - The tokens produced so far.
+fn is_at_line_start(tokens: &[Token]) -> bool {
+    tokens
+        .iter()
+        .rev()
+        .take_while(|token| **token != Token::Newline)
+        .all(|token| *token == Token::Whitespace)
+}
+
+/// Checks whether a `<` opens an HTML tag or an autolink. It has to be followed by a letter, `/`,
+/// `!` or `?`, and closed by a `>` before any other `<`, so that text like `a < b` or `I <3 you`
+/// doesn't swallow a tag that comes later in the line.
+///
+/// # Arguments
+/// * `rest` - The graphemes of the line after the `<`.
+fn is_tag_start(rest: &[&str]) -> bool {
+    let starts_tag = rest.first().is_some_and(|grapheme| {
+        grapheme.starts_with(|ch: char| ch.is_ascii_alphabetic() || matches!(ch, '/' | '!' | '?'))
+    });
+
+    starts_tag
+        && rest
+            .iter()
+            .find(|grapheme| **grapheme == "<" || **grapheme == ">")
+            .is_some_and(|grapheme| *grapheme == ">")
+}
+
+/// Checks whether an ordered list marker can start at the current position, i.e. only indentation
+/// and blockquote markers have been tokenized since the start of the line.
+///
+/// # Arguments
+/// * `tokens` - The tokens produced so far.
+fn is_at_marker_position(tokens: &[Token]) -> bool {
+    tokens
+        .iter()
+        .rev()
+        .take_while(|token| **token != Token::Newline)
+        .all(|token| {
+            matches!(
+                token,
+                Token::Tab { .. } | Token::Whitespace | Token::BlockQuoteMarker
+            )
+        })
+}
+
 /// Helper function to determine if a string is a single punctuation character.
 ///
 /// # Arguments
@@ -220,7 +397,7 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
 /// `false`.
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// use lexer::is_punctuation;
 /// assert!(is_punctuation("!"));
 /// assert!(!is_punctuation("Hello"));
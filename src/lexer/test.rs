@@ -3,6 +3,7 @@ use std::sync::Once;
 use crate::CONFIG;
 use crate::config::Config;
 use crate::lexer::{Token::*, *};
+use crate::types::ListMarkerKind;
 
 static INIT: Once = Once::new();
 
@@ -190,7 +191,73 @@ fn unicode() {
 #[test]
 fn thematic_break() {
     init_test_config();
-    assert_eq!(tokenize("---"), vec![ThematicBreak]);
+    assert_eq!(tokenize("---"), vec![ThematicBreak(String::from("---"))]);
+    assert_eq!(tokenize("***"), vec![ThematicBreak(String::from("***"))]);
+    assert_eq!(
+        tokenize(" _ _ _"),
+        vec![ThematicBreak(String::from(" _ _ _"))]
+    );
+}
+
+#[test]
+fn not_a_thematic_break() {
+    init_test_config();
+    assert_eq!(tokenize("-"), vec![Punctuation(String::from("-"))]);
+    assert_eq!(
+        tokenize("--*"),
+        vec![
+            Punctuation(String::from("-")),
+            Punctuation(String::from("-")),
+            EmphasisRun {
+                delimiter: '*',
+                length: 1
+            }
+        ]
+    );
+    assert_eq!(
+        tokenize("a---b"),
+        vec![
+            Text(String::from("a")),
+            Punctuation(String::from("-")),
+            Punctuation(String::from("-")),
+            Punctuation(String::from("-")),
+            Text(String::from("b"))
+        ]
+    );
+}
+
+#[test]
+fn double_code_tick() {
+    init_test_config();
+    assert_eq!(
+        tokenize("``a ` b``"),
+        vec![
+            CodeTick(2),
+            Text(String::from("a")),
+            Whitespace,
+            CodeTick(1),
+            Whitespace,
+            Text(String::from("b")),
+            CodeTick(2)
+        ]
+    );
+}
+
+#[test]
+fn dollar_runs_are_kept_whole() {
+    init_test_config();
+    assert_eq!(
+        tokenize("$x$ $$y$$"),
+        vec![
+            Punctuation(String::from("$")),
+            Text(String::from("x")),
+            Punctuation(String::from("$")),
+            Whitespace,
+            Punctuation(String::from("$$")),
+            Text(String::from("y")),
+            Punctuation(String::from("$$"))
+        ]
+    );
 }
 
 #[test]
@@ -198,7 +265,7 @@ fn code_tick() {
     init_test_config();
     assert_eq!(
         tokenize("`code`"),
-        vec![CodeTick, Text(String::from("code")), CodeTick]
+        vec![CodeTick(1), Text(String::from("code")), CodeTick(1)]
     );
 }
 
@@ -208,7 +275,10 @@ fn code_fence() {
     assert_eq!(
         tokenize("```rust\nfn main() {\n    println!(\"Hello, world!\");\n}\n```"),
         vec![
-            CodeFence,
+            CodeFence {
+                delimiter: '`',
+                length: 3
+            },
             Text(String::from("rust")),
             Newline,
             Text(String::from("fn")),
@@ -219,7 +289,7 @@ fn code_fence() {
             Whitespace,
             Punctuation(String::from("{")),
             Newline,
-            Tab,
+            Tab { literal: false },
             Text(String::from("println")),
             Punctuation(String::from("!")),
             OpenParenthesis,
@@ -235,7 +305,10 @@ fn code_fence() {
             Newline,
             Punctuation(String::from("}")),
             Newline,
-            CodeFence
+            CodeFence {
+                delimiter: '`',
+                length: 3
+            }
         ]
     )
 }
@@ -258,13 +331,13 @@ fn escape_sequence() {
 #[test]
 fn tab() {
     init_test_config();
-    assert_eq!(tokenize("\t"), vec![Tab]);
+    assert_eq!(tokenize("\t"), vec![Tab { literal: true }]);
 }
 
 #[test]
 fn tab_via_spaces() {
     init_test_config();
-    assert_eq!(tokenize("    "), vec![Tab]);
+    assert_eq!(tokenize("    "), vec![Tab { literal: false }]);
 }
 
 #[test]
@@ -287,6 +360,30 @@ fn blockquote() {
     );
 }
 
+#[test]
+fn nested_blockquote() {
+    init_test_config();
+    assert_eq!(
+        tokenize("> > Nested, not 1 > 2"),
+        vec![
+            BlockQuoteMarker,
+            Whitespace,
+            BlockQuoteMarker,
+            Whitespace,
+            Text(String::from("Nested")),
+            Punctuation(String::from(",")),
+            Whitespace,
+            Text(String::from("not")),
+            Whitespace,
+            Text(String::from("1")),
+            Whitespace,
+            Text(String::from(">")),
+            Whitespace,
+            Text(String::from("2"))
+        ]
+    );
+}
+
 #[test]
 fn raw_html_basic() {
     init_test_config();
@@ -328,7 +425,32 @@ fn malformed_raw_html_no_closing_bracket() {
     init_test_config();
     assert_eq!(
         tokenize("<div Missing bracket"),
-        vec![Text(String::from("<div Missing bracket")),]
+        vec![
+            Text(String::from("<div")),
+            Whitespace,
+            Text(String::from("Missing")),
+            Whitespace,
+            Text(String::from("bracket")),
+        ]
+    );
+}
+
+#[test]
+fn stray_angle_bracket_before_tag() {
+    init_test_config();
+    assert_eq!(
+        tokenize("a < b <i>c</i>"),
+        vec![
+            Text(String::from("a")),
+            Whitespace,
+            Text(String::from("<")),
+            Whitespace,
+            Text(String::from("b")),
+            Whitespace,
+            RawHtmlTag(String::from("<i>")),
+            Text(String::from("c")),
+            RawHtmlTag(String::from("</i>")),
+        ]
     );
 }
 
@@ -386,3 +508,152 @@ fn unicode_mixed() {
         ]
     );
 }
+
+#[test]
+fn ordered_list_markers_accept_period_or_parenthesis() {
+    init_test_config();
+    for (line, delimiter) in [("3. item", '.'), ("3) item", ')')] {
+        assert_eq!(
+            tokenize(line),
+            vec![
+                OrderedListMarker(String::from("3"), ListMarkerKind::Numeric, delimiter),
+                Whitespace,
+                Text(String::from("item"))
+            ]
+        );
+    }
+}
+
+#[test]
+fn numbers_mid_line_are_not_list_markers() {
+    init_test_config();
+    assert_eq!(
+        tokenize("See step 2) below"),
+        vec![
+            Text(String::from("See")),
+            Whitespace,
+            Text(String::from("step")),
+            Whitespace,
+            Text(String::from("2")),
+            CloseParenthesis,
+            Whitespace,
+            Text(String::from("below"))
+        ]
+    );
+}
+
+#[test]
+fn lettered_list_markers() {
+    let line = |s: &'static str| s.graphemes(true).collect::<Vec<_>>();
+
+    assert_eq!(
+        lettered_list_marker(&line("a. item")),
+        Some((String::from("a"), ListMarkerKind::LowerAlpha))
+    );
+    assert_eq!(
+        lettered_list_marker(&line("B. item")),
+        Some((String::from("B"), ListMarkerKind::UpperAlpha))
+    );
+    assert_eq!(
+        lettered_list_marker(&line("i. item")),
+        Some((String::from("i"), ListMarkerKind::LowerRoman))
+    );
+    assert_eq!(
+        lettered_list_marker(&line("IV. item")),
+        Some((String::from("IV"), ListMarkerKind::UpperRoman))
+    );
+}
+
+#[test]
+fn lettered_list_markers_require_period_and_space() {
+    let line = |s: &'static str| s.graphemes(true).collect::<Vec<_>>();
+
+    assert_eq!(lettered_list_marker(&line("a.item")), None);
+    assert_eq!(lettered_list_marker(&line("ab. item")), None);
+    assert_eq!(lettered_list_marker(&line("Iv. item")), None);
+    assert_eq!(lettered_list_marker(&line("word")), None);
+}
+
+#[test]
+fn lettered_list_markers_disabled_by_default() {
+    init_test_config();
+    assert_eq!(
+        tokenize("a. item"),
+        vec![
+            Text(String::from("a")),
+            Punctuation(String::from(".")),
+            Whitespace,
+            Text(String::from("item"))
+        ]
+    );
+}
+
+#[test]
+fn tilde_run() {
+    init_test_config();
+    assert_eq!(
+        tokenize("~~Deleted~~"),
+        vec![
+            EmphasisRun {
+                delimiter: '~',
+                length: 2
+            },
+            Text(String::from("Deleted")),
+            EmphasisRun {
+                delimiter: '~',
+                length: 2
+            }
+        ]
+    );
+}
+
+#[test]
+fn tilde_code_fence() {
+    init_test_config();
+    assert_eq!(
+        tokenize("~~~~python"),
+        vec![
+            CodeFence {
+                delimiter: '~',
+                length: 4
+            },
+            Text(String::from("python"))
+        ]
+    );
+    assert_eq!(
+        tokenize("a ~~~b"),
+        vec![
+            Text(String::from("a")),
+            Whitespace,
+            EmphasisRun {
+                delimiter: '~',
+                length: 3
+            },
+            Text(String::from("b"))
+        ]
+    );
+}
+
+#[test]
+fn caret_run() {
+    init_test_config();
+    assert_eq!(
+        tokenize("x^2^ [^1]"),
+        vec![
+            Text(String::from("x")),
+            EmphasisRun {
+                delimiter: '^',
+                length: 1
+            },
+            Text(String::from("2")),
+            EmphasisRun {
+                delimiter: '^',
+                length: 1
+            },
+            Whitespace,
+            OpenBracket,
+            Text(String::from("^1")),
+            CloseBracket
+        ]
+    );
+}
@@ -0,0 +1,115 @@
+//! Mark-rs is a Markdown parser and static site generator.
+//!
+//! The `markrs` binary builds a whole site from a directory of markdown files. This library
+//! exposes the same parser for rendering a single document, without touching the filesystem:
+//!
+//! ```
+//! use mark_rs::config::Config;
+//! use mark_rs::markdown_to_html;
+//!
+//! let html = markdown_to_html("# Hello\n\nThis is **bold**.", &Config::default());
+//! assert!(html.contains("<b>bold</b>"));
+//! ```
+
+pub mod config;
+pub mod dates;
+pub mod diagnostics;
+pub mod error;
+pub mod feed;
+pub mod frontmatter;
+pub mod highlight;
+pub mod html_generator;
+pub mod io;
+pub mod lexer;
+pub mod minify;
+pub mod parser;
+pub mod post_process;
+pub mod thread_pool;
+pub mod types;
+pub mod utils;
+
+use std::sync::OnceLock;
+
+use crate::config::{Config, with_config};
+use crate::frontmatter::extract_frontmatter;
+use crate::html_generator::{render_content, rewrite_page_links};
+use crate::lexer::tokenize;
+use crate::parser::{group_lines_with_first_lines, parse_blocks_with_first_lines};
+use crate::types::{MdBlockElement, ToMarkdown};
+
+/// The configuration used by the `markrs` binary, initialized once at startup by
+/// [`config::init_config`].
+pub static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Converts a markdown document to HTML using the given configuration.
+///
+/// Any front matter at the top of the document is skipped. Only the document's content is
+/// rendered, without the `<head>`, navbar or footer that the `markrs` binary wraps around each
+/// page, and relative image paths are left as written rather than copied to an output directory.
+/// Links to other markdown pages (i.e. `setup.md`) point at their HTML page, as if the document
+/// were at the root of the site.
+///
+/// # Arguments
+/// * `input` - The markdown document to convert.
+/// * `config` - The configuration to render with, used in place of the global `CONFIG`.
+///
+/// # Returns
+/// Returns a `String` containing the rendered HTML.
+pub fn markdown_to_html(input: &str, config: &Config) -> String {
+    with_config(config, || {
+        let (_frontmatter, markdown) = extract_frontmatter(input);
+        let mut parsed_elements = parse_markdown(markdown);
+        rewrite_page_links(&mut parsed_elements, "", config.html.pretty_urls);
+
+        render_content(&parsed_elements, "", "", "", config)
+    })
+}
+
+/// Formats a markdown document, rewriting it with consistent heading syntax, list markers and
+/// spacing.
+///
+/// Any front matter is kept as written. Formatting a document that has already been formatted
+/// returns it unchanged.
+///
+/// # Arguments
+/// * `input` - The markdown document to format.
+/// * `config` - The configuration to parse with, used in place of the global `CONFIG`.
+///
+/// # Returns
+/// Returns a `String` containing the formatted markdown.
+pub fn format_markdown(input: &str, config: &Config) -> String {
+    with_config(config, || {
+        let (_frontmatter, markdown) = extract_frontmatter(input);
+        let frontmatter = input[..input.len() - markdown.len()].trim_end();
+        let content = parse_markdown(markdown).to_markdown();
+
+        match (frontmatter.is_empty(), content.is_empty()) {
+            (true, true) => String::new(),
+            (true, false) => format!("{content}\n"),
+            (false, true) => format!("{frontmatter}\n"),
+            (false, false) => format!("{frontmatter}\n\n{content}\n"),
+        }
+    })
+}
+
+/// Parses a markdown document, without front matter, into its block-level elements using the
+/// current configuration.
+///
+/// Windows (`\r\n`) and classic Mac (`\r`) line endings are treated as `\n`, so a trailing `\r`
+/// never ends up in the parsed text.
+///
+/// # Arguments
+/// * `markdown` - The markdown to parse.
+///
+/// # Returns
+/// Returns the parsed block-level elements.
+pub fn parse_markdown(markdown: &str) -> Vec<MdBlockElement> {
+    let markdown = markdown.replace("\r\n", "\n").replace('\r', "\n");
+
+    let tokenized_lines = markdown.split('\n').map(tokenize).collect();
+    let (blocks, first_lines) = group_lines_with_first_lines(tokenized_lines);
+    parse_blocks_with_first_lines(&blocks, &first_lines)
+}
+
+#[cfg(test)]
+mod test;
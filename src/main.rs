@@ -1,32 +1,54 @@
-mod config;
-mod error;
-mod html_generator;
-mod io;
-mod lexer;
-mod parser;
-mod thread_pool;
-mod types;
-mod utils;
-
-use clap::{Parser, command};
+mod browser;
+mod cache;
+mod serve;
+
+use clap::{Parser, ValueEnum};
 use env_logger::Env;
-use log::{error, info};
-use std::path::Path;
-use std::sync::{Arc, OnceLock};
-
-use crate::config::{Config, init_config};
-use crate::error::Error;
-use crate::html_generator::{generate_html, generate_index};
-use crate::io::{
-    copy_css_to_output_dir, copy_favicon_to_output_dir, read_input_dir, write_default_css_file,
-    write_html_to_file,
+use log::{error, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use mark_rs::config::{Config, init_config};
+use mark_rs::diagnostics::{Diagnostic, Diagnostics};
+use mark_rs::error::Error;
+use mark_rs::feed::{generate_feed, page_meta};
+use mark_rs::frontmatter::extract_frontmatter;
+use mark_rs::html_generator::{
+    DEFAULT_NOT_FOUND_PAGE, PageOutput, generate_default_css, generate_html, generate_index,
+    generate_not_found_page, generate_sitemap, page_output, rewrite_page_links, set_inline_css,
+};
+use mark_rs::io::{
+    build_index_manifest, collect_asset_files, collect_input_files, copy_asset_to_output_dir,
+    copy_css_to_output_dir, copy_favicon_to_output_dir, is_index_up_to_date, read_file,
+    read_frontmatter, read_input_file, remove_stale_output, reset_copied_images, set_dry_run,
+    take_planned_outputs, write_default_css_file, write_feed_to_file, write_html_to_file,
+    write_index_manifest, write_json_to_file, write_sitemap_to_file,
 };
-use crate::lexer::tokenize;
-use crate::parser::{group_lines_to_blocks, parse_blocks};
-use crate::thread_pool::ThreadPool;
-use crate::types::Token;
+use mark_rs::minify::set_minify;
+use mark_rs::thread_pool::{ThreadPool, catch_panic};
+use mark_rs::utils::{filter_index_files, navigation_order, output_relative_path};
+use mark_rs::{CONFIG, format_markdown, parse_markdown};
 
-static CONFIG: OnceLock<Config> = OnceLock::new();
+use crate::browser::{DefaultBrowser, open_index};
+use crate::cache::{BuildCache, build_fingerprint, source_stamp};
+use crate::serve::{notify_reload, start_server};
+
+/// How often the input directory is checked for changes in serve mode.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What each input file is converted to.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum OutputFormat {
+    /// A generated HTML page, along with the index and the site's assets.
+    Html,
+    /// The parsed elements of the page, serialized as JSON.
+    Json,
+}
 
 #[derive(Parser, Debug)]
 #[command(
@@ -36,18 +58,47 @@ static CONFIG: OnceLock<Config> = OnceLock::new();
     override_usage = "markrs [OPTIONS] <INPUT_DIR>"
 )]
 struct Cli {
-    #[arg(value_name = "INPUT_DIR")]
-    input_dir: String,
+    #[arg(value_name = "INPUT_DIR", required_unless_present = "config_print")]
+    input_dir: Option<String>,
     #[arg(short, long, default_value = "")]
     config: String,
+    #[arg(
+        long,
+        value_name = "TOML",
+        help = "Use the given TOML string as the config instead of reading a config file."
+    )]
+    config_inline: Option<String>,
     #[arg(short, long, default_value = "./output")]
     output_dir: String,
     #[arg(short, long, default_value = "false")]
     recursive: bool,
     #[arg(short, long, default_value = "false")]
     verbose: bool,
+    #[arg(
+        short,
+        long,
+        conflicts_with = "verbose",
+        help = "Only log errors, hiding the warnings that are shown by default."
+    )]
+    quiet: bool,
+    #[arg(
+        long,
+        value_name = "FILTER",
+        help = "Set the log filter using RUST_LOG syntax, i.e. `warn,mark_rs::parser=debug`. Applied on top of RUST_LOG, --quiet and --verbose."
+    )]
+    log: Option<String>,
     #[arg(short, long, default_value = "4")]
     num_threads: usize,
+    #[arg(
+        long,
+        help = "Number of threads used to read input files. Defaults to sharing the pool sized by --num-threads."
+    )]
+    read_threads: Option<usize>,
+    #[arg(
+        long,
+        help = "Number of threads used to parse and generate HTML. Defaults to --num-threads."
+    )]
+    render_threads: Option<usize>,
     #[arg(
         short = 'O',
         long,
@@ -56,6 +107,158 @@ struct Cli {
     open: bool,
     #[arg(short, long, default_value = "", num_args = 0.., help = "Exclude files or directories from the input directory. Can be specified multiple times, or as a space-separated list.")]
     exclude: Vec<String>,
+    #[arg(
+        long,
+        help = "Copy the files in the input directory that aren't markdown, such as images, PDFs and pre-built HTML, to the same paths in the output directory."
+    )]
+    copy_assets: bool,
+    #[arg(
+        long,
+        help = "Run the full pipeline without writing any files, and print the files that would be generated along with their sizes."
+    )]
+    dry_run: bool,
+    #[arg(
+        long,
+        value_name = "PORT",
+        num_args = 0..=1,
+        default_missing_value = "3000",
+        conflicts_with = "dry_run",
+        help = "Serve the output directory over HTTP on the given port (3000 by default), rebuilding and reloading pages when input files change."
+    )]
+    serve: Option<u16>,
+    #[arg(
+        long,
+        help = "Minify the generated HTML and default CSS. Equivalent to setting `minify = true` in the config."
+    )]
+    minify: bool,
+    #[arg(
+        long,
+        help = "Include pages marked with `draft: true` in their front matter, which are skipped by default."
+    )]
+    drafts: bool,
+    #[arg(
+        long,
+        help = "Regenerate every page, even if its source hasn't changed since the last build."
+    )]
+    force: bool,
+    #[arg(
+        long,
+        help = "Exit with an error if any warnings are reported while parsing the pages, i.e. for CI. Every page is regenerated so that none of their warnings are missed."
+    )]
+    strict: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value = "html",
+        conflicts_with_all = ["serve", "open"],
+        help = "Write each page's parsed elements as a .json file instead of generating HTML. The index and assets are only generated for HTML."
+    )]
+    output_format: OutputFormat,
+    #[arg(
+        long,
+        help = "Don't generate the index.html page listing every page. Equivalent to setting `index_page = false` in the config."
+    )]
+    no_index: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["serve", "open", "dry_run"],
+        help = "Rewrite each markdown file in the input directory with consistent headings, list markers and spacing, instead of generating the site."
+    )]
+    format: bool,
+    #[arg(
+        long,
+        help = "Print the config in effect, after defaults and CLI flags are applied, as TOML and exit."
+    )]
+    config_print: bool,
+    #[arg(
+        long,
+        value_name = "CSS_FILE",
+        help = "Use the given stylesheet instead of the configured `css_file`, or \"default\" for the default styles."
+    )]
+    css: Option<String>,
+    #[arg(
+        long,
+        value_name = "FAVICON_FILE",
+        help = "Use the given favicon instead of the configured `favicon_file`."
+    )]
+    favicon: Option<String>,
+    #[arg(
+        long,
+        overrides_with = "no_prism",
+        help = "Highlight code blocks with PrismJS, overriding `use_prism` in the config."
+    )]
+    prism: bool,
+    #[arg(
+        long,
+        overrides_with = "prism",
+        help = "Don't load PrismJS, overriding `use_prism` in the config."
+    )]
+    no_prism: bool,
+    #[arg(
+        long,
+        help = "Don't sanitize the generated HTML, overriding `sanitize_html` in the config."
+    )]
+    no_sanitize: bool,
+    #[arg(
+        long,
+        help = "Write pages as name/index.html and link to them as name/, overriding `pretty_urls` in the config."
+    )]
+    pretty_urls: bool,
+    #[arg(
+        long,
+        help = "Render $inline$ and $$display$$ math with KaTeX, overriding `math` in the config."
+    )]
+    math: bool,
+    #[arg(
+        long,
+        value_name = "URL",
+        help = "The absolute URL the site is served from, overriding `base_url` in the config."
+    )]
+    base_url: Option<String>,
+}
+
+impl Cli {
+    /// The input directory, which is only missing when it isn't needed (i.e. with
+    /// `--config-print`).
+    fn input_dir(&self) -> &str {
+        self.input_dir.as_deref().unwrap_or_default()
+    }
+
+    /// Applies the flags that override config fields to the loaded config. Flags that weren't
+    /// given leave the config's values as they are.
+    fn apply_overrides(&self, config: &mut Config) {
+        let html = &mut config.html;
+        if let Some(css) = &self.css {
+            html.css_file = css.clone();
+        }
+        if let Some(favicon) = &self.favicon {
+            html.favicon_file = favicon.clone();
+        }
+        if self.prism {
+            html.use_prism = true;
+        }
+        if self.no_prism {
+            html.use_prism = false;
+        }
+        if self.no_sanitize {
+            html.sanitize_html = false;
+        }
+        if self.pretty_urls {
+            html.pretty_urls = true;
+        }
+        if self.math {
+            html.math = true;
+        }
+        if let Some(base_url) = &self.base_url {
+            html.base_url = base_url.clone();
+        }
+        if self.no_index {
+            html.index_page = false;
+        }
+        if self.minify {
+            html.minify = true;
+        }
+    }
 }
 
 fn main() -> Result<(), Error> {
@@ -73,42 +276,368 @@ fn main() -> Result<(), Error> {
 
 fn run() -> Result<(), Error> {
     let cli = Cli::parse();
-    let input_dir = &cli.input_dir;
-    let config_path = &cli.config;
+
+    // Setup
+    // RUST_LOG sets the starting filter, which --quiet/--verbose and then --log override
+    let mut logger = env_logger::Builder::from_env(Env::default().default_filter_or("warn"));
+    if cli.quiet {
+        logger.parse_filters("error");
+    } else if cli.verbose {
+        logger.parse_filters("info");
+    }
+    if let Some(filter) = &cli.log {
+        logger.parse_filters(filter);
+    }
+    logger.init();
+
+    init_config(&cli.config, cli.config_inline.as_deref(), |config| {
+        cli.apply_overrides(config)
+    })?;
+    if cli.config_print {
+        print!("{}", CONFIG.get().unwrap().to_toml_string()?);
+        return Ok(());
+    }
+    set_dry_run(cli.dry_run);
+    set_minify(CONFIG.get().unwrap().html.minify);
+    if cli.format {
+        return format_input_files(&cli);
+    }
+    let cli = Arc::new(cli);
+
+    build_site(&cli)?;
+
+    if cli.dry_run {
+        println!("Dry run, the following files would be generated:");
+        for (output_path, size) in take_planned_outputs() {
+            println!("\t{} ({size} bytes)", output_path.display());
+        }
+        return Ok(());
+    }
+
+    if cli.open
+        && let Err(e) = open_index(&DefaultBrowser, &cli.output_dir)
+    {
+        error!("Failed to open index.html in browser: {e}");
+    }
+
+    if let Some(port) = cli.serve {
+        start_server(&cli.output_dir, port)?;
+        watch_and_rebuild(&cli)?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites every input file as formatted markdown. Files that are already formatted are left
+/// untouched.
+fn format_input_files(cli: &Cli) -> Result<(), Error> {
+    let config = CONFIG.get().unwrap();
+    let input_files = collect_input_files(cli.input_dir(), &cli.recursive, &cli.exclude)?;
+
+    for (file_name, file_path) in input_files {
+        let contents = read_input_file(&file_path)?;
+        let formatted = format_markdown(&contents, config);
+
+        if formatted != contents {
+            fs::write(&file_path, formatted)?;
+            info!("Formatted {file_name}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Rebuilds the site whenever an input file is added, removed or modified, notifying the pages
+/// open in the dev server so that they reload. This never returns unless the input directory
+/// can no longer be read.
+fn watch_and_rebuild(cli: &Arc<Cli>) -> Result<(), Error> {
+    let mut last_modified = input_modified_times(cli)?;
+
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+
+        let modified = input_modified_times(cli)?;
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        info!("Changes detected, rebuilding...");
+        match build_site(cli) {
+            Ok(()) => notify_reload(),
+            Err(e) => error!("Failed to rebuild site: {e}"),
+        }
+    }
+}
+
+/// Returns the last modified time of every input file, used to detect changes in serve mode.
+fn input_modified_times(cli: &Cli) -> Result<Vec<(String, Option<SystemTime>)>, Error> {
+    let input_files = collect_input_files(cli.input_dir(), &cli.recursive, &cli.exclude)?;
+
+    Ok(input_files
+        .into_iter()
+        .map(|(name, path)| {
+            let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+            (name, modified)
+        })
+        .collect())
+}
+
+/// Removes the pages marked as drafts in their front matter from the input files, so that they are
+/// neither generated nor listed anywhere. Any page generated for a draft by a previous build is
+/// removed from the output directory.
+fn skip_drafts(
+    input_files: Vec<(String, PathBuf)>,
+    output_dir: &str,
+    pretty_urls: bool,
+) -> Vec<(String, PathBuf)> {
+    input_files
+        .into_iter()
+        .filter(|(file_name, file_path)| {
+            let is_draft = read_frontmatter(file_path).is_ok_and(|fm| fm.is_draft());
+            if is_draft {
+                info!("Skipping draft: {file_name}");
+
+                let html_rel_path = output_relative_path(file_name, pretty_urls);
+                if let Err(e) = remove_stale_output(output_dir, &html_rel_path) {
+                    warn!("Failed to remove stale output for draft {file_name}: {e}");
+                }
+            }
+            !is_draft
+        })
+        .collect()
+}
+
+/// Orders the pages listed in the index for the previous/next page links, by the `order` field in
+/// their front matter and then by path.
+fn nav_order(input_files: &[(String, PathBuf)], file_names: &[String]) -> Vec<String> {
+    let pages = input_files
+        .iter()
+        .filter(|(file_name, _)| file_names.contains(file_name))
+        .map(|(file_name, file_path)| {
+            let order = read_frontmatter(file_path)
+                .ok()
+                .and_then(|frontmatter| frontmatter.order());
+            (file_name.clone(), order)
+        })
+        .collect();
+
+    navigation_order(pages)
+}
+
+/// Generates `404.html` from `404.md` in the input directory, or from a default message if there
+/// isn't one.
+fn generate_404_page(
+    cli: &Cli,
+    not_found_file: Option<&Path>,
+    page_list: &[String],
+) -> Result<(), Error> {
+    let file_contents = match not_found_file {
+        Some(file_path) => read_input_file(file_path)?,
+        None => DEFAULT_NOT_FOUND_PAGE.to_string(),
+    };
+    let (frontmatter, markdown) = extract_frontmatter(&file_contents);
+
+    let config = CONFIG.get().unwrap();
+    let mut parsed_elements = parse_markdown(markdown);
+    rewrite_page_links(&mut parsed_elements, "", config.html.pretty_urls);
+    let generated_html = generate_not_found_page(
+        &parsed_elements,
+        &cli.output_dir,
+        cli.input_dir(),
+        page_list,
+        &frontmatter,
+    );
+
+    write_html_to_file(&generated_html, &cli.output_dir, "404.html")?;
+
+    Ok(())
+}
+
+/// Reads, parses and generates HTML for every input file, then writes the index, sitemap and
+/// assets to the output directory.
+fn build_site(cli: &Arc<Cli>) -> Result<(), Error> {
+    let input_dir = cli.input_dir();
     let run_recursively = &cli.recursive;
     let num_threads = cli.num_threads;
+    let config = CONFIG.get().unwrap();
+    reset_copied_images();
 
-    // Setup
-    let env = if cli.verbose {
-        Env::default().default_filter_or("info")
+    let mut input_files = collect_input_files(input_dir, run_recursively, &cli.exclude)?;
+    // 404.md is the 404 page's content rather than a page of its own when one is generated
+    let not_found_file = if config.html.not_found_page {
+        input_files
+            .iter()
+            .position(|(file_name, _)| file_name == "404.md")
+            .map(|position| input_files.remove(position).1)
     } else {
-        Env::default().default_filter_or("warn")
+        None
     };
-    env_logger::Builder::from_env(env).init();
+    let found_files = !input_files.is_empty();
+    let input_files = if cli.drafts {
+        input_files
+    } else {
+        skip_drafts(input_files, &cli.output_dir, config.html.pretty_urls)
+    };
+    // An empty site still gets an index and assets, so say why it has no pages
+    if !found_files {
+        let hint = if *run_recursively {
+            ""
+        } else {
+            " (pass --recursive to include subdirectories)"
+        };
+        warn!("No markdown files found in '{input_dir}'{hint}, the index will have no pages.");
+    } else if input_files.is_empty() {
+        warn!(
+            "Every markdown file is a draft (pass --drafts to include them), the index will have no pages."
+        );
+    }
+    let file_names: Vec<String> = input_files.iter().map(|(name, _)| name.clone()).collect();
+    // Copied assets never overwrite a generated page
+    let mut generated_paths: HashSet<String> = file_names
+        .iter()
+        .map(|file_name| output_relative_path(file_name, config.html.pretty_urls))
+        .collect();
+    if config.html.index_page {
+        generated_paths.insert(String::from("index.html"));
+    }
+    if config.html.not_found_page {
+        generated_paths.insert(String::from("404.html"));
+    }
+    // A page that's written to index.html is never overwritten by the generated index
+    let index_page = file_names
+        .iter()
+        .find(|file_name| output_relative_path(file_name, config.html.pretty_urls) == "index.html")
+        .cloned();
+    // Excluded pages are still generated, they just aren't listed in the index
+    let file_names = filter_index_files(&file_names, &config.html.index_exclude);
 
-    init_config(config_path)?;
-    let config = CONFIG.get().unwrap();
-    let file_contents = read_input_dir(input_dir, run_recursively, &cli.exclude)?;
-    let mut file_names: Vec<String> = Vec::with_capacity(file_contents.len());
+    let thread_pool =
+        ThreadPool::build(cli.render_threads.unwrap_or(num_threads)).map_err(|e| {
+            error!("Failed to create thread pool: {e}");
+            e
+        })?;
 
-    let thread_pool = ThreadPool::build(num_threads).map_err(|e| {
-        error!("Failed to create thread pool: {e}");
-        e
-    })?;
-    let cli = Arc::new(cli);
+    // Reads share the render pool unless a separate read thread count is requested
+    let read_pool = match cli.read_threads {
+        Some(read_threads) => Some(ThreadPool::build(read_threads).map_err(|e| {
+            error!("Failed to create read thread pool: {e}");
+            e
+        })?),
+        None => None,
+    };
+    // Pages are linked to their neighbours in the page list by the previous/next page links
+    let nav_order = if config.html.prev_next_links {
+        nav_order(&input_files, &file_names)
+    } else {
+        Vec::new()
+    };
+    let page_list = Arc::new(if nav_order.is_empty() {
+        file_names.clone()
+    } else {
+        nav_order.clone()
+    });
 
-    for (file_path, file_content) in file_contents {
-        info!("Generating HTML for file: {}", file_path);
+    // The stylesheet has to be known before any page is generated when it's inlined into them
+    if config.html.inline_css {
+        let css = match config.html.css_file.as_str() {
+            "default" | "" => generate_default_css(),
+            css_file => read_file(css_file).map_err(|e| {
+                error!("Failed to read CSS file {css_file}: {e}");
+                e
+            })?,
+        };
+        set_inline_css(&css);
+    }
+
+    // Pages whose source hasn't changed since the last build with the same fingerprint are skipped
+    let fingerprint = build_fingerprint(&file_names, &nav_order, config);
+    // JSON output is never cached, so that it can't make stale HTML pages look up to date, and
+    // strict builds need every page parsed to find all of the warnings
+    let is_json_output = cli.output_format == OutputFormat::Json;
+    let previous_cache = if cli.force || cli.strict || is_json_output {
+        BuildCache::default()
+    } else {
+        BuildCache::load(&cli.output_dir)
+    };
+    let mut build_cache = BuildCache::new(fingerprint.clone());
 
-        file_names.push(file_path.clone());
+    // The feed reads every listed page, including those the cache lets the render stage skip
+    let feed_files: Vec<(String, PathBuf)> = if config.html.feed {
+        input_files
+            .iter()
+            .filter(|(file_name, _)| file_names.contains(file_name))
+            .cloned()
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    // Read stage: each file is read on the read pool and sent to the render stage once loaded
+    let (read_sender, read_receiver) = mpsc::channel();
+    let mut pages: Vec<PageOutput> = Vec::new();
+    for (file_name, file_path) in input_files {
+        let stamp = source_stamp(&file_path);
+        if let Some(stamp) = &stamp
+            && let Some(page) = previous_cache.fresh_page(&fingerprint, &file_name, stamp)
+            && Path::new(&cli.output_dir)
+                .join(output_relative_path(&file_name, config.html.pretty_urls))
+                .is_file()
+        {
+            info!("Skipping unchanged file: {}", file_name);
+            build_cache.insert(file_name, stamp.clone(), page.clone());
+            pages.push(page.clone());
+            continue;
+        }
+
+        let read_sender = read_sender.clone();
+        read_pool
+            .as_ref()
+            .unwrap_or(&thread_pool)
+            .execute(move || {
+                let file_content = read_input_file(&file_path);
+                // The receiver only hangs up if the render stage has already failed
+                let _ = read_sender.send((file_name, file_content, stamp));
+            })
+            .map_err(|e| {
+                error!("Failed to execute job in thread pool for file read: {e}");
+                e
+            })?;
+    }
+    drop(read_sender);
+
+    // Render stage: parse and generate HTML for each file as soon as it has been read, sending
+    // the result back to be collected once every page is done
+    let (page_sender, page_receiver) = mpsc::channel();
+    let mut rendered_pages = 0;
+    let mut failed_pages = Vec::new();
+    for (file_path, file_content, stamp) in read_receiver {
+        rendered_pages += 1;
+        // A page that can't be read fails on its own, like one that fails to generate
+        let file_content = match file_content {
+            Ok(file_content) => file_content,
+            Err(e) => {
+                error!("{e}");
+                failed_pages.push(file_path);
+                continue;
+            }
+        };
+        info!("Generating HTML for file: {}", file_path);
 
         thread_pool
             .execute({
-                let cli = Arc::clone(&cli);
+                let cli = Arc::clone(cli);
+                let page_list = Arc::clone(&page_list);
+                let page_sender = page_sender.clone();
                 move || {
-                    generate_static_site(cli, &file_path, &file_content).unwrap_or_else(|e| {
-                        error!("Failed to generate HTML for {file_path}: {e}");
-                    });
+                    // A page that panics is reported as failed rather than silently left out
+                    let result = catch_panic(|| {
+                        generate_static_site(cli, &file_path, &file_content, &page_list)
+                    })
+                    .map_err(Error::from)
+                    .and_then(|result| result);
+                    // The receiver is only dropped after every page has been received
+                    let _ = page_sender.send((file_path, stamp, result));
                 }
             })
             .map_err(|e| {
@@ -116,30 +645,152 @@ fn run() -> Result<(), Error> {
                 e
             })?;
     }
+    drop(page_sender);
 
-    thread_pool
-        .execute({
-            let cli = Arc::clone(&cli);
-            move || {
-                let index_html = generate_index(&file_names);
-                write_html_to_file(&index_html, &cli.output_dir, "index.html").unwrap_or_else(
-                    |e| {
-                        error!("Failed to write index.html: {e}");
-                    },
-                );
+    if let Some(read_pool) = read_pool {
+        read_pool.join_all();
+    }
+
+    let mut warnings = Vec::new();
+    for (file_path, stamp, result) in page_receiver {
+        match result {
+            // Failed pages are left out of the cache so that they're retried next build
+            Ok((page, page_warnings)) => {
+                warnings.extend(page_warnings);
+                if let Some(stamp) = stamp {
+                    build_cache.insert(file_path, stamp, page.clone());
+                }
+                pages.push(page);
             }
-        })
-        .map_err(|e| {
-            error!("Failed to execute job in thread pool for index generation: {e}");
-            e
-        })?;
+            Err(e) => {
+                error!("Failed to generate HTML for {file_path}: {e}");
+                failed_pages.push(file_path);
+            }
+        }
+    }
+
+    if is_json_output {
+        check_failed_pages(&failed_pages, rendered_pages)?;
+        return check_warnings(&warnings, cli.strict);
+    }
+
+    // The index keeps the order of the page list, and leaves out pages that failed to generate
+    let mut pages_by_name: HashMap<String, PageOutput> = pages
+        .into_iter()
+        .map(|page| (page.path.clone(), page))
+        .collect();
+    let index_pages: Vec<PageOutput> = file_names
+        .iter()
+        .filter_map(|file_name| pages_by_name.remove(file_name))
+        .collect();
+
+    // The index only lists pages, so it only needs regenerating when pages are added, removed, or
+    // retitled
+    let index_manifest = build_index_manifest(&index_pages, config);
+    if !config.html.index_page {
+        info!("The index is disabled, skipping index.html generation.");
+    } else if let Some(file_name) = index_page {
+        info!("{file_name} is written to index.html, skipping index.html generation.");
+    } else if !cli.force && is_index_up_to_date(&cli.output_dir, &index_manifest) {
+        info!("No pages were added or removed, skipping index.html generation.");
+    } else {
+        thread_pool
+            .execute({
+                let cli = Arc::clone(cli);
+                move || {
+                    let index_html = generate_index(&index_pages);
+                    write_html_to_file(&index_html, &cli.output_dir, "index.html")
+                        .and_then(|_| write_index_manifest(&cli.output_dir, &index_manifest))
+                        .unwrap_or_else(|e| {
+                            error!("Failed to write index.html: {e}");
+                        });
+                }
+            })
+            .map_err(|e| {
+                error!("Failed to execute job in thread pool for index generation: {e}");
+                e
+            })?;
+    }
+
+    if config.html.not_found_page {
+        thread_pool
+            .execute({
+                let cli = Arc::clone(cli);
+                let page_list = Arc::clone(&page_list);
+                move || {
+                    generate_404_page(&cli, not_found_file.as_deref(), &page_list).unwrap_or_else(
+                        |e| {
+                            error!("Failed to write 404.html: {e}");
+                        },
+                    );
+                }
+            })
+            .map_err(|e| {
+                error!("Failed to execute job in thread pool for 404 page generation: {e}");
+                e
+            })?;
+    }
+
+    let base_url = &config.html.base_url;
+    if base_url.is_empty() {
+        info!("No base_url configured, skipping sitemap.xml generation.");
+    } else {
+        thread_pool
+            .execute({
+                let cli = Arc::clone(cli);
+                let page_list = Arc::clone(&page_list);
+                move || {
+                    let sitemap = generate_sitemap(&page_list, base_url);
+                    write_sitemap_to_file(&sitemap, &cli.output_dir).unwrap_or_else(|e| {
+                        error!("Failed to write sitemap.xml: {e}");
+                    });
+                }
+            })
+            .map_err(|e| {
+                error!("Failed to execute job in thread pool for sitemap generation: {e}");
+                e
+            })?;
+    }
+
+    if !config.html.feed {
+        info!("Feed disabled, skipping atom.xml generation.");
+    } else if base_url.is_empty() {
+        warn!("The feed needs a base_url to link to pages, skipping atom.xml generation.");
+    } else {
+        thread_pool
+            .execute({
+                let cli = Arc::clone(cli);
+                move || {
+                    let pages: Vec<_> = feed_files
+                        .iter()
+                        .filter_map(|(file_name, file_path)| match read_input_file(file_path) {
+                            Ok(contents) => Some(page_meta(file_name, &contents)),
+                            Err(e) => {
+                                error!("Failed to read {file_name} for the feed: {e}");
+                                None
+                            }
+                        })
+                        .collect();
+                    let feed = generate_feed(&pages, base_url);
+                    write_feed_to_file(&feed, &cli.output_dir).unwrap_or_else(|e| {
+                        error!("Failed to write atom.xml: {e}");
+                    });
+                }
+            })
+            .map_err(|e| {
+                error!("Failed to execute job in thread pool for feed generation: {e}");
+                e
+            })?;
+    }
 
     let css_file = &config.html.css_file;
-    if css_file != "default" && !css_file.is_empty() {
+    if config.html.inline_css {
+        info!("Inlining CSS into each page, skipping styles.css.");
+    } else if css_file != "default" && !css_file.is_empty() {
         info!("Using custom CSS file: {}", css_file);
         thread_pool
             .execute({
-                let cli = Arc::clone(&cli);
+                let cli = Arc::clone(cli);
                 move || {
                     copy_css_to_output_dir(css_file, &cli.output_dir).unwrap_or_else(|e| {
                         error!("Failed to copy CSS file: {e}");
@@ -155,7 +806,7 @@ fn run() -> Result<(), Error> {
 
         thread_pool
             .execute({
-                let cli = Arc::clone(&cli);
+                let cli = Arc::clone(cli);
                 move || {
                     write_default_css_file(&cli.output_dir).unwrap_or_else(|e| {
                         error!("Failed to write default CSS file: {e}");
@@ -173,7 +824,7 @@ fn run() -> Result<(), Error> {
         info!("Copying favicon from: {}", favicon_path);
         thread_pool
             .execute({
-                let cli = Arc::clone(&cli);
+                let cli = Arc::clone(cli);
                 move || {
                     copy_favicon_to_output_dir(favicon_path, &cli.output_dir).unwrap_or_else(|e| {
                         error!("Failed to copy favicon: {e}");
@@ -188,59 +839,160 @@ fn run() -> Result<(), Error> {
         info!("No favicon specified in config.");
     }
 
+    if cli.copy_assets {
+        copy_assets(cli, &thread_pool, &generated_paths)?;
+    }
+
     thread_pool.join_all();
 
-    if cli.open {
-        let index_path = Path::new(&cli.output_dir).join("index.html");
-        if index_path.exists() {
-            if let Err(e) = webbrowser::open(&index_path.to_string_lossy()) {
-                error!("Failed to open index.html in browser: {e}");
-            } else {
-                info!("Opened index.html in browser.");
-            }
-        } else {
-            error!(
-                "index.html does not exist at path: {}",
-                index_path.display()
-            );
+    if let Err(e) = build_cache.write(&cli.output_dir) {
+        warn!("Failed to write build cache: {e}");
+    }
+
+    check_failed_pages(&failed_pages, rendered_pages)?;
+    check_warnings(&warnings, cli.strict)
+}
+
+/// Copies every file in the input directory that isn't markdown to the same path in the output
+/// directory. Files inside the output directory, i.e. when it's within the input directory, and
+/// files at the same path as a generated page are skipped.
+///
+/// # Arguments
+/// * `cli` - The parsed command line arguments.
+/// * `thread_pool` - The thread pool to copy the files on.
+/// * `generated_paths` - The paths of the generated pages, relative to the output directory.
+fn copy_assets(
+    cli: &Arc<Cli>,
+    thread_pool: &ThreadPool,
+    generated_paths: &HashSet<String>,
+) -> Result<(), Error> {
+    let asset_files = collect_asset_files(cli.input_dir(), &cli.recursive, &cli.exclude)?;
+    let output_dir = fs::canonicalize(&cli.output_dir).ok();
+
+    for (file_name, file_path) in asset_files {
+        let is_in_output_dir = output_dir.as_ref().is_some_and(|output_dir| {
+            fs::canonicalize(&file_path).is_ok_and(|path| path.starts_with(output_dir))
+        });
+        if is_in_output_dir {
+            continue;
+        }
+        if generated_paths.contains(&file_name) {
+            warn!("{file_name} would overwrite a generated page, skipping it.");
+            continue;
         }
+
+        info!("Copying asset: {file_name}");
+        thread_pool
+            .execute({
+                let cli = Arc::clone(cli);
+                move || {
+                    copy_asset_to_output_dir(&file_path, &cli.output_dir, &file_name)
+                        .unwrap_or_else(|e| {
+                            error!("Failed to copy {file_name}: {e}");
+                        });
+                }
+            })
+            .map_err(|e| {
+                error!("Failed to execute job in thread pool for copying assets: {e}");
+                e
+            })?;
     }
 
     Ok(())
 }
 
-fn generate_static_site(cli: Arc<Cli>, file_path: &str, file_contents: &str) -> Result<(), Error> {
-    // Tokenizing
-    let mut tokenized_lines: Vec<Vec<Token>> = Vec::new();
-    for line in file_contents.split('\n') {
-        tokenized_lines.push(tokenize(line));
+/// Summarizes the pages that failed to generate once every job has finished.
+///
+/// # Arguments
+/// * `failed_pages` - The paths of the pages that failed, whose errors have already been logged.
+/// * `total` - The number of pages that were generated, excluding those skipped as unchanged.
+///
+/// # Returns
+/// An `Error::PageGeneration` if any page failed.
+fn check_failed_pages(failed_pages: &[String], total: usize) -> Result<(), Error> {
+    if failed_pages.is_empty() {
+        return Ok(());
     }
 
-    // Parsing
-    let blocks = group_lines_to_blocks(tokenized_lines);
-    let parsed_elements = parse_blocks(&blocks);
+    let mut failed_pages = failed_pages.to_vec();
+    failed_pages.sort();
+    error!("Pages that failed to generate: {}", failed_pages.join(", "));
 
-    // HTML Generation
-    let generated_html = generate_html(
-        file_path,
-        &parsed_elements,
-        &cli.output_dir,
-        &cli.input_dir,
-        file_path,
-    );
+    Err(Error::PageGeneration {
+        failed: failed_pages.len(),
+        total,
+    })
+}
 
-    let html_relative_path = if file_path.ends_with(".md") {
-        file_path.trim_end_matches(".md").to_string() + ".html"
-    } else {
-        file_path.to_string() + ".html"
-    };
+/// Lists every warning reported while parsing the pages once every job has finished, failing the
+/// build if `--strict` was passed.
+///
+/// # Arguments
+/// * `warnings` - The warnings reported while parsing each page, which have already been logged.
+/// * `strict` - Whether warnings should fail the build.
+///
+/// # Returns
+/// An `Error::Warnings` if there were any warnings in a strict build.
+fn check_warnings(warnings: &[Diagnostic], strict: bool) -> Result<(), Error> {
+    if !strict || warnings.is_empty() {
+        return Ok(());
+    }
+
+    let mut warnings = warnings.to_vec();
+    warnings.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    for warning in &warnings {
+        error!("{warning}");
+    }
+
+    Err(Error::Warnings {
+        count: warnings.len(),
+    })
+}
+
+fn generate_static_site(
+    cli: Arc<Cli>,
+    file_path: &str,
+    file_contents: &str,
+    page_list: &[String],
+) -> Result<(PageOutput, Vec<Diagnostic>), Error> {
+    // Tokenizing and parsing, collecting any warnings so that they can be located in the file
+    let mut diagnostics = Diagnostics::new(file_path);
+    let (frontmatter, markdown) = diagnostics.collect(|| extract_frontmatter(file_contents));
+    let frontmatter_lines = file_contents[..file_contents.len() - markdown.len()]
+        .matches('\n')
+        .count();
+    let mut diagnostics = diagnostics.starting_at_line(frontmatter_lines + 1);
+    let mut parsed_elements = diagnostics.collect(|| parse_markdown(markdown));
+    let page = page_output(file_path, &parsed_elements, &frontmatter);
+
+    if cli.output_format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&parsed_elements).map_err(io::Error::other)?;
+        let json_relative_path = format!(
+            "{}.json",
+            file_path.strip_suffix(".md").unwrap_or(file_path)
+        );
+        write_json_to_file(&json, &cli.output_dir, &json_relative_path)?;
 
-    let output_path = Path::new(&cli.output_dir).join(&html_relative_path);
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent)?;
+        return Ok((page, diagnostics.into_warnings()));
     }
 
+    // HTML Generation
+    let config = CONFIG.get().unwrap();
+    let html_relative_path = output_relative_path(file_path, config.html.pretty_urls);
+    rewrite_page_links(&mut parsed_elements, file_path, config.html.pretty_urls);
+    let generated_html = diagnostics.collect(|| {
+        generate_html(
+            file_path,
+            &parsed_elements,
+            &cli.output_dir,
+            cli.input_dir(),
+            &html_relative_path,
+            page_list,
+            &frontmatter,
+        )
+    });
+
     write_html_to_file(&generated_html, &cli.output_dir, &html_relative_path)?;
 
-    Ok(())
+    Ok((page, diagnostics.into_warnings()))
 }
@@ -0,0 +1,145 @@
+//! This module provides functionality for minifying the generated HTML and CSS, enabled by the
+//! `--minify` CLI flag or the `minify` config option.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether generated pages and the default CSS should be minified.
+static MINIFY: AtomicBool = AtomicBool::new(false);
+
+/// The elements whose contents are kept exactly as they are, since whitespace inside them is
+/// significant.
+const PRESERVED_ELEMENTS: [&str; 5] = ["pre", "code", "textarea", "script", "style"];
+
+/// Enables or disables minification of the generated output.
+pub fn set_minify(enabled: bool) {
+    MINIFY.store(enabled, Ordering::SeqCst);
+}
+
+/// Returns whether minification is enabled.
+pub fn is_minify_enabled() -> bool {
+    MINIFY.load(Ordering::SeqCst)
+}
+
+/// Removes the whitespace that doesn't affect how an HTML page is rendered.
+///
+/// Whitespace containing a line break between two tags (i.e. the indentation added by
+/// `indent_html`) is removed entirely, while any other run of whitespace is collapsed to a single
+/// space. The contents of `<pre>`, `<code>`, `<textarea>`, `<script>` and `<style>` elements are
+/// left untouched.
+///
+/// # Arguments
+/// * `html` - The HTML to minify.
+///
+/// # Returns
+/// The minified HTML.
+pub fn minify_html(html: &str) -> String {
+    let html = html.trim();
+    // ASCII lowercasing keeps byte offsets the same, so closing tags can be found in this copy
+    let lowercase_html = html.to_ascii_lowercase();
+
+    let mut minified = String::with_capacity(html.len());
+    let mut pos = 0;
+    while let Some(ch) = html[pos..].chars().next() {
+        let rest = &html[pos..];
+
+        if ch == '<'
+            && let Some(element) = preserved_element(rest)
+        {
+            let closing_tag = format!("</{element}>");
+            let end = lowercase_html[pos..]
+                .find(&closing_tag)
+                .map_or(html.len(), |index| pos + index + closing_tag.len());
+
+            minified.push_str(&html[pos..end]);
+            pos = end;
+        } else if ch.is_whitespace() {
+            let whitespace_len = rest
+                .find(|ch: char| !ch.is_whitespace())
+                .unwrap_or(rest.len());
+            pos += whitespace_len;
+
+            let between_tags = minified.ends_with('>') && html[pos..].starts_with('<');
+            if !(between_tags && rest[..whitespace_len].contains('\n')) {
+                minified.push(' ');
+            }
+        } else {
+            minified.push(ch);
+            pos += ch.len_utf8();
+        }
+    }
+
+    minified
+}
+
+/// Returns the name of the preserved element opened by the tag at the start of `html`, if any.
+fn preserved_element(html: &str) -> Option<&'static str> {
+    PRESERVED_ELEMENTS.into_iter().find(|element| {
+        let tag_len = element.len() + 1;
+        html.get(1..tag_len)
+            .is_some_and(|name| name.eq_ignore_ascii_case(element))
+            && html[tag_len..].starts_with(|ch: char| ch == '>' || ch.is_whitespace())
+    })
+}
+
+/// Removes comments and the whitespace that doesn't affect a stylesheet.
+///
+/// Runs of whitespace are collapsed to a single space, which is then removed entirely around
+/// braces, semicolons, commas, and after colons. Quoted strings are left untouched.
+///
+/// # Arguments
+/// * `css` - The CSS to minify.
+///
+/// # Returns
+/// The minified CSS.
+pub fn minify_css(css: &str) -> String {
+    let mut minified = String::with_capacity(css.len());
+    let mut chars = css.trim().chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' | '\'' => {
+                minified.push(ch);
+                for string_ch in chars.by_ref() {
+                    minified.push(string_ch);
+                    if string_ch == ch {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for comment_ch in chars.by_ref() {
+                    if previous == '*' && comment_ch == '/' {
+                        break;
+                    }
+                    previous = comment_ch;
+                }
+            }
+            _ if ch.is_whitespace() => {
+                while chars.next_if(|next| next.is_whitespace()).is_some() {}
+
+                let next_is_separator = chars
+                    .peek()
+                    .is_none_or(|next| matches!(next, '{' | '}' | ';' | ','));
+                let previous_is_separator = minified
+                    .chars()
+                    .last()
+                    .is_none_or(|previous| matches!(previous, '{' | '}' | ';' | ',' | ':'));
+                if !next_is_separator && !previous_is_separator {
+                    minified.push(' ');
+                }
+            }
+            '}' if minified.ends_with(';') => {
+                minified.pop();
+                minified.push('}');
+            }
+            _ => minified.push(ch),
+        }
+    }
+
+    minified
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,45 @@
+use crate::minify::{minify_css, minify_html};
+
+#[test]
+fn indentation_between_tags_is_removed() {
+    assert_eq!(
+        minify_html("<ul>\n\t<li>One</li>\n\t<li>Two</li>\n</ul>\n"),
+        "<ul><li>One</li><li>Two</li></ul>"
+    );
+}
+
+#[test]
+fn whitespace_in_text_is_collapsed() {
+    assert_eq!(
+        minify_html("<p>Some   <b>bold</b>\n\ttext</p>"),
+        "<p>Some <b>bold</b> text</p>"
+    );
+}
+
+#[test]
+fn preformatted_content_is_preserved() {
+    let html = "<div>\n\t<pre><code class=\"language-rust\">fn main() {\n    let  x = 1;\n}</code></pre>\n\t<p>Inline <CODE>a  b</CODE></p>\n</div>";
+
+    assert_eq!(
+        minify_html(html),
+        "<div><pre><code class=\"language-rust\">fn main() {\n    let  x = 1;\n}</code></pre><p>Inline <CODE>a  b</CODE></p></div>"
+    );
+}
+
+#[test]
+fn unclosed_preserved_element_keeps_the_rest() {
+    assert_eq!(
+        minify_html("<p>a</p>\n<pre>b  \n c"),
+        "<p>a</p><pre>b  \n c"
+    );
+}
+
+#[test]
+fn css_whitespace_and_comments_are_removed() {
+    let css = "\n    /* Links */\n    a:hover,\n    a:focus {\n    color: #fff;\n    font-family: \"Segoe  UI\", sans-serif;\n    }\n";
+
+    assert_eq!(
+        minify_css(css),
+        "a:hover,a:focus{color:#fff;font-family:\"Segoe  UI\",sans-serif}"
+    );
+}
@@ -4,29 +4,180 @@
 //! It provides functions to parse block-level elements like headings, lists, and code blocks,
 //! as well as inline elements like links, images, and emphasis.
 
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::mem::take;
+use std::sync::LazyLock;
 
-use log::warn;
+use regex::Regex;
 
-use crate::CONFIG;
+use crate::config::current_config;
+use crate::diagnostics::{report_warning, set_current_line};
 use crate::types::{
-    Delimiter, MdBlockElement, MdInlineElement, MdListItem, MdTableCell, TableAlignment, Token,
-    TokenCursor,
+    CalloutKind, CodeBlockAttributes, Delimiter, ListMarkerKind, MdBlockElement, MdDefinitionItem,
+    MdInlineElement, MdListItem, MdTableCell, TableAlignment, Token, TokenCursor,
 };
 use crate::utils::push_buffer_to_collection;
 
+/// Matches bare email addresses. This is intentionally conservative, requiring a local part and a
+/// dotted domain so that `@handle` mentions are never matched.
+static EMAIL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").unwrap()
+});
+
+/// Matches `@username` mentions.
+static MENTION_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"@[A-Za-z0-9_]+").unwrap());
+
+/// Matches `#tag` hashtags.
+static HASHTAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"#[A-Za-z0-9_-]+").unwrap());
+
+/// Matches bare `http://` and `https://` URLs. Parentheses are only included when balanced, and
+/// a URL can't end in punctuation, so `(see https://example.com).` links just the URL.
+static URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"https?://(?:[^\s<>()]|\([^\s<>()]*\))*(?:[^\s<>().,:;!?'"*_~]|\([^\s<>()]*\))"#)
+        .unwrap()
+});
+
+/// Matches the contents of a CommonMark URI autolink, i.e. `<https://example.com>`.
+static URI_AUTOLINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]{1,31}:[^\s<>]*$").unwrap());
+
+/// Matches the contents of a CommonMark email autolink, i.e. `<user@example.com>`.
+static EMAIL_AUTOLINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z0-9.!#$%&'*+/=?^_`{|}~-]+@[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?(?:\.[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?)*$").unwrap()
+});
+
+/// Matches a CommonMark open tag, capturing the tag name and the `/` of a self-closing tag.
+static HTML_OPEN_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^<([A-Za-z][A-Za-z0-9-]*)(?:\s+[A-Za-z_:][A-Za-z0-9_.:-]*(?:\s*=\s*(?:[^\s"'=<>`]+|'[^']*'|"[^"]*"))?)*\s*(/?)>$"#,
+    )
+    .unwrap()
+});
+
+/// Matches a CommonMark closing tag, capturing the tag name.
+static HTML_CLOSING_TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^</([A-Za-z][A-Za-z0-9-]*)\s*>$").unwrap());
+
+/// Matches HTML comments, processing instructions, declarations and CDATA sections, none of which
+/// need to be closed.
+static HTML_STANDALONE_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)^(?:<!--.*-->|<\?.*\?>|<![A-Za-z][^>]*>|<!\[CDATA\[.*\]\]>)$").unwrap()
+});
+
+/// HTML elements that never have a closing tag.
+const VOID_HTML_ELEMENTS: [&str; 13] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Maps HTML5 named character references, i.e. `&copy;`, to the characters they stand for.
+static NAMED_ENTITIES: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    entities::ENTITIES
+        .iter()
+        .filter(|entity| entity.entity.ends_with(';'))
+        .map(|entity| (entity.entity, entity.characters))
+        .collect()
+});
+
+thread_local! {
+    /// The link reference definitions (`[label]: url "title"`) of the document being parsed on
+    /// this thread, keyed by their normalized label.
+    static LINK_REFERENCES: RefCell<HashMap<String, (String, Option<String>)>> =
+        RefCell::new(HashMap::new());
+
+    /// The labels of the footnotes defined (`[^label]: note`) in the document being parsed on
+    /// this thread, so that references to undefined footnotes can be left as text.
+    static FOOTNOTE_LABELS: RefCell<HashSet<String>> = RefCell::new(HashSet::new());
+}
+
 /// Parses a vector of tokenized markdown lines into a vector of block-level Markdown elements.
 ///
+/// This is the entry point for parsing a whole document, so any link reference definitions left
+/// over from a previous document are forgotten first.
+///
 /// # Arguments
 /// * `markdown_lines` - A vector of vectors, where each inner vector contains tokens representing a line of markdown.
 ///
 /// # Returns
 /// A vector of parsed block-level Markdown elements.
 pub fn parse_blocks(markdown_lines: &[Vec<Token>]) -> Vec<MdBlockElement> {
+    LINK_REFERENCES.with(|references| references.borrow_mut().clear());
+    FOOTNOTE_LABELS.with(|labels| labels.borrow_mut().clear());
+
+    parse_block_sequence(markdown_lines, None)
+}
+
+/// Parses a whole document like `parse_blocks`, along with the line each block starts on (as
+/// given by `group_lines_with_first_lines`) so that any warnings can point at it.
+///
+/// # Arguments
+/// * `markdown_lines` - The grouped blocks of the document.
+/// * `first_lines` - The line each block starts on, counting from 0.
+///
+/// # Returns
+/// A vector of parsed block-level Markdown elements.
+pub(crate) fn parse_blocks_with_first_lines(
+    markdown_lines: &[Vec<Token>],
+    first_lines: &[usize],
+) -> Vec<MdBlockElement> {
+    LINK_REFERENCES.with(|references| references.borrow_mut().clear());
+    FOOTNOTE_LABELS.with(|labels| labels.borrow_mut().clear());
+
+    parse_block_sequence(markdown_lines, Some(first_lines))
+}
+
+/// Parses a sequence of blocks, i.e. a whole document or the contents of a blockquote.
+///
+/// Link reference and footnote definitions are collected before anything else is parsed, so
+/// references can be used before the line that defines them. Link reference definition lines
+/// produce no output, while footnote definitions become `MdBlockElement::FootnoteDefinition`s.
+///
+/// # Arguments
+/// * `markdown_lines` - The grouped blocks to parse.
+/// * `first_lines` - The line each block starts on, used to locate warnings. Nested sequences
+///   have none, so their warnings point at the line of the block containing them.
+///
+/// # Returns
+/// A vector of parsed block-level Markdown elements.
+fn parse_block_sequence(
+    markdown_lines: &[Vec<Token>],
+    first_lines: Option<&[usize]>,
+) -> Vec<MdBlockElement> {
     let mut block_elements: Vec<MdBlockElement> = Vec::new();
 
-    for line in markdown_lines {
-        if let Some(element) = parse_block(line) {
+    let markdown_lines: Vec<(usize, &Vec<Token>)> = markdown_lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| match parse_link_reference_definition(line) {
+            Some((label, url, title)) => {
+                LINK_REFERENCES.with(|references| {
+                    // Per CommonMark, the first definition of a label wins
+                    references.borrow_mut().entry(label).or_insert((url, title));
+                });
+                false
+            }
+            None => true,
+        })
+        .collect();
+
+    for (_, line) in &markdown_lines {
+        if let Some((label, _)) = split_footnote_definition(line) {
+            FOOTNOTE_LABELS.with(|labels| labels.borrow_mut().insert(label));
+        }
+    }
+
+    for (i, line) in markdown_lines {
+        if let Some(first_line) = first_lines.and_then(|first_lines| first_lines.get(i)) {
+            set_current_line(*first_line);
+        }
+
+        if let Some((label, content)) = split_footnote_definition(line) {
+            block_elements.push(MdBlockElement::FootnoteDefinition {
+                label,
+                content: parse_inline(content),
+            });
+        } else if let Some(element) = parse_block(line) {
             block_elements.push(element)
         }
     }
@@ -42,27 +193,38 @@ pub fn parse_blocks(markdown_lines: &[Vec<Token>]) -> Vec<MdBlockElement> {
 /// # Returns
 /// An `Option<MdBlockElement>`, returning `None` for empty lines
 fn parse_block(line: &[Token]) -> Option<MdBlockElement> {
+    if is_definition_list_block(line) {
+        return Some(parse_definition_list(line));
+    }
+
     let first_token = line.first();
 
     match first_token {
         Some(Token::Punctuation(string)) if string == "#" => Some(parse_heading(line)),
-        Some(Token::Punctuation(string)) if string == "-" || string == "*" => {
-            // Note that setext headings have already been handled in the group_lines_to_blocks
-            // function by this point
-            if line.len() == 1 && string == "-" {
-                // If the line only contains a dash, then it is a thematic break
-                Some(MdBlockElement::ThematicBreak)
-            } else {
-                Some(parse_unordered_list(line))
-            }
+        Some(Token::Punctuation(string))
+            if (string == "-" || string == "*")
+                && matches!(
+                    line.get(1),
+                    None | Some(Token::Whitespace | Token::Tab { .. } | Token::Newline)
+                ) =>
+        {
+            // Note that setext headings and thematic breaks have already been handled by this
+            // point, so a lone `-` is an empty list item, while `-` followed by text (i.e.
+            // `-- Author`) is a paragraph
+            Some(parse_unordered_list(line))
+        }
+        Some(Token::OrderedListMarker(..)) => Some(parse_ordered_list(line)),
+        Some(Token::CodeFence { .. }) => Some(parse_codeblock(line)),
+        Some(Token::Punctuation(string))
+            if string == "$$" && current_config().html.math && is_math_fence(line) =>
+        {
+            Some(parse_math_block(line))
         }
-        Some(Token::OrderedListMarker(_)) => Some(parse_ordered_list(line)),
-        Some(Token::CodeFence) => Some(parse_codeblock(line)),
-        Some(Token::ThematicBreak) => Some(MdBlockElement::ThematicBreak),
+        Some(Token::ThematicBreak(_)) => Some(MdBlockElement::ThematicBreak),
         Some(Token::TableCellSeparator) => Some(parse_table(line)),
         Some(Token::BlockQuoteMarker) => Some(parse_blockquote(line)),
-        Some(Token::RawHtmlTag(_)) => Some(parse_raw_html(line)),
-        Some(Token::Tab) => Some(parse_indented_codeblock(line)),
+        Some(Token::RawHtmlTag(tag)) if autolink_url(tag).is_none() => Some(parse_raw_html(line)),
+        Some(Token::Tab { .. }) => Some(parse_indented_codeblock(line)),
         Some(Token::Newline) => None,
         _ => Some(MdBlockElement::Paragraph {
             content: parse_inline(line),
@@ -70,6 +232,145 @@ fn parse_block(line: &[Token]) -> Option<MdBlockElement> {
     }
 }
 
+/// Parses a link reference definition, i.e. `[label]: https://example.com "Optional title"`.
+///
+/// # Arguments
+/// * `line` - The tokens of a single block.
+///
+/// # Returns
+/// The normalized label, the destination, and the title, or `None` if the block isn't a link
+/// reference definition.
+fn parse_link_reference_definition(line: &[Token]) -> Option<(String, String, Option<String>)> {
+    if line.first() != Some(&Token::OpenBracket) || is_footnote_label_start(line.get(1)) {
+        return None;
+    }
+
+    let label_end = line
+        .iter()
+        .position(|token| token == &Token::CloseBracket)?;
+    let label = normalize_reference_label(&info_string_to_text(&line[1..label_end]));
+    if label.is_empty() || line.get(label_end + 1) != Some(&Token::Punctuation(":".to_string())) {
+        return None;
+    }
+
+    let rest = info_string_to_text(&line[label_end + 2..]);
+    let rest = rest.trim();
+    let (url, title) = match rest.split_once(char::is_whitespace) {
+        Some((url, title)) => (url, title.trim()),
+        None => (rest, ""),
+    };
+
+    let url = url
+        .strip_prefix('<')
+        .and_then(|url| url.strip_suffix('>'))
+        .unwrap_or(url);
+    if url.is_empty() {
+        return None;
+    }
+
+    let title = if title.is_empty() {
+        None
+    } else {
+        let is_delimited = |open: char, close: char| {
+            title.len() >= 2 && title.starts_with(open) && title.ends_with(close)
+        };
+        if !(is_delimited('"', '"') || is_delimited('\'', '\'') || is_delimited('(', ')')) {
+            // Anything other than a title after the destination means this is a paragraph
+            return None;
+        }
+        Some(title[1..title.len() - 1].to_string())
+    };
+
+    Some((label, url.to_string(), title))
+}
+
+/// Returns whether the token following an open bracket starts a footnote label, i.e. the `^1` in
+/// `[^1]`.
+fn is_footnote_label_start(token: Option<&Token>) -> bool {
+    matches!(token, Some(Token::Text(string)) if string.starts_with('^'))
+}
+
+/// Returns the label of a footnote from the tokens between its brackets, i.e. `1` for `[^1]`, or
+/// `None` if the tokens aren't a footnote label.
+fn footnote_label(label_tokens: &[Token]) -> Option<String> {
+    if !is_footnote_label_start(label_tokens.first())
+        || label_tokens.iter().any(|token| {
+            matches!(
+                token,
+                Token::OpenBracket | Token::Whitespace | Token::Tab { .. }
+            )
+        })
+    {
+        return None;
+    }
+
+    let label = info_string_to_text(label_tokens);
+    let label = label.strip_prefix('^')?;
+
+    (!label.is_empty()).then(|| normalize_reference_label(label))
+}
+
+/// Splits a footnote definition (`[^label]: The note`) into its normalized label and the tokens
+/// of the note.
+///
+/// # Arguments
+/// * `line` - The tokens of a single block.
+///
+/// # Returns
+/// The label and the note's tokens, or `None` if the block isn't a footnote definition.
+fn split_footnote_definition(line: &[Token]) -> Option<(String, &[Token])> {
+    if line.first() != Some(&Token::OpenBracket) {
+        return None;
+    }
+
+    let label_end = line
+        .iter()
+        .position(|token| token == &Token::CloseBracket)?;
+    let label = footnote_label(&line[1..label_end])?;
+    if line.get(label_end + 1) != Some(&Token::Punctuation(":".to_string())) {
+        return None;
+    }
+
+    let content = &line[label_end + 2..];
+    let content = match content.first() {
+        Some(Token::Whitespace) => &content[1..],
+        _ => content,
+    };
+
+    Some((label, content))
+}
+
+/// Returns the normalized label of the footnote referenced at the cursor (`[^label]`) if that
+/// footnote is defined, along with the position of the reference's closing bracket.
+fn footnote_reference(cursor: &TokenCursor) -> Option<(String, usize)> {
+    let label_start = cursor.position() + 1;
+    let label_end = cursor.tokens[label_start..]
+        .iter()
+        .position(|token| token == &Token::CloseBracket)
+        .map(|offset| label_start + offset)?;
+
+    let label = footnote_label(&cursor.tokens[label_start..label_end])?;
+    let is_defined = FOOTNOTE_LABELS.with(|labels| labels.borrow().contains(&label));
+
+    is_defined.then_some((label, label_end))
+}
+
+/// Normalizes a link reference label so that labels match case-insensitively and regardless of
+/// internal whitespace, as required by CommonMark.
+fn normalize_reference_label(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Looks up a link reference definition by its (un-normalized) label.
+fn lookup_link_reference(label: &str) -> Option<(String, Option<String>)> {
+    let label = normalize_reference_label(label);
+    LINK_REFERENCES.with(|references| references.borrow().get(&label).cloned())
+}
+
 /// Parses an indented code block from a vector of tokens.
 ///
 /// Note that CommonMark defines indented code blocks as lines that start with at least 4 spaces or
@@ -94,18 +395,18 @@ fn parse_indented_codeblock(line: &[Token]) -> MdBlockElement {
 
         for token in &token_line[1..] {
             match token {
-                Token::Tab => {
-                    line_buffer.push_str(&" ".repeat(CONFIG.get().unwrap().lexer.tab_size));
-                }
+                Token::Tab { literal } => line_buffer.push_str(&code_tab(*literal)),
                 Token::Text(string) | Token::Punctuation(string) => line_buffer.push_str(string),
                 Token::Whitespace => line_buffer.push(' '),
-                Token::Newline => {
+                Token::Newline | Token::HardBreak => {
                     push_buffer_to_collection(&mut code_content, &mut line_buffer);
                 }
                 Token::Escape(esc_char) => {
                     line_buffer.push_str(&format!("\\{esc_char}"));
                 }
-                Token::OrderedListMarker(string) => line_buffer.push_str(string),
+                Token::OrderedListMarker(string, _, delimiter) => {
+                    line_buffer.push_str(&format!("{string}{delimiter}"))
+                }
                 Token::EmphasisRun { delimiter, length } => {
                     line_buffer.push_str(&delimiter.to_string().repeat(*length))
                 }
@@ -114,10 +415,12 @@ fn parse_indented_codeblock(line: &[Token]) -> MdBlockElement {
                 Token::OpenBracket => line_buffer.push('['),
                 Token::CloseBracket => line_buffer.push(']'),
                 Token::TableCellSeparator => line_buffer.push('|'),
-                Token::CodeTick => line_buffer.push('`'),
-                Token::CodeFence => line_buffer.push_str("```"),
+                Token::CodeTick(length) => line_buffer.push_str(&"`".repeat(*length)),
+                Token::CodeFence { delimiter, length } => {
+                    line_buffer.push_str(&delimiter.to_string().repeat(*length))
+                }
                 Token::BlockQuoteMarker => line_buffer.push('>'),
-                Token::ThematicBreak => line_buffer.push_str("---"),
+                Token::ThematicBreak(text) => line_buffer.push_str(text),
                 Token::RawHtmlTag(tag_content) => {
                     // This should never be the first token, but inline html is allowed
                     let escaped_tag = tag_content.replace("<", "&lt;").replace(">", "&gt;");
@@ -131,10 +434,33 @@ fn parse_indented_codeblock(line: &[Token]) -> MdBlockElement {
 
     MdBlockElement::CodeBlock {
         language: None,
+        info: String::new(),
+        attributes: CodeBlockAttributes::default(),
         lines: code_content,
     }
 }
 
+/// Returns the text that a tab token stands for inside of code. Runs of spaces are restored as
+/// written, while tab characters are kept or expanded according to `preserve_code_tabs` and
+/// `code_tab_size`.
+///
+/// # Arguments
+/// * `literal` - Whether the token came from a tab character rather than a run of spaces.
+fn code_tab(literal: bool) -> String {
+    let config = current_config();
+    if !literal {
+        return " ".repeat(config.lexer.tab_size);
+    }
+    if config.html.preserve_code_tabs {
+        return String::from("\t");
+    }
+
+    match config.html.code_tab_size {
+        0 => " ".repeat(config.lexer.tab_size),
+        code_tab_size => " ".repeat(code_tab_size),
+    }
+}
+
 /// Parses raw HTML tags from a vector of tokens into an `MdBlockElement::RawHtml`.
 ///
 /// # Arguments
@@ -152,8 +478,10 @@ fn parse_raw_html(line: &[Token]) -> MdBlockElement {
             Token::Escape(esc_char) => {
                 html_content.push_str(&format!("\\{esc_char}"));
             }
-            Token::Newline => html_content.push('\n'),
-            Token::OrderedListMarker(string) => html_content.push_str(string),
+            Token::Newline | Token::HardBreak => html_content.push('\n'),
+            Token::OrderedListMarker(string, _, delimiter) => {
+                html_content.push_str(&format!("{string}{delimiter}"))
+            }
             Token::EmphasisRun { delimiter, length } => {
                 html_content.push_str(&delimiter.to_string().repeat(*length))
             }
@@ -162,13 +490,15 @@ fn parse_raw_html(line: &[Token]) -> MdBlockElement {
             Token::OpenBracket => html_content.push('['),
             Token::CloseBracket => html_content.push(']'),
             Token::TableCellSeparator => html_content.push('|'),
-            Token::CodeTick => html_content.push('`'),
-            Token::CodeFence => html_content.push_str("```"),
+            Token::CodeTick(length) => html_content.push_str(&"`".repeat(*length)),
+            Token::CodeFence { delimiter, length } => {
+                html_content.push_str(&delimiter.to_string().repeat(*length))
+            }
             Token::BlockQuoteMarker => html_content.push('>'),
-            Token::Tab => {
-                html_content.push_str(&" ".repeat(CONFIG.get().unwrap().lexer.tab_size));
+            Token::Tab { .. } => {
+                html_content.push_str(&" ".repeat(current_config().lexer.tab_size));
             }
-            Token::ThematicBreak => html_content.push_str("---"),
+            Token::ThematicBreak(text) => html_content.push_str(text),
         }
     }
 
@@ -183,8 +513,9 @@ fn parse_raw_html(line: &[Token]) -> MdBlockElement {
 /// * `line` - A vector of tokens representing a blockquote.
 ///
 /// # Returns
-/// An `MdBlockElement::BlockQuote` containing the parsed content, or a `MdBlockElement::Paragraph`
-/// if the content is empty.
+/// An `MdBlockElement::BlockQuote` containing the parsed content, an `MdBlockElement::Details` or
+/// `MdBlockElement::Callout` if the first line is a `[!details]` or `[!TYPE]` marker, or a
+/// `MdBlockElement::Paragraph` if the content is empty.
 fn parse_blockquote(line: &[Token]) -> MdBlockElement {
     let lines_split_by_newline = line.split(|token| token == &Token::Newline);
 
@@ -200,23 +531,128 @@ fn parse_blockquote(line: &[Token]) -> MdBlockElement {
             } else {
                 result.extend_from_slice(tokens);
             }
+
+            // An empty `>` line is a blank line inside the quote, separating its paragraphs
+            if result
+                .iter()
+                .all(|token| matches!(token, Token::Whitespace | Token::Tab { .. }))
+            {
+                result = vec![Token::Newline];
+            }
             result
         })
         .collect();
 
+    let mut inner_blocks = inner_blocks;
+    let marker = inner_blocks
+        .first()
+        .and_then(|line| split_blockquote_marker(line))
+        .map(|(name, rest)| (name.to_string(), rest.to_vec()));
+    if let Some((name, rest)) = marker {
+        if name.eq_ignore_ascii_case("details") {
+            let summary = if rest.is_empty() {
+                vec![MdInlineElement::Text {
+                    content: String::from("Details"),
+                }]
+            } else {
+                parse_inline(&rest)
+            };
+            inner_blocks.remove(0);
+
+            let content = parse_block_sequence(&group_lines_to_blocks(inner_blocks), None);
+            return MdBlockElement::Details { summary, content };
+        }
+
+        // Unknown types fall through to a plain blockquote, keeping the marker as text
+        if let Some(kind) = CalloutKind::from_name(&name) {
+            // Text after the marker is the first line of the callout's content
+            if rest.is_empty() {
+                inner_blocks.remove(0);
+            } else {
+                inner_blocks[0] = rest;
+            }
+
+            let content = parse_block_sequence(&group_lines_to_blocks(inner_blocks), None);
+            return MdBlockElement::Callout { kind, content };
+        }
+    }
+
+    let citation = if current_config().html.blockquote_cite {
+        extract_citation(&mut inner_blocks)
+    } else {
+        None
+    };
+
     let grouped_inner_blocks = group_lines_to_blocks(inner_blocks);
 
-    let content = parse_blocks(&grouped_inner_blocks);
+    let content = parse_block_sequence(&grouped_inner_blocks, None);
 
     if content.is_empty() {
         MdBlockElement::Paragraph {
             content: parse_inline(line),
         }
     } else {
-        MdBlockElement::BlockQuote { content }
+        MdBlockElement::BlockQuote { content, citation }
     }
 }
 
+/// Splits a `[!name]` marker from the first line of a blockquote, which turns the blockquote into
+/// a collapsible `<details>` block (`[!details]`) or a callout (i.e. `[!NOTE]`).
+///
+/// # Arguments
+/// * `line` - The first line of the blockquote, with the `>` marker already stripped.
+///
+/// # Returns
+/// The name inside the marker and the tokens after it, or `None` if the line doesn't start with a
+/// marker.
+fn split_blockquote_marker(line: &[Token]) -> Option<(&str, &[Token])> {
+    match line {
+        [
+            Token::OpenBracket,
+            Token::Punctuation(bang),
+            Token::Text(name),
+            Token::CloseBracket,
+            rest @ ..,
+        ] if bang == "!" => {
+            let rest_start = rest
+                .iter()
+                .position(|token| !matches!(token, Token::Whitespace | Token::Tab { .. }))
+                .unwrap_or(rest.len());
+            Some((name, &rest[rest_start..]))
+        }
+        _ => None,
+    }
+}
+
+/// Removes a trailing `-- Author` line from the lines of a blockquote and parses it as the
+/// blockquote's citation.
+///
+/// # Arguments
+/// * `lines` - The lines of the blockquote, with the `>` markers already stripped.
+///
+/// # Returns
+/// The parsed citation, or `None` if the last line isn't a citation. A lone citation line is
+/// not treated as one, since the blockquote would otherwise be left empty.
+fn extract_citation(lines: &mut Vec<Vec<Token>>) -> Option<Vec<MdInlineElement>> {
+    let last_line = lines.last()?;
+    let is_citation = matches!(
+        last_line.as_slice(),
+        [Token::Punctuation(first), Token::Punctuation(second), Token::Whitespace, rest @ ..]
+            if first == "-" && second == "-" && !rest.is_empty()
+    );
+
+    let has_other_content = lines[..lines.len() - 1]
+        .iter()
+        .any(|line| line.iter().any(|token| token != &Token::Newline));
+
+    if !is_citation || !has_other_content {
+        return None;
+    }
+
+    let last_line = lines.pop()?;
+    Some(parse_inline(&last_line[3..]))
+}
+
 /// Parses a vector of tokens representing an ordered list into an `MdBlockElement::OrderedList`.
 ///
 /// Calls the more generic `parse_list` function, which parses nested list items
@@ -227,22 +663,25 @@ fn parse_blockquote(line: &[Token]) -> MdBlockElement {
 /// # Returns
 /// An `MdBlockElement` representing the ordered list.
 fn parse_ordered_list(list: &[Token]) -> MdBlockElement {
-    let starting_num = if let Some(Token::OrderedListMarker(num)) = list.first() {
-        num.parse::<usize>().unwrap_or(1)
+    let (starting_num, kind) = if let Some(Token::OrderedListMarker(marker, kind, _)) = list.first()
+    {
+        (kind.marker_value(marker).unwrap_or(1), kind.clone())
     } else {
-        1
+        (1, ListMarkerKind::Numeric)
     };
     parse_list(
         list,
         |tokens| {
             matches!(
                 tokens.first(),
-                Some(Token::OrderedListMarker(_)) if tokens.get(1) == Some(&Token::Whitespace)
+                Some(Token::OrderedListMarker(..)) if tokens.get(1) == Some(&Token::Whitespace)
             )
         },
-        |items| MdBlockElement::OrderedList {
+        |items, loose| MdBlockElement::OrderedList {
             items,
             starting_num,
+            kind: kind.clone(),
+            loose,
         },
     )
 }
@@ -260,10 +699,11 @@ fn parse_unordered_list(list: &[Token]) -> MdBlockElement {
     parse_list(
         list,
         |tokens| {
-            matches!(tokens.first(), Some(Token::Punctuation(string)) if (string == "-" || string == "*") && tokens.get(1) == Some(&Token::Whitespace)
-            )
+            // A marker on its own is an empty item
+            matches!(tokens.first(), Some(Token::Punctuation(string)) if (string == "-" || string == "*"))
+                && matches!(tokens.get(1), Some(Token::Whitespace) | None)
         },
-        |items| MdBlockElement::UnorderedList { items },
+        |items, loose| MdBlockElement::UnorderedList { items, loose },
     )
 }
 
@@ -272,30 +712,35 @@ fn parse_unordered_list(list: &[Token]) -> MdBlockElement {
 /// Handles splitting lines, identifying list items, and parsing nested lists. The behavior is
 /// determined by a predicate for identifying list items and a constructor for the resulting block.
 ///
+/// A blank line between two items makes the list loose. Blank lines between the items of a nested
+/// list only make that nested list loose.
+///
 /// # Arguments
 /// * `list` - The tokens to parse.
 /// * `is_list_item` - Predicate to identify a top-level list item.
-/// * `make_block` - Constructor for the resulting `MdBlockElement`.
+/// * `make_block` - Constructor for the resulting `MdBlockElement`, given the items and whether
+///   the list is loose.
 ///
 /// # Returns
 /// An `MdBlockElement` representing either an ordered or unordered list, depending on the passed in constructor.
 fn parse_list<F, G>(list: &[Token], is_list_item: F, make_block: G) -> MdBlockElement
 where
     F: Fn(&[Token]) -> bool,
-    G: Fn(Vec<MdListItem>) -> MdBlockElement,
+    G: Fn(Vec<MdListItem>, bool) -> MdBlockElement,
 {
     let lists_split_by_newline = list
         .split(|token| token == &Token::Newline)
         .collect::<Vec<_>>();
     let mut list_items: Vec<MdListItem> = Vec::new();
+    let mut loose = false;
 
     let mut i = 0;
     while i < lists_split_by_newline.len() {
         let line = lists_split_by_newline[i];
         if is_list_item(line) {
-            let content_tokens = &line[2..];
+            let (checked, content_tokens) = split_task_checkbox(line.get(2..).unwrap_or_default());
             if let Some(content) = parse_block(content_tokens) {
-                list_items.push(MdListItem { content })
+                list_items.push(MdListItem { content, checked })
             }
 
             // Check for consecutive tab-indented lines (nested list)
@@ -303,9 +748,18 @@ where
             let mut j = i + 1;
             while j < lists_split_by_newline.len() {
                 let nested_line = lists_split_by_newline[j];
-                if nested_line.first() == Some(&Token::Tab) {
+                let continues_after_blank = nested_line.is_empty()
+                    && lists_split_by_newline[j + 1..]
+                        .iter()
+                        .find(|line| !line.is_empty())
+                        .is_some_and(|line| matches!(line.first(), Some(Token::Tab { .. })));
+                if continues_after_blank {
+                    // Blank lines between nested items belong to the nested list
+                    nested_lines.push(Vec::new());
+                    j += 1;
+                } else if matches!(nested_line.first(), Some(Token::Tab { .. })) {
                     let mut nested = nested_line.to_vec();
-                    while !nested.is_empty() && nested[0] == Token::Tab {
+                    while matches!(nested.first(), Some(Token::Tab { .. })) {
                         nested.remove(0);
                     }
                     nested_lines.push(nested);
@@ -326,7 +780,7 @@ where
                 }
 
                 // Recursively parse nested list, try ordered first, fallback to unordered
-                let nested_block = if let Some(Token::OrderedListMarker(_)) = nested_tokens.first()
+                let nested_block = if let Some(Token::OrderedListMarker(..)) = nested_tokens.first()
                 {
                     parse_ordered_list(&nested_tokens)
                 } else {
@@ -335,16 +789,52 @@ where
 
                 list_items.push(MdListItem {
                     content: nested_block,
+                    checked: None,
                 });
 
                 i = j - 1; // Skip processed nested lines
             }
+        } else if line.is_empty()
+            && !list_items.is_empty()
+            && lists_split_by_newline[i + 1..]
+                .iter()
+                .any(|line| is_list_item(line))
+        {
+            loose = true;
         }
         i += 1;
     }
 
     // Use the passed in constructor to create the List element
-    make_block(list_items)
+    make_block(list_items, loose)
+}
+
+/// Splits a GitHub-style task list checkbox (`[ ]`, `[x]`, or `[X]` followed by whitespace) from
+/// the start of a list item's content.
+///
+/// # Arguments
+/// * `content_tokens` - The tokens of the list item, after the list marker.
+///
+/// # Returns
+/// The checked state (`None` if the item isn't a task) and the remaining content tokens.
+fn split_task_checkbox(content_tokens: &[Token]) -> (Option<bool>, &[Token]) {
+    match content_tokens {
+        [
+            Token::OpenBracket,
+            Token::Whitespace,
+            Token::CloseBracket,
+            Token::Whitespace,
+            rest @ ..,
+        ] => (Some(false), rest),
+        [
+            Token::OpenBracket,
+            Token::Text(mark),
+            Token::CloseBracket,
+            Token::Whitespace,
+            rest @ ..,
+        ] if mark == "x" || mark == "X" => (Some(true), rest),
+        _ => (None, content_tokens),
+    }
 }
 
 /// Parses a vector of tokens representing a code block into an `MdBlockElement::CodeBlock`.
@@ -359,13 +849,42 @@ where
 fn parse_codeblock(line: &[Token]) -> MdBlockElement {
     let mut code_content: Vec<String> = Vec::new();
     let mut language = None;
+    let mut info = String::new();
     let mut line_buffer: String = String::new();
     let mut lines_split_by_newline = line
         .split(|token| token == &Token::Newline)
         .collect::<Vec<_>>();
 
-    if let Some(Token::Text(string)) = line.get(1) {
-        language = Some(string.clone());
+    // The opening fence line holds the info string, which is never part of the code itself
+    if let Some(&fence_line) = lines_split_by_newline.first()
+        && let Some(opening_fence @ Token::CodeFence { .. }) = fence_line.first()
+    {
+        // Fences that don't close the block, i.e. ``` inside a ~~~ block, are kept as code
+        if lines_split_by_newline.len() > 1
+            && lines_split_by_newline
+                .last()
+                .is_some_and(|last_line| is_closing_fence(last_line, opening_fence))
+        {
+            lines_split_by_newline.pop();
+        }
+
+        let info_string = info_string_to_text(&fence_line[1..]);
+        let info_string = info_string.trim();
+
+        // The first word is the language, everything after it is kept verbatim
+        let (language_token, remainder) = info_string
+            .split_once(char::is_whitespace)
+            .unwrap_or((info_string, ""));
+
+        // Anything that couldn't be a language name is kept in the info string instead, so it
+        // can't end up in the language class
+        if is_language_name(language_token) {
+            language = Some(language_token.to_string());
+            info = remainder.trim().to_string();
+        } else {
+            info = info_string.to_string();
+        }
+
         lines_split_by_newline.remove(0);
     }
 
@@ -378,16 +897,16 @@ fn parse_codeblock(line: &[Token]) -> MdBlockElement {
             match token {
                 Token::Text(string) | Token::Punctuation(string) => line_buffer.push_str(string),
                 Token::Whitespace => line_buffer.push(' '),
-                Token::Newline => {
+                Token::Newline | Token::HardBreak => {
                     push_buffer_to_collection(&mut code_content, &mut line_buffer);
                 }
-                Token::Tab => {
-                    line_buffer.push_str(&" ".repeat(CONFIG.get().unwrap().lexer.tab_size));
-                }
+                Token::Tab { literal } => line_buffer.push_str(&code_tab(*literal)),
                 Token::Escape(esc_char) => {
                     line_buffer.push_str(&format!("\\{esc_char}"));
                 }
-                Token::OrderedListMarker(string) => line_buffer.push_str(string),
+                Token::OrderedListMarker(string, _, delimiter) => {
+                    line_buffer.push_str(&format!("{string}{delimiter}"))
+                }
                 Token::EmphasisRun { delimiter, length } => {
                     line_buffer.push_str(&delimiter.to_string().repeat(*length))
                 }
@@ -396,14 +915,16 @@ fn parse_codeblock(line: &[Token]) -> MdBlockElement {
                 Token::OpenBracket => line_buffer.push('['),
                 Token::CloseBracket => line_buffer.push(']'),
                 Token::TableCellSeparator => line_buffer.push('|'),
-                Token::CodeTick => line_buffer.push('`'),
-                Token::CodeFence => {}
+                Token::CodeTick(length) => line_buffer.push_str(&"`".repeat(*length)),
+                Token::CodeFence { delimiter, length } => {
+                    line_buffer.push_str(&delimiter.to_string().repeat(*length))
+                }
                 Token::BlockQuoteMarker => line_buffer.push('>'),
                 Token::RawHtmlTag(tag_content) => {
                     let escaped_tag = tag_content.replace("<", "&lt;").replace(">", "&gt;");
                     line_buffer.push_str(&escaped_tag);
                 }
-                Token::ThematicBreak => line_buffer.push_str("---"),
+                Token::ThematicBreak(text) => line_buffer.push_str(text),
             }
         }
 
@@ -412,12 +933,166 @@ fn parse_codeblock(line: &[Token]) -> MdBlockElement {
 
     push_buffer_to_collection(&mut code_content, &mut line_buffer);
 
+    let attributes = parse_code_attributes(&info);
+
     MdBlockElement::CodeBlock {
         language,
+        info,
+        attributes,
         lines: code_content,
     }
 }
 
+/// Checks whether a line closes the code block opened by the given fence, which requires a fence
+/// of the same character that is at least as long, with nothing but whitespace after it.
+///
+/// # Arguments
+/// * `line` - The tokens of the line to check.
+/// * `opening_fence` - The `CodeFence` token that opened the code block.
+fn is_closing_fence(line: &[Token], opening_fence: &Token) -> bool {
+    if matches!(opening_fence, Token::Punctuation(_)) {
+        return is_math_fence(line);
+    }
+
+    let (
+        Some(Token::CodeFence { delimiter, length }),
+        Token::CodeFence {
+            delimiter: opening_delimiter,
+            length: opening_length,
+        },
+    ) = (line.first(), opening_fence)
+    else {
+        return false;
+    };
+
+    delimiter == opening_delimiter
+        && length >= opening_length
+        && line[1..]
+            .iter()
+            .all(|token| matches!(token, Token::Whitespace | Token::Tab { .. }))
+}
+
+/// Parses the `{...}` block at the end of a code fence's info string into the code block's
+/// attributes. Each whitespace separated entry is either a `.class`, an `#id`, or a `key=value`
+/// pair, where the value can be wrapped in double quotes to include spaces.
+///
+/// # Arguments
+/// * `info` - The info string after the language.
+///
+/// # Returns
+/// The parsed attributes, which are empty if the info string doesn't end in a `{...}` block.
+fn parse_code_attributes(info: &str) -> CodeBlockAttributes {
+    let mut attributes = CodeBlockAttributes::default();
+    let Some(block) = info
+        .trim_end()
+        .strip_suffix('}')
+        .and_then(|rest| rest.rsplit_once('{'))
+        .map(|(_, block)| block)
+    else {
+        return attributes;
+    };
+
+    for entry in split_attribute_entries(block) {
+        if let Some(class) = entry.strip_prefix('.') {
+            attributes.classes.push(class.to_string());
+        } else if let Some(id) = entry.strip_prefix('#') {
+            attributes.id = Some(id.to_string());
+        } else {
+            let (key, value) = entry.split_once('=').unwrap_or((&entry, ""));
+            let value = value.trim_matches('"').to_string();
+            if key.is_empty()
+                || !key
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'))
+            {
+                report_warning(&format!("Ignoring invalid code block attribute: {entry}"));
+                continue;
+            }
+
+            match key {
+                "id" => attributes.id = Some(value),
+                "class" => attributes
+                    .classes
+                    .extend(value.split_whitespace().map(String::from)),
+                _ => attributes.data.push((key.to_string(), value)),
+            }
+        }
+    }
+
+    attributes
+}
+
+/// Splits the contents of an attribute block on whitespace, keeping double quoted values intact.
+fn split_attribute_entries(block: &str) -> Vec<String> {
+    let mut entries: Vec<String> = Vec::new();
+    let mut buffer = String::new();
+    let mut is_inside_quotes = false;
+    for ch in block.chars() {
+        match ch {
+            '"' => {
+                is_inside_quotes = !is_inside_quotes;
+                buffer.push(ch);
+            }
+            _ if ch.is_whitespace() && !is_inside_quotes => {
+                push_buffer_to_collection(&mut entries, &mut buffer);
+            }
+            _ => buffer.push(ch),
+        }
+    }
+
+    push_buffer_to_collection(&mut entries, &mut buffer);
+
+    entries
+}
+
+/// Checks whether the first word of an info string can be used as a code block's language.
+fn is_language_name(word: &str) -> bool {
+    !word.is_empty()
+        && word
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_' | '+' | '#' | '.'))
+}
+
+/// Converts the tokens following an opening code fence back into the raw info string.
+///
+/// # Arguments
+/// * `tokens` - The tokens after the opening `CodeFence` on the fence line.
+///
+/// # Returns
+/// A string containing the info string exactly as it was written.
+fn info_string_to_text(tokens: &[Token]) -> String {
+    let mut info_string = String::new();
+    for token in tokens {
+        match token {
+            Token::Text(string) | Token::Punctuation(string) => info_string.push_str(string),
+            Token::OrderedListMarker(string, _, delimiter) => {
+                info_string.push_str(&format!("{string}{delimiter}"))
+            }
+            Token::Escape(esc_char) => info_string.push_str(&format!("\\{esc_char}")),
+            Token::EmphasisRun { delimiter, length } => {
+                info_string.push_str(&delimiter.to_string().repeat(*length))
+            }
+            Token::Whitespace => info_string.push(' '),
+            Token::Tab { .. } => info_string.push('\t'),
+            Token::OpenParenthesis => info_string.push('('),
+            Token::CloseParenthesis => info_string.push(')'),
+            Token::OpenBracket => info_string.push('['),
+            Token::CloseBracket => info_string.push(']'),
+            Token::TableCellSeparator => info_string.push('|'),
+            Token::CodeTick(length) => info_string.push_str(&"`".repeat(*length)),
+            Token::CodeFence { delimiter, length } => {
+                info_string.push_str(&delimiter.to_string().repeat(*length))
+            }
+            Token::BlockQuoteMarker => info_string.push('>'),
+            Token::ThematicBreak(text) => info_string.push_str(text),
+            Token::RawHtmlTag(tag_content) => info_string.push_str(tag_content),
+            Token::Newline | Token::HardBreak => {}
+        }
+    }
+
+    info_string
+}
+
 /// Parses a vector of tokens representing a heading into an `MdBlockElement::Header`.
 ///
 /// Determines the heading level and parses the heading content.
@@ -457,6 +1132,42 @@ fn parse_heading(line: &[Token]) -> MdBlockElement {
     }
 }
 
+/// Parses a definition list block, grouped by `group_lines_to_blocks`, into an
+/// `MdBlockElement::DefinitionList`.
+///
+/// Consecutive term lines share the definitions that follow them, and a term line after a
+/// definition starts a new group.
+///
+/// # Arguments
+/// * `line` - The tokens of the block, with each line separated by a `Token::Newline`.
+///
+/// # Returns
+/// An `MdBlockElement::DefinitionList` containing the grouped terms and definitions.
+fn parse_definition_list(line: &[Token]) -> MdBlockElement {
+    let mut items: Vec<MdDefinitionItem> = Vec::new();
+    let mut current_item = MdDefinitionItem::default();
+
+    for list_line in line.split(|token| token == &Token::Newline) {
+        if is_definition_line(list_line) {
+            let content: Vec<Token> = list_line[1..]
+                .iter()
+                .skip_while(|token| matches!(token, Token::Whitespace | Token::Tab { .. }))
+                .cloned()
+                .collect();
+            current_item.definitions.push(parse_inline(&content));
+        } else {
+            if !current_item.definitions.is_empty() {
+                items.push(take(&mut current_item));
+            }
+            current_item.terms.push(parse_inline(list_line));
+        }
+    }
+
+    items.push(current_item);
+
+    MdBlockElement::DefinitionList { items }
+}
+
 /// Parses GitHub-style tables from the input vector of tokens.
 pub fn parse_table(line: &[Token]) -> MdBlockElement {
     let rows = line
@@ -477,48 +1188,33 @@ pub fn parse_table(line: &[Token]) -> MdBlockElement {
 
     let alignments: Vec<TableAlignment> = split_row(alignment_row)
         .into_iter()
-        .map(|cell_content| {
-            let content: String = cell_content
-                .iter()
-                .filter_map(|token| match token {
-                    Token::Text(s) => {
-                        warn!("Table alignment should not contain text as it could result in unexpected behavior: {s}");
-                        Some(s.to_owned())
-                    }
-                    Token::Punctuation(s) => Some(s.to_owned()),
-                    Token::ThematicBreak => Some("---".to_string()),
-                    _ => None,
-                })
-                .collect();
-
-            match (content.starts_with(':'), content.ends_with(':')) {
-                (true, true) => TableAlignment::Center,
-                (true, false) => TableAlignment::Left,
-                (false, true) => TableAlignment::Right,
-                _ => TableAlignment::None,
-            }
-        })
+        .map(parse_alignment)
         .collect();
 
     let headers: Vec<MdTableCell> = split_row(header_row)
         .into_iter()
         .enumerate()
         .map(|(i, cell_content)| MdTableCell {
-            content: parse_inline(cell_content),
+            content: parse_inline(&clamp_cell_tokens(cell_content)),
             alignment: alignments.get(i).cloned().unwrap_or(TableAlignment::None),
             is_header: true,
         })
         .collect();
 
+    // Like GitHub, every body row has as many cells as the header row. Missing cells are left
+    // empty, and any cells past the last column are dropped.
     let body: Vec<Vec<MdTableCell>> = rows
         .iter()
         .skip(2)
         .map(|row| {
-            split_row(row)
-                .into_iter()
-                .enumerate()
-                .map(|(i, cell_tokens)| MdTableCell {
-                    content: parse_inline(cell_tokens),
+            let cells = split_row(row);
+
+            (0..headers.len())
+                .map(|i| MdTableCell {
+                    content: cells
+                        .get(i)
+                        .map(|cell_tokens| parse_inline(&clamp_cell_tokens(cell_tokens)))
+                        .unwrap_or_default(),
                     alignment: alignments.get(i).cloned().unwrap_or(TableAlignment::None),
                     is_header: false,
                 })
@@ -529,29 +1225,86 @@ pub fn parse_table(line: &[Token]) -> MdBlockElement {
     MdBlockElement::Table { headers, body }
 }
 
-/// Helper function to split a row of tokens into individual cells.
+/// Reads the alignment of a column from its cell in the alignment row (i.e. `:---:`). Whitespace
+/// around the marker is ignored.
 ///
-/// By removing the starting and ending "|" characters, it ensures that the row is
-/// split into the proper number of cells.
-fn split_row(row: &[Token]) -> Vec<&[Token]> {
-    let mut cells: Vec<&[Token]> = row
-        .split(|token| token == &Token::TableCellSeparator)
-        .collect();
-
-    if let Some(first) = cells.first() {
-        if first.is_empty() {
-            cells.remove(0);
-        }
+/// # Arguments
+/// * `cell_content` - The tokens of a single cell of the alignment row.
+///
+/// # Returns
+/// The column's alignment, which is `TableAlignment::None` if neither end has a `:`.
+fn parse_alignment(cell_content: &[Token]) -> TableAlignment {
+    let content: String = cell_content
+        .iter()
+        .filter_map(|token| match token {
+            Token::Text(s) => {
+                report_warning(&format!("Table alignment should not contain text as it could result in unexpected behavior: {s}"));
+                Some(s.to_owned())
+            }
+            Token::Punctuation(s) => Some(s.to_owned()),
+            Token::ThematicBreak(text) => Some(text.to_owned()),
+            _ => None,
+        })
+        .collect();
+    let content = content.trim();
+
+    match (content.starts_with(':'), content.ends_with(':')) {
+        (true, true) if content.len() > 1 => TableAlignment::Center,
+        (true, _) => TableAlignment::Left,
+        (false, true) => TableAlignment::Right,
+        _ => TableAlignment::None,
     }
-    if let Some(last) = cells.last() {
-        if last.is_empty() {
-            cells.pop();
-        }
+}
+
+/// Helper function to split a row of tokens into individual cells.
+///
+/// By removing the starting and ending "|" characters, it ensures that the row is
+/// split into the proper number of cells.
+fn split_row(row: &[Token]) -> Vec<&[Token]> {
+    let mut cells: Vec<&[Token]> = row
+        .split(|token| token == &Token::TableCellSeparator)
+        .collect();
+
+    if let Some(first) = cells.first()
+        && first.is_empty()
+    {
+        cells.remove(0);
+    }
+    if let Some(last) = cells.last()
+        && last.is_empty()
+    {
+        cells.pop();
     }
 
     cells
 }
 
+/// Flattens any block-level tokens in a table cell to text, since table cells may only contain
+/// inline content.
+///
+/// # Arguments
+/// * `cell_tokens` - The tokens of a single table cell.
+///
+/// # Returns
+/// The cell's tokens, with list markers, code fences, etc. replaced by their literal text.
+fn clamp_cell_tokens(cell_tokens: &[Token]) -> Vec<Token> {
+    cell_tokens
+        .iter()
+        .map(|token| match token {
+            Token::OrderedListMarker(marker, _, delimiter) => {
+                Token::Text(format!("{marker}{delimiter}"))
+            }
+            Token::CodeFence { delimiter, length } => {
+                Token::Text(delimiter.to_string().repeat(*length))
+            }
+            Token::ThematicBreak(text) => Token::Text(text.to_owned()),
+            Token::BlockQuoteMarker => Token::Text(String::from(">")),
+            Token::Tab { .. } | Token::Newline => Token::Whitespace,
+            _ => token.clone(),
+        })
+        .collect()
+}
+
 /// Parses a vector of tokens into a vector of inline Markdown elements (i.e. links, images,
 /// bold/italics, etc.).
 ///
@@ -572,6 +1325,8 @@ pub fn parse_inline(markdown_tokens: &[Token]) -> Vec<MdInlineElement> {
 
     let mut buffer: String = String::new();
 
+    let valid_html_tags = find_valid_html_tags(&cursor.tokens);
+
     let mut current_token: &Token;
     while !cursor.is_at_eof() {
         current_token = cursor.current().expect("Token should be valid markdown");
@@ -598,31 +1353,69 @@ pub fn parse_inline(markdown_tokens: &[Token]) -> Vec<MdInlineElement> {
             Token::OpenBracket => {
                 push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
 
-                let link_element =
-                    parse_link_type(&mut cursor, |label, title, url| MdInlineElement::Link {
-                        text: label,
-                        title,
-                        url,
-                    });
-                parsed_inline_elements.push(link_element);
+                if let Some((label, label_end)) = footnote_reference(&cursor) {
+                    parsed_inline_elements.push(MdInlineElement::FootnoteReference { label });
+                    cursor.set_position(label_end);
+                } else {
+                    // Without a destination the bracket is literal, and its contents are parsed
+                    // as if it wasn't there so that emphasis can span it
+                    match parse_link_type(&mut cursor, make_link) {
+                        Some(link_element) => parsed_inline_elements.push(link_element),
+                        None => buffer.push('['),
+                    }
+                }
             }
-            Token::CodeTick => {
-                // Search for a matching code tick, everything else is text
-                cursor.advance();
-                push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
+            Token::CodeTick(length)
+            | Token::CodeFence {
+                delimiter: '`',
+                length,
+            } => {
+                // A code span is closed by the next backtick run of the same length. Without
+                // one, the opening run is literal text
+                let run_length = *length;
+                let Some(closing_position) = find_closing_code_run(&cursor, run_length) else {
+                    buffer.push_str(&"`".repeat(run_length));
+                    cursor.advance();
+                    continue;
+                };
 
-                let code_content = parse_code_span(&mut cursor);
+                push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
+                cursor.advance();
+                let code_content = parse_code_span(&mut cursor, closing_position);
 
-                if cursor.current() != Some(&Token::CodeTick) {
-                    parsed_inline_elements.push(MdInlineElement::Text {
-                        content: format!("`{code_content}`"),
-                    });
-                } else {
-                    parsed_inline_elements.push(MdInlineElement::Code {
-                        content: code_content,
-                    });
+                parsed_inline_elements.push(MdInlineElement::Code {
+                    content: code_content,
+                });
+            }
+            Token::Punctuation(string) if string == "&" => {
+                match parse_entity_reference(&cursor) {
+                    Some((text, end)) => {
+                        buffer.push_str(&text);
+                        cursor.set_position(end);
+                    }
+                    // Something shaped like an entity that isn't one is kept literal, rather
+                    // than left for the browser to guess at
+                    None if is_entity_shaped(&cursor) => buffer.push_str("&amp;"),
+                    None => buffer.push('&'),
                 }
             }
+            Token::Punctuation(string)
+                if (string == "$" || string == "$$") && current_config().html.math =>
+            {
+                let delimiter = string.clone();
+                let Some(closing_position) = find_closing_math(&cursor, &delimiter) else {
+                    buffer.push_str(&delimiter);
+                    cursor.advance();
+                    continue;
+                };
+
+                push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
+                parsed_inline_elements.push(MdInlineElement::Math {
+                    content: math_source(&cursor.tokens[cursor.position() + 1..closing_position]),
+                    display: delimiter == "$$",
+                });
+                cursor.set_position(closing_position);
+            }
             Token::Punctuation(string) if string == "!" => {
                 if cursor.peek_ahead(1) != Some(&Token::OpenBracket) {
                     // If the next token is not an open bracket, treat it as text
@@ -634,26 +1427,58 @@ pub fn parse_inline(markdown_tokens: &[Token]) -> Vec<MdInlineElement> {
                 push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
                 cursor.advance(); // Advance to the open bracket
 
-                let image =
-                    parse_link_type(&mut cursor, |label, title, url| MdInlineElement::Image {
-                        alt_text: flatten_inline(&label),
-                        title,
-                        url,
-                    });
-
-                parsed_inline_elements.push(image);
+                match parse_link_type(&mut cursor, make_image) {
+                    Some(mut image) => {
+                        apply_image_attribute_block(&mut image, &mut cursor);
+                        parsed_inline_elements.push(image);
+                    }
+                    None => buffer.push_str("!["),
+                }
+            }
+            Token::Escape(esc_char) if esc_char == "$" && current_config().html.math => {
+                buffer.push('$')
             }
-            Token::Escape(esc_char) => buffer.push_str(&format!("\\{esc_char}")),
+            Token::Escape(esc_char) => buffer.push_str(&escape_to_text(
+                &cursor,
+                esc_char,
+                current_config().html.escaped_space_nbsp,
+            )),
             Token::Text(string) | Token::Punctuation(string) => buffer.push_str(string),
-            Token::OrderedListMarker(string) => buffer.push_str(string),
+            Token::OrderedListMarker(string, _, delimiter) => {
+                buffer.push_str(&format!("{string}{delimiter}"))
+            }
             Token::Whitespace => buffer.push(' '),
+            Token::Tab { .. } => buffer.push('\t'),
+            Token::HardBreak => {
+                push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
+                parsed_inline_elements.push(MdInlineElement::LineBreak);
+            }
             Token::CloseBracket => buffer.push(']'),
             Token::OpenParenthesis => buffer.push('('),
             Token::CloseParenthesis => buffer.push(')'),
-            Token::ThematicBreak => buffer.push_str("---"),
+            Token::ThematicBreak(text) => buffer.push_str(text),
             Token::TableCellSeparator => buffer.push('|'),
             Token::BlockQuoteMarker => buffer.push('>'),
-            Token::RawHtmlTag(tag_content) => buffer.push_str(tag_content),
+            Token::RawHtmlTag(tag_content) => match autolink_url(tag_content) {
+                Some(url) => {
+                    push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
+                    parsed_inline_elements.push(MdInlineElement::Link {
+                        text: vec![MdInlineElement::Text {
+                            content: tag_content[1..tag_content.len() - 1].to_string(),
+                        }],
+                        title: None,
+                        url,
+                    });
+                }
+                None if valid_html_tags.contains(&cursor.position()) => {
+                    push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
+                    parsed_inline_elements.push(MdInlineElement::RawHtml {
+                        content: tag_content.clone(),
+                    });
+                }
+                // Not a valid tag, so it's literal text that's escaped when rendered
+                None => buffer.push_str(tag_content),
+            },
             _ => push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer),
         }
 
@@ -664,27 +1489,533 @@ pub fn parse_inline(markdown_tokens: &[Token]) -> Vec<MdInlineElement> {
 
     delimiter_stack
         .iter_mut()
-        .for_each(|el| el.classify_flanking(&cursor.tokens));
+        .for_each(|el| el.classify_flanking(&mut cursor));
 
     resolve_emphasis(&mut parsed_inline_elements, &mut delimiter_stack);
 
-    parsed_inline_elements
+    let html_config = &current_config().html;
+    let mut merged_elements = link_text_matches(
+        merge_adjacent_text(parsed_inline_elements),
+        &URL_REGEX,
+        &|url| url.to_string(),
+    );
+    if html_config.autolink_emails {
+        merged_elements = autolink_emails(merged_elements);
+    }
+    if !html_config.mention_url.is_empty() {
+        merged_elements = linkify_prefixed_words(merged_elements, '@', &html_config.mention_url);
+    }
+    if !html_config.hashtag_url.is_empty() {
+        merged_elements = linkify_prefixed_words(merged_elements, '#', &html_config.hashtag_url);
+    }
+    if html_config.smart_punctuation {
+        merged_elements = smarten_punctuation(merged_elements, &mut None);
+    }
+
+    merged_elements
 }
 
-/// Parses a code span starting from the current position of the cursor.
+/// Returns the link destination of an autolink (`<https://example.com>` or
+/// `<user@example.com>`), or `None` if the tag is raw HTML.
+///
+/// # Arguments
+/// * `tag` - The tag, including its angle brackets.
+fn autolink_url(tag: &str) -> Option<String> {
+    let inner = tag.strip_prefix('<')?.strip_suffix('>')?;
+
+    if EMAIL_AUTOLINK_REGEX.is_match(inner) {
+        Some(format!("mailto:{inner}"))
+    } else if URI_AUTOLINK_REGEX.is_match(inner) {
+        Some(inner.to_string())
+    } else {
+        None
+    }
+}
+
+/// Finds the raw HTML tags in a run of inline tokens that can be emitted as-is.
+///
+/// A tag is valid if it's well-formed and, unless it's a void element, self-closing, or a comment,
+/// it's matched by a closing tag of the same name (or vice versa). Closing a tag also closes any
+/// tags opened inside it, so those are left unmatched.
+///
+/// # Arguments
+/// * `tokens` - The inline tokens being parsed.
+///
+/// # Returns
+/// The positions of the valid `Token::RawHtmlTag`s. Any others are escaped as literal text.
+fn find_valid_html_tags(tokens: &[Token]) -> HashSet<usize> {
+    let mut valid_tags = HashSet::new();
+    let mut open_tags: Vec<(String, usize)> = Vec::new();
+
+    for (position, token) in tokens.iter().enumerate() {
+        let Token::RawHtmlTag(tag) = token else {
+            continue;
+        };
+
+        if let Some(captures) = HTML_OPEN_TAG_REGEX.captures(tag) {
+            let name = captures[1].to_ascii_lowercase();
+            if !captures[2].is_empty() || VOID_HTML_ELEMENTS.contains(&name.as_str()) {
+                valid_tags.insert(position);
+            } else {
+                open_tags.push((name, position));
+            }
+        } else if let Some(captures) = HTML_CLOSING_TAG_REGEX.captures(tag) {
+            let name = captures[1].to_ascii_lowercase();
+            if let Some(index) = open_tags.iter().rposition(|(open, _)| *open == name) {
+                valid_tags.insert(open_tags[index].1);
+                valid_tags.insert(position);
+                open_tags.truncate(index);
+            }
+        } else if HTML_STANDALONE_TAG_REGEX.is_match(tag) {
+            valid_tags.insert(position);
+        }
+    }
+
+    valid_tags
+}
+
+/// Converts bare email addresses in text elements into `mailto:` links.
+///
+/// # Arguments
+/// * `elements` - The merged inline elements to scan.
+///
+/// # Returns
+/// A vector of inline elements with email addresses wrapped in `MdInlineElement::Link`s.
+fn autolink_emails(elements: Vec<MdInlineElement>) -> Vec<MdInlineElement> {
+    link_text_matches(elements, &EMAIL_REGEX, &|email| format!("mailto:{email}"))
+}
+
+/// Converts `@username` mentions or `#tag` hashtags in text elements into links.
+///
+/// # Arguments
+/// * `elements` - The merged inline elements to scan.
+/// * `prefix` - The character marking the start of a linkable word, either `@` or `#`.
+/// * `url_template` - The URL to link to, where `{name}` is replaced with the word (without the
+///   prefix).
+///
+/// # Returns
+/// A vector of inline elements with matching words wrapped in `MdInlineElement::Link`s.
+fn linkify_prefixed_words(
+    elements: Vec<MdInlineElement>,
+    prefix: char,
+    url_template: &str,
+) -> Vec<MdInlineElement> {
+    let word_regex = match prefix {
+        '@' => &MENTION_REGEX,
+        _ => &HASHTAG_REGEX,
+    };
+
+    link_text_matches(elements, word_regex, &|word| {
+        url_template.replace("{name}", &word[prefix.len_utf8()..])
+    })
+}
+
+/// Wraps each match of `pattern` within text elements in a `MdInlineElement::Link`.
+///
+/// Text inside of existing links is left alone, as is any match that directly follows a
+/// character other than whitespace or `(`. This keeps matches within words, escapes (`\@`), and
+/// raw HTML attributes (i.e. `href="mailto:user@example.com"`) from being linked.
+///
+/// # Arguments
+/// * `elements` - The merged inline elements to scan.
+/// * `pattern` - The pattern to link.
+/// * `make_url` - Builds the link's URL from the matched text.
+///
+/// # Returns
+/// A vector of inline elements with each match linked.
+fn link_text_matches(
+    elements: Vec<MdInlineElement>,
+    pattern: &Regex,
+    make_url: &dyn Fn(&str) -> String,
+) -> Vec<MdInlineElement> {
+    let mut linked_elements: Vec<MdInlineElement> = Vec::with_capacity(elements.len());
+
+    for element in elements {
+        match element {
+            MdInlineElement::Text { content } => {
+                let mut last_end = 0;
+                for found in pattern.find_iter(&content) {
+                    let is_standalone = content[..found.start()]
+                        .chars()
+                        .next_back()
+                        .is_none_or(|ch| ch.is_whitespace() || ch == '(');
+                    if !is_standalone {
+                        continue;
+                    }
+
+                    if found.start() > last_end {
+                        linked_elements.push(MdInlineElement::Text {
+                            content: content[last_end..found.start()].to_string(),
+                        });
+                    }
+                    linked_elements.push(MdInlineElement::Link {
+                        text: vec![MdInlineElement::Text {
+                            content: found.as_str().to_string(),
+                        }],
+                        title: None,
+                        url: make_url(found.as_str()),
+                    });
+                    last_end = found.end();
+                }
+
+                if last_end < content.len() {
+                    linked_elements.push(MdInlineElement::Text {
+                        content: content[last_end..].to_string(),
+                    });
+                }
+            }
+            MdInlineElement::Bold { content } => linked_elements.push(MdInlineElement::Bold {
+                content: link_text_matches(content, pattern, make_url),
+            }),
+            MdInlineElement::Italic { content } => linked_elements.push(MdInlineElement::Italic {
+                content: link_text_matches(content, pattern, make_url),
+            }),
+            MdInlineElement::Strikethrough { content } => {
+                linked_elements.push(MdInlineElement::Strikethrough {
+                    content: link_text_matches(content, pattern, make_url),
+                })
+            }
+            MdInlineElement::Subscript { content } => {
+                linked_elements.push(MdInlineElement::Subscript {
+                    content: link_text_matches(content, pattern, make_url),
+                })
+            }
+            MdInlineElement::Superscript { content } => {
+                linked_elements.push(MdInlineElement::Superscript {
+                    content: link_text_matches(content, pattern, make_url),
+                })
+            }
+            other => linked_elements.push(other),
+        }
+    }
+
+    linked_elements
+}
+
+/// Converts straight quotes to curly quotes, `--` to an en dash, `---` to an em dash, and `...`
+/// to an ellipsis within text elements.
+///
+/// Code spans are left alone, as is anything inside of a raw HTML tag so that attribute quotes
+/// stay intact.
+///
+/// # Arguments
+/// * `elements` - The merged inline elements to convert.
+/// * `previous` - The last character of text seen so far, used to decide whether a quote opens or
+///   closes. Carried across elements so that i.e. a quote right after `**bold**` closes.
+///
+/// # Returns
+/// A vector of inline elements with typographic punctuation.
+fn smarten_punctuation(
+    elements: Vec<MdInlineElement>,
+    previous: &mut Option<char>,
+) -> Vec<MdInlineElement> {
+    elements
+        .into_iter()
+        .map(|element| match element {
+            MdInlineElement::Text { content } => MdInlineElement::Text {
+                content: smarten_text(&content, previous),
+            },
+            MdInlineElement::Bold { content } => MdInlineElement::Bold {
+                content: smarten_punctuation(content, previous),
+            },
+            MdInlineElement::Italic { content } => MdInlineElement::Italic {
+                content: smarten_punctuation(content, previous),
+            },
+            MdInlineElement::Strikethrough { content } => MdInlineElement::Strikethrough {
+                content: smarten_punctuation(content, previous),
+            },
+            MdInlineElement::Subscript { content } => MdInlineElement::Subscript {
+                content: smarten_punctuation(content, previous),
+            },
+            MdInlineElement::Superscript { content } => MdInlineElement::Superscript {
+                content: smarten_punctuation(content, previous),
+            },
+            MdInlineElement::Link { text, title, url } => MdInlineElement::Link {
+                text: smarten_punctuation(text, previous),
+                title,
+                url,
+            },
+            MdInlineElement::Code { content } => {
+                *previous = content.chars().next_back().or(*previous);
+                MdInlineElement::Code { content }
+            }
+            other => other,
+        })
+        .collect()
+}
+
+/// Applies smart punctuation to a single run of text.
+///
+/// # Arguments
+/// * `text` - The text to convert.
+/// * `previous` - The character before the text, updated to the text's last character.
+///
+/// # Returns
+/// The converted text.
+fn smarten_text(text: &str, previous: &mut Option<char>) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+    let mut in_tag = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let ch = chars[i];
+        let next = chars.get(i + 1).copied();
+        let mut consumed = 1;
+
+        if in_tag {
+            in_tag = ch != '>';
+            result.push(ch);
+        } else {
+            match ch {
+                '<' if next.is_some_and(|c| c.is_ascii_alphabetic() || c == '/') => {
+                    in_tag = true;
+                    result.push(ch);
+                }
+                '-' if next == Some('-') => {
+                    if chars.get(i + 2) == Some(&'-') {
+                        result.push('\u{2014}');
+                        consumed = 3;
+                    } else {
+                        result.push('\u{2013}');
+                        consumed = 2;
+                    }
+                }
+                '.' if chars.get(i + 1..i + 3) == Some(&['.', '.']) => {
+                    result.push('\u{2026}');
+                    consumed = 3;
+                }
+                '"' => result.push(if opens_quote(*previous) {
+                    '\u{201C}'
+                } else {
+                    '\u{201D}'
+                }),
+                '\'' => {
+                    let is_contraction = previous.is_some_and(char::is_alphanumeric)
+                        && next.is_some_and(char::is_alphabetic);
+                    result.push(if !is_contraction && opens_quote(*previous) {
+                        '\u{2018}'
+                    } else {
+                        '\u{2019}'
+                    });
+                }
+                _ => result.push(ch),
+            }
+        }
+
+        *previous = result.chars().next_back();
+        i += consumed;
+    }
+
+    result
+}
+
+/// Returns whether a quote following `previous` opens a quotation rather than closing one.
+fn opens_quote(previous: Option<char>) -> bool {
+    previous.is_none_or(|ch| {
+        ch.is_whitespace()
+            || matches!(
+                ch,
+                '(' | '[' | '{' | '\u{2013}' | '\u{2014}' | '\u{201C}' | '\u{2018}'
+            )
+    })
+}
+
+/// Coalesces consecutive `MdInlineElement::Text` elements into a single text element, including
+/// those nested inside of bold, italic, and link elements.
+///
+/// # Arguments
+/// * `elements` - The resolved inline elements to merge.
+///
+/// # Returns
+/// A vector of inline elements with no two adjacent text elements.
+fn merge_adjacent_text(elements: Vec<MdInlineElement>) -> Vec<MdInlineElement> {
+    let mut merged_elements: Vec<MdInlineElement> = Vec::with_capacity(elements.len());
+
+    for element in elements {
+        let element = match element {
+            MdInlineElement::Bold { content } => MdInlineElement::Bold {
+                content: merge_adjacent_text(content),
+            },
+            MdInlineElement::Italic { content } => MdInlineElement::Italic {
+                content: merge_adjacent_text(content),
+            },
+            MdInlineElement::Strikethrough { content } => MdInlineElement::Strikethrough {
+                content: merge_adjacent_text(content),
+            },
+            MdInlineElement::Subscript { content } => MdInlineElement::Subscript {
+                content: merge_adjacent_text(content),
+            },
+            MdInlineElement::Superscript { content } => MdInlineElement::Superscript {
+                content: merge_adjacent_text(content),
+            },
+            MdInlineElement::Link { text, title, url } => MdInlineElement::Link {
+                text: merge_adjacent_text(text),
+                title,
+                url,
+            },
+            other => other,
+        };
+
+        if let MdInlineElement::Text { content } = &element
+            && let Some(MdInlineElement::Text {
+                content: previous_content,
+            }) = merged_elements.last_mut()
+        {
+            previous_content.push_str(content);
+            continue;
+        }
+
+        merged_elements.push(element);
+    }
+
+    merged_elements
+}
+
+/// Converts an escaped character in inline text to its output text.
+///
+/// Escapes map as follows:
+/// * `\ ` (escaped space) becomes `&nbsp;` when `escaped_space_nbsp` is enabled.
+/// * `\-` between two words (i.e. `hyph\-en`) becomes a soft hyphen (`&shy;`), marking where
+///   the word may be broken across lines.
+/// * Anything else is kept as-is, including the backslash.
+///
+/// # Arguments
+/// * `cursor` - The cursor, positioned at the `Token::Escape`.
+/// * `esc_char` - The escaped character.
+/// * `escaped_space_nbsp` - Whether an escaped space should become a non-breaking space.
+///
+/// # Returns
+/// The text to push to the output for the escape.
+fn escape_to_text(cursor: &TokenCursor, esc_char: &str, escaped_space_nbsp: bool) -> String {
+    match esc_char {
+        " " if escaped_space_nbsp => String::from("&nbsp;"),
+        "-" if cursor.position() > 0
+            && matches!(
+                cursor.tokens.get(cursor.position() - 1),
+                Some(Token::Text(_))
+            )
+            && matches!(cursor.peek_ahead(1), Some(Token::Text(_))) =>
+        {
+            String::from("&shy;")
+        }
+        _ => format!("\\{esc_char}"),
+    }
+}
+
+/// Finds the backtick run that closes a code span opened at the current position of the cursor,
+/// which is the next run of exactly the same length.
+///
+/// # Arguments
+/// * `cursor` - A cursor positioned on the opening backtick run.
+/// * `run_length` - The number of backticks in the opening run.
+///
+/// # Returns
+/// The position of the closing run, or `None` if the code span is never closed.
+fn find_closing_code_run(cursor: &TokenCursor, run_length: usize) -> Option<usize> {
+    let start = cursor.position() + 1;
+    cursor.tokens[start..]
+        .iter()
+        .position(|token| match token {
+            Token::CodeTick(length)
+            | Token::CodeFence {
+                delimiter: '`',
+                length,
+            } => *length == run_length,
+            _ => false,
+        })
+        .map(|offset| start + offset)
+}
+
+/// Parses an HTML entity (`&copy;`) or numeric character reference (`&#169;`, `&#xA9;`) starting
+/// at the `&` under the cursor.
+///
+/// Characters that are significant in HTML are kept as their entity, so that `&lt;` still
+/// renders as `<` rather than opening a tag.
+///
+/// # Arguments
+/// * `cursor` - A cursor positioned on the `&` token.
+///
+/// # Returns
+/// The text to output for the reference along with the position of its closing `;`, or `None`
+/// if the tokens after the `&` aren't a valid reference.
+fn parse_entity_reference(cursor: &TokenCursor) -> Option<(String, usize)> {
+    let start = cursor.position();
+    let semicolon = |offset: usize| matches!(cursor.tokens.get(start + offset), Some(Token::Punctuation(s)) if s == ";");
+
+    let (characters, end) = match cursor.tokens.get(start + 1..start + 3)? {
+        [Token::Text(name), _] if semicolon(2) => {
+            let characters = NAMED_ENTITIES.get(format!("&{name};").as_str())?;
+            (characters.to_string(), start + 2)
+        }
+        [Token::Punctuation(hash), Token::Text(number)] if hash == "#" && semicolon(3) => {
+            let code_point = match number.strip_prefix(['x', 'X']) {
+                Some(hex) if (1..=6).contains(&hex.len()) => u32::from_str_radix(hex, 16).ok()?,
+                Some(_) => return None,
+                None if (1..=7).contains(&number.len())
+                    && number.chars().all(|ch| ch.is_ascii_digit()) =>
+                {
+                    number.parse().ok()?
+                }
+                None => return None,
+            };
+            // Null and invalid code points are replaced, as CommonMark requires
+            let character = char::from_u32(code_point)
+                .filter(|&ch| ch != '\0')
+                .unwrap_or(char::REPLACEMENT_CHARACTER);
+            (character.to_string(), start + 3)
+        }
+        _ => return None,
+    };
+
+    let text = match characters.as_str() {
+        "<" => String::from("&lt;"),
+        ">" => String::from("&gt;"),
+        "&" => String::from("&amp;"),
+        "\"" => String::from("&quot;"),
+        _ => characters,
+    };
+
+    Some((text, end))
+}
+
+/// Checks whether the tokens from the `&` under the cursor look like an entity, i.e. `&name;`,
+/// whether or not it's a known one.
+fn is_entity_shaped(cursor: &TokenCursor) -> bool {
+    let start = cursor.position();
+    matches!(
+        cursor.tokens.get(start + 1..start + 3),
+        Some([Token::Text(name), Token::Punctuation(semicolon)])
+            if semicolon == ";" && name.chars().all(|ch| ch.is_ascii_alphanumeric())
+    )
+}
+
+/// Parses a code span starting from the current position of the cursor. Backtick runs inside the
+/// span are kept as literal backticks, and a single space is stripped from both ends when the
+/// content both starts and ends with one, so that `` `` `code` `` `` can hold backticks at its
+/// edges.
 ///
 /// # Arguments
 /// * `cursor` - A mutable reference to a `TokenCursor` that tracks the current position in the
+///   token stream.
+/// * `closing_position` - The position of the backtick run closing the span, which the cursor
+///   is left on.
 ///
 /// # Returns
-/// A string containing the content of the code span, excluding the opening and closing code ticks.
-fn parse_code_span(cursor: &mut TokenCursor) -> String {
+/// A string containing the content of the code span, excluding the opening and closing backticks.
+fn parse_code_span(cursor: &mut TokenCursor, closing_position: usize) -> String {
     let mut code_content: String = String::new();
     while let Some(next_token) = cursor.current() {
+        if cursor.position() == closing_position {
+            break;
+        }
+
         match next_token {
-            Token::CodeTick => break,
+            Token::CodeTick(length) => code_content.push_str(&"`".repeat(*length)),
             Token::Text(string) | Token::Punctuation(string) => code_content.push_str(string),
-            Token::OrderedListMarker(string) => code_content.push_str(string),
+            Token::OrderedListMarker(string, _, delimiter) => {
+                code_content.push_str(&format!("{string}{delimiter}"))
+            }
             Token::Escape(ch) => code_content.push_str(&format!("\\{ch}")),
             Token::OpenParenthesis => code_content.push('('),
             Token::CloseParenthesis => code_content.push(')'),
@@ -695,27 +2026,234 @@ fn parse_code_span(cursor: &mut TokenCursor) -> String {
                 code_content.push_str(&delimiter.to_string().repeat(*length))
             }
             Token::Whitespace => code_content.push(' '),
-            Token::Tab => code_content.push_str(&" ".repeat(CONFIG.get().unwrap().lexer.tab_size)),
-            Token::Newline => code_content.push('\n'),
-            Token::ThematicBreak => code_content.push_str("---"),
+            Token::Tab { literal } => code_content.push_str(&code_tab(*literal)),
+            Token::Newline | Token::HardBreak => code_content.push('\n'),
+            Token::ThematicBreak(text) => code_content.push_str(text),
             Token::BlockQuoteMarker => code_content.push('>'),
             Token::RawHtmlTag(tag_content) => code_content.push_str(tag_content),
-            Token::CodeFence => {}
+            Token::CodeFence { delimiter, length } => {
+                code_content.push_str(&delimiter.to_string().repeat(*length))
+            }
+        }
+
+        cursor.advance();
+    }
+
+    let is_padded = code_content.len() >= 2
+        && code_content.starts_with(' ')
+        && code_content.ends_with(' ')
+        && !code_content.chars().all(|ch| ch == ' ');
+    if is_padded {
+        code_content = code_content[1..code_content.len() - 1].to_string();
+    }
+
+    code_content
+}
+
+/// Finds the `$` or `$$` that closes the math opened by the delimiter under the cursor.
+///
+/// Like Pandoc, inline `$` math can't start with whitespace or end with whitespace, and the
+/// closing `$` can't be directly followed by a digit. This keeps amounts like `$5 and $10` from
+/// being read as math.
+///
+/// # Arguments
+/// * `cursor` - A cursor positioned on the opening delimiter.
+/// * `delimiter` - The opening delimiter, either `$` or `$$`.
+///
+/// # Returns
+/// The position of the closing delimiter, or `None` if the math is never closed.
+fn find_closing_math(cursor: &TokenCursor, delimiter: &str) -> Option<usize> {
+    let start = cursor.position() + 1;
+    let is_space = |token: Option<&Token>| {
+        matches!(
+            token,
+            None | Some(Token::Whitespace | Token::Tab { .. } | Token::Newline | Token::HardBreak)
+        )
+    };
+    if delimiter == "$" && is_space(cursor.tokens.get(start)) {
+        return None;
+    }
+
+    (start + 1..cursor.tokens.len()).find(|&position| {
+        if !matches!(&cursor.tokens[position], Token::Punctuation(string) if string == delimiter) {
+            return false;
+        }
+
+        delimiter == "$$"
+            || !is_space(cursor.tokens.get(position - 1))
+                && !matches!(
+                    cursor.tokens.get(position + 1),
+                    Some(Token::Text(text)) if text.starts_with(|ch: char| ch.is_ascii_digit())
+                )
+    })
+}
+
+/// Returns whether a line is a `$$` fence opening or closing a display math block.
+fn is_math_fence(line: &[Token]) -> bool {
+    line.first() == Some(&Token::Punctuation(String::from("$$")))
+        && line[1..]
+            .iter()
+            .take_while(|token| **token != Token::Newline)
+            .all(|token| matches!(token, Token::Whitespace | Token::Tab { .. }))
+}
+
+/// Parses a display math block between `$$` fences into an `MdBlockElement::MathBlock`.
+///
+/// # Arguments
+/// * `line` - The tokens of the block, including both fences.
+///
+/// # Returns
+/// An `MdBlockElement::MathBlock` containing the math as written.
+fn parse_math_block(line: &[Token]) -> MdBlockElement {
+    let mut math_lines: Vec<&[Token]> = line.split(|token| token == &Token::Newline).collect();
+    math_lines.remove(0);
+    if math_lines.last().is_some_and(|last| is_math_fence(last)) {
+        math_lines.pop();
+    }
+
+    MdBlockElement::MathBlock {
+        content: math_lines
+            .into_iter()
+            .map(math_source)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Rebuilds the source text of math from its tokens, so that TeX commands like `\frac` and
+/// `a_b` reach KaTeX untouched.
+fn math_source(tokens: &[Token]) -> String {
+    let mut source = String::new();
+    for token in tokens {
+        match token {
+            Token::Text(string) | Token::Punctuation(string) => source.push_str(string),
+            Token::OrderedListMarker(string, _, delimiter) => {
+                source.push_str(&format!("{string}{delimiter}"))
+            }
+            Token::Escape(ch) => source.push_str(&format!("\\{ch}")),
+            Token::EmphasisRun { delimiter, length } => {
+                source.push_str(&delimiter.to_string().repeat(*length))
+            }
+            Token::CodeFence { delimiter, length } => {
+                source.push_str(&delimiter.to_string().repeat(*length))
+            }
+            Token::CodeTick(length) => source.push_str(&"`".repeat(*length)),
+            Token::OpenBracket => source.push('['),
+            Token::CloseBracket => source.push(']'),
+            Token::OpenParenthesis => source.push('('),
+            Token::CloseParenthesis => source.push(')'),
+            Token::TableCellSeparator => source.push('|'),
+            Token::Whitespace => source.push(' '),
+            Token::Tab { .. } => source.push_str(&" ".repeat(current_config().lexer.tab_size)),
+            Token::Newline | Token::HardBreak => source.push('\n'),
+            Token::ThematicBreak(text) => source.push_str(text),
+            Token::BlockQuoteMarker => source.push('>'),
+            Token::RawHtmlTag(tag_content) => source.push_str(tag_content),
         }
-
-        cursor.advance();
     }
 
-    code_content
+    source
 }
 
 /// Helper function used in `parse_link_type` to circumvent Rust's limitation on closure recursion
 fn make_image(label: Vec<MdInlineElement>, title: Option<String>, uri: String) -> MdInlineElement {
+    let (title, width, height) = split_image_dimensions(title);
+
     MdInlineElement::Image {
         alt_text: flatten_inline(&label),
         title,
         url: uri,
+        width,
+        height,
+    }
+}
+
+/// Splits a `=WIDTHxHEIGHT` dimension spec, written after an image's URL, from the start of its
+/// title. Either dimension can be left out, as in `=300x` or `=x200`.
+///
+/// # Arguments
+/// * `title` - The image's title, which holds everything after the URL.
+///
+/// # Returns
+/// The rest of the title along with the width and height. If the spec is malformed, the title is
+/// returned untouched without any dimensions.
+fn split_image_dimensions(title: Option<String>) -> (Option<String>, Option<u32>, Option<u32>) {
+    let Some(spec) = title.as_deref().and_then(|title| title.strip_prefix('=')) else {
+        return (title, None, None);
+    };
+
+    let (dimensions, rest) = spec.split_once(' ').unwrap_or((spec, ""));
+    let Some((width, height)) = dimensions.split_once('x') else {
+        return (title, None, None);
+    };
+    let parse_dimension = |dimension: &str| -> Result<Option<u32>, ()> {
+        if dimension.is_empty() {
+            Ok(None)
+        } else {
+            dimension.parse().map(Some).map_err(|_| ())
+        }
+    };
+
+    match (parse_dimension(width), parse_dimension(height)) {
+        (Ok(width), Ok(height)) if width.is_some() || height.is_some() => {
+            let rest = rest.trim();
+            let title = Some(rest.to_string()).filter(|rest| !rest.is_empty());
+            (title, width, height)
+        }
+        _ => (title, None, None),
+    }
+}
+
+/// Applies a `{width=... height=...}` block directly following an image to its dimensions,
+/// moving the cursor past the block.
+///
+/// The block is left in place as text if it doesn't hold a valid width or height, and any other
+/// entries in it are ignored.
+///
+/// # Arguments
+/// * `image` - The image that was just parsed.
+/// * `cursor` - A cursor positioned on the last token of the image.
+fn apply_image_attribute_block(image: &mut MdInlineElement, cursor: &mut TokenCursor) {
+    let MdInlineElement::Image { width, height, .. } = image else {
+        return;
+    };
+    if cursor.peek_ahead(1) != Some(&Token::Punctuation("{".to_string())) {
+        return;
+    }
+
+    let block_start = cursor.position() + 2;
+    let Some(block_end) = cursor.tokens[block_start..]
+        .iter()
+        .take_while(|token| {
+            matches!(
+                token,
+                Token::Text(_) | Token::Punctuation(_) | Token::Whitespace
+            )
+        })
+        .position(|token| token == &Token::Punctuation("}".to_string()))
+        .map(|offset| block_start + offset)
+    else {
+        return;
+    };
+
+    let block = info_string_to_text(&cursor.tokens[block_start..block_end]);
+    let mut block_width = None;
+    let mut block_height = None;
+    for entry in split_attribute_entries(&block) {
+        match entry.split_once('=') {
+            Some(("width", value)) => block_width = value.trim_matches('"').parse().ok(),
+            Some(("height", value)) => block_height = value.trim_matches('"').parse().ok(),
+            _ => {}
+        }
+    }
+
+    if block_width.is_none() && block_height.is_none() {
+        return;
     }
+
+    *width = block_width.or(*width);
+    *height = block_height.or(*height);
+    cursor.set_position(block_end);
 }
 
 /// Helper function used in `parse_link_type` to circumvent Rust's limitation on closure recursion
@@ -735,9 +2273,14 @@ fn make_link(label: Vec<MdInlineElement>, title: Option<String>, uri: String) ->
 /// * `make_element` - A closure that takes the parsed label elements, optional title, and URI,
 ///   and returns an `MdInlineElement` representing the link or image.
 ///
+/// Links are parsed before emphasis, so emphasis in the label is resolved on its own and can't
+/// pair with delimiters outside of the link.
+///
 /// # Returns
-/// An `MdInlineElement` representing the parsed link or image.
-fn parse_link_type<F>(cursor: &mut TokenCursor, make_element: F) -> MdInlineElement
+/// An `MdInlineElement` representing the parsed link or image, or `None` if the brackets aren't
+/// a link. In that case the cursor is moved back to the open bracket, so that the caller can
+/// treat it as literal text and parse the label as regular inline content.
+fn parse_link_type<F>(cursor: &mut TokenCursor, make_element: F) -> Option<MdInlineElement>
 where
     F: Fn(Vec<MdInlineElement>, Option<String>, String) -> MdInlineElement,
 {
@@ -745,6 +2288,7 @@ where
     let mut label_buffer = String::new();
     let mut delimiter_stack: Vec<Delimiter> = Vec::new();
     cursor.advance(); // Move past the open bracket
+    let label_start = cursor.position();
     while let Some(token) = cursor.current() {
         match token {
             Token::CloseBracket => {
@@ -754,8 +2298,10 @@ where
             Token::OpenBracket => {
                 push_buffer_to_collection(&mut label_elements, &mut label_buffer);
 
-                let inner_link = parse_link_type(cursor, make_link);
-                label_elements.push(inner_link);
+                match parse_link_type(cursor, make_link) {
+                    Some(inner_link) => label_elements.push(inner_link),
+                    None => label_buffer.push('['),
+                }
             }
             Token::EmphasisRun { delimiter, length } => {
                 push_buffer_to_collection(&mut label_elements, &mut label_buffer);
@@ -782,15 +2328,18 @@ where
 
                 push_buffer_to_collection(&mut label_elements, &mut label_buffer);
                 cursor.advance(); // Advance to the open bracket
-                let inner_image = parse_link_type(cursor, make_image);
-
-                label_elements.push(inner_image);
+                match parse_link_type(cursor, make_image) {
+                    Some(inner_image) => label_elements.push(inner_image),
+                    None => label_buffer.push_str("!["),
+                }
             }
             Token::Text(s) | Token::Punctuation(s) => label_buffer.push_str(s),
-            Token::OrderedListMarker(s) => label_buffer.push_str(s),
+            Token::OrderedListMarker(s, _, delimiter) => {
+                label_buffer.push_str(&format!("{s}{delimiter}"))
+            }
             Token::Escape(ch) => label_buffer.push_str(&format!("\\{ch}")),
             Token::Whitespace => label_buffer.push(' '),
-            Token::ThematicBreak => label_buffer.push_str("---"),
+            Token::ThematicBreak(text) => label_buffer.push_str(text),
             Token::OpenParenthesis => label_buffer.push('('),
             Token::CloseParenthesis => label_buffer.push(')'),
             Token::TableCellSeparator => label_buffer.push('|'),
@@ -805,39 +2354,62 @@ where
 
     // If we didn't find a closing bracket, treat it as text
     if cursor.current() != Some(&Token::CloseBracket) {
-        return MdInlineElement::Text {
-            content: format!("[{}", flatten_inline(&label_elements)),
-        };
+        cursor.set_position(label_start - 1);
+        return None;
     }
 
-    // At this point we should have parentheses for the uri, otherwise treat it as a
-    // text element
+    // At this point we should have parentheses for the uri or a reference to a link definition,
+    // otherwise treat it as a text element
     if cursor.peek_ahead(1) != Some(&Token::OpenParenthesis) {
-        cursor.advance();
-        return MdInlineElement::Text {
-            content: format!("[{}]", flatten_inline(&label_elements)),
-        };
+        let raw_label = info_string_to_text(&cursor.tokens[label_start..cursor.position()]);
+        if let Some((url, title, reference_end)) = resolve_link_reference(cursor, &raw_label) {
+            cursor.set_position(reference_end);
+            return Some(make_element(label_elements, title, url));
+        }
+
+        cursor.set_position(label_start - 1);
+        return None;
     }
 
     cursor.advance(); // Move to '('
+    cursor.advance(); // Move past '('
 
     let mut uri = String::new();
     let mut title = String::new();
     let mut is_building_title = false;
     let mut is_valid_title = true;
     let mut has_opening_quote = false;
+    // Parentheses in the destination are allowed as long as they're balanced
+    let mut uri_paren_depth = 0;
 
     while let Some(token) = cursor.current() {
         if !is_building_title {
             match token {
-                Token::CloseParenthesis => break,
+                Token::CloseParenthesis if uri_paren_depth == 0 => break,
+                Token::CloseParenthesis => {
+                    uri_paren_depth -= 1;
+                    uri.push(')');
+                }
+                Token::OpenParenthesis => {
+                    uri_paren_depth += 1;
+                    uri.push('(');
+                }
                 Token::Text(s) | Token::Punctuation(s) => uri.push_str(s),
-                Token::OrderedListMarker(s) => uri.push_str(s),
-                Token::Escape(ch) => uri.push_str(&format!("\\{ch}")),
+                Token::OrderedListMarker(s, _, delimiter) => {
+                    uri.push_str(&format!("{s}{delimiter}"))
+                }
+                Token::Escape(ch) => uri.push_str(ch),
+                Token::EmphasisRun { delimiter, length } => {
+                    uri.push_str(&delimiter.to_string().repeat(*length))
+                }
                 Token::Whitespace => is_building_title = true,
-                Token::ThematicBreak => uri.push_str("---"),
+                Token::ThematicBreak(text) => uri.push_str(text),
                 Token::TableCellSeparator => uri.push('|'),
                 Token::BlockQuoteMarker => uri.push('>'),
+                // A destination in angle brackets, i.e. `<my file.png>`, may contain spaces
+                Token::RawHtmlTag(tag_content) if uri.is_empty() => {
+                    uri.push_str(&tag_content[1..tag_content.len() - 1])
+                }
                 Token::RawHtmlTag(tag_content) => uri.push_str(tag_content),
                 _ => {}
             }
@@ -854,8 +2426,10 @@ where
                     }
                 }
                 Token::Text(s) | Token::Punctuation(s) => title.push_str(s),
-                Token::OrderedListMarker(s) => title.push_str(s),
-                Token::Escape(ch) => title.push_str(&format!("\\{ch}")),
+                Token::OrderedListMarker(s, _, delimiter) => {
+                    title.push_str(&format!("{s}{delimiter}"))
+                }
+                Token::Escape(ch) => title.push_str(ch),
                 Token::EmphasisRun { delimiter, length } => {
                     title.push_str(&delimiter.to_string().repeat(*length))
                 }
@@ -863,17 +2437,19 @@ where
                 Token::CloseBracket => title.push(']'),
                 Token::OpenParenthesis => title.push('('),
                 Token::TableCellSeparator => title.push('|'),
-                Token::Tab => title.push('\t'),
-                Token::Newline => title.push_str("\\n"),
+                Token::Tab { .. } => title.push('\t'),
+                Token::Newline | Token::HardBreak => title.push_str("\\n"),
                 Token::Whitespace => title.push(' '),
-                Token::CodeTick => title.push('`'),
-                Token::CodeFence => title.push_str("```"),
-                Token::ThematicBreak => title.push_str("---"),
+                Token::CodeTick(length) => title.push_str(&"`".repeat(*length)),
+                Token::CodeFence { delimiter, length } => {
+                    title.push_str(&delimiter.to_string().repeat(*length))
+                }
+                Token::ThematicBreak(text) => title.push_str(text),
                 Token::BlockQuoteMarker => title.push('>'),
                 Token::RawHtmlTag(tag_content) => {
-                    warn!(
+                    report_warning(&format!(
                         "Raw HTML tags in titles can result in unexpected behavior: {tag_content}"
-                    );
+                    ));
                     title.push_str(tag_content);
                 }
             }
@@ -882,17 +2458,53 @@ where
     }
 
     // If we didn't find a closing parenthesis or if the title is invalid, treat it as text
-    if cursor.current() != Some(&Token::CloseParenthesis) {
-        return MdInlineElement::Text {
-            content: format!("[{}]({} ", flatten_inline(&label_elements), uri),
-        };
-    } else if !title.is_empty() && !is_valid_title {
-        return MdInlineElement::Text {
-            content: format!("[{}]({} {})", flatten_inline(&label_elements), uri, title),
-        };
+    if cursor.current() != Some(&Token::CloseParenthesis) || (!title.is_empty() && !is_valid_title)
+    {
+        cursor.set_position(label_start - 1);
+        return None;
+    }
+
+    Some(make_element(
+        label_elements,
+        Some(title).filter(|t| !t.is_empty()),
+        uri,
+    ))
+}
+
+/// Resolves a reference link (`[text][label]`, `[label][]`, or `[label]`) whose first label has
+/// just been closed.
+///
+/// # Arguments
+/// * `cursor` - A cursor positioned on the closing bracket of the first label.
+/// * `raw_label` - The text of the first label, exactly as it was written.
+///
+/// # Returns
+/// The destination and title of the matching definition, along with the position of the last
+/// token of the reference, or `None` if no definition matches.
+fn resolve_link_reference(
+    cursor: &TokenCursor,
+    raw_label: &str,
+) -> Option<(String, Option<String>, usize)> {
+    if cursor.peek_ahead(1) == Some(&Token::OpenBracket) {
+        let second_start = cursor.position() + 2;
+        let second_end = cursor.tokens[second_start..]
+            .iter()
+            .position(|token| token == &Token::CloseBracket)
+            .map(|offset| second_start + offset);
+
+        if let Some(second_end) = second_end {
+            // A collapsed reference (`[label][]`) uses the first label
+            let label = if second_end == second_start {
+                raw_label.to_string()
+            } else {
+                info_string_to_text(&cursor.tokens[second_start..second_end])
+            };
+
+            return lookup_link_reference(&label).map(|(url, title)| (url, title, second_end));
+        }
     }
 
-    make_element(label_elements, Some(title).filter(|t| !t.is_empty()), uri)
+    lookup_link_reference(raw_label).map(|(url, title)| (url, title, cursor.position()))
 }
 
 /// Flattens a vector of inline Markdown elements into a single string.
@@ -902,14 +2514,20 @@ where
 ///
 /// # Returns
 /// A string containing the concatenated content of all inline elements
-fn flatten_inline(elements: &[MdInlineElement]) -> String {
+pub(crate) fn flatten_inline(elements: &[MdInlineElement]) -> String {
     let mut result = String::new();
     for element in elements {
         match element {
             MdInlineElement::Text { content } => result.push_str(content),
             MdInlineElement::Bold { content } => result.push_str(&flatten_inline(content)),
             MdInlineElement::Italic { content } => result.push_str(&flatten_inline(content)),
-            MdInlineElement::Code { content } => result.push_str(content),
+            MdInlineElement::Strikethrough { content }
+            | MdInlineElement::Subscript { content }
+            | MdInlineElement::Superscript { content } => result.push_str(&flatten_inline(content)),
+            MdInlineElement::Code { content } | MdInlineElement::Math { content, .. } => {
+                result.push_str(content)
+            }
+            MdInlineElement::LineBreak => result.push(' '),
             MdInlineElement::Link { text, .. } => result.push_str(&flatten_inline(text)),
             MdInlineElement::Image { alt_text, .. } => result.push_str(alt_text),
             _ => {}
@@ -925,7 +2543,10 @@ fn resolve_emphasis(elements: &mut Vec<MdInlineElement>, delimiter_stack: &mut [
         // If there is only one delimiter, it cannot be resolved to emphasis
         if delimiter_stack[0].active {
             elements[delimiter_stack[0].parsed_position] = MdInlineElement::Text {
-                content: delimiter_stack[0].ch.to_string(),
+                content: delimiter_stack[0]
+                    .ch
+                    .to_string()
+                    .repeat(delimiter_stack[0].run_length),
             };
         }
         return;
@@ -975,15 +2596,34 @@ fn resolve_emphasis_recursive(
 
         let opener = delimiter_stack[j].clone();
 
-        if !closer.ch.eq(&opener.ch) {
+        if !closer.ch.eq(&opener.ch)
+            || opener.parsed_position >= closer.parsed_position
+            || closer.parsed_position >= elements.len()
+        {
+            continue;
+        }
+
+        // "~~" runs pair as strikethrough, while single "~" and "^" markers pair as subscript
+        // and superscript. Any other combination of runs is left as literal text
+        let is_strikethrough = closer.ch == '~' && closer.run_length >= 2 && opener.run_length >= 2;
+        let is_script = matches!(closer.ch, '~' | '^')
+            && closer.run_length == 1
+            && opener.run_length == 1
+            && !flatten_inline(&elements[opener.parsed_position + 1..closer.parsed_position])
+                .contains(char::is_whitespace);
+        if matches!(closer.ch, '~' | '^') && !is_strikethrough && !is_script {
             continue;
         }
 
         // Rule of 3: If the total length of the run is a multiple of 3 and both run lengths
         // are not divisible by 3, they are not valid for emphasis
         let length_total = closer.run_length + opener.run_length;
-        if ((closer.can_open && closer.can_close) || (opener.can_open && opener.can_close))
-            && (length_total % 3 == 0 && closer.run_length % 3 != 0 && opener.run_length % 3 != 0)
+        if !is_strikethrough
+            && !is_script
+            && ((closer.can_open && closer.can_close) || (opener.can_open && opener.can_close))
+            && (length_total.is_multiple_of(3)
+                && !closer.run_length.is_multiple_of(3)
+                && !opener.run_length.is_multiple_of(3))
         {
             continue;
         }
@@ -1006,22 +2646,54 @@ fn resolve_emphasis_recursive(
             (closer.parsed_position + 1).saturating_sub(delimiters_used)
         };
 
+        // A pair that crosses one which was already resolved has nothing left to wrap, so its
+        // delimiters are left as literal text
+        if range_start >= range_end || range_end >= elements.len() {
+            continue;
+        }
+
         let mut content_slice = elements[range_start + 1..range_end].to_vec();
 
         // Remove any hanging placeholders that map to inactive delimiters
         for i in 0..content_slice.len() {
             if let Some(MdInlineElement::Placeholder { ch, token_position }) = content_slice.get(i)
-            {
-                if delimiter_stack
+                && delimiter_stack
                     .iter()
                     .any(|d| !d.active && d.token_position == *token_position && d.ch == *ch)
+            {
+                content_slice.remove(i);
+            }
+        }
+
+        // Delimiters between the opener and closer can't pair with anything outside of this
+        // element anymore, so they're kept as literal text
+        for delimiter in delimiter_stack[j + 1..index]
+            .iter_mut()
+            .filter(|delimiter| delimiter.active)
+        {
+            delimiter.active = false;
+            for element in content_slice.iter_mut() {
+                if let MdInlineElement::Placeholder { ch, token_position } = element
+                    && *ch == delimiter.ch
+                    && *token_position == delimiter.token_position
                 {
-                    content_slice.remove(i);
+                    *element = MdInlineElement::Text {
+                        content: ch.to_string().repeat(delimiter.run_length),
+                    };
                 }
             }
         }
 
         let element_to_insert = match delimiters_used {
+            2 if is_strikethrough => MdInlineElement::Strikethrough {
+                content: content_slice,
+            },
+            1 if is_script && closer.ch == '~' => MdInlineElement::Subscript {
+                content: content_slice,
+            },
+            1 if is_script => MdInlineElement::Superscript {
+                content: content_slice,
+            },
             2 => MdInlineElement::Bold {
                 content: content_slice,
             },
@@ -1066,6 +2738,15 @@ fn resolve_emphasis_recursive(
     resolve_emphasis_recursive(elements, delimiter_stack, index + 1);
 }
 
+/// Checks whether a thematic break is made up only of dashes, so that it can underline a setext
+/// heading. Breaks with spaces between the dashes (`- - -`), asterisks or underscores can't.
+///
+/// # Arguments
+/// * `text` - The source text of the thematic break.
+fn is_setext_underline(text: &str) -> bool {
+    text.trim().chars().all(|ch| ch == '-')
+}
+
 /// Groups adjacent tokenized lines into groups (blocks) for further parsing.
 ///
 /// # Arguments
@@ -1073,25 +2754,116 @@ fn resolve_emphasis_recursive(
 ///
 /// # Returns
 /// A vector of vectors, where each inner vector represents a grouped block of tokens.
-pub fn group_lines_to_blocks(mut tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<Token>> {
+pub fn group_lines_to_blocks(tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<Token>> {
+    group_lines(tokenized_lines, current_config().lexer.setext_headings)
+}
+
+/// Groups adjacent tokenized lines into blocks like `group_lines_to_blocks`, along with the line
+/// each block starts on, counting from 0.
+///
+/// # Arguments
+/// * `tokenized_lines` - A vector of vectors, where each inner vector contains tokens representing a line of markdown.
+///
+/// # Returns
+/// The grouped blocks, and the line each of them starts on.
+pub(crate) fn group_lines_with_first_lines(
+    tokenized_lines: Vec<Vec<Token>>,
+) -> (Vec<Vec<Token>>, Vec<usize>) {
+    group_located_lines(tokenized_lines, current_config().lexer.setext_headings)
+}
+
+/// Groups adjacent tokenized lines into blocks, as described in `group_lines_to_blocks`.
+///
+/// # Arguments
+/// * `tokenized_lines` - A vector of vectors, where each inner vector contains tokens representing a line of markdown.
+/// * `setext_headings` - Whether `===`/`---` underlines turn the previous line into a heading.
+///   When disabled, `===` underlines are kept as text and `---` underlines are thematic breaks.
+///
+/// # Returns
+/// A vector of vectors, where each inner vector represents a grouped block of tokens.
+fn group_lines(tokenized_lines: Vec<Vec<Token>>, setext_headings: bool) -> Vec<Vec<Token>> {
+    group_located_lines(tokenized_lines, setext_headings).0
+}
+
+/// Groups adjacent tokenized lines into blocks, as described in `group_lines`, keeping track of
+/// the line each block starts on.
+///
+/// # Arguments
+/// * `tokenized_lines` - A vector of vectors, where each inner vector contains tokens representing a line of markdown.
+/// * `setext_headings` - Whether `===`/`---` underlines turn the previous line into a heading.
+///
+/// # Returns
+/// The grouped blocks, and the line each of them starts on, counting from 0.
+fn group_located_lines(
+    mut tokenized_lines: Vec<Vec<Token>>,
+    setext_headings: bool,
+) -> (Vec<Vec<Token>>, Vec<usize>) {
     let mut blocks: Vec<Vec<Token>> = Vec::new();
+    let mut first_lines: Vec<usize> = Vec::new();
+    let line_count = tokenized_lines.len();
     let mut current_block: Vec<Token> = Vec::new();
     let mut previous_block: Vec<Token>;
+    let definition_list_lines = find_definition_list_lines(&tokenized_lines);
     let lines = tokenized_lines.iter_mut();
-    let mut is_inside_code_block = false;
-    for line in lines {
+    let mut open_code_fence: Option<Token> = None;
+    let mut is_inside_ignored_region = false;
+    for (i, line) in lines.enumerate() {
+        // Blocks are only added while grouping their first line, and a block that's replaced
+        // (i.e. by a setext heading) keeps its place
+        first_lines.resize(blocks.len(), i.saturating_sub(1));
         previous_block = blocks.last().unwrap_or(&Vec::new()).to_vec();
 
-        // Appending all tokens between two code fences to one block
-        if is_inside_code_block && line.first() != Some(&Token::CodeFence) {
-            // If we are inside a code block, then we just append the line to the current block
+        // Lines between ignore directives are passed through as a single raw HTML block
+        if is_inside_ignored_region {
+            is_inside_ignored_region = !is_directive_comment(line, "markrs:ignore-end");
+            attach_to_previous_block(&mut blocks, &mut previous_block, line, Some(Token::Newline));
+            continue;
+        } else if open_code_fence.is_none() && is_directive_comment(line, "markrs:ignore-start") {
+            is_inside_ignored_region = true;
+            blocks.push(take(line));
+            continue;
+        }
+
+        // Appending all tokens between two matching code fences to one block
+        if let Some(opening_fence) = &open_code_fence {
+            // A closing fence must match the opening one, so any other line (including a
+            // different fence) is part of the code
+            if is_closing_fence(line, opening_fence) {
+                open_code_fence = None;
+            }
+
+            attach_to_previous_block(&mut blocks, &mut previous_block, line, Some(Token::Newline));
+            continue;
+        }
+
+        // Lines with only whitespace are blank, so they end a paragraph like an empty line does
+        if is_blank_line(line) {
+            *line = vec![Token::Newline];
+        }
+
+        // Definition list lines are kept on separate lines so terms and definitions can be told
+        // apart, and text after the list starts a new paragraph instead of joining the last line
+        let follows_definition_list = i > 0 && definition_list_lines[i - 1];
+        if definition_list_lines[i] && follows_definition_list {
             attach_to_previous_block(&mut blocks, &mut previous_block, line, Some(Token::Newline));
             continue;
-        } else if is_inside_code_block && line.first() == Some(&Token::CodeFence) {
-            // If we are inside a code block and the line starts with a code fence, then we end the
-            // code block
-            is_inside_code_block = false;
-            attach_to_previous_block(&mut blocks, &mut previous_block, line, None);
+        } else if definition_list_lines[i]
+            || (follows_definition_list && matches!(line.first(), Some(Token::Text(_))))
+        {
+            blocks.push(take(line));
+            continue;
+        }
+
+        // Wrapped lines after a list item, indented or not, continue the item's last line so
+        // that the whole item is parsed as one paragraph
+        if let Some(continuation) = list_item_continuation(&previous_block, line) {
+            let separator = take_hard_break(&mut previous_block).unwrap_or(Token::Whitespace);
+            attach_to_previous_block(
+                &mut blocks,
+                &mut previous_block,
+                continuation,
+                Some(separator),
+            );
             continue;
         }
 
@@ -1101,18 +2873,27 @@ pub fn group_lines_to_blocks(mut tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<To
                 blocks.push(line.to_owned());
             }
             Some(Token::Punctuation(string)) if string == "-" => {
-                group_dashed_lines(&mut blocks, &mut current_block, &mut previous_block, line);
+                group_dashed_lines(
+                    &mut blocks,
+                    &mut current_block,
+                    &mut previous_block,
+                    line,
+                    setext_headings,
+                );
             }
             Some(Token::Punctuation(string)) if string == "*" => {
                 group_asterisked_lines(&mut blocks, &mut current_block, &mut previous_block, line);
             }
-            Some(Token::Tab) => {
+            Some(Token::Tab { .. }) => {
                 group_tabbed_lines(&mut blocks, &mut current_block, &mut previous_block, line);
             }
-            Some(Token::OrderedListMarker(_)) => {
+            Some(Token::OrderedListMarker(..)) => {
                 group_ordered_list(&mut blocks, &mut current_block, &mut previous_block, line);
             }
-            Some(Token::ThematicBreak) => {
+            Some(Token::ThematicBreak(text)) if !setext_headings || !is_setext_underline(text) => {
+                blocks.push(take(line));
+            }
+            Some(Token::ThematicBreak(_)) => {
                 // Check if the previous line starts with anything other than a heading
                 // If so, then this is actually a setext heading 2
                 if let Some(previous_line_start) = previous_block.first() {
@@ -1149,23 +2930,25 @@ pub fn group_lines_to_blocks(mut tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<To
                     current_block.extend_from_slice(line);
                 }
             }
-            Some(Token::CodeTick) => {
+            Some(Token::CodeTick(_)) => {
                 current_block.extend_from_slice(line);
             }
-            Some(Token::CodeFence) => {
-                if !is_inside_code_block {
-                    is_inside_code_block = true;
-                    current_block.extend_from_slice(line);
-                } else {
-                    is_inside_code_block = false;
-                    current_block.extend_from_slice(line);
-                    blocks.push(take(&mut current_block));
-                }
+            Some(fence @ Token::CodeFence { .. }) => {
+                open_code_fence = Some(fence.clone());
+                current_block.extend_from_slice(line);
+            }
+            Some(fence @ Token::Punctuation(string))
+                if string == "$$" && current_config().html.math && is_math_fence(line) =>
+            {
+                open_code_fence = Some(fence.clone());
+                current_block.extend_from_slice(line);
             }
-            Some(Token::Text(string)) if string == "=" => {
+            Some(Token::Text(string))
+                if setext_headings && !string.is_empty() && string.chars().all(|ch| ch == '=') =>
+            {
                 let has_trailing_content = line.iter().skip(1).any(|token| match token {
-                    Token::Text(s) if s == "=" => false,
-                    Token::Whitespace | Token::Tab | Token::Newline => false,
+                    Token::Text(s) if s.chars().all(|ch| ch == '=') => false,
+                    Token::Whitespace | Token::Tab { .. } | Token::Newline => false,
                     _ => true,
                 });
 
@@ -1211,7 +2994,233 @@ pub fn group_lines_to_blocks(mut tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<To
 
         current_block.clear();
     }
-    blocks
+    first_lines.resize(blocks.len(), line_count.saturating_sub(1));
+
+    join_blank_separated_list_items(blocks.into_iter().zip(first_lines).collect())
+        .into_iter()
+        .unzip()
+}
+
+/// Returns whether a line is blank, i.e. empty or made up of only spaces and tabs.
+fn is_blank_line(line: &[Token]) -> bool {
+    line.iter().all(|token| {
+        matches!(
+            token,
+            Token::Whitespace | Token::Tab { .. } | Token::Newline
+        )
+    })
+}
+
+/// Finds the lines that make up definition lists, which are runs of term lines followed by
+/// `: definition` lines. Every line in the paragraph before a definition is one of its terms.
+///
+/// # Arguments
+/// * `tokenized_lines` - The tokenized lines of the document.
+///
+/// # Returns
+/// Whether each line belongs to a definition list. Definition lines without a preceding term
+/// aren't included, so they fall back to paragraphs.
+fn find_definition_list_lines(tokenized_lines: &[Vec<Token>]) -> Vec<bool> {
+    let mut definition_list_lines = vec![false; tokenized_lines.len()];
+
+    for (i, line) in tokenized_lines.iter().enumerate() {
+        if !is_definition_line(line) || i == 0 {
+            continue;
+        }
+
+        let previous_line = &tokenized_lines[i - 1];
+        if !definition_list_lines[i - 1] && !is_definition_term(previous_line) {
+            continue;
+        }
+
+        definition_list_lines[i] = true;
+
+        let mut term_index = i - 1;
+        while !definition_list_lines[term_index] && is_definition_term(&tokenized_lines[term_index])
+        {
+            definition_list_lines[term_index] = true;
+            if term_index == 0 {
+                break;
+            }
+            term_index -= 1;
+        }
+    }
+
+    definition_list_lines
+}
+
+/// Checks whether a line is a definition in a definition list, i.e. `: The definition`.
+fn is_definition_line(line: &[Token]) -> bool {
+    matches!(line, [Token::Punctuation(colon), Token::Whitespace, rest @ ..]
+        if colon == ":" && rest.iter().any(|token| !matches!(token, Token::Whitespace | Token::Tab { .. })))
+}
+
+/// Checks whether a line could be a term in a definition list, meaning it starts with inline
+/// content rather than a block marker.
+fn is_definition_term(line: &[Token]) -> bool {
+    matches!(
+        line.first(),
+        Some(
+            Token::Text(_)
+                | Token::EmphasisRun { .. }
+                | Token::OpenBracket
+                | Token::CodeTick(_)
+                | Token::Escape(_)
+        )
+    )
+}
+
+/// Checks whether a block is a definition list grouped by `group_lines_to_blocks`, meaning it
+/// starts with a term and contains at least one definition line.
+fn is_definition_list_block(line: &[Token]) -> bool {
+    is_definition_term(line)
+        && line
+            .split(|token| token == &Token::Newline)
+            .any(is_definition_line)
+}
+
+/// Joins list blocks that are only separated by blank lines back into a single list block, which
+/// `parse_list` will treat as a loose list.
+///
+/// Each blank line is kept as an empty line in the joined block. Items are only joined if they
+/// continue the same list, meaning they use the same bullet character or ordered marker style, or
+/// are tab-indented items of a nested list.
+///
+/// # Arguments
+/// * `blocks` - The grouped blocks, along with the line each of them starts on.
+///
+/// # Returns
+/// The blocks, with blank-separated list items joined.
+fn join_blank_separated_list_items(blocks: Vec<(Vec<Token>, usize)>) -> Vec<(Vec<Token>, usize)> {
+    let mut joined_blocks: Vec<(Vec<Token>, usize)> = Vec::new();
+    let mut blank_lines: Vec<(Vec<Token>, usize)> = Vec::new();
+
+    for (block, first_line) in blocks {
+        if block == [Token::Newline] {
+            blank_lines.push((block, first_line));
+            continue;
+        }
+
+        let previous_list = joined_blocks.last_mut().filter(|(previous, _)| {
+            list_marker(previous).is_some_and(|marker| {
+                list_marker(&block).is_some_and(|next_marker| is_same_list(marker, next_marker))
+                    || (!blank_lines.is_empty() && is_nested_list_item(&block))
+            })
+        });
+
+        match previous_list {
+            Some((previous_list, _)) => {
+                previous_list.push(Token::Newline);
+                previous_list.extend(blank_lines.drain(..).map(|_| Token::Newline));
+                previous_list.extend(block);
+            }
+            None => {
+                joined_blocks.append(&mut blank_lines);
+                joined_blocks.push((block, first_line));
+            }
+        }
+    }
+
+    joined_blocks.append(&mut blank_lines);
+    joined_blocks
+}
+
+/// Returns the marker of the list item that a line starts with, i.e. `Punctuation("-")` or an
+/// `OrderedListMarker`, or `None` if the line isn't a list item.
+fn list_marker(line: &[Token]) -> Option<&Token> {
+    let marker = line.first()?;
+    let is_marker = match marker {
+        Token::Punctuation(string) => string == "-" || string == "*",
+        Token::OrderedListMarker(..) => true,
+        _ => false,
+    };
+
+    (is_marker && line.get(1) == Some(&Token::Whitespace)).then_some(marker)
+}
+
+/// Returns the content of a line that continues the last item of the previous block, which is
+/// any line of paragraph text directly following a list, with or without indentation. Lines that
+/// start another block, such as a nested list item or a heading, aren't continuations.
+///
+/// # Arguments
+/// * `previous_block` - The block before the line.
+/// * `line` - The tokenized line to check.
+///
+/// # Returns
+/// The line without its indentation, or `None` if it isn't a continuation.
+fn list_item_continuation<'a>(previous_block: &[Token], line: &'a [Token]) -> Option<&'a [Token]> {
+    list_marker(previous_block)?;
+
+    let indent = line
+        .iter()
+        .take_while(|token| matches!(token, Token::Whitespace | Token::Tab { .. }))
+        .count();
+    let content = &line[indent..];
+
+    let is_paragraph_text = match content.first()? {
+        Token::Text(string) => !string.chars().all(|ch| ch == '='),
+        Token::Punctuation(string) => !matches!(string.as_str(), "#" | "-" | "*"),
+        Token::OpenBracket => {
+            parse_link_reference_definition(content).is_none()
+                && split_footnote_definition(content).is_none()
+        }
+        Token::EmphasisRun { .. }
+        | Token::CodeTick(_)
+        | Token::Escape(_)
+        | Token::OpenParenthesis
+        | Token::CloseParenthesis
+        | Token::CloseBracket => true,
+        _ => false,
+    };
+
+    (is_paragraph_text && list_marker(content).is_none()).then_some(content)
+}
+
+/// Returns whether two list markers belong to the same list, meaning they are the same bullet
+/// character or the same kind of ordered marker with the same delimiter.
+fn is_same_list(marker: &Token, other_marker: &Token) -> bool {
+    match (marker, other_marker) {
+        (
+            Token::OrderedListMarker(_, kind, delimiter),
+            Token::OrderedListMarker(_, other_kind, other_delimiter),
+        ) => kind == other_kind && delimiter == other_delimiter,
+        _ => marker == other_marker,
+    }
+}
+
+/// Returns whether a line is a tab-indented list item, i.e. an item of a nested list.
+fn is_nested_list_item(line: &[Token]) -> bool {
+    let indent = line
+        .iter()
+        .take_while(|token| matches!(token, Token::Tab { .. }))
+        .count();
+    indent > 0 && list_marker(&line[indent..]).is_some()
+}
+
+/// Checks whether a line consists of only an HTML comment containing the given directive, i.e.
+/// `<!-- markrs:ignore-start -->`.
+///
+/// # Arguments
+/// * `line` - The tokenized line to check.
+/// * `directive` - The directive to look for, such as `markrs:ignore-start`.
+///
+/// # Returns
+/// `true` if the line is the directive comment, otherwise `false`.
+fn is_directive_comment(line: &[Token], directive: &str) -> bool {
+    let mut content_tokens = line.iter().filter(|token| {
+        !matches!(
+            token,
+            Token::Whitespace | Token::Tab { .. } | Token::Newline
+        )
+    });
+
+    match (content_tokens.next(), content_tokens.next()) {
+        (Some(Token::RawHtmlTag(tag_content)), None) => tag_content
+            .strip_prefix("<!--")
+            .and_then(|comment| comment.strip_suffix("-->"))
+            .is_some_and(|comment| comment.trim() == directive),
+        _ => false,
+    }
 }
 
 /// Groups lines beginning with "|" denoting Markdown tables.
@@ -1255,7 +3264,8 @@ fn group_text_lines(
 ) {
     if !previous_block.is_empty() {
         if matches!(previous_block.first(), Some(Token::Text(_))) {
-            attach_to_previous_block(blocks, previous_block, line, Some(Token::Whitespace));
+            let separator = take_hard_break(previous_block).unwrap_or(Token::Whitespace);
+            attach_to_previous_block(blocks, previous_block, line, Some(separator));
         } else if matches!(previous_block.first(), Some(Token::Punctuation(_))) {
             // If the previous block was a heading, then this is a new paragraph
             current_block.extend_from_slice(line);
@@ -1301,7 +3311,7 @@ fn group_ordered_list(
 ) {
     if let Some(previous_line_start) = previous_block.first() {
         match previous_line_start {
-            Token::OrderedListMarker(_) if previous_block.get(1) == Some(&Token::Whitespace) => {
+            Token::OrderedListMarker(..) if previous_block.get(1) == Some(&Token::Whitespace) => {
                 // If the previous block is a list, then we append the line to it
                 attach_to_previous_block(blocks, previous_block, line, Some(Token::Newline));
             }
@@ -1314,6 +3324,37 @@ fn group_ordered_list(
     }
 }
 
+/// Removes a trailing hard break marker (two or more spaces, or an unescaped backslash) from the
+/// end of a paragraph line.
+///
+/// This has to happen while grouping, since trailing whitespace is collapsed once the lines of
+/// a paragraph are joined. An escaped backslash is lexed as `Token::Escape`, so `foo\\` is left
+/// alone.
+///
+/// # Arguments
+/// * `previous_block` - The paragraph that the next line is about to be attached to.
+///
+/// # Returns
+/// `Some(Token::HardBreak)` if the marker was found and removed, otherwise `None`.
+fn take_hard_break(previous_block: &mut Vec<Token>) -> Option<Token> {
+    if previous_block.last() == Some(&Token::Text("\\".to_string())) {
+        previous_block.pop();
+        return Some(Token::HardBreak);
+    }
+
+    let trailing_spaces = previous_block
+        .iter()
+        .rev()
+        .take_while(|token| token == &&Token::Whitespace)
+        .count();
+    if trailing_spaces >= 2 {
+        previous_block.truncate(previous_block.len() - trailing_spaces);
+        return Some(Token::HardBreak);
+    }
+
+    None
+}
+
 /// Attaches the current line to the previous block, optionally adding a separator token.
 fn attach_to_previous_block(
     blocks: &mut Vec<Vec<Token>>,
@@ -1352,9 +3393,12 @@ fn group_tabbed_lines(
         return;
     }
 
-    let non_whitespace_index = line
-        .iter()
-        .position(|token| !matches!(token, Token::Whitespace | Token::Tab | Token::Newline));
+    let non_whitespace_index = line.iter().position(|token| {
+        !matches!(
+            token,
+            Token::Whitespace | Token::Tab { .. } | Token::Newline
+        )
+    });
 
     if let Some(first_content_token) = line.get(non_whitespace_index.unwrap_or(0)) {
         if matches!(first_content_token, Token::RawHtmlTag(_))
@@ -1363,7 +3407,7 @@ fn group_tabbed_lines(
             // If the first token is a raw HTML tag, we attach the line to the previous block
             let line_to_attach = line
                 .iter()
-                .skip_while(|t| matches!(t, Token::Whitespace | Token::Tab | Token::Newline))
+                .skip_while(|t| matches!(t, Token::Whitespace | Token::Tab { .. } | Token::Newline))
                 .cloned()
                 .collect::<Vec<Token>>();
 
@@ -1378,7 +3422,9 @@ fn group_tabbed_lines(
         } else if matches!(first_content_token, Token::RawHtmlTag(_)) {
             current_block.extend(
                 line.iter()
-                    .skip_while(|t| matches!(t, Token::Whitespace | Token::Tab | Token::Newline))
+                    .skip_while(|t| {
+                        matches!(t, Token::Whitespace | Token::Tab { .. } | Token::Newline)
+                    })
                     .cloned(),
             );
             return;
@@ -1394,7 +3440,7 @@ fn group_tabbed_lines(
                     // If the previous block is a list, then we append the line to it
                     attach_to_previous_block(blocks, previous_block, line, Some(Token::Newline));
                 }
-                Some(Token::OrderedListMarker(_))
+                Some(Token::OrderedListMarker(..))
                     if previous_block.get(1) == Some(&Token::Whitespace) =>
                 {
                     // If the previous block is an ordered list, then we append the
@@ -1404,7 +3450,7 @@ fn group_tabbed_lines(
                 Some(Token::RawHtmlTag(_)) => {
                     attach_to_previous_block(blocks, previous_block, line, Some(Token::Newline));
                 }
-                Some(Token::Tab) => {
+                Some(Token::Tab { .. }) => {
                     attach_to_previous_block(blocks, previous_block, line, Some(Token::Newline));
                 }
                 _ => {
@@ -1437,16 +3483,15 @@ fn group_lines_with_leading_whitespace(
 ) {
     if let Some(first_content_token) = line
         .iter()
-        .find(|t| !matches!(t, Token::Whitespace | Token::Tab | Token::Newline))
+        .find(|t| !matches!(t, Token::Whitespace | Token::Tab { .. } | Token::Newline))
     {
         if let Some(previous_line_start) = previous_block.first() {
             match previous_line_start {
                 Token::Whitespace => {
                     // Check if the previous line has non-whitespace content
-                    if line
-                        .iter()
-                        .any(|t| !matches!(t, Token::Whitespace | Token::Tab | Token::Newline))
-                    {
+                    if line.iter().any(|t| {
+                        !matches!(t, Token::Whitespace | Token::Tab { .. } | Token::Newline)
+                    }) {
                         attach_to_previous_block(
                             blocks,
                             previous_block,
@@ -1490,7 +3535,7 @@ fn group_lines_with_leading_whitespace(
                     current_block.extend(
                         line.iter()
                             .skip_while(|t| {
-                                matches!(t, Token::Whitespace | Token::Tab | Token::Newline)
+                                matches!(t, Token::Whitespace | Token::Tab { .. } | Token::Newline)
                             })
                             .cloned(),
                     );
@@ -1510,16 +3555,19 @@ fn group_lines_with_leading_whitespace(
 /// * `previous_block` - A mutable reference to the previous block, used for context.
 /// * `line` - A mutable reference to the current line being processed, which is a vector of
 ///   tokens.
+/// * `setext_headings` - Whether a lone `-` underline turns the previous block into a heading.
 fn group_dashed_lines(
     blocks: &mut Vec<Vec<Token>>,
     current_block: &mut Vec<Token>,
     previous_block: &mut Vec<Token>,
     line: &[Token],
+    setext_headings: bool,
 ) {
     if let Some(previous_line_start) = previous_block.first() {
         match previous_line_start {
             Token::Punctuation(string)
-                if string == "-" && previous_block.get(1) == Some(&Token::Whitespace) =>
+                if string == "-"
+                    && matches!(previous_block.get(1), Some(Token::Whitespace) | None) =>
             {
                 // Then it is either the start of a list or part of a list
 
@@ -1528,8 +3576,12 @@ fn group_dashed_lines(
             Token::Punctuation(string) if string == "#" => {
                 blocks.push(line.to_owned());
             }
+            // There is no paragraph to underline after a blank line, so this starts a list
+            Token::Newline => {
+                current_block.extend_from_slice(line);
+            }
             _ => {
-                if line.len() > 1 {
+                if line.len() > 1 || !setext_headings {
                     current_block.extend_from_slice(line);
                 } else {
                     // Then this is a Setext heading 2
@@ -1,8 +1,14 @@
 use crate::CONFIG;
 use crate::config::Config;
 use crate::lexer::tokenize;
-use crate::parser::{parse_block, parse_inline};
-use crate::types::{MdBlockElement::*, MdInlineElement::*, MdListItem, ToHtml};
+use crate::parser::{
+    autolink_emails, escape_to_text, linkify_prefixed_words, parse_block, parse_inline,
+    smarten_punctuation,
+};
+use crate::types::{
+    CalloutKind, CodeBlockAttributes, ListMarkerKind, MdBlockElement, MdBlockElement::*,
+    MdDefinitionItem, MdInlineElement, MdInlineElement::*, MdListItem, ToHtml, Token, TokenCursor,
+};
 
 use std::sync::Once;
 static INIT: Once = Once::new();
@@ -13,6 +19,20 @@ fn init_test_config() {
     });
 }
 
+/// Runs `f` with math parsing enabled.
+fn with_math<T>(f: impl FnOnce() -> T) -> T {
+    let mut config = Config::default();
+    config.html.math = true;
+    crate::config::with_config(&config, f)
+}
+
+/// Runs `f` with blockquote citations enabled.
+fn with_citations<T>(f: impl FnOnce() -> T) -> T {
+    let mut config = Config::default();
+    config.html.blockquote_cite = true;
+    crate::config::with_config(&config, f)
+}
+
 mod inline {
     use super::*;
 
@@ -38,6 +58,50 @@ mod inline {
         );
     }
 
+    #[test]
+    fn escaped_hyphen_inside_word_is_soft_hyphen() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("hyph\\-en\\-ation")),
+            vec![Text {
+                content: String::from("hyph&shy;en&shy;ation")
+            }]
+        );
+    }
+
+    #[test]
+    fn escaped_hyphen_outside_word_is_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("\\- not a list")),
+            vec![Text {
+                content: String::from("\\- not a list")
+            }]
+        );
+    }
+
+    #[test]
+    fn escaped_space_is_literal_by_default() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("10\\ km")),
+            vec![Text {
+                content: String::from("10\\ km")
+            }]
+        );
+    }
+
+    #[test]
+    fn escaped_space_as_nbsp() {
+        init_test_config();
+        let cursor = TokenCursor {
+            tokens: tokenize("10\\ km"),
+            current_position: 1,
+        };
+        assert_eq!(escape_to_text(&cursor, " ", true), "&nbsp;");
+        assert_eq!(escape_to_text(&cursor, " ", false), "\\ ");
+    }
+
     #[test]
     fn bold() {
         init_test_config();
@@ -56,6 +120,254 @@ mod inline {
         );
     }
 
+    #[test]
+    fn strikethrough() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("~~Deleted~~ text")),
+            vec![
+                Strikethrough {
+                    content: vec![Text {
+                        content: String::from("Deleted")
+                    }]
+                },
+                Text {
+                    content: String::from(" text")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn strikethrough_crossing_emphasis() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("~~a *b~~ c*")),
+            vec![
+                Strikethrough {
+                    content: vec![Text {
+                        content: String::from("a *b")
+                    }]
+                },
+                Text {
+                    content: String::from(" c*")
+                }
+            ]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("*a ~b* c~")),
+            vec![
+                Italic {
+                    content: vec![Text {
+                        content: String::from("a ~b")
+                    }]
+                },
+                Text {
+                    content: String::from(" c~")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn single_tilde_is_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("~Not deleted~ and ~one")),
+            vec![Text {
+                content: String::from("~Not deleted~ and ~one")
+            }]
+        );
+    }
+
+    #[test]
+    fn subscript_and_superscript() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("H~2~O and E = mc^2^")),
+            vec![
+                Text {
+                    content: String::from("H")
+                },
+                Subscript {
+                    content: vec![Text {
+                        content: String::from("2")
+                    }]
+                },
+                Text {
+                    content: String::from("O and E = mc")
+                },
+                Superscript {
+                    content: vec![Text {
+                        content: String::from("2")
+                    }]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_or_spaced_script_markers_are_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("2^10 and a^b c^ and x~1")),
+            vec![Text {
+                content: String::from("2^10 and a^b c^ and x~1")
+            }]
+        );
+    }
+
+    #[test]
+    fn script_markers_crossing_emphasis() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("x^a *b^ c*")),
+            vec![
+                Text {
+                    content: String::from("x^a ")
+                },
+                Italic {
+                    content: vec![Text {
+                        content: String::from("b^ c")
+                    }]
+                }
+            ]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("^_a^_")),
+            vec![
+                Superscript {
+                    content: vec![Text {
+                        content: String::from("_a")
+                    }]
+                },
+                Text {
+                    content: String::from("_")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn strikethrough_in_code_span_is_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("`~~code~~`")),
+            vec![Code {
+                content: String::from("~~code~~")
+            }]
+        );
+    }
+
+    #[test]
+    fn code_span_with_embedded_backtick() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("``code with ` backtick``")),
+            vec![Code {
+                content: String::from("code with ` backtick")
+            }]
+        );
+    }
+
+    #[test]
+    fn code_span_strips_single_padding_spaces() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("`` `tick` ``")),
+            vec![Code {
+                content: String::from("`tick`")
+            }]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("`  `")),
+            vec![Code {
+                content: String::from("  ")
+            }]
+        );
+    }
+
+    #[test]
+    fn code_span_with_triple_backticks_mid_line() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("Use ```a `` b``` here")),
+            vec![
+                Text {
+                    content: String::from("Use ")
+                },
+                Code {
+                    content: String::from("a `` b")
+                },
+                Text {
+                    content: String::from(" here")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_backtick_run_is_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("``not code` *but italic*")),
+            vec![
+                Text {
+                    content: String::from("``not code` ")
+                },
+                Italic {
+                    content: vec![Text {
+                        content: String::from("but italic")
+                    }]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn entity_references_are_decoded() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("&copy; &#169; &#x41; &#X42; &frac12;")),
+            vec![Text {
+                content: String::from("© © A B ½")
+            }]
+        );
+    }
+
+    #[test]
+    fn entity_references_for_html_characters_stay_escaped() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("&lt;div&gt; &amp; &#60; &quot;")),
+            vec![Text {
+                content: String::from("&lt;div&gt; &amp; &lt; &quot;")
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_entity_references_are_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("&foo; & bar &#; &#0; &#x110000; &#12345678;")),
+            vec![Text {
+                content: String::from("&amp;foo; & bar &#; \u{FFFD} \u{FFFD} &#12345678;")
+            }]
+        );
+    }
+
+    #[test]
+    fn entity_references_in_code_spans_are_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("`&copy;`")),
+            vec![Code {
+                content: String::from("&copy;")
+            }]
+        );
+    }
+
     #[test]
     fn italic() {
         init_test_config();
@@ -201,18 +513,9 @@ mod inline {
                     content: String::from("_")
                 },
                 Bold {
-                    content: vec![
-                        Text {
-                            content: String::from("Bold and ")
-                        },
-                        Placeholder {
-                            ch: '_',
-                            token_position: 6
-                        },
-                        Text {
-                            content: String::from("not italic")
-                        }
-                    ]
+                    content: vec![Text {
+                        content: String::from("Bold and _not italic")
+                    }]
                 },
             ]
         )
@@ -306,6 +609,44 @@ mod inline {
         );
     }
 
+    #[test]
+    fn link_with_parenthesized_url() {
+        init_test_config();
+        let link = |url: &str| Link {
+            text: vec![Text {
+                content: String::from("x"),
+            }],
+            title: None,
+            url: String::from(url),
+        };
+
+        assert_eq!(
+            parse_inline(&tokenize("[x](path\\(1\\).png)")),
+            vec![link("path(1).png")]
+        );
+        assert_eq!(
+            parse_inline(&tokenize(
+                "[x](https://en.wikipedia.org/wiki/Rust_(language))"
+            )),
+            vec![link("https://en.wikipedia.org/wiki/Rust_(language)")]
+        );
+    }
+
+    #[test]
+    fn link_with_spaced_url_and_escaped_title() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("[x](<my file.md> \"A \\\"quoted\\\" title\")")),
+            vec![Link {
+                text: vec![Text {
+                    content: String::from("x")
+                }],
+                title: Some(String::from("A \"quoted\" title")),
+                url: String::from("my file.md")
+            }]
+        );
+    }
+
     #[test]
     fn link_with_emphasized_title() {
         init_test_config();
@@ -333,7 +674,9 @@ mod inline {
             vec![Image {
                 alt_text: String::from("alt text"),
                 title: None,
-                url: String::from("http://example.com/image.png")
+                url: String::from("http://example.com/image.png"),
+                width: None,
+                height: None,
             }]
         );
     }
@@ -348,7 +691,9 @@ mod inline {
             vec![Image {
                 alt_text: String::from("alt text"),
                 title: Some(String::from("Title")),
-                url: String::from("http://example.com/image.png")
+                url: String::from("http://example.com/image.png"),
+                width: None,
+                height: None,
             }]
         );
     }
@@ -361,7 +706,9 @@ mod inline {
             vec![Image {
                 alt_text: String::from(""),
                 title: None,
-                url: String::from("http://example.com/image.png")
+                url: String::from("http://example.com/image.png"),
+                width: None,
+                height: None,
             }]
         )
     }
@@ -376,7 +723,9 @@ mod inline {
             vec![Image {
                 alt_text: String::from("bold alt text"), //Only plain string content is added
                 title: None,
-                url: String::from("http://example.com/image.png")
+                url: String::from("http://example.com/image.png"),
+                width: None,
+                height: None,
             }]
         );
     }
@@ -391,75 +740,887 @@ mod inline {
             vec![Image {
                 alt_text: String::from("alt text"),
                 title: Some(String::from("**bold title**")),
-                url: String::from("http://example.com/image.png")
+                url: String::from("http://example.com/image.png"),
+                width: None,
+                height: None,
             }]
         );
     }
 
     #[test]
-    fn raw_inline_html() {
+    fn image_with_dimensions() {
         init_test_config();
         assert_eq!(
-            parse_inline(&tokenize("<span>Inline HTML</span>")),
-            vec![Text {
-                content: String::from("<span>Inline HTML</span>")
+            parse_inline(&tokenize(
+                "![alt text](http://example.com/image.png =300x200 \"Title\")"
+            )),
+            vec![Image {
+                alt_text: String::from("alt text"),
+                title: Some(String::from("Title")),
+                url: String::from("http://example.com/image.png"),
+                width: Some(300),
+                height: Some(200),
             }]
         );
     }
 
     #[test]
-    fn malformed_raw_html_no_closing_bracket() {
+    fn image_with_width_only() {
         init_test_config();
         assert_eq!(
-            parse_inline(&tokenize("<span Malformed HTML")),
-            vec![Text {
-                content: String::from("<span Malformed HTML")
+            parse_inline(&tokenize("![alt text](http://example.com/image.png =300x)")),
+            vec![Image {
+                alt_text: String::from("alt text"),
+                title: None,
+                url: String::from("http://example.com/image.png"),
+                width: Some(300),
+                height: None,
             }]
         );
     }
 
     #[test]
-    fn malformed_raw_html_no_closing_tag() {
+    fn image_with_malformed_dimensions_keeps_title() {
         init_test_config();
         assert_eq!(
-            parse_inline(&tokenize("<span>Unclosed HTML")),
-            vec![Text {
-                content: String::from("<span>Unclosed HTML")
-            }]
+            parse_inline(&tokenize("![alt text](http://example.com/image.png =wide)")),
+            vec![Image {
+                alt_text: String::from("alt text"),
+                title: Some(String::from("=wide")),
+                url: String::from("http://example.com/image.png"),
+                width: None,
+                height: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn image_with_attribute_block() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize(
+                "![alt text](http://example.com/image.png){width=300 height=\"200\"} after"
+            )),
+            vec![
+                Image {
+                    alt_text: String::from("alt text"),
+                    title: None,
+                    url: String::from("http://example.com/image.png"),
+                    width: Some(300),
+                    height: Some(200),
+                },
+                Text {
+                    content: String::from(" after")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn image_with_invalid_attribute_block_keeps_text() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize(
+                "![alt text](http://example.com/image.png){width=big}"
+            )),
+            vec![
+                Image {
+                    alt_text: String::from("alt text"),
+                    title: None,
+                    url: String::from("http://example.com/image.png"),
+                    width: None,
+                    height: None,
+                },
+                Text {
+                    content: String::from("{width=big}")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_inline_html() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("<span>Inline *HTML*</span>")),
+            vec![
+                MdInlineElement::RawHtml {
+                    content: String::from("<span>")
+                },
+                Text {
+                    content: String::from("Inline ")
+                },
+                Italic {
+                    content: vec![Text {
+                        content: String::from("HTML")
+                    }]
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("</span>")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn void_and_self_closing_html_tags_are_raw_html() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("a<br>b<x-icon/><!-- note -->")),
+            vec![
+                Text {
+                    content: String::from("a")
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("<br>")
+                },
+                Text {
+                    content: String::from("b")
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("<x-icon/>")
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("<!-- note -->")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_html_tag_is_escaped() {
+        init_test_config();
+        let parsed = parse_inline(&tokenize("I <3 you, see <a href=\"x\" !>"));
+        assert_eq!(
+            parsed,
+            vec![Text {
+                content: String::from("I <3 you, see <a href=\"x\" !>")
+            }]
+        );
+        assert_eq!(
+            parsed[0].to_html("", "", ""),
+            "I &lt;3 you, see &lt;a href=\"x\" !&gt;"
+        );
+    }
+
+    #[test]
+    fn stray_angle_bracket_before_inline_html() {
+        init_test_config();
+        let parsed = parse_inline(&tokenize("a < b and <b>bold</b>"));
+        assert_eq!(
+            parsed,
+            vec![
+                Text {
+                    content: String::from("a < b and ")
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("<b>")
+                },
+                Text {
+                    content: String::from("bold")
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("</b>")
+                }
+            ]
+        );
+        assert_eq!(
+            parsed
+                .iter()
+                .map(|el| el.to_html("", "", ""))
+                .collect::<String>(),
+            "a &lt; b and <b>bold</b>"
+        );
+
+        assert_eq!(
+            parse_inline(&tokenize("x <3 and `a<b` <em>y</em>"))[3],
+            MdInlineElement::RawHtml {
+                content: String::from("<em>")
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_raw_html_no_closing_bracket() {
+        init_test_config();
+        let parsed = parse_inline(&tokenize("<span Malformed HTML"));
+        assert_eq!(
+            parsed,
+            vec![Text {
+                content: String::from("<span Malformed HTML")
+            }]
+        );
+        assert_eq!(parsed[0].to_html("", "", ""), "&lt;span Malformed HTML");
+    }
+
+    #[test]
+    fn malformed_raw_html_no_closing_tag() {
+        init_test_config();
+        let parsed = parse_inline(&tokenize("<span>Unclosed HTML"));
+        assert_eq!(
+            parsed,
+            vec![Text {
+                content: String::from("<span>Unclosed HTML")
+            }]
+        );
+        assert_eq!(parsed[0].to_html("", "", ""), "&lt;span&gt;Unclosed HTML");
+    }
+
+    #[test]
+    fn malformed_raw_html_mismatched_tags() {
+        init_test_config();
+        let parsed = parse_inline(&tokenize("<span>Unmatched </div> tags"));
+        assert_eq!(
+            parsed,
+            vec![Text {
+                content: String::from("<span>Unmatched </div> tags")
+            }]
+        );
+        assert_eq!(
+            parsed[0].to_html("", "", ""),
+            "&lt;span&gt;Unmatched &lt;/div&gt; tags"
+        );
+    }
+
+    #[test]
+    fn bare_email_is_autolinked() {
+        init_test_config();
+        assert_eq!(
+            autolink_emails(parse_inline(&tokenize(
+                "Contact user.name@example.com today."
+            ))),
+            vec![
+                Text {
+                    content: String::from("Contact ")
+                },
+                Link {
+                    text: vec![Text {
+                        content: String::from("user.name@example.com")
+                    }],
+                    title: None,
+                    url: String::from("mailto:user.name@example.com")
+                },
+                Text {
+                    content: String::from(" today.")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn bare_urls_are_autolinked() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize(
+                "See https://example.com/wiki/Rust_(language). (Or https://example.com)"
+            )),
+            vec![
+                Text {
+                    content: String::from("See ")
+                },
+                Link {
+                    text: vec![Text {
+                        content: String::from("https://example.com/wiki/Rust_(language)")
+                    }],
+                    title: None,
+                    url: String::from("https://example.com/wiki/Rust_(language)")
+                },
+                Text {
+                    content: String::from(". (Or ")
+                },
+                Link {
+                    text: vec![Text {
+                        content: String::from("https://example.com")
+                    }],
+                    title: None,
+                    url: String::from("https://example.com")
+                },
+                Text {
+                    content: String::from(")")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn angle_bracket_autolinks() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize(
+                "<https://example.com/a?b=c> or <user@example.com>."
+            )),
+            vec![
+                Link {
+                    text: vec![Text {
+                        content: String::from("https://example.com/a?b=c")
+                    }],
+                    title: None,
+                    url: String::from("https://example.com/a?b=c")
+                },
+                Text {
+                    content: String::from(" or ")
+                },
+                Link {
+                    text: vec![Text {
+                        content: String::from("user@example.com")
+                    }],
+                    title: None,
+                    url: String::from("mailto:user@example.com")
+                },
+                Text {
+                    content: String::from(".")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn handle_mention_is_not_autolinked() {
+        init_test_config();
+        assert_eq!(
+            autolink_emails(parse_inline(&tokenize("Follow me on twitter @user"))),
+            vec![Text {
+                content: String::from("Follow me on twitter @user")
+            }]
+        );
+    }
+
+    #[test]
+    fn email_in_html_attribute_is_not_autolinked() {
+        init_test_config();
+        assert_eq!(
+            autolink_emails(parse_inline(&tokenize(
+                "<a href=\"mailto:user@example.com\">Mail</a>"
+            ))),
+            vec![
+                MdInlineElement::RawHtml {
+                    content: String::from("<a href=\"mailto:user@example.com\">")
+                },
+                Text {
+                    content: String::from("Mail")
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("</a>")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn mentions_are_linkified_at_word_boundaries() {
+        init_test_config();
+        assert_eq!(
+            linkify_prefixed_words(
+                parse_inline(&tokenize("Thanks @zliel, not me@home or \\@escaped")),
+                '@',
+                "https://example.com/u/{name}"
+            ),
+            vec![
+                Text {
+                    content: String::from("Thanks ")
+                },
+                Link {
+                    text: vec![Text {
+                        content: String::from("@zliel")
+                    }],
+                    title: None,
+                    url: String::from("https://example.com/u/zliel")
+                },
+                Text {
+                    content: String::from(", not me@home or \\@escaped")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn hashtags_are_linkified_at_word_boundaries() {
+        init_test_config();
+        assert_eq!(
+            linkify_prefixed_words(
+                parse_inline(&tokenize(
+                    "Posted in #rust-lang (#markdown) and page#section"
+                )),
+                '#',
+                "/tags/{name}.html"
+            ),
+            vec![
+                Text {
+                    content: String::from("Posted in ")
+                },
+                Link {
+                    text: vec![Text {
+                        content: String::from("#rust-lang")
+                    }],
+                    title: None,
+                    url: String::from("/tags/rust-lang.html")
+                },
+                Text {
+                    content: String::from(" (")
+                },
+                Link {
+                    text: vec![Text {
+                        content: String::from("#markdown")
+                    }],
+                    title: None,
+                    url: String::from("/tags/markdown.html")
+                },
+                Text {
+                    content: String::from(") and page#section")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn smart_punctuation_converts_quotes_dashes_and_ellipses() {
+        init_test_config();
+        assert_eq!(
+            smarten_punctuation(
+                parse_inline(&tokenize(
+                    "\"Don't,\" she said -- 'wait'... 1990--2000---done"
+                )),
+                &mut None
+            ),
+            vec![Text {
+                content: String::from(
+                    "\u{201C}Don\u{2019}t,\u{201D} she said \u{2013} \u{2018}wait\u{2019}\u{2026} 1990\u{2013}2000\u{2014}done"
+                )
+            }]
+        );
+    }
+
+    #[test]
+    fn smart_punctuation_skips_code_and_html_attributes() {
+        init_test_config();
+        assert_eq!(
+            smarten_punctuation(
+                parse_inline(&tokenize(
+                    "**\"bold\"** `\"code\" --` <span title=\"a\"></span>"
+                )),
+                &mut None
+            ),
+            vec![
+                Bold {
+                    content: vec![Text {
+                        content: String::from("\u{201C}bold\u{201D}")
+                    }]
+                },
+                Text {
+                    content: String::from(" ")
+                },
+                Code {
+                    content: String::from("\"code\" --")
+                },
+                Text {
+                    content: String::from(" ")
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("<span title=\"a\">")
+                },
+                MdInlineElement::RawHtml {
+                    content: String::from("</span>")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn code_span_keeps_tabs_when_preserved() {
+        init_test_config();
+        let mut config = Config::default();
+        config.html.preserve_code_tabs = true;
+
+        assert_eq!(
+            crate::config::with_config(&config, || parse_inline(&tokenize("`a\tb`"))),
+            vec![Code {
+                content: String::from("a\tb")
+            }]
+        );
+    }
+
+    #[test]
+    fn inline_and_display_math() {
+        init_test_config();
+        assert_eq!(
+            with_math(|| parse_inline(&tokenize(r"Area $\pi r^2$ and $$a_b * c$$."))),
+            vec![
+                Text {
+                    content: String::from("Area ")
+                },
+                Math {
+                    content: String::from(r"\pi r^2"),
+                    display: false
+                },
+                Text {
+                    content: String::from(" and ")
+                },
+                Math {
+                    content: String::from("a_b * c"),
+                    display: true
+                },
+                Text {
+                    content: String::from(".")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn dollars_that_are_not_math_stay_literal() {
+        init_test_config();
+        for input in ["It costs $5 and $10.", "A $ b $ c", r"Escaped \$x$"] {
+            let expected = input.replace(r"\$", "$");
+            assert_eq!(
+                with_math(|| parse_inline(&tokenize(input))),
+                vec![Text { content: expected }],
+                "{input}"
+            );
+        }
+
+        // Math is only parsed when enabled
+        assert_eq!(
+            parse_inline(&tokenize("$x$")),
+            vec![Text {
+                content: String::from("$x$")
+            }]
+        );
+    }
+
+    #[test]
+    fn adjacent_text_is_merged() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("a b c")),
+            vec![Text {
+                content: String::from("a b c")
+            }]
+        );
+    }
+
+    #[test]
+    fn unmatched_delimiter_is_merged_with_text() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("a _b c")),
+            vec![Text {
+                content: String::from("a _b c")
+            }]
+        );
+    }
+
+    #[test]
+    fn peek_behind() {
+        let mut cursor = TokenCursor {
+            tokens: tokenize("a *b*"),
+            current_position: 0,
+        };
+        assert_eq!(cursor.peek_behind(1), None);
+
+        cursor.set_position(2);
+        assert_eq!(cursor.peek_behind(1), Some(&Token::Whitespace));
+        assert_eq!(cursor.peek_behind(2), Some(&Token::Text(String::from("a"))));
+        assert_eq!(cursor.peek_behind(3), None);
+    }
+
+    #[test]
+    fn intraword_delimiters_are_both_flanking() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("foo**bar**baz")),
+            vec![
+                Text {
+                    content: String::from("foo")
+                },
+                Bold {
+                    content: vec![Text {
+                        content: String::from("bar")
+                    }]
+                },
+                Text {
+                    content: String::from("baz")
+                }
+            ]
+        );
+        // Intraword underscores can't open or close emphasis
+        assert_eq!(
+            parse_inline(&tokenize("foo__bar__baz")),
+            vec![Text {
+                content: String::from("foo__bar__baz")
+            }]
+        );
+    }
+
+    #[test]
+    fn delimiters_between_whitespace_and_text() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize(" **bar** ")),
+            vec![
+                Text {
+                    content: String::from(" ")
+                },
+                Bold {
+                    content: vec![Text {
+                        content: String::from("bar")
+                    }]
+                },
+                Text {
+                    content: String::from(" ")
+                }
+            ]
+        );
+        for markdown in ["** bar**", "**bar **"] {
+            assert_eq!(
+                parse_inline(&tokenize(markdown)),
+                vec![Text {
+                    content: markdown.to_string()
+                }],
+                "{markdown}"
+            );
+        }
+        assert!(
+            !parse_inline(&tokenize("**bar\t**"))
+                .iter()
+                .any(|element| matches!(element, Bold { .. }))
+        );
+    }
+
+    #[test]
+    fn unicode_whitespace_and_punctuation_affect_flanking() {
+        init_test_config();
+        // A non-breaking space is whitespace, even though it's part of a text token
+        assert_eq!(
+            parse_inline(&tokenize("*\u{a0}a\u{a0}*")),
+            vec![Text {
+                content: String::from("*\u{a0}a\u{a0}*")
+            }]
+        );
+        // `=` is a symbol, so a run after a letter and before it isn't left-flanking
+        assert_eq!(
+            parse_inline(&tokenize("a**=b**")),
+            vec![Text {
+                content: String::from("a**=b**")
+            }]
+        );
+        // Punctuation on both sides of a run still lets it open emphasis
+        assert_eq!(
+            parse_inline(&tokenize("(**«b»**)")),
+            vec![
+                Text {
+                    content: String::from("(")
+                },
+                Bold {
+                    content: vec![Text {
+                        content: String::from("«b»")
+                    }]
+                },
+                Text {
+                    content: String::from(")")
+                }
+            ]
+        );
+    }
+}
+
+mod block {
+    use crate::{
+        parser::{group_lines, group_lines_to_blocks, parse_blocks},
+        types::{MdTableCell, TableAlignment},
+    };
+
+    use super::*;
+
+    #[test]
+    fn heading() {
+        init_test_config();
+        assert_eq!(
+            parse_block(&tokenize("# Heading 1")),
+            Some(Header {
+                level: 1,
+                content: vec![Text {
+                    content: String::from("Heading 1")
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn code_tabs_are_expanded_or_preserved() {
+        init_test_config();
+        let code_lines = |config: &Config| {
+            let lines = ["```make", "all:", "\tgo build", "    @echo `\tdone`", "```"]
+                .iter()
+                .map(|line| tokenize(line))
+                .collect();
+            match crate::config::with_config(config, || parse_blocks(&group_lines_to_blocks(lines)))
+                .pop()
+            {
+                Some(CodeBlock { lines, .. }) => lines,
+                other => panic!("Expected a code block, got {other:?}"),
+            }
+        };
+
+        let mut config = Config::default();
+        assert_eq!(
+            code_lines(&config),
+            vec!["all:", "    go build", "    @echo `    done`"]
+        );
+
+        config.html.code_tab_size = 2;
+        assert_eq!(
+            code_lines(&config),
+            vec!["all:", "  go build", "    @echo `  done`"]
+        );
+
+        config.html.preserve_code_tabs = true;
+        assert_eq!(
+            code_lines(&config),
+            vec!["all:", "\tgo build", "    @echo `\tdone`"]
+        );
+    }
+
+    #[test]
+    fn display_math_block() {
+        init_test_config();
+        let lines = ["$$", r"\int_0^1 x\,dx", "  = *1/2*", "$$", "", "After"]
+            .iter()
+            .map(|line| tokenize(line))
+            .collect();
+
+        assert_eq!(
+            with_math(|| parse_blocks(&group_lines_to_blocks(lines))),
+            vec![
+                MathBlock {
+                    content: String::from("\\int_0^1 x\\,dx\n  = *1/2*")
+                },
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("After")
+                    }]
+                }
+            ]
         );
     }
 
     #[test]
-    fn malformed_raw_html_mismatched_tags() {
+    fn setext_headings_enabled() {
         init_test_config();
         assert_eq!(
-            parse_inline(&tokenize("<span>Unmatched </div> tags")),
-            vec![Text {
-                content: String::from("<span>Unmatched </div> tags")
-            }]
+            parse_blocks(&group_lines(
+                vec![
+                    tokenize("Heading 1"),
+                    tokenize("==="),
+                    tokenize(""),
+                    tokenize("Heading 2"),
+                    tokenize("---")
+                ],
+                true
+            )),
+            vec![
+                Header {
+                    level: 1,
+                    content: vec![Text {
+                        content: String::from("Heading 1")
+                    }]
+                },
+                Header {
+                    level: 2,
+                    content: vec![Text {
+                        content: String::from("Heading 2")
+                    }]
+                }
+            ]
         );
     }
-}
 
-mod block {
-    use crate::{
-        parser::{group_lines_to_blocks, parse_blocks},
-        types::{MdTableCell, TableAlignment},
-    };
+    #[test]
+    fn setext_headings_disabled() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines(
+                vec![
+                    tokenize("Not a heading"),
+                    tokenize("==="),
+                    tokenize(""),
+                    tokenize("Also not a heading"),
+                    tokenize("---")
+                ],
+                false
+            )),
+            vec![
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("Not a heading ===")
+                    }]
+                },
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("Also not a heading")
+                    }]
+                },
+                ThematicBreak
+            ]
+        );
+    }
 
-    use super::*;
+    #[test]
+    fn thematic_breaks() {
+        init_test_config();
+        for line in ["---", "***", "___", "- - -", " * * * *", "_____"] {
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![tokenize(line)])),
+                vec![ThematicBreak],
+                "{line}"
+            );
+        }
+    }
 
     #[test]
-    fn heading() {
+    fn only_dashed_breaks_underline_setext_headings() {
         init_test_config();
         assert_eq!(
-            parse_block(&tokenize("# Heading 1")),
-            Some(Header {
-                level: 1,
-                content: vec![Text {
-                    content: String::from("Heading 1")
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Not a heading"),
+                tokenize("***")
+            ])),
+            vec![
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("Not a heading")
+                    }]
+                },
+                ThematicBreak
+            ]
+        );
+    }
+
+    #[test]
+    fn lone_dash_is_an_empty_list_item() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![tokenize("-")])),
+            vec![UnorderedList {
+                loose: false,
+                items: vec![MdListItem {
+                    content: Paragraph { content: vec![] },
+                    checked: None
                 }]
-            })
+            }]
+        );
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Not a heading"),
+                tokenize(""),
+                tokenize("-")
+            ]))[1],
+            UnorderedList {
+                loose: false,
+                items: vec![MdListItem {
+                    content: Paragraph { content: vec![] },
+                    checked: None
+                }]
+            }
         );
     }
 
@@ -541,20 +1702,71 @@ mod block {
         );
     }
 
+    fn paragraphs(markdown: &str) -> Vec<MdBlockElement> {
+        parse_blocks(&group_lines_to_blocks(
+            markdown.split('\n').map(tokenize).collect(),
+        ))
+    }
+
+    fn paragraph_of(text: &str) -> MdBlockElement {
+        Paragraph {
+            content: vec![Text {
+                content: String::from(text),
+            }],
+        }
+    }
+
     #[test]
-    fn multiline_paragraphs() {
+    fn blank_lines_separate_paragraphs() {
         init_test_config();
+        assert_eq!(paragraphs("a\nb"), vec![paragraph_of("a b")]);
         assert_eq!(
-            parse_block(&tokenize("First line.\nSecond line.")),
-            Some(Paragraph {
+            paragraphs("a\n\nb"),
+            vec![paragraph_of("a"), paragraph_of("b")]
+        );
+        assert_eq!(
+            paragraphs("a\n\n\n\nb\nc"),
+            vec![paragraph_of("a"), paragraph_of("b c")]
+        );
+    }
+
+    #[test]
+    fn whitespace_only_lines_separate_paragraphs() {
+        init_test_config();
+        assert_eq!(
+            paragraphs("a\n  \nb\n\t\nc"),
+            vec![paragraph_of("a"), paragraph_of("b"), paragraph_of("c")]
+        );
+    }
+
+    #[test]
+    fn hard_breaks_stay_in_one_paragraph() {
+        init_test_config();
+        assert_eq!(
+            paragraphs("a  \nb"),
+            vec![Paragraph {
                 content: vec![
                     Text {
-                        content: String::from("First line.")
+                        content: String::from("a")
                     },
+                    LineBreak,
                     Text {
-                        content: String::from("Second line.")
+                        content: String::from("b")
                     }
                 ]
+            }]
+        );
+    }
+
+    #[test]
+    fn multiline_paragraphs() {
+        init_test_config();
+        assert_eq!(
+            parse_block(&tokenize("First line.\nSecond line.")),
+            Some(Paragraph {
+                content: vec![Text {
+                    content: String::from("First line.Second line.")
+                }]
             })
         );
     }
@@ -657,7 +1869,9 @@ mod block {
                     Image {
                         alt_text: String::from("an image"),
                         title: None,
-                        url: String::from("http://example.com/image.png")
+                        url: String::from("http://example.com/image.png"),
+                        width: None,
+                        height: None,
                     },
                     Text {
                         content: String::from(" and ")
@@ -728,7 +1942,9 @@ mod block {
                         Image {
                             alt_text: String::from("an image"),
                             title: None,
-                            url: String::from("http://example.com/image.png")
+                            url: String::from("http://example.com/image.png"),
+                            width: None,
+                            height: None,
                         },
                         Text {
                             content: String::from(" and some ")
@@ -749,6 +1965,8 @@ mod block {
                 },
                 CodeBlock {
                     language: Some(String::from("rust")),
+                    info: String::new(),
+                    attributes: CodeBlockAttributes::default(),
                     lines: vec![
                         String::from("fn main() {"),
                         String::from("    println!(\"Hello, world!\");"),
@@ -759,6 +1977,338 @@ mod block {
         )
     }
 
+    #[test]
+    fn task_list() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("- [ ] Todo"),
+                tokenize("- [x] Done"),
+                tokenize("- [X] Also done"),
+                tokenize("- [] Not a task")
+            ])),
+            vec![UnorderedList {
+                loose: false,
+                items: vec![
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("Todo")
+                            }]
+                        },
+                        checked: Some(false)
+                    },
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("Done")
+                            }]
+                        },
+                        checked: Some(true)
+                    },
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("Also done")
+                            }]
+                        },
+                        checked: Some(true)
+                    },
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("[] Not a task")
+                            }]
+                        },
+                        checked: None
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn autolink_at_line_start_is_paragraph() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![tokenize(
+                "<https://example.com>"
+            )])),
+            vec![Paragraph {
+                content: vec![Link {
+                    text: vec![Text {
+                        content: String::from("https://example.com")
+                    }],
+                    title: None,
+                    url: String::from("https://example.com")
+                }]
+            }]
+        );
+    }
+
+    #[test]
+    fn blank_separated_items_make_a_loose_list() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("1. First"),
+                tokenize(""),
+                tokenize("2. Second"),
+                tokenize(""),
+                tokenize("Not in the list")
+            ])),
+            vec![
+                OrderedList {
+                    starting_num: 1,
+                    kind: ListMarkerKind::Numeric,
+                    loose: true,
+                    items: vec![
+                        MdListItem {
+                            content: Paragraph {
+                                content: vec![Text {
+                                    content: String::from("First")
+                                }]
+                            },
+                            checked: None
+                        },
+                        MdListItem {
+                            content: Paragraph {
+                                content: vec![Text {
+                                    content: String::from("Second")
+                                }]
+                            },
+                            checked: None
+                        }
+                    ]
+                },
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("Not in the list")
+                    }]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn footnotes() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Defined[^Note] and undefined[^missing]."),
+                tokenize(""),
+                tokenize("[^note]: The *note*."),
+            ])),
+            vec![
+                Paragraph {
+                    content: vec![
+                        Text {
+                            content: String::from("Defined")
+                        },
+                        FootnoteReference {
+                            label: String::from("note")
+                        },
+                        Text {
+                            content: String::from(" and undefined[^missing].")
+                        }
+                    ]
+                },
+                FootnoteDefinition {
+                    label: String::from("note"),
+                    content: vec![
+                        Text {
+                            content: String::from("The ")
+                        },
+                        Italic {
+                            content: vec![Text {
+                                content: String::from("note")
+                            }]
+                        },
+                        Text {
+                            content: String::from(".")
+                        }
+                    ]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn definition_lists() {
+        init_test_config();
+        let text = |content: &str| {
+            vec![Text {
+                content: String::from(content),
+            }]
+        };
+
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Apple"),
+                tokenize(": A fruit"),
+                tokenize(": A company"),
+                tokenize("Orange"),
+                tokenize("Tangerine"),
+                tokenize(":   Citrus fruits"),
+                tokenize("After the list"),
+            ])),
+            vec![
+                DefinitionList {
+                    items: vec![
+                        MdDefinitionItem {
+                            terms: vec![text("Apple")],
+                            definitions: vec![text("A fruit"), text("A company")]
+                        },
+                        MdDefinitionItem {
+                            terms: vec![text("Orange"), text("Tangerine")],
+                            definitions: vec![text("Citrus fruits")]
+                        }
+                    ]
+                },
+                Paragraph {
+                    content: text("After the list")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn definition_without_term_is_paragraph() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("# Heading"),
+                tokenize(": Not a definition"),
+            ])),
+            vec![
+                Header {
+                    level: 1,
+                    content: vec![Text {
+                        content: String::from("Heading")
+                    }]
+                },
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from(": Not a definition")
+                    }]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn hard_line_breaks() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Two spaces  "),
+                tokenize("Backslash\\"),
+                tokenize("Escaped backslash\\\\"),
+                tokenize("Last line  "),
+            ])),
+            vec![Paragraph {
+                content: vec![
+                    Text {
+                        content: String::from("Two spaces")
+                    },
+                    LineBreak,
+                    Text {
+                        content: String::from("Backslash")
+                    },
+                    LineBreak,
+                    Text {
+                        content: String::from("Escaped backslash\\\\ Last line  ")
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn reference_links() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("See [the docs][Docs  Page], [docs page][] and [Docs Page]."),
+                tokenize(""),
+                tokenize("[docs page]: https://example.com \"The Docs\""),
+            ])),
+            vec![Paragraph {
+                content: vec![
+                    Text {
+                        content: String::from("See ")
+                    },
+                    Link {
+                        text: vec![Text {
+                            content: String::from("the docs")
+                        }],
+                        title: Some(String::from("The Docs")),
+                        url: String::from("https://example.com")
+                    },
+                    Text {
+                        content: String::from(", ")
+                    },
+                    Link {
+                        text: vec![Text {
+                            content: String::from("docs page")
+                        }],
+                        title: Some(String::from("The Docs")),
+                        url: String::from("https://example.com")
+                    },
+                    Text {
+                        content: String::from(" and ")
+                    },
+                    Link {
+                        text: vec![Text {
+                            content: String::from("Docs Page")
+                        }],
+                        title: Some(String::from("The Docs")),
+                        url: String::from("https://example.com")
+                    },
+                    Text {
+                        content: String::from(".")
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn reference_image() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("![Logo][logo]"),
+                tokenize(""),
+                tokenize("[logo]: <./logo.png>"),
+            ])),
+            vec![Paragraph {
+                content: vec![Image {
+                    alt_text: String::from("Logo"),
+                    title: None,
+                    url: String::from("./logo.png"),
+                    width: None,
+                    height: None,
+                }]
+            }]
+        );
+    }
+
+    #[test]
+    fn undefined_reference_is_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![tokenize(
+                "An [undefined] reference"
+            )])),
+            vec![Paragraph {
+                content: vec![Text {
+                    content: String::from("An [undefined] reference")
+                }]
+            }]
+        );
+    }
+
     #[test]
     fn unordered_list() {
         init_test_config();
@@ -768,20 +2318,23 @@ mod block {
                 tokenize("- Item 2")
             ])),
             vec![UnorderedList {
+                loose: false,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 1")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 2")
                             }]
-                        }
+                        },
+                        checked: None
                     }
                 ]
             }]
@@ -799,40 +2352,47 @@ mod block {
                 tokenize("- Item 2")
             ])),
             vec![UnorderedList {
+                loose: false,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 1")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: UnorderedList {
+                            loose: false,
                             items: vec![
                                 MdListItem {
                                     content: Paragraph {
                                         content: vec![Text {
                                             content: String::from("Nested Item 1.1")
                                         }]
-                                    }
+                                    },
+                                    checked: None
                                 },
                                 MdListItem {
                                     content: Paragraph {
                                         content: vec![Text {
                                             content: String::from("Nested Item 1.2")
                                         }]
-                                    }
+                                    },
+                                    checked: None
                                 }
                             ]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 2")
                             }]
-                        }
+                        },
+                        checked: None
                     }
                 ]
             }]
@@ -848,20 +2408,23 @@ mod block {
                 tokenize("* Item 2")
             ])),
             vec![UnorderedList {
+                loose: false,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 1")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 2")
                             }]
-                        }
+                        },
+                        checked: None
                     }
                 ]
             }]
@@ -879,40 +2442,47 @@ mod block {
                 tokenize("* Item 2")
             ])),
             vec![UnorderedList {
+                loose: false,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 1")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: UnorderedList {
+                            loose: false,
                             items: vec![
                                 MdListItem {
                                     content: Paragraph {
                                         content: vec![Text {
                                             content: String::from("Nested Item 1.1")
                                         }]
-                                    }
+                                    },
+                                    checked: None
                                 },
                                 MdListItem {
                                     content: Paragraph {
                                         content: vec![Text {
                                             content: String::from("Nested Item 1.2")
                                         }]
-                                    }
+                                    },
+                                    checked: None
                                 }
                             ]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 2")
                             }]
-                        }
+                        },
+                        checked: None
                     }
                 ]
             }]
@@ -929,21 +2499,25 @@ mod block {
             ])),
             vec![
                 UnorderedList {
+                    loose: false,
                     items: vec![MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 1")
                             }]
-                        }
+                        },
+                        checked: None
                     }]
                 },
                 UnorderedList {
+                    loose: false,
                     items: vec![MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("New List")
                             }]
-                        }
+                        },
+                        checked: None
                     }]
                 }
             ]
@@ -962,43 +2536,51 @@ mod block {
             ])),
             vec![
                 UnorderedList {
+                    loose: false,
                     items: vec![
                         MdListItem {
                             content: Paragraph {
                                 content: vec![Text {
                                     content: String::from("Item 1")
                                 }]
-                            }
+                            },
+                            checked: None
                         },
                         MdListItem {
                             content: UnorderedList {
+                                loose: false,
                                 items: vec![
                                     MdListItem {
                                         content: Paragraph {
                                             content: vec![Text {
                                                 content: String::from("Nested Item 1.1")
                                             }]
-                                        }
+                                        },
+                                        checked: None
                                     },
                                     MdListItem {
                                         content: Paragraph {
                                             content: vec![Text {
                                                 content: String::from("Nested Item 1.2")
                                             }]
-                                        }
+                                        },
+                                        checked: None
                                     }
                                 ]
-                            }
+                            },
+                            checked: None
                         },
                     ]
                 },
                 UnorderedList {
+                    loose: false,
                     items: vec![MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 2")
                             }]
-                        }
+                        },
+                        checked: None
                     }]
                 }
             ]
@@ -1016,7 +2598,9 @@ mod block {
                 tokenize("4. ![Image Item 4](http://example.com/image.png)"),
             ])),
             vec![OrderedList {
+                loose: false,
                 starting_num: 1,
+                kind: ListMarkerKind::Numeric,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
@@ -1025,7 +2609,8 @@ mod block {
                                     content: String::from("Bold Item 1")
                                 }]
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
@@ -1034,7 +2619,8 @@ mod block {
                                     content: String::from("Italic Item 2")
                                 }]
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
@@ -1045,16 +2631,20 @@ mod block {
                                 title: None,
                                 url: String::from("http://example.com")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Image {
                                 alt_text: String::from("Image Item 4"),
                                 title: None,
-                                url: String::from("http://example.com/image.png")
+                                url: String::from("http://example.com/image.png"),
+                                width: None,
+                                height: None,
                             }]
-                        }
+                        },
+                        checked: None
                     }
                 ]
             }]
@@ -1070,21 +2660,25 @@ mod block {
                 tokenize("2. Second")
             ])),
             vec![OrderedList {
+                loose: false,
                 starting_num: 1,
+                kind: ListMarkerKind::Numeric,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("First")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Second")
                             }]
-                        }
+                        },
+                        checked: None
                     }
                 ]
             }]
@@ -1102,48 +2696,116 @@ mod block {
                 tokenize("2. Item 2")
             ])),
             vec![OrderedList {
+                loose: false,
                 starting_num: 1,
+                kind: ListMarkerKind::Numeric,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 1")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: OrderedList {
+                            loose: false,
                             starting_num: 1,
+                            kind: ListMarkerKind::Numeric,
                             items: vec![
                                 MdListItem {
                                     content: Paragraph {
                                         content: vec![Text {
                                             content: String::from("Nested Item 1.1")
                                         }]
-                                    }
+                                    },
+                                    checked: None
                                 },
                                 MdListItem {
                                     content: Paragraph {
                                         content: vec![Text {
                                             content: String::from("Nested Item 1.2")
                                         }]
-                                    }
+                                    },
+                                    checked: None
                                 }
                             ]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Item 2")
                             }]
-                        }
+                        },
+                        checked: None
                     }
                 ]
             }]
         );
     }
 
+    #[test]
+    fn ordered_list_with_alpha_markers() {
+        init_test_config();
+        let line = |marker: &str, text: &str| {
+            vec![
+                Token::OrderedListMarker(String::from(marker), ListMarkerKind::LowerAlpha, '.'),
+                Token::Whitespace,
+                Token::Text(String::from(text)),
+            ]
+        };
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                line("c", "Third"),
+                line("d", "Fourth")
+            ])),
+            vec![OrderedList {
+                loose: false,
+                starting_num: 3,
+                kind: ListMarkerKind::LowerAlpha,
+                items: vec![
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("Third")
+                            }]
+                        },
+                        checked: None
+                    },
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("Fourth")
+                            }]
+                        },
+                        checked: None
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn ordered_list_with_roman_markers() {
+        init_test_config();
+        assert!(matches!(
+            parse_block(&[
+                Token::OrderedListMarker(String::from("IV"), ListMarkerKind::UpperRoman, '.'),
+                Token::Whitespace,
+                Token::Text(String::from("Fourth")),
+            ]),
+            Some(OrderedList {
+                loose: false,
+                starting_num: 4,
+                kind: ListMarkerKind::UpperRoman,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn ordered_list_with_different_starting_num() {
         init_test_config();
@@ -1153,21 +2815,25 @@ mod block {
                 tokenize("6. Sixth Item")
             ])),
             vec![OrderedList {
+                loose: false,
                 starting_num: 5,
+                kind: ListMarkerKind::Numeric,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Fifth Item")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Text {
                                 content: String::from("Sixth Item")
                             }]
-                        }
+                        },
+                        checked: None
                     }
                 ]
             }]
@@ -1185,7 +2851,9 @@ mod block {
                 tokenize("4. ![Image Item 4](http://example.com/image.png \"Some title\")"),
             ])),
             vec![OrderedList {
+                loose: false,
                 starting_num: 1,
+                kind: ListMarkerKind::Numeric,
                 items: vec![
                     MdListItem {
                         content: Paragraph {
@@ -1194,7 +2862,8 @@ mod block {
                                     content: String::from("Bold Item 1")
                                 }]
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
@@ -1203,7 +2872,8 @@ mod block {
                                     content: String::from("Italic Item 2")
                                 }]
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
@@ -1214,35 +2884,159 @@ mod block {
                                 title: None,
                                 url: String::from("http://example.com")
                             }]
-                        }
+                        },
+                        checked: None
                     },
                     MdListItem {
                         content: Paragraph {
                             content: vec![Image {
                                 alt_text: String::from("Image Item 4"),
                                 title: Some(String::from("Some title")),
-                                url: String::from("http://example.com/image.png")
+                                url: String::from("http://example.com/image.png"),
+                                width: None,
+                                height: None,
                             }]
-                        }
+                        },
+                        checked: None
+                    }
+                ]
+            }]
+        )
+    }
+
+    #[test]
+    fn blockquote() {
+        init_test_config();
+        assert_eq!(
+            parse_block(&tokenize("> This is a blockquote.")),
+            Some(BlockQuote {
+                content: vec![Paragraph {
+                    content: vec![Text {
+                        content: String::from("This is a blockquote.")
+                    }]
+                }],
+                citation: None
+            })
+        );
+    }
+
+    #[test]
+    fn blockquote_with_multiple_paragraphs() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> First paragraph"),
+                tokenize(">"),
+                tokenize("> Second paragraph"),
+                tokenize("> "),
+                tokenize("> Third paragraph"),
+            ])),
+            vec![BlockQuote {
+                content: vec![
+                    Paragraph {
+                        content: vec![Text {
+                            content: String::from("First paragraph")
+                        }]
+                    },
+                    Paragraph {
+                        content: vec![Text {
+                            content: String::from("Second paragraph")
+                        }]
+                    },
+                    Paragraph {
+                        content: vec![Text {
+                            content: String::from("Third paragraph")
+                        }]
+                    }
+                ],
+                citation: None
+            }]
+        );
+    }
+
+    #[test]
+    fn blank_line_ends_blockquote() {
+        init_test_config();
+        let quote = |text: &str| BlockQuote {
+            content: vec![Paragraph {
+                content: vec![Text {
+                    content: String::from(text),
+                }],
+            }],
+            citation: None,
+        };
+
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> First quote"),
+                tokenize(""),
+                tokenize("> Second quote"),
+            ])),
+            vec![quote("First quote"), quote("Second quote")]
+        );
+    }
+
+    #[test]
+    fn blockquote_with_citation() {
+        init_test_config();
+        assert_eq!(
+            with_citations(|| parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> Simplicity is prerequisite for reliability."),
+                tokenize("> -- Edsger *Dijkstra*")
+            ]))),
+            vec![BlockQuote {
+                content: vec![Paragraph {
+                    content: vec![Text {
+                        content: String::from("Simplicity is prerequisite for reliability.")
+                    }]
+                }],
+                citation: Some(vec![
+                    Text {
+                        content: String::from("Edsger ")
+                    },
+                    Italic {
+                        content: vec![Text {
+                            content: String::from("Dijkstra")
+                        }]
+                    }
+                ])
+            }]
+        );
+    }
+
+    #[test]
+    fn blockquote_citations_disabled_by_default() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> A quote."),
+                tokenize("> -- Someone")
+            ])),
+            vec![BlockQuote {
+                content: vec![
+                    Paragraph {
+                        content: vec![Text {
+                            content: String::from("A quote.")
+                        }]
+                    },
+                    Paragraph {
+                        content: vec![Text {
+                            content: String::from("-- Someone")
+                        }]
                     }
-                ]
+                ],
+                citation: None
             }]
-        )
+        );
     }
 
     #[test]
-    fn blockquote() {
+    fn blockquote_with_only_citation_marker() {
         init_test_config();
-        assert_eq!(
-            parse_block(&tokenize("> This is a blockquote.")),
-            Some(BlockQuote {
-                content: vec![Paragraph {
-                    content: vec![Text {
-                        content: String::from("This is a blockquote.")
-                    }]
-                }]
-            })
-        );
+        assert!(matches!(
+            with_citations(|| parse_block(&tokenize("> -- Not a citation"))),
+            Some(BlockQuote { citation: None, .. })
+        ));
     }
 
     #[test]
@@ -1262,28 +3056,122 @@ mod block {
                         }]
                     },
                     UnorderedList {
+                        loose: false,
                         items: vec![
                             MdListItem {
                                 content: Paragraph {
                                     content: vec![Text {
                                         content: String::from("Item 1")
                                     }]
-                                }
+                                },
+                                checked: None
                             },
                             MdListItem {
                                 content: Paragraph {
                                     content: vec![Text {
                                         content: String::from("Item 2")
                                     }]
-                                }
+                                },
+                                checked: None
                             }
                         ]
                     }
+                ],
+                citation: None
+            }]
+        );
+    }
+
+    #[test]
+    fn details_block() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> [!Details] Click *me*"),
+                tokenize("> Hidden text"),
+            ])),
+            vec![Details {
+                summary: vec![
+                    Text {
+                        content: String::from("Click ")
+                    },
+                    Italic {
+                        content: vec![Text {
+                            content: String::from("me")
+                        }]
+                    }
+                ],
+                content: vec![Paragraph {
+                    content: vec![Text {
+                        content: String::from("Hidden text")
+                    }]
+                }]
+            }]
+        );
+    }
+
+    #[test]
+    fn nested_details_blocks() {
+        init_test_config();
+        let text = |content: &str| Paragraph {
+            content: vec![Text {
+                content: String::from(content),
+            }],
+        };
+
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> [!details]"),
+                tokenize("> Outer text"),
+                tokenize(">"),
+                tokenize("> > [!details] Inner"),
+                tokenize("> > Inner text"),
+            ])),
+            vec![Details {
+                summary: vec![Text {
+                    content: String::from("Details")
+                }],
+                content: vec![
+                    text("Outer text"),
+                    Details {
+                        summary: vec![Text {
+                            content: String::from("Inner")
+                        }],
+                        content: vec![text("Inner text")]
+                    }
                 ]
             }]
         );
     }
 
+    #[test]
+    fn callout() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> [!warning]"),
+                tokenize("> Be careful"),
+            ])),
+            vec![Callout {
+                kind: CalloutKind::Warning,
+                content: vec![Paragraph {
+                    content: vec![Text {
+                        content: String::from("Be careful")
+                    }]
+                }]
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_callout_type_is_blockquote() {
+        init_test_config();
+        assert!(matches!(
+            parse_block(&tokenize("> [!UNKNOWN] Some text")),
+            Some(BlockQuote { .. })
+        ));
+    }
+
     #[test]
     fn code_block() {
         init_test_config();
@@ -1291,6 +3179,8 @@ mod block {
             parse_block(&tokenize("```\ncode block\n```")),
             Some(CodeBlock {
                 language: None,
+                info: String::new(),
+                attributes: CodeBlockAttributes::default(),
                 lines: vec![String::from("code block")]
             })
         );
@@ -1303,17 +3193,155 @@ mod block {
             parse_block(&tokenize("```rust\nfn main() {}\n```")),
             Some(CodeBlock {
                 language: Some(String::from("rust")),
+                info: String::new(),
+                attributes: CodeBlockAttributes::default(),
+                lines: vec![String::from("fn main() {}")]
+            })
+        );
+    }
+
+    #[test]
+    fn fenced_code_block_with_odd_info_string() {
+        init_test_config();
+        assert_eq!(
+            parse_block(&tokenize("```text {weird} [x](y) *a*\nplain\n```")),
+            Some(CodeBlock {
+                language: Some(String::from("text")),
+                info: String::from("{weird} [x](y) *a*"),
+                attributes: CodeBlockAttributes::default(),
+                lines: vec![String::from("plain")]
+            })
+        );
+    }
+
+    #[test]
+    fn fenced_code_block_with_info_but_no_language() {
+        init_test_config();
+        assert_eq!(
+            parse_block(&tokenize("``` {weird}\ncode\n```")),
+            Some(CodeBlock {
+                language: None,
+                info: String::from("{weird}"),
+                attributes: CodeBlockAttributes {
+                    data: vec![(String::from("weird"), String::new())],
+                    ..Default::default()
+                },
+                lines: vec![String::from("code")]
+            })
+        );
+    }
+
+    #[test]
+    fn fenced_code_block_with_attributes() {
+        init_test_config();
+        assert_eq!(
+            parse_block(&tokenize(
+                "```rust {#demo .line-numbers highlight=2-4 title=\"Hello world\"}\nfn main() {}\n```"
+            )),
+            Some(CodeBlock {
+                language: Some(String::from("rust")),
+                info: String::from("{#demo .line-numbers highlight=2-4 title=\"Hello world\"}"),
+                attributes: CodeBlockAttributes {
+                    id: Some(String::from("demo")),
+                    classes: vec![String::from("line-numbers")],
+                    data: vec![
+                        (String::from("highlight"), String::from("2-4")),
+                        (String::from("title"), String::from("Hello world"))
+                    ]
+                },
                 lines: vec![String::from("fn main() {}")]
             })
         );
     }
 
+    #[test]
+    fn tilde_fenced_code_block() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(
+                ["~~~rust", "let a = 1;", "```", "let b = 2;", "~~~"]
+                    .iter()
+                    .map(|line| tokenize(line))
+                    .collect()
+            )),
+            vec![CodeBlock {
+                language: Some(String::from("rust")),
+                info: String::new(),
+                attributes: CodeBlockAttributes::default(),
+                lines: vec![
+                    String::from("let a = 1;"),
+                    String::from("```"),
+                    String::from("let b = 2;")
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn tilde_fence_inside_backtick_block_stays_literal() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(
+                ["````", "~~~", "```", "````", "after"]
+                    .iter()
+                    .map(|line| tokenize(line))
+                    .collect()
+            )),
+            vec![
+                CodeBlock {
+                    language: None,
+                    info: String::new(),
+                    attributes: CodeBlockAttributes::default(),
+                    lines: vec![String::from("~~~"), String::from("```")]
+                },
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("after")
+                    }]
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn ignore_directive_passes_region_through() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Before"),
+                tokenize("<!-- markrs:ignore-start -->"),
+                tokenize("<div id=\"widget\">"),
+                tokenize("# Not a *heading*"),
+                tokenize("</div>"),
+                tokenize("<!-- markrs:ignore-end -->"),
+                tokenize("After")
+            ])),
+            vec![
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("Before")
+                    }]
+                },
+                MdBlockElement::RawHtml {
+                    content: String::from(
+                        "<!-- markrs:ignore-start -->\n<div id=\"widget\">\n# Not a *heading*\n</div>\n<!-- markrs:ignore-end -->"
+                    )
+                },
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("After")
+                    }]
+                }
+            ]
+        );
+    }
+
     #[test]
     fn raw_html_basic() {
         init_test_config();
         assert_eq!(
             parse_block(&tokenize("<div>Raw HTML content</div>")),
-            Some(RawHtml {
+            Some(MdBlockElement::RawHtml {
                 content: String::from("<div>Raw HTML content</div>")
             })
         );
@@ -1324,7 +3352,7 @@ mod block {
         init_test_config();
         assert_eq!(
             parse_block(&tokenize("<img src=\"image.png\" alt=\"Image\"/>")),
-            Some(RawHtml {
+            Some(MdBlockElement::RawHtml {
                 content: String::from("<img src=\"image.png\" alt=\"Image\"/>")
             })
         );
@@ -1336,9 +3364,23 @@ mod block {
         assert_eq!(
             parse_block(&tokenize("This is <span>inline HTML</span> content.")),
             Some(Paragraph {
-                content: vec![Text {
-                    content: String::from("This is <span>inline HTML</span> content.")
-                }]
+                content: vec![
+                    Text {
+                        content: String::from("This is ")
+                    },
+                    MdInlineElement::RawHtml {
+                        content: String::from("<span>")
+                    },
+                    Text {
+                        content: String::from("inline HTML")
+                    },
+                    MdInlineElement::RawHtml {
+                        content: String::from("</span>")
+                    },
+                    Text {
+                        content: String::from(" content.")
+                    }
+                ]
             })
         );
     }
@@ -1353,9 +3395,19 @@ mod block {
             Some(Paragraph {
                 content: vec![
                     Text {
-                        content: String::from(
-                            "This is a paragraph with strong <strong>HTML</strong> and "
-                        )
+                        content: String::from("This is a paragraph with strong ")
+                    },
+                    MdInlineElement::RawHtml {
+                        content: String::from("<strong>")
+                    },
+                    Text {
+                        content: String::from("HTML")
+                    },
+                    MdInlineElement::RawHtml {
+                        content: String::from("</strong>")
+                    },
+                    Text {
+                        content: String::from(" and ")
                     },
                     Bold {
                         content: vec![Text {
@@ -1373,14 +3425,19 @@ mod block {
     #[test]
     fn malformed_raw_html_no_closing_bracket() {
         init_test_config();
+        let parsed = parse_block(&tokenize("<div Malformed HTML"));
         assert_eq!(
-            parse_block(&tokenize("<div Malformed HTML")),
+            parsed,
             Some(Paragraph {
                 content: vec![Text {
                     content: String::from("<div Malformed HTML")
                 }]
             })
         );
+        assert_eq!(
+            parsed.unwrap().to_html("", "", ""),
+            "<p>&lt;div Malformed HTML</p>"
+        );
     }
 
     #[test]
@@ -1388,7 +3445,7 @@ mod block {
         init_test_config();
         assert_eq!(
             parse_block(&tokenize("<div>Unclosed HTML")),
-            Some(RawHtml {
+            Some(MdBlockElement::RawHtml {
                 content: String::from("<div>Unclosed HTML")
             })
         );
@@ -1399,7 +3456,7 @@ mod block {
         init_test_config();
         assert_eq!(
             parse_block(&tokenize("<div>Unmatched </span> tags")),
-            Some(RawHtml {
+            Some(MdBlockElement::RawHtml {
                 content: String::from("<div>Unmatched </span> tags")
             })
         );
@@ -1714,7 +3771,9 @@ mod block {
                                 Image {
                                     alt_text: String::from("Image"),
                                     title: None,
-                                    url: String::from("http://example.com/image.png")
+                                    url: String::from("http://example.com/image.png"),
+                                    width: None,
+                                    height: None,
                                 },
                                 Text {
                                     content: " ".to_string()
@@ -1834,16 +3893,165 @@ mod block {
                             is_header: false,
                         }
                     ],
-                    vec![MdTableCell {
+                    vec![
+                        MdTableCell {
+                            content: vec![Text {
+                                content: String::from(" Cell 3 ")
+                            }],
+                            alignment: TableAlignment::None,
+                            is_header: false,
+                        },
+                        MdTableCell {
+                            content: vec![],
+                            alignment: TableAlignment::None,
+                            is_header: false,
+                        }
+                    ]
+                ]
+            }]
+        )
+    }
+
+    #[test]
+    fn table_with_extra_cells() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("| Header 1 |"),
+                tokenize("| -- |"),
+                tokenize("| Cell 1 | Cell 2 | Cell 3 |")
+            ])),
+            vec![Table {
+                headers: vec![MdTableCell {
+                    content: vec![Text {
+                        content: String::from(" Header 1 ")
+                    }],
+                    alignment: TableAlignment::None,
+                    is_header: true,
+                }],
+                body: vec![vec![MdTableCell {
+                    content: vec![Text {
+                        content: String::from(" Cell 1 ")
+                    }],
+                    alignment: TableAlignment::None,
+                    is_header: false,
+                }]]
+            }]
+        )
+    }
+
+    #[test]
+    fn table_alignment_ignores_surrounding_whitespace() {
+        init_test_config();
+        let Some(Table { headers, .. }) = parse_blocks(&group_lines_to_blocks(vec![
+            tokenize("| A | B | C | D |"),
+            tokenize("|   :---:   |\t---: | :---\t| --- |"),
+            tokenize("| 1 | 2 | 3 | 4 |"),
+        ]))
+        .pop() else {
+            panic!("Expected a table");
+        };
+
+        assert_eq!(
+            headers
+                .iter()
+                .map(|cell| cell.alignment.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                TableAlignment::Center,
+                TableAlignment::Right,
+                TableAlignment::Left,
+                TableAlignment::None
+            ]
+        );
+    }
+
+    #[test]
+    fn table_cell_block_tokens_are_inline() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("| Steps | Notes |"),
+                tokenize("| -- | -- |"),
+                tokenize("| 1. first | ```code``` \t--- |")
+            ])),
+            vec![Table {
+                headers: vec![
+                    MdTableCell {
+                        content: vec![Text {
+                            content: String::from(" Steps ")
+                        }],
+                        alignment: TableAlignment::None,
+                        is_header: true,
+                    },
+                    MdTableCell {
+                        content: vec![Text {
+                            content: String::from(" Notes ")
+                        }],
+                        alignment: TableAlignment::None,
+                        is_header: true,
+                    }
+                ],
+                body: vec![vec![
+                    MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Cell 3 ")
+                            content: String::from(" 1. first ")
                         }],
                         alignment: TableAlignment::None,
                         is_header: false,
-                    }]
-                ]
+                    },
+                    MdTableCell {
+                        content: vec![Text {
+                            content: String::from(" ```code```  --- ")
+                        }],
+                        alignment: TableAlignment::None,
+                        is_header: false,
+                    }
+                ]]
+            }]
+        )
+    }
+    #[test]
+    fn table_cell_list_markers_keep_their_delimiter() {
+        init_test_config();
+        let Some(Table { body, .. }) = parse_blocks(&group_lines_to_blocks(vec![
+            tokenize("| Step | Action |"),
+            tokenize("| -- | -- |"),
+            tokenize("| 1) a | 2. b |"),
+        ]))
+        .pop() else {
+            panic!("Expected a table");
+        };
+
+        assert_eq!(
+            body[0]
+                .iter()
+                .map(|cell| cell.content.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![Text {
+                    content: String::from(" 1) a ")
+                }],
+                vec![Text {
+                    content: String::from(" 2. b ")
+                }]
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_mid_paragraph_are_text() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![tokenize(
+                "See step 2) below, then step 3. next"
+            )])),
+            vec![Paragraph {
+                content: vec![Text {
+                    content: String::from("See step 2) below, then step 3. next")
+                }]
             }]
-        )
+        );
     }
 }
 
@@ -1879,6 +4087,43 @@ mod html_generation {
             );
         }
 
+        #[test]
+        fn raw_inline_html_wraps_markdown() {
+            init_test_config();
+            assert_eq!(
+                parse_inline(&tokenize("Press <kbd>**Ctrl**</kbd> or <kbd>"))
+                    .iter()
+                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .collect::<String>(),
+                "Press <kbd><b>Ctrl</b></kbd> or &lt;kbd&gt;"
+            );
+        }
+
+        #[test]
+        fn links_take_precedence_over_emphasis() {
+            init_test_config();
+            let render = |input: &str| {
+                parse_inline(&tokenize(input))
+                    .iter()
+                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .collect::<String>()
+            };
+
+            // Cases from the CommonMark spec, where emphasis can't cross into or out of a link
+            assert_eq!(render("*[foo*](/uri)"), "*<a href=\"/uri\">foo*</a>");
+            assert_eq!(render("[foo *bar](baz*)"), "<a href=\"baz*\">foo *bar</a>");
+            assert_eq!(render("*foo [bar* baz]"), "<i>foo [bar</i> baz]");
+            assert_eq!(render("![*alt*] text"), "![<i>alt</i>] text");
+            assert_eq!(
+                render("**foo [bar** baz](/u)"),
+                "**foo <a href=\"/u\">bar** baz</a>"
+            );
+            assert_eq!(
+                render("*foo [bar](/url)*"),
+                "<i>foo <a href=\"/url\">bar</a></i>"
+            );
+        }
+
         #[test]
         fn bold() {
             init_test_config();
@@ -1891,6 +4136,18 @@ mod html_generation {
             );
         }
 
+        #[test]
+        fn strikethrough() {
+            init_test_config();
+            assert_eq!(
+                parse_inline(&tokenize("~~Deleted~~ text"))
+                    .iter()
+                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .collect::<String>(),
+                "<del>Deleted</del> text"
+            );
+        }
+
         #[test]
         fn italic() {
             init_test_config();
@@ -1971,7 +4228,19 @@ mod html_generation {
                     .iter()
                     .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                     .collect::<String>(),
-                "<a href=\"http://example.com\" target=\"_blank\">link text⮺</a>"
+                "<a href=\"http://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">link text⮺</a>"
+            );
+        }
+
+        #[test]
+        fn link_url_is_percent_encoded() {
+            init_test_config();
+            assert_eq!(
+                parse_inline(&tokenize("[x](<my file.md> \"Say \\\"hi\\\"\")"))
+                    .iter()
+                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .collect::<String>(),
+                "<a href=\"my%20file.md\" title=\"Say &quot;hi&quot;\">x</a>"
             );
         }
 
@@ -1987,6 +4256,20 @@ mod html_generation {
             );
         }
 
+        #[test]
+        fn image_with_dimensions() {
+            init_test_config();
+            assert_eq!(
+                parse_inline(&tokenize(
+                    "![alt text](http://example.com/image.png =300x200)"
+                ))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<img src=\"http://example.com/image.png\" alt=\"alt text\" width=\"300\" height=\"200\"/>"
+            );
+        }
+
         #[test]
         fn code_span() {
             init_test_config();
@@ -2059,7 +4342,7 @@ mod html_generation {
                     .iter()
                     .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                     .collect::<String>(),
-                "<p><a href=\"http://example.com\" target=\"_blank\">link text⮺</a></p>"
+                "<p><a href=\"http://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">link text⮺</a></p>"
             );
         }
 
@@ -2095,7 +4378,7 @@ mod html_generation {
                     .iter()
                     .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                     .collect::<String>(),
-                "\n<h1 id=\"heading-1\">Heading 1</h1>\n"
+                "\n<h1 id=\"heading-1\">Heading 1<a class=\"heading-anchor\" href=\"#heading-1\">#</a></h1>\n"
             );
         }
 
@@ -2107,7 +4390,7 @@ mod html_generation {
                     .iter()
                     .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                     .collect::<String>(),
-                "\n<h3 id=\"heading-3\">Heading 3</h3>\n"
+                "\n<h3 id=\"heading-3\">Heading 3<a class=\"heading-anchor\" href=\"#heading-3\">#</a></h3>\n"
             );
         }
 
@@ -2119,7 +4402,7 @@ mod html_generation {
                     .iter()
                     .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                     .collect::<String>(),
-                "\n<h2 id=\"heading-2-with-bold-words\">Heading 2 with <b>bold words</b></h2>\n"
+                "\n<h2 id=\"heading-2-with-bold-words\">Heading 2 with <b>bold words</b><a class=\"heading-anchor\" href=\"#heading-2-with-bold-words\">#</a></h2>\n"
             );
         }
 
@@ -2140,6 +4423,27 @@ mod html_generation {
             );
         }
 
+        #[test]
+        fn code_block_with_attributes() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(
+                    [
+                        "```rust {.line-numbers highlight=2-4}",
+                        "fn main() {}",
+                        "```"
+                    ]
+                    .iter()
+                    .map(|tokens| tokenize(tokens))
+                    .collect::<Vec<_>>()
+                ))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<pre class=\"non_prism line-numbers\" data-highlight=\"2-4\"><code class=\"non_prism\">fn main() {}</code></pre>"
+            );
+        }
+
         #[test]
         fn code_block_with_language() {
             init_test_config();
@@ -2158,11 +4462,12 @@ mod html_generation {
         }
 
         #[test]
-        fn unordered_list() {
+        fn loose_list() {
             init_test_config();
             assert_eq!(
                 parse_blocks(&group_lines_to_blocks(vec![
                     tokenize("- Item 1"),
+                    tokenize(""),
                     tokenize("- Item 2")
                 ]))
                 .iter()
@@ -2172,6 +4477,106 @@ mod html_generation {
             );
         }
 
+        #[test]
+        fn nested_list_looseness_is_independent() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("- Item 1"),
+                    tokenize("\t- Nested 1"),
+                    tokenize(""),
+                    tokenize("\t- Nested 2"),
+                    tokenize("- Item 2")
+                ]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<ul>\n\t<li>Item 1</li>\n\t<ul>\n\t\t<li>\n\t\t\t<p>Nested 1</p>\n\t\t</li>\n\t\t<li>\n\t\t\t<p>Nested 2</p>\n\t\t</li>\n\t</ul><li>Item 2</li>\n</ul>"
+            );
+        }
+
+        #[test]
+        fn list_item_continuation_lines() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("- The first item,"),
+                    tokenize("wrapped lazily."),
+                    tokenize("- The second item,"),
+                    tokenize("  indented with spaces,"),
+                    tokenize("\tand with a tab."),
+                    tokenize("1. An *ordered* item"),
+                    tokenize("   that wraps.")
+                ]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<ul>\n\t<li>The first item, wrapped lazily.</li>\n\t<li>The second item, indented with spaces, and with a tab.</li>\n</ul><ol>\n\t<li>An <i>ordered</i> item that wraps.</li>\n</ol>"
+            );
+        }
+
+        #[test]
+        fn continuation_line_is_not_a_nested_item() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("- Item 1"),
+                    tokenize("\t- Nested 1"),
+                    tokenize("\tcontinued"),
+                    tokenize("- Item 2")
+                ]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<ul>\n\t<li>Item 1</li>\n\t<ul>\n\t\t<li>Nested 1 continued</li>\n\t</ul><li>Item 2</li>\n</ul>"
+            );
+        }
+
+        #[test]
+        fn hard_line_break() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("Roses are red,  "),
+                    tokenize("violets are blue.")
+                ]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<p>Roses are red,<br>\nviolets are blue.</p>"
+            );
+        }
+
+        #[test]
+        fn task_list() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("- [ ] Todo"),
+                    tokenize("- [x] Done")
+                ]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<ul>\n\t<li class=\"task-list-item\"><input type=\"checkbox\" disabled> Todo</li>\n\t<li class=\"task-list-item\"><input type=\"checkbox\" disabled checked> Done</li>\n</ul>"
+            );
+        }
+
+        #[test]
+        fn unordered_list() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("- Item 1"),
+                    tokenize("- Item 2")
+                ]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<ul>\n\t<li>Item 1</li>\n\t<li>Item 2</li>\n</ul>"
+            );
+        }
+
         #[test]
         fn unordered_list_using_asterisks() {
             init_test_config();
@@ -2183,7 +4588,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ul>\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n\t<li>\n\t\t<p>Item 2</p>\n\t</li>\n</ul>"
+                "<ul>\n\t<li>Item 1</li>\n\t<li>Item 2</li>\n</ul>"
             );
         }
 
@@ -2200,7 +4605,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ul>\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n\t<ul>\n\t\t<li>\n\t\t\t<p>Nested Item 1.1</p>\n\t\t</li>\n\t\t<li>\n\t\t\t<p>Nested Item 1.2</p>\n\t\t</li>\n\t</ul><li>\n\t\t<p>Item 2</p>\n\t</li>\n</ul>"
+                "<ul>\n\t<li>Item 1</li>\n\t<ul>\n\t\t<li>Nested Item 1.1</li>\n\t\t<li>Nested Item 1.2</li>\n\t</ul><li>Item 2</li>\n</ul>"
             );
         }
 
@@ -2217,7 +4622,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ul>\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n\t<ul>\n\t\t<li>\n\t\t\t<p>Nested Item 1.1</p>\n\t\t</li>\n\t\t<li>\n\t\t\t<p>Nested Item 1.2</p>\n\t\t</li>\n\t</ul><li>\n\t\t<p>Item 2</p>\n\t</li>\n</ul>"
+                "<ul>\n\t<li>Item 1</li>\n\t<ul>\n\t\t<li>Nested Item 1.1</li>\n\t\t<li>Nested Item 1.2</li>\n\t</ul><li>Item 2</li>\n</ul>"
             );
         }
 
@@ -2233,7 +4638,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ul>\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n</ul><ul>\n\t<li>\n\t\t<p>Item 2</p>\n\t</li>\n</ul>"
+                "<ul>\n\t<li>Item 1</li>\n</ul><ul>\n\t<li>Item 2</li>\n</ul>"
             );
         }
 
@@ -2251,7 +4656,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ul>\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n\t<ul>\n\t\t<li>\n\t\t\t<p>Nested Item 1.1</p>\n\t\t</li>\n\t\t<li>\n\t\t\t<p>Nested Item 1.2</p>\n\t\t</li>\n\t</ul>\n</ul><ul>\n\t<li>\n\t\t<p>Item 2</p>\n\t</li>\n</ul>"
+                "<ul>\n\t<li>Item 1</li>\n\t<ul>\n\t\t<li>Nested Item 1.1</li>\n\t\t<li>Nested Item 1.2</li>\n\t</ul>\n</ul><ul>\n\t<li>Item 2</li>\n</ul>"
             );
         }
 
@@ -2266,7 +4671,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ol start=\"1\">\n\t<li>\n\t\t<p>First</p>\n\t</li>\n\t<li>\n\t\t<p>Second</p>\n\t</li>\n</ol>"
+                "<ol>\n\t<li>First</li>\n\t<li>Second</li>\n</ol>"
             );
         }
 
@@ -2283,7 +4688,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ol start=\"1\">\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n\t<ol start=\"1\">\n\t<li>\n\t\t<p>Nested Item 1.1</p>\n\t</li>\n\t<li>\n\t\t<p>Nested Item 1.2</p>\n\t</li>\n\n\t</ol><li>\n\t\t<p>Item 2</p>\n\t</li>\n</ol>"
+                "<ol>\n\t<li>Item 1</li>\n\t<ol>\n\t<li>Nested Item 1.1</li>\n\t<li>Nested Item 1.2</li>\n\n\t</ol><li>Item 2</li>\n</ol>"
             );
         }
 
@@ -2300,7 +4705,22 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ol start=\"1\">\n\t<li>\n\t\t<p><b>Bold Item 1</b></p>\n\t</li>\n\t<li>\n\t\t<p><i>Italic Item 2</i></p>\n\t</li>\n\t<li>\n\t\t<p><a href=\"http://example.com\" target=\"_blank\">Link Item 3⮺</a></p>\n\t</li>\n\t<li>\n\t\t<p><img src=\"http://example.com/image.png\" alt=\"Image Item 4\" title=\"Some title\"/></p>\n\t</li>\n</ol>"
+                "<ol>\n\t<li><b>Bold Item 1</b></li>\n\t<li><i>Italic Item 2</i></li>\n\t<li><a href=\"http://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">Link Item 3⮺</a></li>\n\t<li><img src=\"http://example.com/image.png\" alt=\"Image Item 4\" title=\"Some title\"/></li>\n</ol>"
+            );
+        }
+
+        #[test]
+        fn ordered_list_with_roman_markers() {
+            init_test_config();
+            assert_eq!(
+                parse_block(&[
+                    Token::OrderedListMarker(String::from("ii"), ListMarkerKind::LowerRoman, '.'),
+                    Token::Whitespace,
+                    Token::Text(String::from("Second")),
+                ])
+                .unwrap()
+                .to_html("test_output", "test_input", "test_rel_path"),
+                "<ol start=\"2\" type=\"i\">\n\t<li>Second</li>\n</ol>"
             );
         }
 
@@ -2315,7 +4735,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<ol start=\"5\">\n\t<li>\n\t\t<p>Item 5</p>\n\t</li>\n\t<li>\n\t\t<p>Item 6</p>\n\t</li>\n</ol>"
+                "<ol start=\"5\">\n\t<li>Item 5</li>\n\t<li>Item 6</li>\n</ol>"
             );
         }
 
@@ -2333,6 +4753,50 @@ mod html_generation {
             );
         }
 
+        #[test]
+        fn details_block() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("> [!details] Summary"),
+                    tokenize("> Hidden text")
+                ]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<details>\n<summary>Summary</summary>\n<p>Hidden text</p>\n</details>"
+            );
+        }
+
+        #[test]
+        fn callout() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![tokenize(
+                    "> [!NOTE] Useful info"
+                )]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<div class=\"callout callout-note\">\n<p class=\"callout-title\"><span class=\"callout-icon\">ℹ️</span>Note</p>\n<p>Useful info</p>\n</div>"
+            );
+        }
+
+        #[test]
+        fn blockquote_with_citation() {
+            init_test_config();
+            assert_eq!(
+                with_citations(|| parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("> A quote."),
+                    tokenize("> -- Someone")
+                ])))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<figure>\n<blockquote>\n<p>A quote.</p>\n</blockquote>\n<figcaption>&mdash; <cite>Someone</cite></figcaption>\n</figure>"
+            );
+        }
+
         #[test]
         fn blockquote_with_nested_block_element() {
             init_test_config();
@@ -2344,7 +4808,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<blockquote>\n<p>This is a blockquote with a nested heading:</p>\n<h1 id=\"heading-1\">Heading 1</h1>\n\n</blockquote>"
+                "<blockquote>\n<p>This is a blockquote with a nested heading:</p>\n<h1 id=\"heading-1\">Heading 1<a class=\"heading-anchor\" href=\"#heading-1\">#</a></h1>\n\n</blockquote>"
             );
         }
 
@@ -2391,7 +4855,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "\n<h1 id=\"this-is-a-heading-with-bold-text-and-italic-text\">This is a heading with <strong>bold text</strong> and <em>italic text</em>.</h1>\n<div>Some raw HTML content</div>\n"
+                "\n<h1 id=\"this-is-a-heading-with-bold-text-and-italic-text\">This is a heading with <strong>bold text</strong> and <em>italic text</em>.<a class=\"heading-anchor\" href=\"#this-is-a-heading-with-bold-text-and-italic-text\">#</a></h1>\n<div>Some raw HTML content</div>\n"
             );
         }
 
@@ -2405,7 +4869,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<p><div Missing bracket</p>"
+                "<p>&lt;div Missing bracket</p>"
             );
         }
 
@@ -2452,6 +4916,27 @@ mod html_generation {
             );
         }
 
+        #[test]
+        fn table_without_body_rows() {
+            use crate::types::{MdTableCell, TableAlignment};
+
+            init_test_config();
+            assert_eq!(
+                Table {
+                    headers: vec![MdTableCell {
+                        content: vec![Text {
+                            content: String::from(" Header 1 ")
+                        }],
+                        alignment: TableAlignment::Center,
+                        is_header: true,
+                    }],
+                    body: vec![]
+                }
+                .to_html("test_output", "test_input", "test_rel_path"),
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:center;\"> Header 1 </th>\n\t\t</tr>\n\t</thead>\n</table>"
+            );
+        }
+
         #[test]
         fn table_mixed_align() {
             init_test_config();
@@ -2501,7 +4986,7 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> <b>Bold Cell</b> </td>\n\t\t\t<td style=\"text-align:left;\"> <i>Italic Cell</i> </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> <a href=\"http://example.com\" target=\"_blank\">Link⮺</a> </td>\n\t\t\t<td style=\"text-align:left;\"> <img src=\"http://example.com/image.png\" alt=\"Image\"/> </td>\n\t\t</tr>\n\t</tbody>\n</table>"
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> <b>Bold Cell</b> </td>\n\t\t\t<td style=\"text-align:left;\"> <i>Italic Cell</i> </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> <a href=\"http://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">Link⮺</a> </td>\n\t\t\t<td style=\"text-align:left;\"> <img src=\"http://example.com/image.png\" alt=\"Image\"/> </td>\n\t\t</tr>\n\t</tbody>\n</table>"
             );
         }
 
@@ -2535,8 +5020,139 @@ mod html_generation {
                 .iter()
                 .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
                 .collect::<String>(),
-                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 1 </td>\n\t\t\t<td style=\"text-align:left;\"> Cell 2 </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 3 </td>\n\t\t</tr>\n\t</tbody>\n</table>"
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 1 </td>\n\t\t\t<td style=\"text-align:left;\"> Cell 2 </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 3 </td>\n\t\t\t<td style=\"text-align:left;\"></td>\n\t\t</tr>\n\t</tbody>\n</table>"
+            );
+        }
+
+        #[test]
+        fn table_with_one_body_row() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("| Header 1 | Header 2 |"),
+                    tokenize("| :-: | --: |"),
+                    tokenize("| Cell 1 | Cell 2 | Cell 3 |")
+                ]))
+                .iter()
+                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .collect::<String>(),
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:center;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:right;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:center;\"> Cell 1 </td>\n\t\t\t<td style=\"text-align:right;\"> Cell 2 </td>\n\t\t</tr>\n\t</tbody>\n</table>"
             );
         }
     }
+
+    #[test]
+    fn definition_list() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Term"),
+                tokenize(": *First*"),
+                tokenize(": Second"),
+            ]))
+            .iter()
+            .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+            .collect::<String>(),
+            "<dl>\n\t<dt>Term</dt>\n\t<dd><i>First</i></dd>\n\t<dd>Second</dd>\n</dl>"
+        );
+    }
+}
+
+mod markdown_generation {
+    use crate::types::ToMarkdown;
+
+    use super::*;
+
+    #[test]
+    fn ordered_list_markers_follow_their_kind() {
+        init_test_config();
+        let item = |text: &str| MdListItem {
+            content: Paragraph {
+                content: vec![Text {
+                    content: text.to_string(),
+                }],
+            },
+            checked: None,
+        };
+
+        assert_eq!(
+            OrderedList {
+                starting_num: 4,
+                kind: ListMarkerKind::LowerRoman,
+                items: vec![item("four"), item("five")],
+                loose: false,
+            }
+            .to_markdown(),
+            "iv. four\nv. five"
+        );
+        assert_eq!(
+            OrderedList {
+                starting_num: 2,
+                kind: ListMarkerKind::UpperAlpha,
+                items: vec![item("bee"), item("cee")],
+                loose: true,
+            }
+            .to_markdown(),
+            "B. bee\n\nC. cee"
+        );
+    }
+
+    #[test]
+    fn code_spans_keep_their_backticks_and_padding() {
+        assert_eq!(
+            Code {
+                content: "a`b".to_string()
+            }
+            .to_markdown(),
+            "``a`b``"
+        );
+        assert_eq!(
+            Code {
+                content: "`tick".to_string()
+            }
+            .to_markdown(),
+            "`` `tick ``"
+        );
+        assert_eq!(
+            Code {
+                content: " padded ".to_string()
+            }
+            .to_markdown(),
+            "`  padded  `"
+        );
+    }
+
+    #[test]
+    fn links_use_the_shortest_form() {
+        let link = |text: &str, url: &str, title: Option<&str>| Link {
+            text: vec![Text {
+                content: text.to_string(),
+            }],
+            title: title.map(str::to_string),
+            url: url.to_string(),
+        };
+
+        assert_eq!(
+            link("https://example.com", "https://example.com", None).to_markdown(),
+            "<https://example.com>"
+        );
+        assert_eq!(
+            link("Docs", "/my docs", Some("The \"docs\"")).to_markdown(),
+            "[Docs](</my docs> \"The \\\"docs\\\"\")"
+        );
+    }
+
+    #[test]
+    fn code_blocks_unescape_tags() {
+        assert_eq!(
+            CodeBlock {
+                language: Some("html".to_string()),
+                info: String::new(),
+                attributes: CodeBlockAttributes::default(),
+                lines: vec!["&lt;div&gt;".to_string(), "```".to_string()],
+            }
+            .to_markdown(),
+            "````html\n<div>\n```\n````"
+        );
+    }
 }
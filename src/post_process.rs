@@ -0,0 +1,94 @@
+//! This module provides an extension point for transforming each generated page's HTML before it
+//! is written, i.e. to add analytics or rewrite links, without forking the generator.
+//!
+//! ```
+//! use mark_rs::post_process::{PageContext, register_post_processor};
+//!
+//! register_post_processor(|html: &str, page: &PageContext| {
+//!     html.replace("</body>", &format!("<!-- {} -->\n</body>", page.path))
+//! });
+//! ```
+
+use std::sync::RwLock;
+
+/// Information about the page being generated, passed to each `HtmlPostProcessor`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageContext<'a> {
+    /// The path of the generated HTML file, relative to the output directory (i.e.
+    /// `guides/setup.html`).
+    pub path: &'a str,
+    /// The page's title, as shown in its `<title>` and navbar.
+    pub title: &'a str,
+    /// The directory the page is written to.
+    pub output_dir: &'a str,
+}
+
+/// Transforms the HTML of a generated page before it is written.
+///
+/// Closures taking the HTML and the `PageContext` implement this trait, so simple processors don't
+/// need a type of their own.
+pub trait HtmlPostProcessor: Send + Sync {
+    /// Returns the processed HTML of the page.
+    ///
+    /// # Arguments
+    /// * `html` - The page's complete HTML document, as generated or returned by the previous
+    ///   processor.
+    /// * `page` - Information about the page being generated.
+    fn process(&self, html: &str, page: &PageContext) -> String;
+}
+
+impl<F> HtmlPostProcessor for F
+where
+    F: Fn(&str, &PageContext) -> String + Send + Sync,
+{
+    fn process(&self, html: &str, page: &PageContext) -> String {
+        self(html, page)
+    }
+}
+
+/// The processors run on every generated page, in the order they were registered. Empty by
+/// default, so pages are written exactly as generated.
+static POST_PROCESSORS: RwLock<Vec<Box<dyn HtmlPostProcessor>>> = RwLock::new(Vec::new());
+
+/// Adds a processor to the end of the chain run on every generated page.
+pub fn register_post_processor(processor: impl HtmlPostProcessor + 'static) {
+    POST_PROCESSORS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Box::new(processor));
+}
+
+/// Removes every registered processor.
+pub fn clear_post_processors() {
+    POST_PROCESSORS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+}
+
+/// Runs the registered processors on a page's HTML.
+///
+/// # Arguments
+/// * `html` - The page's generated HTML.
+/// * `page` - Information about the page being generated.
+///
+/// # Returns
+/// The HTML returned by the last processor, or the original HTML if none are registered.
+pub fn run_post_processors(html: String, page: &PageContext) -> String {
+    let processors = POST_PROCESSORS.read().unwrap_or_else(|e| e.into_inner());
+    apply_post_processors(html, page, &processors)
+}
+
+/// Runs each processor on the output of the previous one.
+fn apply_post_processors(
+    html: String,
+    page: &PageContext,
+    processors: &[Box<dyn HtmlPostProcessor>],
+) -> String {
+    processors
+        .iter()
+        .fold(html, |html, processor| processor.process(&html, page))
+}
+
+#[cfg(test)]
+mod test;
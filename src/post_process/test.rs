@@ -0,0 +1,36 @@
+use crate::post_process::{HtmlPostProcessor, PageContext, apply_post_processors};
+
+const PAGE: PageContext = PageContext {
+    path: "guides/setup.html",
+    title: "Setup",
+    output_dir: "site",
+};
+
+struct Analytics;
+
+impl HtmlPostProcessor for Analytics {
+    fn process(&self, html: &str, _page: &PageContext) -> String {
+        html.replace("</body>", "<script src=\"/analytics.js\"></script></body>")
+    }
+}
+
+#[test]
+fn empty_chain_leaves_html_unchanged() {
+    assert_eq!(
+        apply_post_processors(String::from("<body></body>"), &PAGE, &[]),
+        "<body></body>"
+    );
+}
+
+#[test]
+fn processors_run_in_order() {
+    let processors: Vec<Box<dyn HtmlPostProcessor>> = vec![
+        Box::new(Analytics),
+        Box::new(|html: &str, page: &PageContext| format!("<!-- {} -->{html}", page.path)),
+    ];
+
+    assert_eq!(
+        apply_post_processors(String::from("<body></body>"), &PAGE, &processors),
+        "<!-- guides/setup.html --><body><script src=\"/analytics.js\"></script></body>"
+    );
+}
@@ -0,0 +1,247 @@
+//! This module provides the development server used by `--serve`, which hosts the output
+//! directory over HTTP and reloads open pages when the site is rebuilt.
+//!
+//! Pages are notified of rebuilds through a server-sent events endpoint. The script listening to
+//! it is injected into HTML responses as they are served, so the generated files themselves never
+//! contain it.
+
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use log::{error, warn};
+
+/// The path of the server-sent events endpoint that pages listen to for reloads.
+const RELOAD_ENDPOINT: &str = "/__markrs/reload";
+
+/// The script injected into served HTML pages, reloading the page when a rebuild is announced.
+const RELOAD_SCRIPT: &str =
+    "<script>new EventSource(\"/__markrs/reload\").onmessage = () => location.reload();</script>";
+
+/// How often open reload connections check whether the site has been rebuilt.
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Incremented every time the site is rebuilt, so that open pages know to reload.
+static BUILD_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts serving the output directory on the given port in a background thread.
+///
+/// # Arguments
+/// * `output_dir` - The directory to serve files from.
+/// * `port` - The port to listen on.
+///
+/// # Returns
+/// Returns a `Result` indicating whether the server could bind to the port.
+pub fn start_server(output_dir: &str, port: u16) -> Result<(), io::Error> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let output_dir = PathBuf::from(output_dir);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let output_dir = output_dir.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream, &output_dir) {
+                            warn!("Failed to handle request: {e}");
+                        }
+                    });
+                }
+                Err(e) => error!("Failed to accept connection: {e}"),
+            }
+        }
+    });
+
+    println!("Serving site at http://localhost:{port}/");
+    Ok(())
+}
+
+/// Tells every page open in the dev server to reload, called after the site is rebuilt.
+pub fn notify_reload() {
+    BUILD_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Handles a single HTTP request, serving either a file from the output directory or the reload
+/// event stream.
+fn handle_connection(mut stream: TcpStream, output_dir: &Path) -> Result<(), io::Error> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // The headers aren't needed, but they have to be read before responding
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return write_response(&mut stream, "400 Bad Request", "text/plain", b"Bad Request");
+    };
+
+    if method != "GET" {
+        return write_response(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"Method Not Allowed",
+        );
+    }
+
+    if target == RELOAD_ENDPOINT {
+        return stream_reload_events(stream);
+    }
+
+    let Some(file_path) = resolve_request_path(output_dir, target) else {
+        return write_response(&mut stream, "404 Not Found", "text/plain", b"Not Found");
+    };
+
+    let contents = fs::read(&file_path)?;
+    let content_type = content_type(&file_path);
+    if content_type.starts_with("text/html") {
+        let html = inject_reload_script(&String::from_utf8_lossy(&contents));
+        write_response(&mut stream, "200 OK", content_type, html.as_bytes())
+    } else {
+        write_response(&mut stream, "200 OK", content_type, &contents)
+    }
+}
+
+/// Writes a complete HTTP response with the given status, content type and body.
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<(), io::Error> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// Keeps the connection open as a server-sent events stream, sending an event whenever the site
+/// is rebuilt. Returns once the page disconnects.
+fn stream_reload_events(mut stream: TcpStream) -> Result<(), io::Error> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+    )?;
+    stream.flush()?;
+
+    let mut last_generation = BUILD_GENERATION.load(Ordering::SeqCst);
+    loop {
+        thread::sleep(RELOAD_POLL_INTERVAL);
+
+        let generation = BUILD_GENERATION.load(Ordering::SeqCst);
+        if generation != last_generation {
+            last_generation = generation;
+            stream.write_all(b"data: reload\n\n")?;
+        } else {
+            // Comments keep the connection alive and reveal when the page has been closed
+            stream.write_all(b": ping\n\n")?;
+        }
+        stream.flush()?;
+    }
+}
+
+/// Resolves a request target to a file in the output directory.
+///
+/// The query string is ignored, percent-encoded characters are decoded, and directories resolve
+/// to their `index.html` so that pretty URLs work. Targets that would escape the output directory
+/// are rejected.
+///
+/// # Arguments
+/// * `output_dir` - The directory files are served from.
+/// * `target` - The request target, i.e. `/guides/setup.html?foo=bar`.
+///
+/// # Returns
+/// Returns the path of the file to serve, or `None` if no such file exists.
+pub fn resolve_request_path(output_dir: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    let path = percent_decode(path);
+
+    let mut file_path = output_dir.to_path_buf();
+    for component in Path::new(path.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => file_path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    if file_path.is_dir() {
+        file_path.push("index.html");
+    }
+
+    file_path.is_file().then_some(file_path)
+}
+
+/// Decodes `%XX` escapes in a URL path, leaving malformed escapes as they are.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Returns the `Content-Type` to serve a file with, based on its extension.
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Injects the live reload script into an HTML page, just before its closing `</body>` tag, or at
+/// the end of the page if it has none.
+pub fn inject_reload_script(html: &str) -> String {
+    match html.rfind("</body>") {
+        Some(index) => format!("{}{RELOAD_SCRIPT}\n{}", &html[..index], &html[index..]),
+        None => format!("{html}{RELOAD_SCRIPT}\n"),
+    }
+}
+
+#[cfg(test)]
+mod test;
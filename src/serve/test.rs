@@ -0,0 +1,84 @@
+use std::fs;
+use std::path::Path;
+
+use tempfile::TempDir;
+
+use crate::serve::{content_type, inject_reload_script, resolve_request_path};
+
+fn site_output_dir() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir_all(dir.path().join("guides/setup")).unwrap();
+    fs::write(dir.path().join("index.html"), "<html></html>").unwrap();
+    fs::write(dir.path().join("guides/setup/index.html"), "<html></html>").unwrap();
+    fs::write(dir.path().join("my page.html"), "<html></html>").unwrap();
+    dir
+}
+
+#[test]
+fn requests_resolve_to_files_and_directory_indexes() {
+    let output_dir = site_output_dir();
+
+    assert_eq!(
+        resolve_request_path(output_dir.path(), "/"),
+        Some(output_dir.path().join("index.html"))
+    );
+    assert_eq!(
+        resolve_request_path(output_dir.path(), "/guides/setup/?tab=1"),
+        Some(output_dir.path().join("guides/setup/index.html"))
+    );
+    assert_eq!(
+        resolve_request_path(output_dir.path(), "/my%20page.html"),
+        Some(output_dir.path().join("my page.html"))
+    );
+    assert_eq!(
+        resolve_request_path(output_dir.path(), "/missing.html"),
+        None
+    );
+}
+
+#[test]
+fn requests_outside_output_dir_are_rejected() {
+    let output_dir = site_output_dir();
+
+    assert_eq!(
+        resolve_request_path(output_dir.path(), "/../index.html"),
+        None
+    );
+    assert_eq!(
+        resolve_request_path(output_dir.path(), "/guides/%2e%2e/%2e%2e/etc"),
+        None
+    );
+}
+
+#[test]
+fn reload_script_is_injected_before_closing_body() {
+    let html = inject_reload_script("<html>\n\t<body>\n\t</body>\n</html>\n");
+
+    let script_index = html.find("<script>").unwrap();
+    assert!(script_index < html.find("</body>").unwrap());
+    assert!(html.contains("EventSource(\"/__markrs/reload\")"));
+}
+
+#[test]
+fn reload_script_is_appended_without_body() {
+    let html = inject_reload_script("<p>Fragment</p>");
+
+    assert!(html.starts_with("<p>Fragment</p><script>"));
+}
+
+#[test]
+fn content_type_matches_extension() {
+    assert_eq!(
+        content_type(Path::new("index.html")),
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(
+        content_type(Path::new("styles.CSS")),
+        "text/css; charset=utf-8"
+    );
+    assert_eq!(content_type(Path::new("media/logo.png")), "image/png");
+    assert_eq!(
+        content_type(Path::new("archive")),
+        "application/octet-stream"
+    );
+}
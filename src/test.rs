@@ -0,0 +1,207 @@
+use crate::config::{Config, with_config};
+use crate::{format_markdown, markdown_to_html, parse_markdown};
+
+#[cfg(feature = "spec")]
+mod spec;
+
+#[test]
+fn test_markdown_to_html() {
+    let html = markdown_to_html("# Title\n\nSome *text* here.", &Config::default());
+    // The sanitizer adds a `rel` to every link
+    let rel = if cfg!(feature = "sanitize") {
+        " rel=\"noopener noreferrer\""
+    } else {
+        ""
+    };
+
+    assert_eq!(
+        html,
+        format!(
+            "\n<h1 id=\"title\">Title<a class=\"heading-anchor\" href=\"#title\"{rel}>#</a></h1>\n\n<p>Some <i>text</i> here.</p>"
+        )
+    );
+}
+
+#[test]
+fn test_markdown_to_html_leaves_relative_images_in_place() {
+    let html = markdown_to_html("![Logo](./img/logo.png)", &Config::default());
+
+    assert!(
+        html.contains("<img src=\"./img/logo.png\" alt=\"Logo\""),
+        "{html}"
+    );
+}
+
+#[test]
+fn test_markdown_to_html_wraps_math_for_katex() {
+    let mut config = Config::default();
+    config.html.math = true;
+    let html = markdown_to_html("If $a < b$ then:\n\n$$\na^2 < b^2\n$$", &config);
+
+    assert!(
+        html.contains("<span class=\"math math-inline\">\\(a &lt; b\\)</span>"),
+        "{html}"
+    );
+    assert!(
+        html.contains("<div class=\"math math-display\">\\[\na^2 &lt; b^2\n\\]</div>"),
+        "{html}"
+    );
+}
+
+#[test]
+fn test_markdown_to_html_keeps_callouts_and_details_when_sanitized() {
+    let html = markdown_to_html(
+        "> [!TIP]\n> A tip.\n\n> [!details] More\n> Hidden.",
+        &Config::default(),
+    );
+
+    assert!(
+        html.contains("<div class=\"callout callout-tip\">\n<p class=\"callout-title\">"),
+        "{html}"
+    );
+    assert!(
+        html.contains("<details>\n<summary>More</summary>\n<p>Hidden.</p>\n</details>"),
+        "{html}"
+    );
+}
+
+#[test]
+fn test_markdown_to_html_normalizes_crlf_line_endings() {
+    let mut config = Config::default();
+    config.html.sanitize_html = false;
+    let unix = markdown_to_html(
+        "---\ntitle: Page\n---\nTitle\n=====\n\n```rust\nlet x = 1;\n```\n\nSome\ntext",
+        &config,
+    );
+    let windows = markdown_to_html(
+        "---\r\ntitle: Page\r\n---\r\nTitle\r\n=====\r\n\r\n```rust\r\nlet x = 1;\r\n```\r\n\r\nSome\r\ntext",
+        &config,
+    );
+
+    assert!(!windows.contains('\r'), "{windows}");
+    assert_eq!(windows, unix);
+    assert!(windows.contains("<h1 id=\"title\">"), "{windows}");
+}
+
+#[test]
+fn test_markdown_to_html_rewrites_links_to_markdown_pages() {
+    let html = markdown_to_html(
+        "See [setup](guides/setup.md#install) or **[the FAQ](faq.md)**.",
+        &Config::default(),
+    );
+
+    assert!(
+        html.contains("<a href=\"guides/setup.html#install\""),
+        "{html}"
+    );
+    assert!(html.contains("<a href=\"faq.html\""), "{html}");
+}
+
+#[test]
+fn test_format_markdown_normalizes_syntax() {
+    let formatted = format_markdown(
+        "Title\n=====\nSome  *text*.\n\n* one\n* [x] two\n    * nested\n\n|a|b|\n|:-|-:|\n|1|22|",
+        &Config::default(),
+    );
+
+    assert_eq!(
+        formatted,
+        "# Title\n\nSome  *text*.\n\n- one\n- [x] two\n    - nested\n\n| a   | b   |\n| :-- | --: |\n| 1   | 22  |\n"
+    );
+}
+
+#[test]
+fn test_format_markdown_keeps_frontmatter() {
+    let formatted = format_markdown("---\ntitle: Hello\n---\n## Intro", &Config::default());
+
+    assert_eq!(formatted, "---\ntitle: Hello\n---\n\n## Intro\n");
+}
+
+#[test]
+fn test_format_markdown_is_stable() {
+    let input = "Heading\n-------\n\nText with **bold _and italic_**, `co`de``, ~~gone~~ and a\nhard break\\\nhere.\n\n\
+        [link](/a \"A \\\"title\\\"\") <https://example.com> ![alt](img.png){width=10}\n\n\
+        3. three\n4. four\n\n- a\n\n- b\n    1. nested\n\n\
+        > quote\n>\n> more\n> -- Someone\n\n> [!warning]\n> careful\n\n> [!details] More\n> hidden\n\n\
+        ```rust {.example}\nfn main() { <div> }\n```\n\nTerm\n: Definition\n\n***\n\n[^1]: A note";
+    let mut config = Config::default();
+    config.html.blockquote_cite = true;
+
+    let formatted = format_markdown(input, &config);
+
+    assert_eq!(format_markdown(&formatted, &config), formatted);
+    assert_eq!(
+        markdown_to_html(&formatted, &config),
+        markdown_to_html(input, &config)
+    );
+}
+
+#[test]
+fn test_format_markdown_keeps_adjacent_lists_apart() {
+    let input = "* a\n* b\n- [x] done\n* c\n\n1. one\n\n2) two";
+    let config = Config::default();
+
+    let formatted = format_markdown(input, &config);
+
+    assert_eq!(
+        formatted,
+        "- a\n- b\n\n* [x] done\n\n- c\n\n1. one\n\n2) two\n"
+    );
+    with_config(&config, || {
+        assert_eq!(parse_markdown(&formatted), parse_markdown(input));
+    });
+}
+
+#[test]
+fn test_task_list_serializes_checked_and_loose() {
+    let config = Config::default();
+    let tight = with_config(&config, || parse_markdown("- [x] a\n- [ ] b"));
+    let loose = with_config(&config, || parse_markdown("- [x] a\n\n- [ ] b"));
+
+    let paragraph = |text: &str| {
+        serde_json::json!({
+            "type": "Paragraph",
+            "content": [{ "type": "Text", "content": text }]
+        })
+    };
+    assert_eq!(
+        serde_json::to_value(&tight).unwrap(),
+        serde_json::json!([{
+            "type": "UnorderedList",
+            "items": [
+                { "content": paragraph("a"), "checked": true },
+                { "content": paragraph("b"), "checked": false }
+            ],
+            "loose": false
+        }])
+    );
+    assert_eq!(serde_json::to_value(&loose).unwrap()[0]["loose"], true);
+}
+
+#[test]
+fn test_format_markdown_keeps_text_and_html_verbatim() {
+    let config = Config::default();
+    let corpus = [
+        "shift x << 2",
+        "a < b > c and <b>bold</b> <span class=\"x\">s</span>",
+        "I <3 you, see <a href=\"x\" !>",
+        "<span>Unclosed HTML",
+        "a < b and <b>bold</b>",
+        "x <3 and `a<b` <em>y</em>",
+        "a &amp; b &lt; c and AT&T",
+        "col1\tcol2",
+        "a\tb *c\td*",
+    ];
+
+    for input in corpus {
+        let formatted = format_markdown(input, &config);
+
+        assert_eq!(formatted, format!("{input}\n"));
+        assert_eq!(format_markdown(&formatted, &config), formatted);
+        assert_eq!(
+            markdown_to_html(&formatted, &config),
+            markdown_to_html(input, &config)
+        );
+    }
+    assert!(markdown_to_html("shift x << 2", &config).contains("shift x &lt;&lt; 2"));
+}
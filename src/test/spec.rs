@@ -0,0 +1,131 @@
+//! Runs the examples from the CommonMark spec through the parser and reports how many of them
+//! produce the expected HTML, overall and per section.
+//!
+//! The examples aren't checked in. Download them before running the tests:
+//!
+//! ```bash
+//! curl -o tests/spec.json https://spec.commonmark.org/0.31.2/spec.json
+//! cargo test --features spec commonmark_spec -- --nocapture
+//! ```
+//!
+//! The following environment variables change how the examples are run:
+//! * `MARKRS_SPEC_JSON` - The path of `spec.json`, instead of `tests/spec.json`.
+//! * `MARKRS_SPEC_SECTION` - Only run the examples in this section, i.e. `Emphasis and strong
+//!   emphasis`.
+//! * `MARKRS_SPEC_MIN_PASSED` - Fail if fewer examples than this pass, to catch regressions.
+
+use std::collections::BTreeMap;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::markdown_to_html;
+
+static HEADING_ANCHOR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<a class="heading-anchor"[^>]*>#</a>"#).unwrap());
+static HEADING_ID_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"<h([1-6]) id="[^"]*">"#).unwrap());
+static BETWEEN_TAGS_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r">\s+<").unwrap());
+static WHITESPACE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+
+/// A single example from `spec.json`.
+#[derive(Deserialize)]
+struct SpecExample {
+    markdown: String,
+    html: String,
+    example: usize,
+    section: String,
+}
+
+/// Normalizes HTML so that markrs' output can be compared with the spec's.
+///
+/// Whitespace between tags is removed and any other run of whitespace is collapsed, since markrs
+/// indents its output. Heading anchors and ids are removed, and `<i>`/`<b>` are compared as
+/// `<em>`/`<strong>`.
+fn normalize_html(html: &str) -> String {
+    let html = HEADING_ANCHOR_REGEX.replace_all(html, "");
+    let html = HEADING_ID_REGEX.replace_all(&html, "<h$1>");
+    let html = html
+        .replace("<i>", "<em>")
+        .replace("</i>", "</em>")
+        .replace("<b>", "<strong>")
+        .replace("</b>", "</strong>");
+    let html = BETWEEN_TAGS_REGEX.replace_all(&html, "><");
+
+    WHITESPACE_REGEX.replace_all(&html, " ").trim().to_string()
+}
+
+#[test]
+fn normalized_html_ignores_indentation_and_anchors() {
+    assert_eq!(
+        normalize_html(
+            "\n<h1 id=\"title\">Title<a class=\"heading-anchor\" href=\"#title\">#</a></h1>\n\t<p>Some  <i>text</i></p>\n"
+        ),
+        normalize_html("<h1>Title</h1>\n<p>Some <em>text</em></p>\n")
+    );
+}
+
+#[test]
+fn commonmark_spec() {
+    let spec_path = std::env::var("MARKRS_SPEC_JSON")
+        .unwrap_or_else(|_| format!("{}/tests/spec.json", env!("CARGO_MANIFEST_DIR")));
+    let spec = std::fs::read_to_string(&spec_path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to read {spec_path}: {e}. Download it from https://spec.commonmark.org/0.31.2/spec.json"
+        )
+    });
+    let examples: Vec<SpecExample> = serde_json::from_str(&spec).expect("Invalid spec.json");
+    let section_filter = std::env::var("MARKRS_SPEC_SECTION").ok();
+
+    let mut config = Config::default();
+    config.html.sanitize_html = false;
+
+    // Each section's passed and failed example numbers
+    let mut results: BTreeMap<&str, (usize, Vec<usize>)> = BTreeMap::new();
+    for example in &examples {
+        if section_filter
+            .as_deref()
+            .is_some_and(|section| !section.eq_ignore_ascii_case(&example.section))
+        {
+            continue;
+        }
+
+        // A panic is counted as a failure rather than stopping the run
+        let html = catch_unwind(AssertUnwindSafe(|| {
+            markdown_to_html(&example.markdown, &config)
+        }));
+        let passed = html.is_ok_and(|html| normalize_html(&html) == normalize_html(&example.html));
+
+        let (passed_count, failed) = results.entry(&example.section).or_default();
+        if passed {
+            *passed_count += 1;
+        } else {
+            failed.push(example.example);
+        }
+    }
+
+    let mut total_passed = 0;
+    let mut total = 0;
+    for (section, (passed, failed)) in &results {
+        println!("{section}: {passed}/{}", passed + failed.len());
+        if !failed.is_empty() {
+            println!("\tFailed: {failed:?}");
+        }
+        total_passed += passed;
+        total += passed + failed.len();
+    }
+    println!("Passed {total_passed}/{total} CommonMark spec examples");
+
+    if let Some(min_passed) = std::env::var("MARKRS_SPEC_MIN_PASSED")
+        .ok()
+        .and_then(|min| min.parse::<usize>().ok())
+    {
+        assert!(
+            total_passed >= min_passed,
+            "Only {total_passed} examples passed, expected at least {min_passed}"
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
     fmt,
+    panic::{self, AssertUnwindSafe},
     sync::{Arc, Mutex, mpsc},
     thread,
 };
@@ -8,18 +9,48 @@ use log::warn;
 
 pub struct ThreadPool {
     workers: Vec<Worker>,
-    sender: mpsc::Sender<Job>,
+    sender: JobSender,
+}
+
+/// The sending half of the job queue, which is only limited in size for pools created with
+/// `ThreadPool::build_bounded`.
+enum JobSender {
+    Unbounded(mpsc::Sender<Job>),
+    Bounded(mpsc::SyncSender<Job>),
+}
+
+impl JobSender {
+    fn send(&self, job: Job) -> Result<(), mpsc::SendError<Job>> {
+        match self {
+            JobSender::Unbounded(sender) => sender.send(job),
+            JobSender::Bounded(sender) => sender.send(job),
+        }
+    }
 }
 
 impl ThreadPool {
     pub fn build(size: usize) -> Result<Self, Error> {
+        let (sender, receiver) = mpsc::channel();
+
+        Self::spawn(size, JobSender::Unbounded(sender), receiver)
+    }
+
+    /// Creates a thread pool whose queue holds at most `capacity` jobs that haven't been picked up
+    /// by a worker yet. Once the queue is full, `execute` blocks until a worker takes a job, which
+    /// limits how many jobs (and the data they own) are held in memory at once.
+    pub fn build_bounded(size: usize, capacity: usize) -> Result<Self, Error> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+
+        Self::spawn(size, JobSender::Bounded(sender), receiver)
+    }
+
+    fn spawn(size: usize, sender: JobSender, receiver: mpsc::Receiver<Job>) -> Result<Self, Error> {
         if size == 0 {
             return Err(Error::PoolCreation {
                 message: "Thread pool size must be greater than 0".to_string(),
             });
         }
 
-        let (sender, receiver) = mpsc::channel();
         let receiver = Arc::new(Mutex::new(receiver));
 
         let mut workers = Vec::with_capacity(size);
@@ -96,6 +127,26 @@ impl Worker {
 
 type Job = Box<dyn FnOnce() + Send + 'static>;
 
+/// Runs a job, catching a panic so that it can be reported like any other failure. Without this,
+/// a panicking job takes its worker thread down with it, along with anything it was meant to send
+/// back.
+///
+/// # Returns
+/// The job's result, or an `Error::JobExecution` with the panic's message if it panicked.
+pub fn catch_panic<T>(job: impl FnOnce() -> T) -> Result<T, Error> {
+    panic::catch_unwind(AssertUnwindSafe(job)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("unknown panic"));
+
+        Error::JobExecution {
+            message: format!("Job panicked: {message}"),
+        }
+    })
+}
+
 #[derive(Debug)]
 pub enum Error {
     PoolCreation { message: String },
@@ -114,3 +165,6 @@ impl fmt::Display for Error {
 }
 
 impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod test;
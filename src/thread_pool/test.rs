@@ -0,0 +1,74 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::thread_pool::{Error, ThreadPool, catch_panic};
+
+#[test]
+fn empty_pool_is_rejected() {
+    assert!(matches!(
+        ThreadPool::build(0),
+        Err(Error::PoolCreation { .. })
+    ));
+    assert!(matches!(
+        ThreadPool::build_bounded(0, 4),
+        Err(Error::PoolCreation { .. })
+    ));
+}
+
+#[test]
+fn bounded_pool_runs_every_job() {
+    let pool = ThreadPool::build_bounded(2, 1).unwrap();
+    let completed = Arc::new(AtomicUsize::new(0));
+
+    // Far more jobs than the queue holds, so `execute` has to wait for the workers
+    for _ in 0..50 {
+        let completed = Arc::clone(&completed);
+        pool.execute(move || {
+            thread::sleep(Duration::from_millis(1));
+            completed.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+    }
+    pool.join_all();
+
+    assert_eq!(completed.load(Ordering::SeqCst), 50);
+}
+
+#[test]
+fn bounded_pool_limits_queued_jobs() {
+    let pool = ThreadPool::build_bounded(1, 2).unwrap();
+    let queued = Arc::new(AtomicUsize::new(0));
+    let max_queued = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..20 {
+        let queued_now = queued.fetch_add(1, Ordering::SeqCst) + 1;
+        max_queued.fetch_max(queued_now, Ordering::SeqCst);
+
+        let queued = Arc::clone(&queued);
+        pool.execute(move || {
+            queued.fetch_sub(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(1));
+        })
+        .unwrap();
+    }
+    pool.join_all();
+
+    // At most `capacity` jobs wait in the queue, plus the one that's blocked being sent and the
+    // one the worker has just taken
+    assert!(max_queued.load(Ordering::SeqCst) <= 4);
+}
+
+#[test]
+fn panicking_job_is_reported_as_an_error() {
+    assert_eq!(catch_panic(|| 2 + 2).unwrap(), 4);
+
+    let error = catch_panic(|| -> usize { panic!("page exploded") }).unwrap_err();
+    assert!(matches!(error, Error::JobExecution { .. }));
+    assert!(error.to_string().contains("page exploded"));
+
+    let page = String::from("page.md");
+    let error = catch_panic(|| -> usize { panic!("{page} exploded") }).unwrap_err();
+    assert!(error.to_string().contains("page.md exploded"));
+}
@@ -2,38 +2,227 @@
 //! block elements, and a cursor for navigating through tokens.
 
 use log::warn;
+use serde::Serialize;
+use unicode_categories::UnicodeCategories;
 
-use crate::html_generator::indent_html;
-use crate::{CONFIG, io::copy_image_to_output_dir, utils::build_rel_prefix};
+use crate::highlight::highlight_code_block;
+use crate::html_generator::{footnote_number, indent_html, unique_heading_slug};
+use crate::{
+    config::current_config,
+    io::copy_image_to_output_dir,
+    utils::{build_rel_prefix, is_relative_url, percent_encode_url, slugify},
+};
 
 pub trait ToHtml {
     /// Converts the implementing type to an String representing its HTML equivalent.
     fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String;
 }
 
+pub trait ToMarkdown {
+    /// Converts the implementing type back to normalized markdown, which parses to the same
+    /// elements.
+    fn to_markdown(&self) -> String;
+}
+
 /// Represents the different types of tokens that can be found in a markdown line.
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     Text(String),
-    EmphasisRun { delimiter: char, length: usize },
+    EmphasisRun {
+        delimiter: char,
+        length: usize,
+    },
     Punctuation(String),
     OpenBracket,
     CloseBracket,
     OpenParenthesis,
     CloseParenthesis,
     TableCellSeparator,
-    OrderedListMarker(String),
+    /// An ordered list marker's number or letters, its kind, and its delimiter (`.` or `)`).
+    OrderedListMarker(String, ListMarkerKind, char),
     Whitespace,
-    CodeTick,
-    CodeFence,
-    ThematicBreak,
+    /// A run of one or two backticks, which opens or closes a code span.
+    CodeTick(usize),
+    /// A run of three or more backticks or tildes, which opens or closes a fenced code block.
+    CodeFence {
+        delimiter: char,
+        length: usize,
+    },
+    /// A line that is a thematic break (i.e. `---`, `* * *`), holding its source text so that it
+    /// can be restored inside code blocks.
+    ThematicBreak(String),
     Escape(String),
-    Tab,
+    /// A tab character, or a run of `tab_size` spaces when `literal` is false.
+    Tab {
+        literal: bool,
+    },
     Newline,
+    /// A hard line break between two lines of a paragraph, from a line ending in two or more
+    /// spaces or a backslash.
+    HardBreak,
     BlockQuoteMarker,
     RawHtmlTag(String),
 }
 
+/// Represents the style of marker used for an ordered list item (i.e. `1.`, `a.`, or `iv.`).
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum ListMarkerKind {
+    Numeric,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl ListMarkerKind {
+    /// Returns the number represented by a list marker of this kind (i.e. `c` -> 3, `iv` -> 4).
+    ///
+    /// # Arguments
+    /// * `marker` - The marker text, without the trailing `.`.
+    ///
+    /// # Returns
+    /// The value of the marker, or `None` if the marker isn't valid for this kind.
+    pub fn marker_value(&self, marker: &str) -> Option<usize> {
+        match self {
+            ListMarkerKind::Numeric => marker.parse::<usize>().ok(),
+            ListMarkerKind::LowerAlpha | ListMarkerKind::UpperAlpha => {
+                let mut chars = marker.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(letter), None) if letter.is_ascii_alphabetic() => {
+                        Some((letter.to_ascii_lowercase() as u8 - b'a') as usize + 1)
+                    }
+                    _ => None,
+                }
+            }
+            ListMarkerKind::LowerRoman | ListMarkerKind::UpperRoman => roman_to_number(marker),
+        }
+    }
+
+    /// Returns the value of the HTML `type` attribute for an `<ol>` using this kind of marker.
+    pub fn html_type(&self) -> &'static str {
+        match self {
+            ListMarkerKind::Numeric => "1",
+            ListMarkerKind::LowerAlpha => "a",
+            ListMarkerKind::UpperAlpha => "A",
+            ListMarkerKind::LowerRoman => "i",
+            ListMarkerKind::UpperRoman => "I",
+        }
+    }
+}
+
+/// Represents the type of a GitHub-style callout, i.e. `> [!NOTE]`.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum CalloutKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl CalloutKind {
+    /// Returns the callout kind with the given name, ignoring case.
+    ///
+    /// # Arguments
+    /// * `name` - The name between `[!` and `]`, i.e. `NOTE`.
+    ///
+    /// # Returns
+    /// The matching kind, or `None` if the name isn't a known callout type.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "note" => Some(CalloutKind::Note),
+            "tip" => Some(CalloutKind::Tip),
+            "important" => Some(CalloutKind::Important),
+            "warning" => Some(CalloutKind::Warning),
+            "caution" => Some(CalloutKind::Caution),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the kind as shown in the callout's title.
+    pub fn title(&self) -> &'static str {
+        match self {
+            CalloutKind::Note => "Note",
+            CalloutKind::Tip => "Tip",
+            CalloutKind::Important => "Important",
+            CalloutKind::Warning => "Warning",
+            CalloutKind::Caution => "Caution",
+        }
+    }
+
+    /// Returns the icon shown before the callout's title.
+    pub fn icon(&self) -> &'static str {
+        match self {
+            CalloutKind::Note => "ℹ️",
+            CalloutKind::Tip => "💡",
+            CalloutKind::Important => "❗",
+            CalloutKind::Warning => "⚠️",
+            CalloutKind::Caution => "🛑",
+        }
+    }
+}
+
+/// Converts a roman numeral (in either case) to a number, returning `None` if the string contains
+/// anything other than roman numeral characters.
+pub fn roman_to_number(numeral: &str) -> Option<usize> {
+    let values = numeral
+        .chars()
+        .map(|ch| match ch.to_ascii_lowercase() {
+            'i' => Some(1),
+            'v' => Some(5),
+            'x' => Some(10),
+            'l' => Some(50),
+            'c' => Some(100),
+            'd' => Some(500),
+            'm' => Some(1000),
+            _ => None,
+        })
+        .collect::<Option<Vec<usize>>>()?;
+
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut total = 0;
+    for (i, value) in values.iter().enumerate() {
+        match values.get(i + 1) {
+            Some(next) if next > value => total -= *value as isize,
+            _ => total += *value as isize,
+        }
+    }
+
+    usize::try_from(total).ok().filter(|total| *total > 0)
+}
+
+/// Converts a number to an uppercase roman numeral, the inverse of [`roman_to_number`].
+pub fn number_to_roman(mut number: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut numeral = String::new();
+    for (value, symbol) in NUMERALS {
+        while number >= value {
+            numeral.push_str(symbol);
+            number -= value;
+        }
+    }
+
+    numeral
+}
+
 impl From<String> for Token {
     fn from(s: String) -> Self {
         Token::Text(s.to_string())
@@ -41,7 +230,8 @@ impl From<String> for Token {
 }
 
 /// Represents block-level markdown elements.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "type")]
 pub enum MdBlockElement {
     Header {
         level: u8,
@@ -52,15 +242,24 @@ pub enum MdBlockElement {
     },
     CodeBlock {
         language: Option<String>,
+        /// The raw remainder of the info string after the language, kept verbatim.
+        info: String,
+        /// The attributes from a `{...}` block at the end of the info string.
+        attributes: CodeBlockAttributes,
         lines: Vec<String>,
     },
     ThematicBreak,
     UnorderedList {
         items: Vec<MdListItem>,
+        /// Whether any of the items are separated by blank lines, in which case each item's
+        /// content is wrapped in a `<p>`.
+        loose: bool,
     },
     OrderedList {
         starting_num: usize,
+        kind: ListMarkerKind,
         items: Vec<MdListItem>,
+        loose: bool,
     },
     Table {
         headers: Vec<MdTableCell>,
@@ -68,10 +267,36 @@ pub enum MdBlockElement {
     },
     BlockQuote {
         content: Vec<MdBlockElement>,
+        citation: Option<Vec<MdInlineElement>>,
     },
     RawHtml {
         content: String,
     },
+    /// A footnote's note (`[^label]: The note`). These are rendered together at the end of the
+    /// page rather than where they are defined.
+    FootnoteDefinition {
+        label: String,
+        content: Vec<MdInlineElement>,
+    },
+    /// A PHP Markdown Extra style definition list, made of term lines followed by `: definition`
+    /// lines.
+    DefinitionList {
+        items: Vec<MdDefinitionItem>,
+    },
+    /// Display math between `$$` lines, kept as written for KaTeX to render.
+    MathBlock {
+        content: String,
+    },
+    /// A collapsible block, written as a blockquote starting with `[!details] Summary`.
+    Details {
+        summary: Vec<MdInlineElement>,
+        content: Vec<MdBlockElement>,
+    },
+    /// A GitHub-style callout, written as a blockquote starting with `[!TYPE]`.
+    Callout {
+        kind: CalloutKind,
+        content: Vec<MdBlockElement>,
+    },
 }
 
 impl ToHtml for MdBlockElement {
@@ -83,14 +308,16 @@ impl ToHtml for MdBlockElement {
                     .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
                     .collect::<String>();
 
-                let id = content
+                let heading_text = content
                     .iter()
                     .map(MdInlineElement::to_plain_text)
                     .collect::<String>();
 
-                let id = clean_id(id);
+                let id = unique_heading_slug(&heading_text);
 
-                format!("\n<h{level} id=\"{id}\">{inner_html}</h{level}>\n")
+                format!(
+                    "\n<h{level} id=\"{id}\">{inner_html}<a class=\"heading-anchor\" href=\"#{id}\">#</a></h{level}>\n"
+                )
             }
             MdBlockElement::Paragraph { content } => {
                 let inner_html = content
@@ -99,17 +326,27 @@ impl ToHtml for MdBlockElement {
                     .collect::<String>();
                 format!("<p>{inner_html}</p>")
             }
-            MdBlockElement::CodeBlock { language, lines } => {
+            MdBlockElement::CodeBlock {
+                language,
+                lines,
+                attributes,
+                ..
+            } => {
                 let language_class = match language {
                     Some(language) => format!("language-{language}"),
                     None => "language-none".to_string(),
                 };
+                let extra_classes = attributes.class_suffix();
+                let extra_attrs = attributes.html_attributes();
 
-                if CONFIG.get().unwrap().html.use_prism {
+                let config = &current_config();
+                let code_block = if config.html.syntect_enabled() {
+                    highlight_code_block(lines, language.as_deref(), attributes)
+                } else if config.html.prism_enabled() {
                     let code = lines.join("\n");
 
                     format!(
-                        "<pre class=\"{language_class} line-numbers\" style=\"white-space: pre-wrap;\" data-prismjs-copy=\"📋\">\n<code class=\"{language_class} line-numbers\">{code}</code></pre>"
+                        "<pre class=\"{language_class} line-numbers{extra_classes}\"{extra_attrs} style=\"white-space: pre-wrap;\" data-prismjs-copy=\"📋\">\n<code class=\"{language_class} line-numbers\">{code}</code></pre>"
                     )
                 } else {
                     let code = lines
@@ -117,14 +354,26 @@ impl ToHtml for MdBlockElement {
                         .map(|line| format!("<code class=\"non_prism\">{line}</code>"))
                         .collect::<String>();
 
-                    format!("<pre class=\"non_prism\">{code}</pre>")
+                    format!("<pre class=\"non_prism{extra_classes}\"{extra_attrs}>{code}</pre>")
+                };
+
+                let collapse_lines = config.html.code_collapse_lines;
+                if collapse_lines > 0 && lines.len() > collapse_lines {
+                    // The block itself isn't indented, since that would change its contents
+                    let toggle = indent_html(
+                        "<label class=\"code-collapse-toggle\"><input type=\"checkbox\"></label>",
+                        1,
+                    );
+                    format!("<div class=\"code-collapse\">\n{code_block}\n{toggle}\n</div>")
+                } else {
+                    code_block
                 }
             }
             MdBlockElement::ThematicBreak => "<hr>".to_string(),
-            MdBlockElement::UnorderedList { items } => {
+            MdBlockElement::UnorderedList { items, loose } => {
                 let inner_items = items
                     .iter()
-                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
+                    .map(|item| item.to_html_in_list(*loose, output_dir, input_dir, html_rel_path))
                     .collect::<String>();
 
                 let inner_items = indent_html(&inner_items, 1);
@@ -133,102 +382,509 @@ impl ToHtml for MdBlockElement {
             MdBlockElement::OrderedList {
                 items,
                 starting_num,
+                kind,
+                loose,
             } => {
                 let inner_items = items
                     .iter()
-                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
+                    .map(|item| item.to_html_in_list(*loose, output_dir, input_dir, html_rel_path))
                     .collect::<String>();
 
                 let inner_items = indent_html(&inner_items, 1);
-                format!("<ol start=\"{starting_num}\">\n{inner_items}\n</ol>")
+                let start_attr = ordered_list_start_attr(*starting_num);
+                let type_attr = ordered_list_type_attr(kind);
+                format!("<ol{start_attr}{type_attr}>\n{inner_items}\n</ol>")
             }
             MdBlockElement::Table { headers, body } => {
-                let header_html = headers
-                    .iter()
-                    .map(|cell| cell.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<Vec<_>>()
-                    .join("\n");
+                // Each row is rendered on its own line, with each cell indented one level below it
+                let render_row = |row: &[MdTableCell]| {
+                    let cell_html = row
+                        .iter()
+                        .map(|cell| cell.to_html(output_dir, input_dir, html_rel_path))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+
+                    let cell_html = indent_html(&cell_html, 1);
+
+                    format!("<tr>\n{cell_html}\n</tr>")
+                };
 
-                let header_html = indent_html(&header_html, 3);
+                let header_html = indent_html(&render_row(headers), 1);
+                let mut sections = format!("<thead>\n{header_html}\n</thead>");
 
-                let body_html = body
+                // GFM omits the <tbody> entirely for tables without any body rows
+                if !body.is_empty() {
+                    let body_html = body
+                        .iter()
+                        .map(|row| render_row(row))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    let body_html = indent_html(&body_html, 1);
+                    sections.push_str(&format!("\n<tbody>\n{body_html}\n</tbody>"));
+                }
+
+                format!("<table>\n{}\n</table>", indent_html(&sections, 1))
+            }
+            MdBlockElement::BlockQuote { content, citation } => {
+                let inner_html = content
                     .iter()
-                    .map(|row| {
-                        let cell_html = row
-                            .iter()
-                            .map(|cell| cell.to_html(output_dir, input_dir, html_rel_path))
-                            .collect::<Vec<_>>()
-                            .join("\n");
+                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                    .collect::<String>();
 
-                        let cell_html = indent_html(&cell_html, 1);
+                match citation {
+                    Some(citation) => {
+                        let citation_html = citation
+                            .iter()
+                            .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                            .collect::<String>();
 
-                        format!("<tr>\n{cell_html}\n</tr>")
-                    })
+                        format!(
+                            "<figure>\n<blockquote>\n{inner_html}\n</blockquote>\n<figcaption>&mdash; <cite>{citation_html}</cite></figcaption>\n</figure>"
+                        )
+                    }
+                    None => format!("<blockquote>\n{inner_html}\n</blockquote>"),
+                }
+            }
+            MdBlockElement::DefinitionList { items } => {
+                let items_html = items
+                    .iter()
+                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
                     .collect::<Vec<_>>()
                     .join("\n");
 
-                let body_html = indent_html(&body_html, 2);
+                format!("<dl>\n{}\n</dl>", indent_html(&items_html, 1))
+            }
+            MdBlockElement::RawHtml { content } => {
+                format!("{}\n", content)
+            }
+            MdBlockElement::MathBlock { content } => format!(
+                "<div class=\"math math-display\">\\[\n{}\n\\]</div>",
+                escape_math(content)
+            ),
+            MdBlockElement::Details { summary, content } => {
+                let summary_html = summary
+                    .iter()
+                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                    .collect::<String>();
+                let inner_html = content
+                    .iter()
+                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                    .collect::<String>();
 
-                format!(
-                    "<table>\n\t<thead>\n\t\t<tr>\n{header_html}\n\t\t</tr>\n\t</thead>\n\t<tbody>\n{body_html}\n\t</tbody>\n</table>"
-                )
+                format!("<details>\n<summary>{summary_html}</summary>\n{inner_html}\n</details>")
             }
-            MdBlockElement::BlockQuote { content } => {
+            MdBlockElement::Callout { kind, content } => {
                 let inner_html = content
                     .iter()
                     .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
                     .collect::<String>();
+                let class = kind.title().to_ascii_lowercase();
 
-                format!("<blockquote>\n{inner_html}\n</blockquote>")
+                format!(
+                    "<div class=\"callout callout-{class}\">\n<p class=\"callout-title\"><span class=\"callout-icon\">{}</span>{}</p>\n{inner_html}\n</div>",
+                    kind.icon(),
+                    kind.title()
+                )
             }
-            MdBlockElement::RawHtml { content } => {
-                format!("{}\n", content)
+            MdBlockElement::FootnoteDefinition { .. } => String::new(),
+        }
+    }
+}
+
+impl ToMarkdown for MdBlockElement {
+    fn to_markdown(&self) -> String {
+        match self {
+            MdBlockElement::Header { level, content } => {
+                format!("{} {}", "#".repeat(*level as usize), content.to_markdown())
             }
+            MdBlockElement::Paragraph { content } => content.to_markdown(),
+            MdBlockElement::CodeBlock {
+                language,
+                info,
+                lines,
+                ..
+            } => {
+                // Tags in code blocks are stored escaped, but are written out as they were typed
+                let code = lines
+                    .iter()
+                    .map(|line| line.replace("&lt;", "<").replace("&gt;", ">"))
+                    .collect::<Vec<_>>();
+
+                // The fence has to be longer than any run of backticks that starts a line
+                let longest_run = code
+                    .iter()
+                    .map(|line| {
+                        line.trim_start()
+                            .chars()
+                            .take_while(|&ch| ch == '`')
+                            .count()
+                    })
+                    .max()
+                    .unwrap_or(0);
+                let fence = "`".repeat(longest_run.max(2) + 1);
+
+                let info_string = match (language, info.is_empty()) {
+                    (Some(language), true) => language.clone(),
+                    (Some(language), false) => format!("{language} {info}"),
+                    (None, _) => info.clone(),
+                };
+
+                if code.is_empty() {
+                    format!("{fence}{info_string}\n{fence}")
+                } else {
+                    format!("{fence}{info_string}\n{}\n{fence}", code.join("\n"))
+                }
+            }
+            MdBlockElement::ThematicBreak => "---".to_string(),
+            MdBlockElement::UnorderedList { .. } | MdBlockElement::OrderedList { .. } => {
+                list_block_to_markdown(self, false)
+            }
+            MdBlockElement::Table { headers, body } => {
+                let rows = std::iter::once(headers)
+                    .chain(body)
+                    .map(|row| {
+                        row.iter()
+                            .map(|cell| cell.content.to_markdown().trim().to_string())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+
+                // Every column is padded to its widest cell, and at least as wide as `---`
+                let widths = (0..headers.len())
+                    .map(|i| {
+                        rows.iter()
+                            .filter_map(|row| row.get(i))
+                            .map(|cell| cell.chars().count())
+                            .fold(3, usize::max)
+                    })
+                    .collect::<Vec<_>>();
+
+                let render_row = |row: &[String]| {
+                    let cells = row
+                        .iter()
+                        .enumerate()
+                        .map(|(i, cell)| {
+                            let width = widths.get(i).copied().unwrap_or(0);
+                            format!("{cell:<width$}")
+                        })
+                        .collect::<Vec<_>>();
+                    format!("| {} |", cells.join(" | "))
+                };
+
+                let delimiter_row = headers
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| match cell.alignment {
+                        TableAlignment::Left => format!(":{}", "-".repeat(width - 1)),
+                        TableAlignment::Center => format!(":{}:", "-".repeat(width - 2)),
+                        TableAlignment::Right => format!("{}:", "-".repeat(width - 1)),
+                        TableAlignment::None => "-".repeat(*width),
+                    })
+                    .collect::<Vec<_>>();
+
+                std::iter::once(render_row(&rows[0]))
+                    .chain(std::iter::once(format!(
+                        "| {} |",
+                        delimiter_row.join(" | ")
+                    )))
+                    .chain(rows[1..].iter().map(|row| render_row(row)))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            MdBlockElement::BlockQuote { content, citation } => {
+                let mut inner = content.to_markdown();
+                if let Some(citation) = citation {
+                    inner.push_str(&format!("\n-- {}", citation.to_markdown()));
+                }
+
+                quote_lines(&inner)
+            }
+            MdBlockElement::RawHtml { content } => content.trim_end().to_string(),
+            MdBlockElement::FootnoteDefinition { label, content } => {
+                format!("[^{label}]: {}", content.to_markdown())
+            }
+            MdBlockElement::DefinitionList { items } => items
+                .iter()
+                .map(ToMarkdown::to_markdown)
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            MdBlockElement::MathBlock { content } => format!("$$\n{content}\n$$"),
+            MdBlockElement::Details { summary, content } => quote_lines(&format!(
+                "[!details] {}\n{}",
+                summary.to_markdown(),
+                content.to_markdown()
+            )),
+            MdBlockElement::Callout { kind, content } => quote_lines(&format!(
+                "[!{}]\n{}",
+                kind.title().to_ascii_uppercase(),
+                content.to_markdown()
+            )),
+        }
+    }
+}
+
+impl ToMarkdown for [MdBlockElement] {
+    fn to_markdown(&self) -> String {
+        // Two lists in a row would be read back as one list if they used the same markers, so
+        // every other one uses `*` bullets or `)` delimiters instead
+        let mut alternate_markers = false;
+        self.iter()
+            .enumerate()
+            .map(|(i, block)| {
+                let follows_same_list = match (i.checked_sub(1).map(|i| &self[i]), block) {
+                    (
+                        Some(MdBlockElement::UnorderedList { .. }),
+                        MdBlockElement::UnorderedList { .. },
+                    ) => true,
+                    (
+                        Some(MdBlockElement::OrderedList { kind, .. }),
+                        MdBlockElement::OrderedList {
+                            kind: other_kind, ..
+                        },
+                    ) => kind == other_kind,
+                    _ => false,
+                };
+                alternate_markers = follows_same_list && !alternate_markers;
+
+                list_block_to_markdown(block, alternate_markers)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// Prefixes every line with a blockquote marker, leaving blank lines as a bare `>`.
+fn quote_lines(markdown: &str) -> String {
+    markdown
+        .lines()
+        .map(|line| match line {
+            "" => ">".to_string(),
+            _ => format!("> {line}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Converts an unordered or ordered list to markdown.
+///
+/// # Arguments
+/// * `list` - The list to convert.
+/// * `alternate_markers` - Whether to use `*` bullets or `)` delimiters in place of `-` and `.`, to
+///   keep the list separate from a list of the same type right before it.
+fn list_block_to_markdown(list: &MdBlockElement, alternate_markers: bool) -> String {
+    match list {
+        MdBlockElement::UnorderedList { items, loose } => {
+            let bullet = if alternate_markers { "*" } else { "-" };
+            list_to_markdown(items, *loose, |_| bullet.to_string())
         }
+        MdBlockElement::OrderedList {
+            items,
+            starting_num,
+            kind,
+            loose,
+        } => {
+            let delimiter = if alternate_markers { ')' } else { '.' };
+            list_to_markdown(items, *loose, |i| {
+                format!("{}{delimiter}", ordered_list_marker(kind, starting_num + i))
+            })
+        }
+        _ => list.to_markdown(),
     }
 }
 
-/// Cleans the ID string by removing HTML tags and special characters, and replacing spaces and underscores with hyphens.
-fn clean_id(old_id: String) -> String {
-    let mut new_id = String::new();
+/// Converts the items of a list to markdown. Nested lists are indented by `tab_size` spaces below
+/// the item before them, and don't use up a marker.
+///
+/// # Arguments
+/// * `items` - The items of the list.
+/// * `loose` - Whether the items are separated by blank lines.
+/// * `marker` - Builds the marker for the item at the given index, not counting nested lists.
+fn list_to_markdown<F>(items: &[MdListItem], loose: bool, marker: F) -> String
+where
+    F: Fn(usize) -> String,
+{
+    let indent = " ".repeat(current_config().lexer.tab_size);
+    let mut markdown = String::new();
+    let mut item_index = 0;
+
+    for item in items {
+        let is_nested = matches!(
+            item.content,
+            MdBlockElement::UnorderedList { .. } | MdBlockElement::OrderedList { .. }
+        );
+
+        if !markdown.is_empty() {
+            markdown.push_str(if loose && !is_nested { "\n\n" } else { "\n" });
+        }
 
-    let mut in_tag = false;
-    for char in old_id.chars() {
-        if char == '<' {
-            in_tag = true;
-        } else if char == '>' {
-            in_tag = false;
+        if is_nested {
+            let nested = item
+                .content
+                .to_markdown()
+                .lines()
+                .map(|line| match line {
+                    "" => String::new(),
+                    _ => format!("{indent}{line}"),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            markdown.push_str(&nested);
             continue;
         }
 
-        if !in_tag && (char.is_alphanumeric() || char == '_' || char == ' ') {
-            new_id.push(char);
+        let checkbox = match item.checked {
+            Some(true) => "[x] ",
+            Some(false) => "[ ] ",
+            None => "",
+        };
+        markdown.push_str(&format!(
+            "{} {checkbox}{}",
+            marker(item_index),
+            item.content.to_markdown()
+        ));
+        item_index += 1;
+    }
+
+    markdown
+}
+
+/// Builds the marker for an ordered list item with the given value, without the trailing `.`.
+fn ordered_list_marker(kind: &ListMarkerKind, value: usize) -> String {
+    match kind {
+        ListMarkerKind::Numeric => value.to_string(),
+        ListMarkerKind::LowerAlpha => {
+            char::from(b'a' + (value.saturating_sub(1) % 26) as u8).to_string()
+        }
+        ListMarkerKind::UpperAlpha => {
+            char::from(b'A' + (value.saturating_sub(1) % 26) as u8).to_string()
         }
+        ListMarkerKind::LowerRoman => number_to_roman(value).to_ascii_lowercase(),
+        ListMarkerKind::UpperRoman => number_to_roman(value),
+    }
+}
+
+/// Escapes the double quotes in a link or image title, so it can't end the `title` attribute early.
+fn escape_title(title: &str) -> String {
+    title.replace('"', "&quot;")
+}
+
+/// Builds the `start` attribute for an ordered list, which is omitted for lists starting at 1.
+fn ordered_list_start_attr(starting_num: usize) -> String {
+    match starting_num {
+        1 => String::new(),
+        _ => format!(" start=\"{starting_num}\""),
+    }
+}
+
+/// Builds the `type` attribute for an ordered list, which is omitted for numeric lists.
+fn ordered_list_type_attr(kind: &ListMarkerKind) -> String {
+    match kind {
+        ListMarkerKind::Numeric => String::new(),
+        _ => format!(" type=\"{}\"", kind.html_type()),
+    }
+}
+
+/// Represents the attributes given in braces at the end of a code fence's info string, i.e.
+/// ```` ```rust {#example .line-numbers highlight=2-4} ````.
+///
+/// # Fields
+/// * `id` - The id from a `#id` or `id=...` attribute.
+/// * `classes` - The classes from `.class` or `class=...` attributes.
+/// * `data` - Every other attribute as a key/value pair, rendered as `data-key="value"`. Bare
+///   words without a value have an empty value.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct CodeBlockAttributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub data: Vec<(String, String)>,
+}
+
+impl CodeBlockAttributes {
+    /// Builds the classes to append to the code block's `class` attribute, each preceded by a
+    /// space.
+    pub fn class_suffix(&self) -> String {
+        self.classes
+            .iter()
+            .map(|class| format!(" {}", escape_attribute(class)))
+            .collect()
     }
 
-    new_id
-        .replace([' ', '_'], "-")
-        .to_lowercase()
-        .trim_matches('-')
-        .to_string()
+    /// Builds the `id` and `data-*` attributes of the code block, each preceded by a space.
+    pub fn html_attributes(&self) -> String {
+        let id_attr = match &self.id {
+            Some(id) => format!(" id=\"{}\"", escape_attribute(id)),
+            None => String::new(),
+        };
+
+        let data_attrs = self
+            .data
+            .iter()
+            .map(|(key, value)| {
+                // Keys that are already data attributes aren't prefixed twice
+                let key = key.strip_prefix("data-").unwrap_or(key);
+                format!(" data-{key}=\"{}\"", escape_attribute(value))
+            })
+            .collect::<String>();
+
+        format!("{id_attr}{data_attrs}")
+    }
+}
+
+/// Escapes the angle brackets in text, which is stored as it was written so that it can be
+/// written back out as markdown. Entities like `&amp;` were already resolved by the parser, so `&`
+/// is left alone.
+fn escape_text(text: &str) -> String {
+    text.replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Escapes a string for use inside a double-quoted HTML attribute.
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Escapes the characters in math that are significant in HTML. KaTeX reads the text content, so
+/// they reach it unescaped.
+fn escape_math(content: &str) -> String {
+    content
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
 
 /// Represents a list item in markdown, which can contain block elements.
 ///
 /// # Fields
 /// * `content` - The content of the list item, which can be any block-level markdown element.
-#[derive(Debug, PartialEq)]
+/// * `checked` - For task list items (`- [ ]`/`- [x]`), whether the checkbox is checked. `None`
+///   for regular list items.
+#[derive(Debug, PartialEq, Serialize)]
 pub struct MdListItem {
     pub content: MdBlockElement,
+    pub checked: Option<bool>,
 }
 
-impl ToHtml for MdListItem {
-    fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String {
+impl MdListItem {
+    /// Converts the list item to HTML.
+    ///
+    /// # Arguments
+    /// * `loose` - Whether the list containing this item is loose. Paragraphs in items of tight
+    ///   lists are rendered without the surrounding `<p>`.
+    pub fn to_html_in_list(
+        &self,
+        loose: bool,
+        output_dir: &str,
+        input_dir: &str,
+        html_rel_path: &str,
+    ) -> String {
         match &self.content {
-            MdBlockElement::UnorderedList { items } => {
+            MdBlockElement::UnorderedList { items, loose } => {
                 let inner_items = items
                     .iter()
-                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
+                    .map(|item| item.to_html_in_list(*loose, output_dir, input_dir, html_rel_path))
                     .collect::<String>();
                 let inner_items = indent_html(&inner_items, 1);
                 format!("<ul>\n{inner_items}\n</ul>")
@@ -236,26 +892,105 @@ impl ToHtml for MdListItem {
             MdBlockElement::OrderedList {
                 items,
                 starting_num,
+                kind,
+                loose,
             } => {
                 let inner_items = items
                     .iter()
-                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
+                    .map(|item| item.to_html_in_list(*loose, output_dir, input_dir, html_rel_path))
                     .collect::<String>();
-                format!("<ol start=\"{starting_num}\">\n{inner_items}\n</ol>")
+                let start_attr = ordered_list_start_attr(*starting_num);
+                let type_attr = ordered_list_type_attr(kind);
+                format!("<ol{start_attr}{type_attr}>\n{inner_items}\n</ol>")
+            }
+            MdBlockElement::Paragraph { content } if !loose => {
+                let inner_html = content
+                    .iter()
+                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                    .collect::<String>();
+
+                match self.checked {
+                    Some(checked) => {
+                        let checked_attr = if checked { " checked" } else { "" };
+                        format!(
+                            "<li class=\"task-list-item\"><input type=\"checkbox\" disabled{checked_attr}> {inner_html}</li>\n"
+                        )
+                    }
+                    None => format!("<li>{inner_html}</li>\n"),
+                }
             }
             _ => {
                 let inner_html = indent_html(
                     &self.content.to_html(output_dir, input_dir, html_rel_path),
                     1,
                 );
-                format!("<li>\n{inner_html}\n</li>\n")
+
+                match self.checked {
+                    Some(checked) => {
+                        let checked_attr = if checked { " checked" } else { "" };
+                        let checkbox = indent_html(
+                            &format!("<input type=\"checkbox\" disabled{checked_attr}>"),
+                            1,
+                        );
+                        format!("<li class=\"task-list-item\">\n{checkbox}\n{inner_html}\n</li>\n")
+                    }
+                    None => format!("<li>\n{inner_html}\n</li>\n"),
+                }
             }
         }
     }
 }
 
+/// Represents a group of terms in a definition list along with the definitions that follow them.
+///
+/// # Fields
+/// * `terms` - The terms being defined, one per line.
+/// * `definitions` - The definitions shared by all of the terms, one per `: ` line.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct MdDefinitionItem {
+    pub terms: Vec<Vec<MdInlineElement>>,
+    pub definitions: Vec<Vec<MdInlineElement>>,
+}
+
+impl ToHtml for MdDefinitionItem {
+    fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String {
+        let render_inline = |content: &[MdInlineElement]| {
+            content
+                .iter()
+                .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                .collect::<String>()
+        };
+
+        let terms_html = self
+            .terms
+            .iter()
+            .map(|term| format!("<dt>{}</dt>", render_inline(term)));
+        let definitions_html = self
+            .definitions
+            .iter()
+            .map(|definition| format!("<dd>{}</dd>", render_inline(definition)));
+
+        terms_html
+            .chain(definitions_html)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl ToMarkdown for MdDefinitionItem {
+    fn to_markdown(&self) -> String {
+        let terms = self.terms.iter().map(|term| term.to_markdown());
+        let definitions = self
+            .definitions
+            .iter()
+            .map(|definition| format!(": {}", definition.to_markdown()));
+
+        terms.chain(definitions).collect::<Vec<_>>().join("\n")
+    }
+}
+
 /// Represents a cell in a markdown table.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct MdTableCell {
     pub content: Vec<MdInlineElement>,
     pub alignment: TableAlignment,
@@ -284,7 +1019,7 @@ impl ToHtml for MdTableCell {
 }
 
 /// Represents the alignment of table cells in markdown tables.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum TableAlignment {
     Left,
     Center,
@@ -293,7 +1028,8 @@ pub enum TableAlignment {
 }
 
 /// Represents inline markdown elements (text, bold/italic, link, etc.)
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "type")]
 pub enum MdInlineElement {
     Text {
         content: String,
@@ -304,6 +1040,17 @@ pub enum MdInlineElement {
     Italic {
         content: Vec<MdInlineElement>,
     },
+    Strikethrough {
+        content: Vec<MdInlineElement>,
+    },
+    /// Pandoc-style `~subscript~` text.
+    Subscript {
+        content: Vec<MdInlineElement>,
+    },
+    /// Pandoc-style `^superscript^` text.
+    Superscript {
+        content: Vec<MdInlineElement>,
+    },
     Link {
         text: Vec<MdInlineElement>,
         title: Option<String>,
@@ -313,10 +1060,26 @@ pub enum MdInlineElement {
         alt_text: String,
         title: Option<String>,
         url: String,
+        /// Set by a `=WIDTHxHEIGHT` spec after the URL or a trailing `{width=... height=...}`.
+        width: Option<u32>,
+        height: Option<u32>,
     },
     Code {
         content: String,
     },
+    /// `$inline$` or `$$display$$` math, kept as written for KaTeX to render.
+    Math {
+        content: String,
+        display: bool,
+    },
+    LineBreak,
+    /// An inline HTML tag, i.e. `<span class="note">`, which is emitted as written.
+    RawHtml {
+        content: String,
+    },
+    FootnoteReference {
+        label: String,
+    },
     Placeholder {
         ch: char,
         token_position: usize,
@@ -334,7 +1097,7 @@ impl From<String> for MdInlineElement {
 impl ToHtml for MdInlineElement {
     fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String {
         match self {
-            MdInlineElement::Text { content } => content.clone(),
+            MdInlineElement::Text { content } => escape_text(content),
             MdInlineElement::Bold { content } => {
                 let inner_html = content
                     .iter()
@@ -349,11 +1112,34 @@ impl ToHtml for MdInlineElement {
                     .collect::<String>();
                 format!("<i>{}</i>", inner_html)
             }
+            MdInlineElement::Strikethrough { content } => {
+                let inner_html = content
+                    .iter()
+                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                    .collect::<String>();
+                format!("<del>{}</del>", inner_html)
+            }
+            MdInlineElement::Subscript { content } => {
+                let inner_html = content
+                    .iter()
+                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                    .collect::<String>();
+                format!("<sub>{}</sub>", inner_html)
+            }
+            MdInlineElement::Superscript { content } => {
+                let inner_html = content
+                    .iter()
+                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
+                    .collect::<String>();
+                format!("<sup>{}</sup>", inner_html)
+            }
             MdInlineElement::Link { text, title, url } => {
                 let label_html = text
                     .iter()
                     .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
                     .collect::<String>();
+                let url = percent_encode_url(url);
+                let title = title.as_deref().map(escape_title);
 
                 if url.contains("youtube.com") && url.contains("v=") {
                     let video_id = url
@@ -371,15 +1157,21 @@ impl ToHtml for MdInlineElement {
                     );
                 }
 
-                // Links to external URLs will open in a new tab
+                // Links to external URLs are marked, and open in a new tab if configured to
                 if url.starts_with("http") {
+                    let target_attrs = if current_config().html.external_links_new_tab {
+                        " target=\"_blank\" rel=\"noopener noreferrer\""
+                    } else {
+                        ""
+                    };
+
                     match title {
                         Some(text) => {
                             format!(
-                                "<a href=\"{url}\" title=\"{text}\" target=\"_blank\">{label_html}⮺</a>"
+                                "<a href=\"{url}\" title=\"{text}\"{target_attrs}>{label_html}⮺</a>"
                             )
                         }
-                        None => format!("<a href=\"{url}\" target=\"_blank\">{label_html}⮺</a>"),
+                        None => format!("<a href=\"{url}\"{target_attrs}>{label_html}⮺</a>"),
                     }
                 } else {
                     match title {
@@ -394,31 +1186,79 @@ impl ToHtml for MdInlineElement {
                 alt_text,
                 title,
                 url,
+                width,
+                height,
             } => {
-                // If the image uses a relative path, copy it to the output directory
-                let media_url = if !url.starts_with("http") {
+                // If the image uses a relative path, copy it to the output directory. Without one,
+                // as when rendering through `markdown_to_html`, the URL is left as written.
+                let media_url = if is_relative_url(url) && !output_dir.is_empty() {
                     if let Err(e) = copy_image_to_output_dir(url, output_dir, input_dir) {
                         warn!("Unable to copy image {url}: {e}");
                     }
 
                     // Update the URL to point to the copied image in the output directory
-                    let url = url.rsplit('/').next().unwrap_or(url);
-
-                    let rel_prefix = build_rel_prefix(html_rel_path);
+                    let file_name = url.rsplit('/').next().unwrap_or(url);
+                    let media_path = build_rel_prefix(html_rel_path)
+                        .join("media")
+                        .join(file_name);
 
-                    &format!("./{}/media/{}", rel_prefix.to_string_lossy(), url)
+                    &media_path.to_string_lossy().replace('\\', "/")
                 } else {
                     url
                 };
+                let media_url = percent_encode_url(media_url);
+                let title = title.as_deref().map(escape_title);
+
+                let mut dimension_attrs = String::new();
+                if let Some(width) = width {
+                    dimension_attrs.push_str(&format!(" width=\"{width}\""));
+                }
+                if let Some(height) = height {
+                    dimension_attrs.push_str(&format!(" height=\"{height}\""));
+                }
 
                 match title {
-                    Some(text) => {
-                        format!("<img src=\"{media_url}\" alt=\"{alt_text}\" title=\"{text}\"/>")
-                    }
-                    None => format!("<img src=\"{media_url}\" alt=\"{alt_text}\"/>"),
+                    Some(text) => format!(
+                        "<img src=\"{media_url}\" alt=\"{}\" title=\"{text}\"{dimension_attrs}/>",
+                        escape_text(alt_text)
+                    ),
+                    None => format!(
+                        "<img src=\"{media_url}\" alt=\"{}\"{dimension_attrs}/>",
+                        escape_text(alt_text)
+                    ),
                 }
             }
             MdInlineElement::Code { content } => format!("<code>{content}</code>"),
+            MdInlineElement::Math {
+                content,
+                display: false,
+            } => format!(
+                "<span class=\"math math-inline\">\\({}\\)</span>",
+                escape_math(content)
+            ),
+            MdInlineElement::Math {
+                content,
+                display: true,
+            } => format!(
+                "<span class=\"math math-display\">\\[{}\\]</span>",
+                escape_math(content)
+            ),
+            MdInlineElement::LineBreak => "<br>\n".to_string(),
+            MdInlineElement::RawHtml { content } => content.clone(),
+            MdInlineElement::FootnoteReference { label } => {
+                let (number, is_first_reference) = footnote_number(label);
+                let slug = slugify(label);
+                // Only the first reference gets an id, which the footnote's backlink points to
+                let id_attr = if is_first_reference {
+                    format!(" id=\"fnref-{slug}\"")
+                } else {
+                    String::new()
+                };
+
+                format!(
+                    "<sup class=\"footnote-ref\"><a href=\"#fn-{slug}\"{id_attr}>{number}</a></sup>"
+                )
+            }
             MdInlineElement::Placeholder {
                 ch,
                 token_position: _,
@@ -427,11 +1267,115 @@ impl ToHtml for MdInlineElement {
     }
 }
 
+impl ToMarkdown for MdInlineElement {
+    fn to_markdown(&self) -> String {
+        match self {
+            MdInlineElement::Text { content } => content.clone(),
+            MdInlineElement::Bold { content } => format!("**{}**", content.to_markdown()),
+            MdInlineElement::Italic { content } => format!("*{}*", content.to_markdown()),
+            MdInlineElement::Strikethrough { content } => format!("~~{}~~", content.to_markdown()),
+            MdInlineElement::Subscript { content } => format!("~{}~", content.to_markdown()),
+            MdInlineElement::Superscript { content } => format!("^{}^", content.to_markdown()),
+            MdInlineElement::Link { text, title, url } => {
+                // Autolinks are written in their shortest form
+                let is_autolink = title.is_none()
+                    && matches!(text.as_slice(), [MdInlineElement::Text { content }] if content == url);
+                if is_autolink && url.starts_with("http") {
+                    return format!("<{url}>");
+                }
+
+                format!(
+                    "[{}]({}{})",
+                    text.to_markdown(),
+                    link_destination(url),
+                    link_title(title)
+                )
+            }
+            MdInlineElement::Image {
+                alt_text,
+                title,
+                url,
+                width,
+                height,
+            } => {
+                let mut dimensions = Vec::new();
+                if let Some(width) = width {
+                    dimensions.push(format!("width={width}"));
+                }
+                if let Some(height) = height {
+                    dimensions.push(format!("height={height}"));
+                }
+                let dimensions = match dimensions.is_empty() {
+                    true => String::new(),
+                    false => format!("{{{}}}", dimensions.join(" ")),
+                };
+
+                format!(
+                    "![{alt_text}]({}{}){dimensions}",
+                    link_destination(url),
+                    link_title(title)
+                )
+            }
+            MdInlineElement::Code { content } => {
+                let ticks = if content.contains('`') { "``" } else { "`" };
+                // Padding is stripped from code spans, so content that needs it gets an extra space
+                let needs_padding = content.starts_with('`')
+                    || content.ends_with('`')
+                    || (content.starts_with(' ')
+                        && content.ends_with(' ')
+                        && !content.chars().all(|ch| ch == ' '));
+
+                match needs_padding {
+                    true => format!("{ticks} {content} {ticks}"),
+                    false => format!("{ticks}{content}{ticks}"),
+                }
+            }
+            MdInlineElement::Math {
+                content,
+                display: false,
+            } => format!("${content}$"),
+            MdInlineElement::Math {
+                content,
+                display: true,
+            } => format!("$${content}$$"),
+            MdInlineElement::LineBreak => "\\\n".to_string(),
+            MdInlineElement::RawHtml { content } => content.clone(),
+            MdInlineElement::FootnoteReference { label } => format!("[^{label}]"),
+            MdInlineElement::Placeholder {
+                ch,
+                token_position: _,
+            } => ch.to_string(),
+        }
+    }
+}
+
+impl ToMarkdown for [MdInlineElement] {
+    fn to_markdown(&self) -> String {
+        self.iter().map(ToMarkdown::to_markdown).collect()
+    }
+}
+
+/// Writes a link or image destination, wrapped in `<...>` when it's empty or contains spaces.
+fn link_destination(url: &str) -> String {
+    match url.is_empty() || url.contains(' ') {
+        true => format!("<{url}>"),
+        false => url.to_string(),
+    }
+}
+
+/// Writes the ` "title"` part of a link or image, or nothing if it has no title.
+fn link_title(title: &Option<String>) -> String {
+    match title {
+        Some(title) => format!(" \"{}\"", title.replace('"', "\\\"")),
+        None => String::new(),
+    }
+}
+
 impl MdInlineElement {
     /// Converts the inline element to a plain text representation.
     pub fn to_plain_text(&self) -> String {
         match self {
-            MdInlineElement::Text { content } => content.clone(),
+            MdInlineElement::Text { content } => escape_text(content),
             MdInlineElement::Bold { content } => content
                 .iter()
                 .map(MdInlineElement::to_plain_text)
@@ -442,13 +1386,26 @@ impl MdInlineElement {
                 .map(MdInlineElement::to_plain_text)
                 .collect::<Vec<_>>()
                 .join(""),
+            MdInlineElement::Strikethrough { content }
+            | MdInlineElement::Subscript { content }
+            | MdInlineElement::Superscript { content } => content
+                .iter()
+                .map(MdInlineElement::to_plain_text)
+                .collect::<Vec<_>>()
+                .join(""),
             MdInlineElement::Link { text, .. } => text
                 .iter()
                 .map(MdInlineElement::to_plain_text)
                 .collect::<Vec<_>>()
                 .join(""),
-            MdInlineElement::Image { alt_text, .. } => alt_text.clone(),
-            MdInlineElement::Code { content } => content.clone(),
+            MdInlineElement::Image { alt_text, .. } => escape_text(alt_text),
+            MdInlineElement::Code { content } | MdInlineElement::Math { content, .. } => {
+                content.clone()
+            }
+            MdInlineElement::LineBreak => " ".to_string(),
+            MdInlineElement::RawHtml { .. } | MdInlineElement::FootnoteReference { .. } => {
+                String::new()
+            }
             MdInlineElement::Placeholder {
                 ch,
                 token_position: _,
@@ -496,8 +1453,11 @@ impl TokenCursor {
     ///
     /// # Returns
     /// An `Option` containing a reference to the token if it exists, or `None` if it is out of
-    pub fn _peek_behind(&self, n: usize) -> Option<&Token> {
-        self.tokens.get(self.current_position - n)
+    /// bounds.
+    pub fn peek_behind(&self, n: usize) -> Option<&Token> {
+        self.current_position
+            .checked_sub(n)
+            .and_then(|position| self.tokens.get(position))
     }
 
     /// Moves the cursor forward one position.
@@ -514,7 +1474,7 @@ impl TokenCursor {
     ///
     /// # Panics
     /// Panics if the position is out of bounds for the token list.
-    pub fn _set_position(&mut self, pos: usize) {
+    pub fn set_position(&mut self, pos: usize) {
         if pos < self.tokens.len() {
             self.current_position = pos;
         } else {
@@ -534,8 +1494,8 @@ impl TokenCursor {
 }
 
 /// Manages Delimiter runs in a markdown document.
-/// A delimiter run is a sequence of the same character (e.g., `*`, `_`, `~`) that can be used for
-/// bold/italic writing.
+/// A delimiter run is a sequence of the same character (e.g., `*`, `_`, `~`, `^`) that can be used
+/// for bold/italic, strikethrough, or subscript/superscript writing.
 ///
 /// # Fields
 /// * `ch` - The character that represents the delimiter (e.g., `*`, `_`, `~`).
@@ -559,29 +1519,35 @@ pub struct Delimiter {
 }
 
 impl Delimiter {
-    /// Determines whether a delimiter is "Left", "Right", or "Both" flanking
-    /// For exmample, it is left flanking if it's not followed by non-whitespace, and either:
+    /// Determines whether a delimiter is "Left", "Right", or "Both" flanking, from the characters
+    /// directly before and after the run. For example, it is left flanking if it's not followed by
+    /// whitespace, and either:
     /// 1. Not followed by punctuation
     /// 2. Followed by punctuation and preceded by whitespace or punctuation
     ///
+    /// The start and end of the line count as whitespace. Whitespace and punctuation are checked
+    /// per Unicode character, so they're found inside of text tokens too (i.e. a non-breaking
+    /// space, or a symbol like `=`).
+    ///
     /// Modifies the `can_open` and `can_close` fields in-place based on the classification.
     ///
-    /// See i<https://spec.commonmark.org/0.31.2/#left-flanking-delimiter-run> for more information.
+    /// See <https://spec.commonmark.org/0.31.2/#left-flanking-delimiter-run> for more information.
     ///
     /// # Arguments
-    /// * `tokens` - A slice of tokens to classify the delimiter against.
-    pub fn classify_flanking(&mut self, tokens: &[Token]) {
-        let before = if self.token_position > 0 {
-            Some(&tokens[self.token_position - 1])
-        } else {
-            None
-        };
-
-        let after = tokens.get(self.token_position + 1);
-        let followed_by_whitespace = after.is_none_or(is_whitespace);
+    /// * `cursor` - A cursor over the tokens the delimiter is in, which is moved to the delimiter.
+    pub fn classify_flanking(&mut self, cursor: &mut TokenCursor) {
+        cursor.set_position(self.token_position);
+        let before = cursor
+            .peek_behind(1)
+            .and_then(|token| token_source(token).chars().next_back());
+        let after = cursor
+            .peek_ahead(1)
+            .and_then(|token| token_source(token).chars().next());
+
+        let followed_by_whitespace = after.is_none_or(char::is_whitespace);
         let followed_by_punctuation = after.is_some_and(is_punctuation);
 
-        let preceded_by_whitespace = before.is_none_or(is_whitespace);
+        let preceded_by_whitespace = before.is_none_or(char::is_whitespace);
         let preceded_by_punctuation = before.is_some_and(is_punctuation);
 
         let is_left_flanking = if followed_by_whitespace {
@@ -606,7 +1572,7 @@ impl Delimiter {
         let is_underscore = delimiter_char == '_';
 
         if is_underscore {
-            self.can_open = is_left_flanking && (!is_right_flanking || followed_by_punctuation);
+            self.can_open = is_left_flanking && (!is_right_flanking || preceded_by_punctuation);
 
             self.can_close = is_right_flanking && (!is_left_flanking || followed_by_punctuation);
         } else {
@@ -616,29 +1582,39 @@ impl Delimiter {
     }
 }
 
-/// Helper function to determine if a token is whitespace or newline.
+/// Returns the source text of a token, used to find the characters around a delimiter run.
 ///
 /// # Arguments
-/// * `token` - The token to check.
-fn is_whitespace(token: &Token) -> bool {
-    matches!(token, Token::Newline | Token::Whitespace)
+/// * `token` - The token to get the source text of.
+fn token_source(token: &Token) -> String {
+    match token {
+        Token::Text(text)
+        | Token::Punctuation(text)
+        | Token::ThematicBreak(text)
+        | Token::RawHtmlTag(text) => text.clone(),
+        Token::Escape(escaped) => format!("\\{escaped}"),
+        Token::OrderedListMarker(marker, _, delimiter) => format!("{marker}{delimiter}"),
+        Token::EmphasisRun { delimiter, length } | Token::CodeFence { delimiter, length } => {
+            delimiter.to_string().repeat(*length)
+        }
+        Token::CodeTick(length) => "`".repeat(*length),
+        Token::OpenBracket => String::from("["),
+        Token::CloseBracket => String::from("]"),
+        Token::OpenParenthesis => String::from("("),
+        Token::CloseParenthesis => String::from(")"),
+        Token::TableCellSeparator => String::from("|"),
+        Token::BlockQuoteMarker => String::from(">"),
+        Token::Whitespace => String::from(" "),
+        Token::Tab { .. } => String::from("\t"),
+        Token::Newline | Token::HardBreak => String::from("\n"),
+    }
 }
 
-/// Helper function to determine if a token is punctuation.
+/// Helper function to determine if a character is punctuation, which per CommonMark is any ASCII
+/// punctuation character or any Unicode punctuation or symbol.
 ///
 /// # Arguments
-/// * `token` - The token to check.
-fn is_punctuation(token: &Token) -> bool {
-    matches!(
-        token,
-        Token::Punctuation(_)
-            | Token::EmphasisRun {
-                delimiter: _,
-                length: _
-            }
-            | Token::OpenBracket
-            | Token::CloseBracket
-            | Token::OpenParenthesis
-            | Token::CloseParenthesis
-    )
+/// * `ch` - The character to check.
+fn is_punctuation(ch: char) -> bool {
+    ch.is_ascii_punctuation() || ch.is_punctuation() || ch.is_symbol()
 }
@@ -1,8 +1,12 @@
 use std::{
+    cmp::Ordering,
     mem::take,
     path::{Path, PathBuf},
 };
 
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use log::warn;
+
 /// Utility function for pushing a String buffer to a generic collection.
 ///
 /// This function checks if the buffer is not empty, converts it to the type `T`, and pushes it to
@@ -35,3 +39,335 @@ pub fn build_rel_prefix(html_rel_path: &str) -> PathBuf {
     }
     rel_prefix
 }
+
+/// Derives the path of a generated HTML page, relative to the output directory, from the path of
+/// its Markdown source relative to the input directory.
+///
+/// A trailing `.md` extension is replaced with `.html`, while any other file name has `.html`
+/// appended so that pages generated from differently named sources never collide. With
+/// `pretty_urls`, each page is instead written to an `index.html` inside a directory named after
+/// it, unless it is already named `index`.
+///
+/// # Arguments
+/// * `input_rel` - The path of the source file, relative to the input directory.
+/// * `pretty_urls` - Whether pages are written as `name/index.html` rather than `name.html`.
+///
+/// # Returns
+/// The relative path of the generated HTML file.
+///
+/// # Example
+/// ```
+/// use mark_rs::utils::output_relative_path;
+/// assert_eq!(output_relative_path("guides/setup.md", false), "guides/setup.html");
+/// assert_eq!(output_relative_path("guides/setup.md", true), "guides/setup/index.html");
+/// ```
+pub fn output_relative_path(input_rel: &str, pretty_urls: bool) -> String {
+    let stem = input_rel.strip_suffix(".md").unwrap_or(input_rel);
+
+    let file_name = stem.rsplit('/').next().unwrap_or(stem);
+    if pretty_urls && file_name != "index" {
+        format!("{stem}/index.html")
+    } else {
+        format!("{stem}.html")
+    }
+}
+
+/// Derives the path used when linking to a generated page, relative to the output directory.
+///
+/// This is the same as [`output_relative_path`], except that with `pretty_urls` the trailing
+/// `index.html` is dropped so that links point at the page's directory, e.g. `guides/setup/`.
+///
+/// # Arguments
+/// * `input_rel` - The path of the source file, relative to the input directory.
+/// * `pretty_urls` - Whether pages are written as `name/index.html` rather than `name.html`.
+///
+/// # Returns
+/// The relative path to link to, which is empty for a top-level `index` page with `pretty_urls`.
+pub fn page_link_path(input_rel: &str, pretty_urls: bool) -> String {
+    let output_path = output_relative_path(input_rel, pretty_urls);
+    if !pretty_urls {
+        return output_path;
+    }
+
+    output_path
+        .strip_suffix("index.html")
+        .unwrap_or(&output_path)
+        .to_string()
+}
+
+/// Rewrites a relative link to another markdown page (i.e. `other.md#setup`) to point at the page
+/// generated from it, relative to the page containing the link. Any query string or fragment is
+/// kept.
+///
+/// # Arguments
+/// * `url` - The link's destination, which is resolved against the directory of `page_path`.
+/// * `page_path` - The path of the page containing the link, relative to the input directory.
+/// * `pretty_urls` - Whether pages are written as `name/index.html` rather than `name.html`.
+///
+/// # Returns
+/// The rewritten link, or `None` if the URL isn't a relative link to a `.md` file inside the
+/// input directory.
+///
+/// # Example
+/// ```
+/// use mark_rs::utils::markdown_link_href;
+/// assert_eq!(
+///     markdown_link_href("setup.md#install", "guides/intro.md", false).as_deref(),
+///     Some("../guides/setup.html#install")
+/// );
+/// assert_eq!(markdown_link_href("https://example.com/a.md", "intro.md", false), None);
+/// ```
+pub fn markdown_link_href(url: &str, page_path: &str, pretty_urls: bool) -> Option<String> {
+    if !is_relative_url(url) {
+        return None;
+    }
+
+    let (path, suffix) = url.split_at(url.find(['?', '#']).unwrap_or(url.len()));
+    if !path.ends_with(".md") {
+        return None;
+    }
+
+    // Resolve the link against the page's directory, giving up on links that leave the input
+    // directory since no page could have been generated for them
+    let mut target: Vec<&str> = page_path.split('/').collect();
+    target.pop();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                target.pop()?;
+            }
+            component => target.push(component),
+        }
+    }
+
+    let target_link = page_link_path(&target.join("/"), pretty_urls);
+    let page_output = output_relative_path(page_path, pretty_urls);
+    let href = build_rel_prefix(&page_output)
+        .join(target_link)
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    match href.is_empty() {
+        true => Some(format!("./{suffix}")),
+        false => Some(format!("{href}{suffix}")),
+    }
+}
+
+/// Checks whether a URL is a relative path to a local file, rather than an absolute path or a
+/// URL with a scheme like `https:` or `data:`.
+///
+/// # Example
+/// ```
+/// use mark_rs::utils::is_relative_url;
+/// assert!(is_relative_url("./img/logo.png"));
+/// assert!(!is_relative_url("https://example.com/logo.png"));
+/// ```
+pub fn is_relative_url(url: &str) -> bool {
+    if url.is_empty() || url.starts_with(['/', '\\', '#']) {
+        return false;
+    }
+
+    // A scheme is only recognized before the first path separator, so "img/a:b.png" is relative
+    let scheme_end = url.find(':');
+    let path_start = url.find(['/', '?', '#']).unwrap_or(url.len());
+    scheme_end.is_none_or(|index| index >= path_start)
+}
+
+/// Percent-encodes the characters in a link destination that aren't allowed in a URL, like spaces
+/// and non-ASCII characters. Characters with a meaning in URLs and existing `%XX` escapes are kept,
+/// so already-encoded URLs are left unchanged.
+///
+/// # Example
+/// ```
+/// use mark_rs::utils::percent_encode_url;
+/// assert_eq!(percent_encode_url("my file.png"), "my%20file.png");
+/// assert_eq!(percent_encode_url("a%20b?q=ä"), "a%20b?q=%C3%A4");
+/// ```
+pub fn percent_encode_url(url: &str) -> String {
+    let mut encoded = String::with_capacity(url.len());
+    for (i, ch) in url.char_indices() {
+        let is_escape = ch == '%'
+            && url
+                .as_bytes()
+                .get(i + 1..i + 3)
+                .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit));
+
+        if is_escape || ch.is_ascii_alphanumeric() || "-._~!$&'()*+,;=:@/?#[]".contains(ch) {
+            encoded.push(ch);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in ch.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{byte:02X}"));
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Builds a GitHub-style slug from the text of a heading, for use as its `id`.
+///
+/// The text is lowercased, spaces become hyphens, and any punctuation other than `-` and `_` is
+/// stripped. Inline HTML tags are dropped entirely, so `<em>` in a raw HTML heading doesn't leak
+/// into the slug.
+///
+/// # Example
+/// ```
+/// use mark_rs::utils::slugify;
+/// assert_eq!(slugify("Hello, World!"), "hello-world");
+/// ```
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+
+    let mut in_tag = false;
+    for ch in text.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if in_tag => {}
+            ' ' => slug.push('-'),
+            '-' | '_' => slug.push(ch),
+            _ if ch.is_alphanumeric() => slug.extend(ch.to_lowercase()),
+            _ => {}
+        }
+    }
+
+    slug
+}
+
+/// Removes the pages matching any of the given glob patterns from the list of pages shown in the
+/// index. Patterns are matched against both the page's path relative to the input directory and
+/// its file name, so `_*.md` excludes `_drafts.md` as well as `notes/_drafts.md`.
+///
+/// # Arguments
+/// * `file_names` - The paths of the pages, relative to the input directory.
+/// * `patterns` - The glob patterns of pages to exclude. Invalid patterns are skipped.
+///
+/// # Returns
+/// The pages to list in the index, in their original order.
+pub fn filter_index_files(file_names: &[String], patterns: &[String]) -> Vec<String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => warn!("Skipping invalid index_exclude pattern '{pattern}': {e}"),
+        }
+    }
+
+    let excluded = builder.build().unwrap_or_else(|e| {
+        warn!("Failed to build index_exclude patterns: {e}");
+        GlobSet::empty()
+    });
+
+    file_names
+        .iter()
+        .filter(|file_name| {
+            let base_name = Path::new(file_name.as_str())
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            !excluded.is_match(file_name.as_str()) && !excluded.is_match(&base_name)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Orders pages for the previous/next page links. Pages with an `order` in their front matter
+/// come first, from lowest to highest, followed by the rest. Ties are broken by path, so the
+/// order is stable between builds.
+///
+/// # Arguments
+/// * `pages` - The name of each page along with its `order`, if it has one.
+///
+/// # Returns
+/// The page names in navigation order.
+///
+/// # Example
+/// ```
+/// use mark_rs::utils::navigation_order;
+/// let pages = vec![
+///     (String::from("b.md"), None),
+///     (String::from("c.md"), Some(1)),
+///     (String::from("a.md"), None),
+/// ];
+/// assert_eq!(navigation_order(pages), vec!["c.md", "a.md", "b.md"]);
+/// ```
+pub fn navigation_order(mut pages: Vec<(String, Option<i64>)>) -> Vec<String> {
+    pages.sort_by(|(a_name, a_order), (b_name, b_order)| {
+        let by_order = match (a_order, b_order) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        by_order.then_with(|| a_name.cmp(b_name))
+    });
+
+    pages.into_iter().map(|(name, _)| name).collect()
+}
+
+/// A 64-bit FNV-1a hash of a list of fields, used for fingerprints that are written to the output
+/// directory and compared by the next build.
+///
+/// Unlike `std::hash::DefaultHasher`, whose algorithm may change between Rust releases, FNV-1a is
+/// specified, so a fingerprint only changes when the fields it was built from do.
+///
+/// # Example
+/// ```
+/// use mark_rs::utils::Fingerprint;
+///
+/// let mut fingerprint = Fingerprint::new();
+/// fingerprint.add("a").add("bc");
+/// assert_eq!(fingerprint.to_hex(), Fingerprint::new().add("a").add("bc").to_hex());
+/// assert_ne!(fingerprint.to_hex(), Fingerprint::new().add("ab").add("c").to_hex());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    hash: u64,
+}
+
+impl Fingerprint {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Creates a fingerprint of no fields.
+    pub fn new() -> Self {
+        Fingerprint {
+            hash: Self::OFFSET_BASIS,
+        }
+    }
+
+    /// Adds a field to the fingerprint. Each field is prefixed with its length, so that splitting
+    /// the same bytes into fields differently gives a different fingerprint.
+    pub fn add(&mut self, field: impl AsRef<[u8]>) -> &mut Self {
+        let field = field.as_ref();
+        self.write(&(field.len() as u64).to_le_bytes());
+        self.write(field);
+        self
+    }
+
+    /// Returns the fingerprint as a 16 digit hex string.
+    pub fn to_hex(&self) -> String {
+        format!("{:016x}", self.hash)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash ^= u64::from(byte);
+            self.hash = self.hash.wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+impl Default for Fingerprint {
+    fn default() -> Self {
+        Fingerprint::new()
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,202 @@
+use crate::utils::{
+    Fingerprint, filter_index_files, is_relative_url, markdown_link_href, navigation_order,
+    output_relative_path, page_link_path, percent_encode_url, slugify,
+};
+
+#[test]
+fn output_path_replaces_md_extension() {
+    assert_eq!(output_relative_path("index.md", false), "index.html");
+}
+
+#[test]
+fn output_path_keeps_nested_directories() {
+    assert_eq!(
+        output_relative_path("guides/setup/install.md", false),
+        "guides/setup/install.html"
+    );
+}
+
+#[test]
+fn output_path_only_strips_one_md_extension() {
+    assert_eq!(output_relative_path("notes.md.md", false), "notes.md.html");
+    assert_eq!(
+        output_relative_path("archive.tar.md", false),
+        "archive.tar.html"
+    );
+}
+
+#[test]
+fn output_path_appends_html_to_other_extensions() {
+    assert_eq!(output_relative_path("README", false), "README.html");
+    assert_eq!(
+        output_relative_path("notes.markdown", false),
+        "notes.markdown.html"
+    );
+    assert_eq!(output_relative_path("page.MD", false), "page.MD.html");
+}
+
+#[test]
+fn pretty_urls_write_pages_to_directories() {
+    assert_eq!(
+        output_relative_path("guides/setup.md", true),
+        "guides/setup/index.html"
+    );
+    assert_eq!(output_relative_path("index.md", true), "index.html");
+    assert_eq!(
+        output_relative_path("guides/index.md", true),
+        "guides/index.html"
+    );
+}
+
+#[test]
+fn pretty_url_links_drop_index_html() {
+    assert_eq!(page_link_path("guides/setup.md", true), "guides/setup/");
+    assert_eq!(page_link_path("guides/index.md", true), "guides/");
+    assert_eq!(page_link_path("index.md", true), "");
+    assert_eq!(
+        page_link_path("guides/setup.md", false),
+        "guides/setup.html"
+    );
+}
+
+#[test]
+fn index_exclude_removes_underscore_prefixed_pages() {
+    let file_names = vec![
+        String::from("index.md"),
+        String::from("_partial.md"),
+        String::from("guides/_draft.md"),
+        String::from("guides/setup.md"),
+        String::from("404.md"),
+    ];
+    let patterns = vec![String::from("_*.md"), String::from("404.md")];
+
+    assert_eq!(
+        filter_index_files(&file_names, &patterns),
+        vec![String::from("index.md"), String::from("guides/setup.md")]
+    );
+}
+
+#[test]
+fn index_exclude_skips_invalid_patterns() {
+    let file_names = vec![String::from("a.md"), String::from("b.md")];
+    let patterns = vec![String::from("a[.md"), String::from("b.md")];
+
+    assert_eq!(
+        filter_index_files(&file_names, &patterns),
+        vec![String::from("a.md")]
+    );
+}
+
+#[test]
+fn markdown_links_point_at_generated_pages() {
+    assert_eq!(
+        markdown_link_href("other.md", "index.md", false).as_deref(),
+        Some("other.html")
+    );
+    assert_eq!(
+        markdown_link_href("./setup.md#install", "guides/intro.md", false).as_deref(),
+        Some("../guides/setup.html#install")
+    );
+    assert_eq!(
+        markdown_link_href("../index.md?v=2", "guides/intro.md", false).as_deref(),
+        Some("../index.html?v=2")
+    );
+}
+
+#[test]
+fn markdown_links_use_pretty_urls() {
+    assert_eq!(
+        markdown_link_href("setup.md#install", "guides/intro.md", true).as_deref(),
+        Some("../../guides/setup/#install")
+    );
+    assert_eq!(
+        markdown_link_href("../index.md", "guides/intro.md", true).as_deref(),
+        Some("../../")
+    );
+    assert_eq!(
+        markdown_link_href("index.md", "index.md", true).as_deref(),
+        Some("./")
+    );
+}
+
+#[test]
+fn other_links_are_not_rewritten() {
+    assert_eq!(
+        markdown_link_href("https://example.com/a.md", "index.md", false),
+        None
+    );
+    assert_eq!(markdown_link_href("/docs/a.md", "index.md", false), None);
+    assert_eq!(markdown_link_href("#setup", "index.md", false), None);
+    assert_eq!(markdown_link_href("notes.txt", "index.md", false), None);
+    assert_eq!(markdown_link_href("../outside.md", "index.md", false), None);
+}
+
+#[test]
+fn percent_encoding_keeps_reserved_characters_and_escapes() {
+    assert_eq!(
+        percent_encode_url("https://example.com/a b/café?q=1&r=%2F#top"),
+        "https://example.com/a%20b/caf%C3%A9?q=1&r=%2F#top"
+    );
+    assert_eq!(percent_encode_url("100%.png"), "100%25.png");
+}
+
+#[test]
+fn slugify_strips_punctuation() {
+    assert_eq!(slugify("What's New in v1.3?"), "whats-new-in-v13");
+}
+
+#[test]
+fn slugify_keeps_hyphens_and_underscores() {
+    assert_eq!(
+        slugify("snake_case and kebab-case"),
+        "snake_case-and-kebab-case"
+    );
+}
+
+#[test]
+fn relative_urls_are_local_paths() {
+    assert!(is_relative_url("./img/logo.png"));
+    assert!(is_relative_url("img/logo.png"));
+    assert!(is_relative_url("../shared/a:b.png"));
+}
+
+#[test]
+fn absolute_urls_and_schemes_are_not_relative() {
+    assert!(!is_relative_url("https://example.com/logo.png"));
+    assert!(!is_relative_url("//cdn.example.com/logo.png"));
+    assert!(!is_relative_url("/static/logo.png"));
+    assert!(!is_relative_url("data:image/png;base64,AAAA"));
+    assert!(!is_relative_url("C:\\images\\logo.png"));
+    assert!(!is_relative_url(""));
+}
+
+#[test]
+fn navigation_order_puts_ordered_pages_first() {
+    let pages = vec![
+        (String::from("b.md"), None),
+        (String::from("z.md"), Some(2)),
+        (String::from("a.md"), None),
+        (String::from("y.md"), Some(-1)),
+        (String::from("x.md"), Some(2)),
+    ];
+
+    assert_eq!(
+        navigation_order(pages),
+        vec!["y.md", "x.md", "z.md", "a.md", "b.md"]
+    );
+}
+
+#[test]
+fn fingerprint_matches_fnv1a() {
+    // FNV-1a of "a" prefixed with its length as a little-endian u64
+    let mut expected: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in [1, 0, 0, 0, 0, 0, 0, 0, b'a'] {
+        expected = (expected ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    assert_eq!(
+        Fingerprint::new().add("a").to_hex(),
+        format!("{expected:016x}")
+    );
+    assert_eq!(Fingerprint::new().to_hex(), "cbf29ce484222325");
+}